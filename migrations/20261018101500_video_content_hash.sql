@@ -0,0 +1,3 @@
+-- Cheap content fingerprint used to follow moved files
+alter table videos add column content_hash text;
+create index if not exists videos_content_hash_idx on videos (content_hash);
@@ -0,0 +1,23 @@
+create table if not exists libraries (
+  id integer not null primary key autoincrement,
+  name text not null,
+  -- 'movie' or 'show'
+  media_type text not null,
+  -- Server wide language when null
+  metadata_language text,
+  -- Comma separated providers, server wide order when null
+  provider_order text,
+  detect_intros boolean not null default false,
+  generate_previews boolean not null default false,
+  -- Automatic scan period in minutes, manual scans only when null
+  scan_interval integer,
+  last_scan datetime,
+  created_at datetime default current_timestamp not null
+);
+
+create table if not exists library_paths (
+  id integer not null primary key autoincrement,
+  library_id integer not null,
+  path text not null unique,
+  foreign key (library_id) references libraries (id) on delete cascade
+);
@@ -0,0 +1,23 @@
+-- Movie collections (franchises) and their movies, including the ones missing in the library
+create table if not exists collections (
+  id integer not null primary key autoincrement,
+  metadata_id text not null,
+  metadata_provider text not null,
+  title text not null,
+  plot text,
+  poster text,
+  backdrop text,
+  unique (metadata_provider, metadata_id)
+);
+
+create table if not exists collection_parts (
+  id integer not null primary key autoincrement,
+  collection_id integer not null,
+  metadata_id text not null,
+  metadata_provider text not null,
+  title text not null,
+  release_date text,
+  poster text,
+  foreign key (collection_id) references collections (id) on delete cascade,
+  unique (collection_id, metadata_provider, metadata_id)
+);
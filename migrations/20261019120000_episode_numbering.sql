@@ -0,0 +1,13 @@
+-- 'provider' translates absolute episode numbers into provider seasons, 'file' keeps file numbering
+alter table shows add column episode_numbering text not null default 'provider';
+
+-- Manual absolute (scene) episode number to provider season/episode mapping
+create table if not exists episode_mappings (
+  id integer not null primary key autoincrement,
+  show_id integer not null,
+  absolute_number integer not null,
+  season integer not null,
+  episode integer not null,
+  foreign key (show_id) references shows (id) on delete cascade,
+  unique (show_id, absolute_number)
+);
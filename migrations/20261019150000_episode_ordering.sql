@@ -0,0 +1,2 @@
+-- Provider episode ordering of the show: 'aired', 'dvd', 'absolute' or 'story'
+alter table shows add column episode_ordering text not null default 'aired';
@@ -0,0 +1,13 @@
+-- Upcoming and recently aired episodes of the tracked shows, refreshed from providers
+create table if not exists calendar_episodes (
+  id integer not null primary key autoincrement,
+  show_id integer not null,
+  season integer not null,
+  episode integer not null,
+  title text not null,
+  -- ISO 8601 date
+  air_date text not null,
+  poster text,
+  foreign key (show_id) references shows (id) on delete cascade,
+  unique (show_id, season, episode)
+);
@@ -0,0 +1,24 @@
+-- Background jobs started by the scheduler
+create table if not exists scheduled_jobs (
+  id integer not null primary key autoincrement,
+  kind text not null unique,
+  enabled boolean not null default true,
+  -- Run period in minutes, used when cron expression is null
+  interval_minutes integer,
+  -- Five field cron expression evaluated in UTC
+  cron text,
+  last_run datetime,
+  last_finished datetime,
+  -- 'running', 'success' or 'failure'
+  last_status text,
+  last_error text,
+  check (interval_minutes is not null or cron is not null)
+);
+
+insert into scheduled_jobs (kind, enabled, interval_minutes, cron) values
+  ('library_scan', false, 360, null),
+  ('metadata_refresh', false, null, '0 4 * * *'),
+  ('missing_previews', false, null, '0 3 * * *'),
+  ('intro_detection', false, null, '30 3 * * *'),
+  ('temp_cleanup', false, 1440, null),
+  ('calendar_refresh', true, 720, null);
@@ -0,0 +1,11 @@
+-- Problems found by the media health check, videos without rows are healthy or not checked
+create table if not exists video_health_issues (
+  id integer not null primary key autoincrement,
+  video_id integer not null,
+  kind text not null,
+  message text not null,
+  checked_at datetime not null default current_timestamp,
+  foreign key (video_id) references videos (id) on delete cascade
+);
+
+create index if not exists video_health_issues_video_id_idx on video_health_issues (video_id);
@@ -0,0 +1,7 @@
+-- Gitignore-style exclude patterns relative to the library folders
+create table if not exists library_ignore_patterns (
+  id integer not null primary key autoincrement,
+  library_id integer not null,
+  pattern text not null,
+  foreign key (library_id) references libraries (id) on delete cascade
+);
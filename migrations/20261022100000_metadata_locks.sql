@@ -0,0 +1,12 @@
+-- Metadata fields that are kept during refreshes and re-matches
+create table if not exists metadata_locks (
+  metadata_id integer not null primary key,
+  title integer not null default 0,
+  plot integer not null default 0,
+  poster integer not null default 0,
+  backdrop integer not null default 0,
+  release_date integer not null default 0,
+  genres integer not null default 0,
+  cast_members integer not null default 0,
+  foreign key (metadata_id) references metadata (id) on delete cascade
+);
@@ -0,0 +1,11 @@
+-- Raw responses of metadata providers
+create table provider_cache (
+    provider text not null,
+    -- Request url including query
+    url text not null,
+    body text not null,
+    fetched_at integer not null,
+    -- Response is served without revalidation until this unix timestamp
+    expires_at integer not null,
+    primary key (provider, url)
+);
@@ -0,0 +1,68 @@
+-- Local copy of the IMDb non-commercial datasets, see https://developer.imdb.com/non-commercial-datasets
+
+-- title.basics without adult titles, games and videos
+create table imdb_titles (
+  tconst text not null primary key,
+  title_type text not null,
+  primary_title text not null,
+  original_title text,
+  is_adult integer not null default 0,
+  start_year integer,
+  end_year integer,
+  runtime_minutes integer,
+  -- Comma separated IMDb genre names
+  genres text
+);
+
+-- Title search over movies and shows, rowid is the rowid of imdb_titles
+create virtual table imdb_titles_fts using fts5(primary_title, original_title, content='');
+
+-- title.episode
+create table imdb_episodes (
+  tconst text not null primary key,
+  parent_tconst text not null,
+  season_number integer,
+  episode_number integer
+);
+
+create index imdb_episodes_parent_idx on imdb_episodes (parent_tconst, season_number, episode_number);
+
+-- title.ratings
+create table imdb_ratings (
+  tconst text not null primary key,
+  average_rating real not null,
+  num_votes integer not null
+);
+
+-- title.principals of movies and shows, episodes are skipped
+create table imdb_principals (
+  tconst text not null,
+  ordering integer not null,
+  nconst text not null,
+  category text not null,
+  job text,
+  -- JSON array of played characters
+  characters text,
+  primary key (tconst, ordering)
+);
+
+create index imdb_principals_nconst_idx on imdb_principals (nconst);
+
+-- name.basics of people that appear in imdb_principals
+create table imdb_names (
+  nconst text not null primary key,
+  primary_name text not null,
+  birth_year integer,
+  death_year integer,
+  -- Comma separated professions
+  primary_profession text,
+  -- Comma separated tconsts
+  known_for_titles text
+);
+
+-- Last import of every dataset
+create table imdb_imports (
+  dataset text not null primary key,
+  rows integer not null,
+  imported_at datetime not null default current_timestamp
+);
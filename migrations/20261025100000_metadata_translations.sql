@@ -0,0 +1,14 @@
+-- Titles, plots and posters in the additional metadata languages.
+-- Main metadata row holds the configured metadata language.
+-- Row with empty fields means that the provider doesn't have the translation.
+-- Outdated translations are served until they are fetched again
+create table metadata_translations (
+  metadata_id integer not null,
+  language text not null,
+  title text,
+  plot text,
+  poster text,
+  outdated boolean not null default false,
+  primary key (metadata_id, language),
+  foreign key (metadata_id) references metadata (id) on delete cascade
+);
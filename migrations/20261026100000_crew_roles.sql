@@ -0,0 +1,3 @@
+-- Crew job of the role: director, writer or composer.
+-- Roles without the job belong to the cast
+alter table roles add column job text;
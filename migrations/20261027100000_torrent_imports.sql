@@ -0,0 +1,6 @@
+-- Finished torrents that were handled by the download importer
+create table if not exists torrent_imports (
+  torrent_id integer not null primary key,
+  imported_at datetime not null default current_timestamp,
+  foreign key (torrent_id) references torrents (id) on delete cascade
+);
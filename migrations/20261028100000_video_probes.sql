@@ -0,0 +1,10 @@
+-- Stream summary of the last successful probe, used by the library statistics
+create table if not exists video_probes (
+  video_id integer not null primary key,
+  video_codec text,
+  audio_codec text,
+  width integer,
+  height integer,
+  hdr boolean not null default false,
+  foreign key (video_id) references videos (id) on delete cascade
+);
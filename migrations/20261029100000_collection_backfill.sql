@@ -0,0 +1,11 @@
+-- Collection parts are refetched once they get stale
+alter table collections add column parts_updated_at datetime;
+update collections set parts_updated_at = current_timestamp
+where exists (select 1 from collection_parts where collection_parts.collection_id = collections.id);
+
+-- Movies added before collections were introduced, their collections are fetched once
+create table if not exists collection_backfill (
+  metadata_id integer not null primary key,
+  foreign key (metadata_id) references metadata (id) on delete cascade
+);
+insert or ignore into collection_backfill (metadata_id) select metadata_id from movies;
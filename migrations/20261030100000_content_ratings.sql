@@ -0,0 +1,11 @@
+-- Ratings that come with the metadata, like the ones of the imported nfo files
+create table if not exists content_ratings (
+  metadata_id integer not null,
+  source text not null,
+  value real not null,
+  max_value real,
+  votes integer,
+  is_default boolean not null default false,
+  primary key (metadata_id, source),
+  foreign key (metadata_id) references metadata (id) on delete cascade
+);
@@ -0,0 +1,2 @@
+-- Metadata and artwork of the library are written back to nfo files and images next to the media
+alter table libraries add column export_nfo boolean not null default false;
@@ -4,7 +4,7 @@ use crate::{
     OffsetDateTime,
     api::api_data::local_actor::LocalActorData,
     db::{self, ListKind},
-    metadata::{LocaleMetadata, MetadataProvider, PersonMetadata},
+    metadata::{CrewJob, LocaleMetadata, MetadataProvider, PersonMetadata},
 };
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -69,6 +69,8 @@ pub struct Actor {
     pub metadata_provider: MetadataProvider,
     pub imdb_id: Option<String>,
     pub character: Option<String>,
+    /// Crew job, `None` for the actors
+    pub job: Option<CrewJob>,
     pub local: Option<LocalActorData>,
 }
 
@@ -93,6 +95,7 @@ impl From<PersonMetadata> for Actor {
             name,
             imdb_id,
             role,
+            job,
         }: PersonMetadata,
     ) -> Self {
         Self {
@@ -102,6 +105,7 @@ impl From<PersonMetadata> for Actor {
             metadata_provider,
             imdb_id,
             character: role.map(|r| r.character),
+            job,
             local: None,
         }
     }
@@ -116,6 +120,7 @@ impl Actor {
             metadata_provider: meta.metadata_provider,
             imdb_id: meta.imdb_id,
             character: meta.role.map(|r| r.character),
+            job: meta.job,
             local,
         }
     }
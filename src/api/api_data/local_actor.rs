@@ -1,4 +1,309 @@
+use std::collections::HashSet;
+
+use crate::{
+    db::{Db, DbQueryBuilder},
+    metadata::{CreditMetadata, CrewJob, MetadataProvider, ParentMediaType},
+};
+
 #[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct LocalActorData {
     pub id: i64,
 }
+
+/// Actor or crew member with the details from the metadata provider
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct Person {
+    pub id: i64,
+    pub name: String,
+    pub poster: Option<String>,
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub imdb_id: Option<String>,
+    pub biography: Option<String>,
+    /// Birth date, or only the year when provider doesn't know the date
+    pub birthday: Option<String>,
+    /// Death date, or only the year when provider doesn't know the date
+    pub deathday: Option<String>,
+    pub place_of_birth: Option<String>,
+    /// Department the person is known for, for example `Acting` or `Directing`
+    pub known_for_department: Option<String>,
+    /// Library movies and shows of the person
+    pub filmography: Vec<LocalCredit>,
+    /// Provider credits of the person that are not in the library
+    pub other_works: Vec<CreditMetadata>,
+}
+
+/// Library movie or show the person is credited in.
+///
+/// Show credits include the roles in its seasons and episodes
+#[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
+pub struct LocalCredit {
+    pub content_type: ParentMediaType,
+    /// Local movie or show id
+    pub id: i64,
+    pub metadata_id: i64,
+    pub title: String,
+    pub release_date: Option<String>,
+    pub poster: Option<String>,
+    pub character: Option<String>,
+    /// Crew job, `None` for the acting credits
+    pub job: Option<CrewJob>,
+}
+
+/// Library movies and shows of the actor, newest first
+pub async fn filmography(db: &Db, actor_id: i64) -> sqlx::Result<Vec<LocalCredit>> {
+    sqlx::query_as!(
+        LocalCredit,
+        r#"SELECT 'movie' as "content_type!: ParentMediaType", movies.id as "id!",
+        metadata.id as "metadata_id!", metadata.title as "title!", metadata.release_date,
+        metadata.poster, roles.character, roles.job as "job: CrewJob"
+        FROM roles JOIN movies ON movies.metadata_id = roles.metadata_id
+        JOIN metadata ON metadata.id = movies.metadata_id WHERE roles.actor_id = ?1
+        UNION
+        SELECT 'show', shows.id, metadata.id, metadata.title, metadata.release_date,
+        metadata.poster, roles.character, roles.job
+        FROM roles JOIN shows ON shows.metadata_id = roles.metadata_id
+        JOIN metadata ON metadata.id = shows.metadata_id WHERE roles.actor_id = ?1
+        UNION
+        SELECT 'show', shows.id, metadata.id, metadata.title, metadata.release_date,
+        metadata.poster, roles.character, roles.job
+        FROM roles JOIN seasons ON seasons.metadata_id = roles.metadata_id
+        JOIN shows ON shows.id = seasons.show_id
+        JOIN metadata ON metadata.id = shows.metadata_id WHERE roles.actor_id = ?1
+        UNION
+        SELECT 'show', shows.id, metadata.id, metadata.title, metadata.release_date,
+        metadata.poster, roles.character, roles.job
+        FROM roles JOIN episodes ON episodes.metadata_id = roles.metadata_id
+        JOIN seasons ON seasons.id = episodes.season_id
+        JOIN shows ON shows.id = seasons.show_id
+        JOIN metadata ON metadata.id = shows.metadata_id WHERE roles.actor_id = ?1
+        ORDER BY 5 DESC, 4, 7"#,
+        actor_id
+    )
+    .fetch_all(&db.pool)
+    .await
+}
+
+/// Provider credits of the movies and shows that are not in the library.
+///
+/// Credits are matched against all external ids of the library content because provider
+/// credits can list the person in content where they have no local role
+pub async fn not_in_library(
+    db: &Db,
+    credits: Vec<CreditMetadata>,
+) -> sqlx::Result<Vec<CreditMetadata>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        external_provider: MetadataProvider,
+        external_id: String,
+        content_type: ParentMediaType,
+    }
+    if credits.is_empty() {
+        return Ok(credits);
+    }
+    let local: HashSet<_> = DbQueryBuilder::new(
+        "SELECT external_ids.external_provider, external_ids.external_id,
+        CASE WHEN movies.id IS NULL THEN 'show' ELSE 'movie' END AS content_type
+        FROM external_ids
+        LEFT JOIN movies ON movies.metadata_id = external_ids.metadata_id
+        LEFT JOIN shows ON shows.metadata_id = external_ids.metadata_id
+        WHERE (movies.id IS NOT NULL OR shows.id IS NOT NULL)
+        AND (external_ids.external_provider, external_ids.external_id) IN ",
+    )
+    .push_tuples(credits.iter(), |mut b, credit| {
+        b.push_bind(credit.metadata_provider)
+            .push_bind(&credit.metadata_id);
+    })
+    .build_query_as::<Row>()
+    .fetch_all(&db.pool)
+    .await?
+    .into_iter()
+    .map(|row| (row.external_provider, row.external_id, row.content_type))
+    .collect();
+    Ok(credits
+        .into_iter()
+        .filter(|credit| {
+            !local.contains(&(
+                credit.metadata_provider,
+                credit.metadata_id.clone(),
+                credit.content_type,
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use crate::{
+        db::{Db, DbActions, DbActor, DbContentType, DbExternalId, DbMetadata, DbRole},
+        metadata::{CreditMetadata, CrewJob, MetadataProvider, ParentMediaType},
+    };
+
+    use super::{filmography, not_in_library};
+
+    #[sqlx::test]
+    async fn local_filmography(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        let metadata = |content_type, title: &str, release_date: &str| DbMetadata {
+            id: None,
+            content_type,
+            title: title.into(),
+            original_language: None,
+            original_title: None,
+            release_date: Some(release_date.into()),
+            poster: None,
+            plot: None,
+        };
+        let movie_metadata = db
+            .insert_metadata(&metadata(DbContentType::Movie, "Unforgiven", "1992-08-07"))
+            .await?;
+        let movie_id: i64 = sqlx::query_scalar!(
+            r#"INSERT INTO movies (metadata_id, duration) VALUES (?, 7800) RETURNING id as "id!""#,
+            movie_metadata
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        let show_metadata = db
+            .insert_metadata(&metadata(DbContentType::Show, "Rawhide", "1959-01-09"))
+            .await?;
+        let show_id: i64 = sqlx::query_scalar!(
+            r#"INSERT INTO shows (metadata_id) VALUES (?) RETURNING id as "id!""#,
+            show_metadata
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        let season_metadata = db
+            .insert_metadata(&metadata(DbContentType::Season, "Season 1", "1959-01-09"))
+            .await?;
+        let season_id: i64 = sqlx::query_scalar!(
+            r#"INSERT INTO seasons (metadata_id, show_id, number) VALUES (?, ?, 1) RETURNING id as "id!""#,
+            season_metadata,
+            show_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        let mut episode_metadata = Vec::new();
+        for number in [1, 2] {
+            let metadata_id = db
+                .insert_metadata(&metadata(DbContentType::Episode, "Episode", "1959-01-09"))
+                .await?;
+            sqlx::query!(
+                "INSERT INTO episodes (metadata_id, season_id, number, duration) VALUES (?, ?, ?, 0)",
+                metadata_id,
+                season_id,
+                number
+            )
+            .execute(&db.pool)
+            .await?;
+            episode_metadata.push(metadata_id);
+        }
+        db.insert_external_id(DbExternalId {
+            id: None,
+            external_provider: MetadataProvider::Tmdb,
+            external_id: "33".into(),
+            metadata_id: Some(movie_metadata),
+            is_prime: true.into(),
+        })
+        .await?;
+
+        let actor_id = db
+            .insert_actor(&DbActor {
+                name: "Clint Eastwood".into(),
+                external_metadata_id: "190".into(),
+                external_metadata_provider: MetadataProvider::Tmdb,
+                ..Default::default()
+            })
+            .await?;
+        let role = |metadata_id, character: Option<&str>, job| DbRole {
+            id: None,
+            actor_id,
+            metadata_id,
+            character: character.map(Into::into),
+            job,
+        };
+        db.insert_role(&role(movie_metadata, Some("William Munny"), None))
+            .await?;
+        db.insert_role(&role(movie_metadata, None, Some(CrewJob::Director)))
+            .await?;
+        for metadata_id in episode_metadata {
+            db.insert_role(&role(metadata_id, Some("Rowdy Yates"), None))
+                .await?;
+        }
+
+        let credits = filmography(&db, actor_id).await?;
+        let credits: Vec<_> = credits
+            .iter()
+            .map(|c| (c.content_type, c.id, c.character.as_deref(), c.job))
+            .collect();
+        assert_eq!(
+            credits,
+            [
+                (
+                    ParentMediaType::Movie,
+                    movie_id,
+                    None,
+                    Some(CrewJob::Director)
+                ),
+                (
+                    ParentMediaType::Movie,
+                    movie_id,
+                    Some("William Munny"),
+                    None
+                ),
+                // Episode roles are merged into the show
+                (ParentMediaType::Show, show_id, Some("Rowdy Yates"), None),
+            ]
+        );
+
+        let credit = |metadata_id: &str| CreditMetadata {
+            metadata_id: metadata_id.into(),
+            metadata_provider: MetadataProvider::Tmdb,
+            content_type: ParentMediaType::Movie,
+            title: "Title".into(),
+            release_date: None,
+            poster: None,
+            character: None,
+            job: None,
+        };
+        // Library movie without a local role of the person
+        let uncredited_metadata = db
+            .insert_metadata(&metadata(DbContentType::Movie, "Gran Torino", "2008-12-12"))
+            .await?;
+        sqlx::query!(
+            "INSERT INTO movies (metadata_id, duration) VALUES (?, 6960)",
+            uncredited_metadata
+        )
+        .execute(&db.pool)
+        .await?;
+        db.insert_external_id(DbExternalId {
+            id: None,
+            external_provider: MetadataProvider::Tmdb,
+            external_id: "13223".into(),
+            metadata_id: Some(uncredited_metadata),
+            is_prime: true.into(),
+        })
+        .await?;
+        let mut show_credit = credit("33");
+        show_credit.content_type = ParentMediaType::Show;
+        let other = not_in_library(
+            &db,
+            vec![credit("33"), credit("1056"), credit("13223"), show_credit],
+        )
+        .await?;
+        let other: Vec<_> = other
+            .iter()
+            .map(|c| (c.metadata_id.as_str(), c.content_type))
+            .collect();
+        // Same id of the other content type is a different title
+        assert_eq!(
+            other,
+            [
+                ("1056", ParentMediaType::Movie),
+                ("33", ParentMediaType::Show)
+            ]
+        );
+        Ok(())
+    }
+}
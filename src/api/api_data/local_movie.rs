@@ -40,6 +40,8 @@ impl From<Movie> for MovieMetadata {
             genres: None,
             cast: None,
             external_ids: None,
+            collection: None,
+            ratings: None,
         }
     }
 }
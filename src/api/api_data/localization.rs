@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{
+    api::{
+        history::{HistoryEntry, ShowSuggestion},
+        lists::ListEpisode,
+    },
+    config,
+    db::{Db, DbQueryBuilder},
+    metadata::{
+        Language,
+        translations::{self, Translation},
+    },
+};
+
+use super::{
+    local_actor::LocalCredit,
+    local_movie::Movie,
+    local_show::{Episode, Season, Show},
+};
+
+/// Content which titles, plots and posters can be replaced with stored translations
+pub trait Localize {
+    /// Id of the local metadata row, `None` for the content that is not in the library
+    fn local_metadata_id(&self) -> Option<i64>;
+
+    fn apply_translation(&mut self, translation: Translation);
+}
+
+impl Localize for Show {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.local.as_ref().map(|l| l.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        if let Some(title) = translation.title {
+            self.title = title;
+        }
+        self.plot = translation.plot.or(self.plot.take());
+        self.poster = translation.poster.or(self.poster.take());
+    }
+}
+
+impl Localize for Movie {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.local.as_ref().map(|l| l.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        if let Some(title) = translation.title {
+            self.title = title;
+        }
+        self.plot = translation.plot.or(self.plot.take());
+        self.poster = translation.poster.or(self.poster.take());
+    }
+}
+
+impl Localize for Season {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.local.as_ref().map(|l| l.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        self.title = translation.title.or(self.title.take());
+        self.plot = translation.plot.or(self.plot.take());
+        self.poster = translation.poster.or(self.poster.take());
+    }
+}
+
+impl Localize for Episode {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.local.as_ref().map(|l| l.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        if let Some(title) = translation.title {
+            self.title = title;
+        }
+        self.plot = translation.plot.or(self.plot.take());
+        self.poster = translation.poster.or(self.poster.take());
+    }
+}
+
+impl Localize for LocalCredit {
+    fn local_metadata_id(&self) -> Option<i64> {
+        Some(self.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        if let Some(title) = translation.title {
+            self.title = title;
+        }
+        self.poster = translation.poster.or(self.poster.take());
+    }
+}
+
+impl Localize for ListEpisode {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.episode.local_metadata_id()
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        self.episode.apply_translation(translation);
+    }
+}
+
+impl Localize for ShowSuggestion {
+    fn local_metadata_id(&self) -> Option<i64> {
+        self.episode.local_metadata_id()
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        self.episode.apply_translation(translation);
+    }
+}
+
+impl Localize for HistoryEntry {
+    fn local_metadata_id(&self) -> Option<i64> {
+        Some(self.metadata_id)
+    }
+
+    fn apply_translation(&mut self, translation: Translation) {
+        if let Some(title) = translation.title {
+            self.content.title = title;
+        }
+        self.content.plot = translation.plot.or(self.content.plot.take());
+        self.content.poster = translation.poster.or(self.content.poster.take());
+    }
+}
+
+/// Metadata rows that are not in `lang` already
+async fn foreign_rows(db: &Db, metadata_ids: Vec<i64>, lang: Language) -> sqlx::Result<Vec<i64>> {
+    let config::MetadataLanguage(metadata_lang) = config::CONFIG.get_value();
+    let languages = translations::main_languages(db, &metadata_ids, metadata_lang).await?;
+    Ok(metadata_ids
+        .into_iter()
+        .filter(|id| languages.get(id) != Some(&lang))
+        .collect())
+}
+
+/// Replace metadata of the local content with its translation to `lang`.
+///
+/// Content keeps the metadata language when there is no translation or it is already in `lang`
+pub async fn localize<T: Localize>(
+    db: &Db,
+    content: &mut [T],
+    lang: Option<Language>,
+) -> sqlx::Result<()> {
+    let Some(lang) = lang else {
+        return Ok(());
+    };
+    let ids: Vec<_> = content
+        .iter()
+        .filter_map(|c| c.local_metadata_id())
+        .collect();
+    let ids = foreign_rows(db, ids, lang).await?;
+    let mut translations = translations::localized(db, &ids, lang).await?;
+    for item in content {
+        if let Some(translation) = item
+            .local_metadata_id()
+            .and_then(|id| translations.remove(&id))
+        {
+            item.apply_translation(translation);
+        }
+    }
+    Ok(())
+}
+
+/// Titles of the local shows translated to `lang`, keyed by the local show id.
+///
+/// Shows without the translation are not included
+pub async fn localized_show_titles(
+    db: &Db,
+    show_ids: &[i64],
+    lang: Option<Language>,
+) -> sqlx::Result<HashMap<i64, String>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        id: i64,
+        metadata_id: i64,
+    }
+    let Some(lang) = lang else {
+        return Ok(HashMap::new());
+    };
+    if show_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut query = DbQueryBuilder::new("SELECT id, metadata_id FROM shows WHERE id IN (");
+    let mut ids = query.separated(", ");
+    for id in show_ids {
+        ids.push_bind(id);
+    }
+    query.push(")");
+    let shows: Vec<Row> = query.build_query_as().fetch_all(&db.pool).await?;
+    let metadata_ids =
+        foreign_rows(db, shows.iter().map(|s| s.metadata_id).collect(), lang).await?;
+    let mut translations = translations::localized(db, &metadata_ids, lang).await?;
+    Ok(shows
+        .into_iter()
+        .filter_map(|show| {
+            let title = translations.remove(&show.metadata_id)?.title?;
+            Some((show.id, title))
+        })
+        .collect())
+}
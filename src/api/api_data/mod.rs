@@ -15,6 +15,7 @@ pub mod api_types;
 pub mod local_actor;
 pub mod local_movie;
 pub mod local_show;
+pub mod localization;
 
 #[derive(Debug)]
 pub struct LocalDataLookup {
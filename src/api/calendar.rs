@@ -0,0 +1,116 @@
+use axum::{
+    extract::State,
+    http::{HeaderValue, header},
+    response::IntoResponse,
+};
+use serde::Deserialize;
+use time::{Date, Duration, OffsetDateTime, format_description::well_known::Iso8601};
+
+use crate::{
+    AppError,
+    api::{Json, Query},
+    app_state::AppState,
+    db::Db,
+    library::calendar::{self, CalendarEntry},
+};
+
+/// Days before today included in the calendar by default
+const DEFAULT_DAYS_BEFORE: i64 = 7;
+/// Days after today included in the calendar by default
+const DEFAULT_DAYS_AFTER: i64 = 60;
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct CalendarQuery {
+    /// ISO 8601 start date, week ago by default
+    pub from: Option<String>,
+    /// ISO 8601 end date (inclusive), two months ahead by default
+    pub to: Option<String>,
+}
+
+impl CalendarQuery {
+    fn range(&self) -> Result<(Date, Date), AppError> {
+        let parse = |date: &Option<String>, default_offset: i64| match date {
+            Some(date) => Date::parse(date, &Iso8601::DATE)
+                .map_err(|_| AppError::bad_request(format!("Invalid date: {date}"))),
+            None => Ok(OffsetDateTime::now_utc().date() + Duration::days(default_offset)),
+        };
+        Ok((
+            parse(&self.from, -DEFAULT_DAYS_BEFORE)?,
+            parse(&self.to, DEFAULT_DAYS_AFTER)?,
+        ))
+    }
+}
+
+/// Get upcoming episodes of the watched and watchlisted shows and watchlisted movie releases
+#[utoipa::path(
+    get,
+    path = "/api/calendar",
+    params(CalendarQuery),
+    responses(
+        (status = 200, description = "Releases ordered by date", body = Vec<CalendarEntry>),
+        (status = 400, description = "Invalid date range", body = AppError),
+    ),
+    tag = "Calendar",
+)]
+async fn calendar_entries(
+    State(db): State<Db>,
+    Query(query): Query<CalendarQuery>,
+) -> crate::Result<Json<Vec<CalendarEntry>>> {
+    let (from, to) = query.range()?;
+    Ok(Json(calendar::entries(&db, from, to).await?))
+}
+
+/// Calendar as iCalendar feed for calendar app subscriptions
+#[utoipa::path(
+    get,
+    path = "/api/calendar/feed.ics",
+    params(CalendarQuery),
+    responses(
+        (status = 200, description = "iCalendar feed", content_type = "text/calendar"),
+        (status = 400, description = "Invalid date range", body = AppError),
+    ),
+    tag = "Calendar",
+)]
+async fn calendar_feed(
+    State(db): State<Db>,
+    Query(query): Query<CalendarQuery>,
+) -> crate::Result<impl IntoResponse> {
+    let (from, to) = query.range()?;
+    let entries = calendar::entries(&db, from, to).await?;
+    let feed = calendar::to_ical(&entries, OffsetDateTime::now_utc());
+    Ok((
+        [(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("text/calendar; charset=utf-8"),
+        )],
+        feed,
+    ))
+}
+
+/// Refresh air dates of the tracked shows
+#[utoipa::path(
+    post,
+    path = "/api/calendar/refresh",
+    responses(
+        (status = 200, description = "Calendar is refreshed"),
+    ),
+    tag = "Calendar",
+)]
+async fn refresh_calendar(State(app_state): State<AppState>) -> crate::Result<()> {
+    calendar::refresh(
+        app_state.db,
+        app_state.providers_stack,
+        app_state.metadata_fetch_params(),
+    )
+    .await?;
+    Ok(())
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::{get, post};
+
+    axum::Router::new()
+        .route("/", get(calendar_entries))
+        .route("/feed.ics", get(calendar_feed))
+        .route("/refresh", post(refresh_calendar))
+}
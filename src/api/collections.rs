@@ -0,0 +1,106 @@
+use axum::{extract::State, response::IntoResponse};
+use axum_extra::TypedHeader;
+
+use crate::{
+    AppError,
+    api::{Json, Path},
+    app_state::AppState,
+    db::Db,
+    library::{
+        assets::{BackdropAsset, BackdropContentType, FileAsset, PosterAsset, PosterContentType},
+        collections::{self, Collection, CollectionDetails},
+    },
+};
+
+/// Get all collections that have movies in the library
+#[utoipa::path(
+    get,
+    path = "/api/collections",
+    responses(
+        (status = 200, description = "All collections", body = Vec<Collection>),
+    ),
+    tag = "Collections",
+)]
+async fn all_collections(State(db): State<Db>) -> crate::Result<Json<Vec<Collection>>> {
+    Ok(Json(collections::all(&db).await?))
+}
+
+/// Get collection with owned and missing movies
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}",
+    params(
+        ("id", description = "Collection id"),
+    ),
+    responses(
+        (status = 200, description = "Collection", body = CollectionDetails),
+        (status = 404, description = "Collection not found", body = AppError),
+    ),
+    tag = "Collections",
+)]
+async fn get_collection(
+    Path(id): Path<i64>,
+    State(db): State<Db>,
+) -> crate::Result<Json<CollectionDetails>> {
+    Ok(Json(collections::get(&db, id).await?))
+}
+
+/// Get collection poster
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}/poster",
+    params(
+        ("id", description = "Collection id"),
+    ),
+    responses(
+        (status = 200, content_type = "image/*"),
+        (status = 304),
+        (status = 404, body = AppError)
+    ),
+    tag = "Collections",
+)]
+async fn collection_poster(
+    Path(id): Path<i64>,
+    is_modified_since: Option<TypedHeader<axum_extra::headers::IfModifiedSince>>,
+) -> crate::Result<impl IntoResponse> {
+    let asset = PosterAsset::new(id, PosterContentType::Collection);
+    let response = asset
+        .into_response(axum_extra::headers::ContentType::jpeg(), is_modified_since)
+        .await?;
+    Ok(response)
+}
+
+/// Get collection backdrop image
+#[utoipa::path(
+    get,
+    path = "/api/collections/{id}/backdrop",
+    params(
+        ("id", description = "Collection id"),
+    ),
+    responses(
+        (status = 200, content_type = "image/*"),
+        (status = 304),
+        (status = 404, body = AppError)
+    ),
+    tag = "Collections",
+)]
+async fn collection_backdrop(
+    Path(id): Path<i64>,
+    is_modified_since: Option<TypedHeader<axum_extra::headers::IfModifiedSince>>,
+) -> crate::Result<impl IntoResponse> {
+    let asset = BackdropAsset::new(id, BackdropContentType::Collection);
+    let response = asset
+        .into_response(axum_extra::headers::ContentType::jpeg(), is_modified_since)
+        .await?;
+    Ok(response)
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::get;
+
+    axum::Router::new()
+        .route("/", get(all_collections))
+        .route("/{id}", get(get_collection))
+        .route("/{id}/poster", get(collection_poster))
+        .route("/{id}/backdrop", get(collection_backdrop))
+}
@@ -0,0 +1,44 @@
+use axum::extract::State;
+
+use crate::{
+    AppError,
+    api::Json,
+    app_state::AppState,
+    scan::dry_run::{self, DryRunPayload, DryRunResult},
+};
+
+/// Maximum amount of paths in one request, every path can query all providers
+const MAX_PATHS: usize = 50;
+
+/// Parse and match paths the way the scan does it without writing anything
+#[utoipa::path(
+    post,
+    path = "/api/parser/dry_run",
+    request_body = DryRunPayload,
+    responses(
+        (status = 200, description = "Parser and matcher output of every path", body = Vec<DryRunResult>),
+        (status = 400, body = AppError, description = "Too many paths"),
+    ),
+    tag = "Metadata",
+)]
+async fn parser_dry_run(
+    State(AppState {
+        db,
+        providers_stack,
+        ..
+    }): State<AppState>,
+    Json(payload): Json<DryRunPayload>,
+) -> crate::Result<Json<Vec<DryRunResult>>> {
+    if payload.paths.len() > MAX_PATHS {
+        return Err(AppError::bad_request(format!(
+            "At most {MAX_PATHS} paths are allowed per request"
+        )));
+    }
+    Ok(Json(dry_run::dry_run(db, providers_stack, payload).await?))
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::post;
+
+    axum::Router::new().route("/dry_run", post(parser_dry_run))
+}
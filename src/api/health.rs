@@ -0,0 +1,58 @@
+use axum::{extract::State, http::StatusCode};
+
+use crate::{
+    AppError,
+    api::{HealthCheckQuery, Json, Query},
+    app_state::AppState,
+    db::Db,
+    library::health::{self, HealthCheckTask, VideoHealthReport},
+    progress::ProgressDispatcher,
+};
+
+/// Get videos that failed the last health check
+#[utoipa::path(
+    get,
+    path = "/api/media_health",
+    responses(
+        (status = 200, description = "Videos with health issues", body = Vec<VideoHealthReport>),
+    ),
+    tag = "Videos",
+)]
+async fn health_report(State(db): State<Db>) -> crate::Result<Json<Vec<VideoHealthReport>>> {
+    Ok(Json(health::report(&db).await?))
+}
+
+/// Check all library videos for corruption, truncation and missing streams
+#[utoipa::path(
+    post,
+    path = "/api/media_health/check",
+    params(HealthCheckQuery),
+    responses(
+        (status = 202, description = "Health check is started"),
+        (status = 400, body = AppError, description = "Health check is already in progress"),
+    ),
+    tag = "Videos",
+)]
+async fn start_health_check(
+    State(app_state): State<AppState>,
+    Query(HealthCheckQuery { decode }): Query<HealthCheckQuery>,
+) -> crate::Result<StatusCode> {
+    let tasks = app_state.tasks;
+    let cancel = app_state.cancelation_token.child_token();
+    let task_id = tasks
+        .health_check_tasks
+        .start_task(HealthCheckTask { decode }, Some(cancel.clone()))?;
+    tasks.tracker.spawn(async move {
+        let progress = ProgressDispatcher::new(&tasks.health_check_tasks, task_id);
+        health::check_library(app_state.db, app_state.library, decode, progress, cancel).await;
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::{get, post};
+
+    axum::Router::new()
+        .route("/", get(health_report))
+        .route("/check", post(start_health_check))
+}
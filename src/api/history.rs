@@ -7,10 +7,12 @@ use serde::{Deserialize, Serialize};
 use crate::{
     AppError,
     api::{
-        CursorQuery, Json, OptionalUuidQuery, Path, Query, TakeQuery,
+        ContentLanguage, CursorQuery, Json, LanguageQuery, OptionalUuidQuery, Path, Query,
+        TakeQuery,
         api_data::{
             api_types::{Content, History},
             local_show::Episode,
+            localization::{localize, localized_show_titles},
         },
         lists::EpisodesList,
         server::CursoredResponse,
@@ -104,12 +106,14 @@ impl From<DbHistoryQuery> for HistoryEntry {
     params(
         TakeQuery,
         CursorQuery,
+        LanguageQuery,
     ),
     tag = "History",
 )]
 pub async fn all_history(
     Query(TakeQuery { take }): Query<TakeQuery>,
     Query(CursorQuery { cursor }): Query<CursorQuery>,
+    ContentLanguage(lang): ContentLanguage,
     State(db): State<Db>,
 ) -> crate::Result<Json<CursoredResponse<HistoryEntry>>> {
     let take = take.unwrap_or(50);
@@ -121,13 +125,33 @@ pub async fn all_history(
         .transpose()?;
     let mut builder = db::DbQueryBuilder::default();
     DbHistoryQuery::build(cursor, take, &mut builder);
-    let history: Vec<HistoryEntry> = builder
+    let mut history: Vec<HistoryEntry> = builder
         .build_query_as::<DbHistoryQuery>()
         .fetch_all(&db.pool)
         .await?
         .into_iter()
         .map(Into::into)
         .collect();
+    localize(&db, &mut history, lang).await?;
+    let show_ids: Vec<_> = history
+        .iter()
+        .filter_map(|entry| match entry.history_content_type {
+            HistoryContentType::Episode { show_id, .. } => Some(show_id),
+            HistoryContentType::Movie { .. } => None,
+        })
+        .collect();
+    let show_titles = localized_show_titles(&db, &show_ids, lang).await?;
+    for entry in &mut history {
+        if let HistoryContentType::Episode {
+            show_id,
+            show_title,
+            ..
+        } = &mut entry.history_content_type
+            && let Some(title) = show_titles.get(show_id)
+        {
+            *show_title = title.clone();
+        }
+    }
 
     let cursor = history
         .last()
@@ -158,12 +182,18 @@ pub struct ShowSuggestion {
 #[utoipa::path(
     get,
     path = "/api/history/suggest/movies",
+    params(
+        LanguageQuery,
+    ),
     responses(
         (status = 200, description = "Suggested movies", body = Vec<MovieHistory>),
     ),
     tag = "History",
 )]
-pub async fn suggest_movies(State(db): State<Db>) -> crate::Result<Json<Vec<MovieHistory>>> {
+pub async fn suggest_movies(
+    ContentLanguage(lang): ContentLanguage,
+    State(db): State<Db>,
+) -> crate::Result<Json<Vec<MovieHistory>>> {
     let history = sqlx::query!(
         r#"SELECT history.id AS history_id, history.time, history.is_finished, history.update_time,
         history.metadata_id, movies.id AS movie_id FROM history
@@ -175,10 +205,11 @@ pub async fn suggest_movies(State(db): State<Db>) -> crate::Result<Json<Vec<Movi
 
     let mut movie_suggestions = Vec::with_capacity(history.len());
     for entry in history {
-        let Ok(movie_metadata) = db.get_movie(entry.movie_id).await else {
+        let Ok(mut movie_metadata) = db.get_movie(entry.movie_id).await else {
             tracing::error!("Failed to get movie connected to the history");
             continue;
         };
+        localize(&db, std::slice::from_mut(&mut movie_metadata), lang).await?;
         movie_suggestions.push(MovieHistory {
             history: History {
                 id: entry.history_id,
@@ -196,12 +227,18 @@ pub async fn suggest_movies(State(db): State<Db>) -> crate::Result<Json<Vec<Movi
 #[utoipa::path(
     get,
     path = "/api/history/suggest/shows",
+    params(
+        LanguageQuery,
+    ),
     responses(
         (status = 200, description = "Suggested shows", body = Vec<ShowSuggestion>),
     ),
     tag = "History",
 )]
-pub async fn suggest_shows(State(db): State<Db>) -> crate::Result<Json<Vec<ShowSuggestion>>> {
+pub async fn suggest_shows(
+    ContentLanguage(lang): ContentLanguage,
+    State(db): State<Db>,
+) -> crate::Result<Json<Vec<ShowSuggestion>>> {
     let history = sqlx::query!(
         r#"SELECT history.id AS history_id, history.time, history.is_finished, history.update_time,
         history.metadata_id, episodes.number AS episode_number, seasons.show_id AS show_id,
@@ -247,6 +284,7 @@ pub async fn suggest_shows(State(db): State<Db>) -> crate::Result<Json<Vec<ShowS
             break;
         }
     }
+    localize(&db, &mut show_suggestions, lang).await?;
 
     Ok(Json(show_suggestions))
 }
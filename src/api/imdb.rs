@@ -0,0 +1,131 @@
+use std::path::PathBuf;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppError,
+    api::Json,
+    app_state::AppState,
+    db::Db,
+    metadata::imdb::{
+        self, ImdbRating,
+        import::{self, ImdbDataset, ImdbImportTask},
+    },
+    progress::ProgressDispatcher,
+};
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ImdbImportPayload {
+    /// Directory with the downloaded dumps, for example `title.basics.tsv.gz`
+    #[schema(value_type = String)]
+    pub directory: PathBuf,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ImdbImport {
+    pub dataset: ImdbDataset,
+    /// Rows kept after the import
+    pub rows: i64,
+    pub imported_at: crate::OffsetDateTime,
+}
+
+/// Import IMDb dataset dumps from the server directory
+#[utoipa::path(
+    post,
+    path = "/api/imdb/import",
+    request_body = ImdbImportPayload,
+    responses(
+        (status = 202, description = "Import is started"),
+        (status = 400, body = AppError, description = "Directory doesn't have the title.basics dump or import is already in progress"),
+    ),
+    tag = "Metadata",
+)]
+async fn import_imdb_datasets(
+    State(app_state): State<AppState>,
+    Json(payload): Json<ImdbImportPayload>,
+) -> crate::Result<StatusCode> {
+    let directory = payload.directory;
+    if import::dataset_path(&directory, ImdbDataset::TitleBasics).is_none() {
+        return Err(AppError::bad_request(format!(
+            "Directory must contain {} dump",
+            ImdbDataset::TitleBasics.file_name()
+        )));
+    }
+    let tasks = app_state.tasks;
+    let cancel = app_state.cancelation_token.child_token();
+    let task_id = tasks.imdb_import_tasks.start_task(
+        ImdbImportTask {
+            directory: directory.clone(),
+        },
+        Some(cancel.clone()),
+    )?;
+    tasks.tracker.spawn(async move {
+        let progress = ProgressDispatcher::new(&tasks.imdb_import_tasks, task_id);
+        import::import_directory(app_state.db, &directory, progress, cancel).await;
+        app_state.providers_stack.register_imdb(app_state.db).await;
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Last import of every IMDb dataset
+#[utoipa::path(
+    get,
+    path = "/api/imdb/imports",
+    responses(
+        (status = 200, body = Vec<ImdbImport>),
+    ),
+    tag = "Metadata",
+)]
+async fn imdb_imports(State(db): State<Db>) -> crate::Result<Json<Vec<ImdbImport>>> {
+    let imports = sqlx::query!(
+        r#"SELECT dataset as "dataset: ImdbDataset", rows, imported_at FROM imdb_imports"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(Json(
+        imports
+            .into_iter()
+            .map(|i| ImdbImport {
+                dataset: i.dataset,
+                rows: i.rows,
+                imported_at: i.imported_at.into(),
+            })
+            .collect(),
+    ))
+}
+
+/// IMDb rating of the title
+#[utoipa::path(
+    get,
+    path = "/api/imdb/titles/{id}/rating",
+    params(
+        ("id", description = "IMDb title id, for example tt0133093"),
+    ),
+    responses(
+        (status = 200, body = ImdbRating),
+        (status = 404, body = AppError, description = "Title doesn't have a rating"),
+    ),
+    tag = "Metadata",
+)]
+async fn imdb_rating(
+    State(db): State<Db>,
+    Path(id): Path<String>,
+) -> crate::Result<Json<ImdbRating>> {
+    imdb::rating(&db, &id)
+        .await?
+        .map(Json)
+        .ok_or(AppError::not_found("Rating is not found"))
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::{get, post};
+
+    axum::Router::new()
+        .route("/import", post(import_imdb_datasets))
+        .route("/imports", get(imdb_imports))
+        .route("/titles/{id}/rating", get(imdb_rating))
+}
@@ -0,0 +1,161 @@
+use axum::{extract::State, http::StatusCode};
+
+use crate::{
+    AppError,
+    api::{Json, Path},
+    app_state::AppState,
+    db::Db,
+    library::libraries::{self, LibrarySettings, MediaLibrary},
+};
+
+fn validate(settings: &LibrarySettings) -> crate::Result<()> {
+    if settings.name.trim().is_empty() {
+        return Err(AppError::bad_request("Library name is empty"));
+    }
+    if settings.paths.is_empty() {
+        return Err(AppError::bad_request(
+            "Library must have at least one folder",
+        ));
+    }
+    if let Some(path) = settings.paths.iter().find(|p| !p.is_absolute()) {
+        return Err(AppError::bad_request(format!(
+            "Library folder {} is not absolute",
+            path.display()
+        )));
+    }
+    if let Some(pattern) = settings
+        .ignore_patterns
+        .iter()
+        .find(|p| p.trim().is_empty())
+    {
+        return Err(AppError::bad_request(format!(
+            "Ignore pattern {pattern:?} is empty"
+        )));
+    }
+    if settings.scan_interval == Some(0) {
+        return Err(AppError::bad_request("Scan interval must be positive"));
+    }
+    Ok(())
+}
+
+/// Pick up videos from the changed library folders
+fn refresh_library(app_state: AppState) {
+    tokio::spawn(async move { app_state.partial_refresh().await });
+}
+
+/// Get all libraries
+#[utoipa::path(
+    get,
+    path = "/api/libraries",
+    responses(
+        (status = 200, description = "All libraries", body = Vec<MediaLibrary>),
+    ),
+    tag = "Libraries",
+)]
+async fn all_libraries(State(db): State<Db>) -> crate::Result<Json<Vec<MediaLibrary>>> {
+    Ok(Json(libraries::all(&db).await?))
+}
+
+/// Get single library
+#[utoipa::path(
+    get,
+    path = "/api/libraries/{id}",
+    params(
+        ("id", description = "Library id"),
+    ),
+    responses(
+        (status = 200, description = "Library", body = MediaLibrary),
+        (status = 404, description = "Library not found", body = AppError),
+    ),
+    tag = "Libraries",
+)]
+async fn get_library(
+    Path(id): Path<i64>,
+    State(db): State<Db>,
+) -> crate::Result<Json<MediaLibrary>> {
+    Ok(Json(libraries::get(&db, id).await?))
+}
+
+/// Create library
+#[utoipa::path(
+    post,
+    path = "/api/libraries",
+    request_body = LibrarySettings,
+    responses(
+        (status = 201, description = "Created library", body = MediaLibrary),
+        (status = 400, description = "Invalid library settings", body = AppError),
+        (status = 409, description = "Folder is already used by another library", body = AppError),
+    ),
+    tag = "Libraries",
+)]
+async fn create_library(
+    State(app_state): State<AppState>,
+    Json(settings): Json<LibrarySettings>,
+) -> crate::Result<(StatusCode, Json<MediaLibrary>)> {
+    validate(&settings)?;
+    let id = libraries::insert(app_state.db, &settings).await?;
+    refresh_library(app_state);
+    Ok((StatusCode::CREATED, Json(MediaLibrary { id, settings })))
+}
+
+/// Update library
+#[utoipa::path(
+    put,
+    path = "/api/libraries/{id}",
+    params(
+        ("id", description = "Library id"),
+    ),
+    request_body = LibrarySettings,
+    responses(
+        (status = 200, description = "Updated library", body = MediaLibrary),
+        (status = 400, description = "Invalid library settings", body = AppError),
+        (status = 404, description = "Library not found", body = AppError),
+        (status = 409, description = "Folder is already used by another library", body = AppError),
+    ),
+    tag = "Libraries",
+)]
+async fn update_library(
+    Path(id): Path<i64>,
+    State(app_state): State<AppState>,
+    Json(settings): Json<LibrarySettings>,
+) -> crate::Result<Json<MediaLibrary>> {
+    validate(&settings)?;
+    libraries::update(app_state.db, id, &settings).await?;
+    refresh_library(app_state);
+    Ok(Json(MediaLibrary { id, settings }))
+}
+
+/// Delete library
+///
+/// Library content that is not covered by other folders is removed on the next refresh
+#[utoipa::path(
+    delete,
+    path = "/api/libraries/{id}",
+    params(
+        ("id", description = "Library id"),
+    ),
+    responses(
+        (status = 200, description = "Successfully deleted library"),
+        (status = 404, description = "Library not found", body = AppError),
+    ),
+    tag = "Libraries",
+)]
+async fn delete_library(
+    Path(id): Path<i64>,
+    State(app_state): State<AppState>,
+) -> crate::Result<StatusCode> {
+    libraries::delete(app_state.db, id).await?;
+    refresh_library(app_state);
+    Ok(StatusCode::OK)
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::get;
+
+    axum::Router::new()
+        .route("/", get(all_libraries).post(create_library))
+        .route(
+            "/{id}",
+            get(get_library).put(update_library).delete(delete_library),
+        )
+}
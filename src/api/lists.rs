@@ -1,10 +1,11 @@
 use crate::{
     AppError,
     api::{
-        Json, Path,
+        ContentLanguage, Json, LanguageQuery, Path,
         api_data::{
             local_movie::Movie,
             local_show::{Episode, Show},
+            localization::{Localize, localize, localized_show_titles},
         },
     },
     app_state::AppState,
@@ -161,6 +162,7 @@ pub struct ListEpisode {
     path = "/api/lists/{id}/items",
     params(
         ("id", description = "List id"),
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Content stored in the list", body = Vec<ListContent>),
@@ -169,6 +171,7 @@ pub struct ListEpisode {
 )]
 async fn list_contents(
     Path(id): Path<i64>,
+    ContentLanguage(lang): ContentLanguage,
     State(db): State<Db>,
 ) -> crate::Result<Json<Vec<ListContent>>> {
     // Insertion order of the list, used to order the result once the per-kind queries are merged.
@@ -212,25 +215,40 @@ async fn list_contents(
         .fetch_all(&db.pool)
         .await?;
 
+    let mut shows: Vec<Show> = shows.into_iter().map(Into::into).collect();
+    let mut movies: Vec<Movie> = movies.into_iter().map(Into::into).collect();
+    let mut episodes: Vec<ListEpisode> = episodes
+        .into_iter()
+        .map(|query_result| ListEpisode {
+            episode: query_result.episode.into(),
+            show_id: query_result.show_id,
+            show_title: query_result.show_title,
+        })
+        .collect();
+    localize(&db, &mut shows, lang).await?;
+    localize(&db, &mut movies, lang).await?;
+    localize(&db, &mut episodes, lang).await?;
+    let show_ids: Vec<_> = episodes.iter().map(|e| e.show_id).collect();
+    let show_titles = localized_show_titles(&db, &show_ids, lang).await?;
+
     let mut by_metadata: HashMap<i64, ListContent> = HashMap::new();
     for show in shows {
-        by_metadata.insert(show.metadata.id.unwrap(), ListContent::Show(show.into()));
+        if let Some(metadata_id) = show.local_metadata_id() {
+            by_metadata.insert(metadata_id, ListContent::Show(show));
+        }
     }
     for movie in movies {
-        by_metadata.insert(movie.metadata.id.unwrap(), ListContent::Movie(movie.into()));
+        if let Some(metadata_id) = movie.local_metadata_id() {
+            by_metadata.insert(metadata_id, ListContent::Movie(movie));
+        }
     }
-    for query_result in episodes {
-        let metadata_id = query_result.episode.metadata.id.unwrap();
-        let show_id = query_result.show_id;
-        let show_title = query_result.show_title;
-        by_metadata.insert(
-            metadata_id,
-            ListContent::Episode(ListEpisode {
-                episode: query_result.episode.into(),
-                show_id,
-                show_title,
-            }),
-        );
+    for mut episode in episodes {
+        if let Some(title) = show_titles.get(&episode.show_id) {
+            episode.show_title = title.clone();
+        }
+        if let Some(metadata_id) = episode.local_metadata_id() {
+            by_metadata.insert(metadata_id, ListContent::Episode(episode));
+        }
     }
 
     let contents = ordered
@@ -0,0 +1,42 @@
+use axum::extract::State;
+
+use crate::{
+    AppError,
+    api::{Json, Path, Query, SpecialsQuery},
+    app_state::AppState,
+    library::missing_episodes::{self, ShowEpisodesReport},
+    metadata::ParentMediaType,
+};
+
+/// Get missing and upcoming episodes of the show, grouped by season
+#[utoipa::path(
+    get,
+    path = "/api/local_show/{id}/missing_episodes",
+    params(
+        ("id", description = "Show id"),
+        SpecialsQuery,
+    ),
+    responses(
+        (status = 200, description = "Missing and upcoming episodes", body = ShowEpisodesReport),
+        (status = 404, description = "Show is not found", body = AppError),
+    ),
+    tag = "Shows",
+)]
+pub async fn show_missing_episodes(
+    State(app_state): State<AppState>,
+    Path(id): Path<i64>,
+    Query(SpecialsQuery { include_specials }): Query<SpecialsQuery>,
+) -> crate::Result<Json<ShowEpisodesReport>> {
+    let fetch_params = app_state
+        .content_fetch_params(ParentMediaType::Show, id)
+        .await?;
+    let report = missing_episodes::show_report(
+        app_state.db,
+        app_state.providers_stack,
+        id,
+        fetch_params,
+        include_specials,
+    )
+    .await?;
+    Ok(Json(report))
+}
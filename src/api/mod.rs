@@ -26,17 +26,41 @@ use utoipa::OpenApi;
 ///
 /// This module defines the data types used by the API, as well as the methods required for their construction.
 pub mod api_data;
+/// Release calendar endpoints
+pub mod calendar;
+/// Movie collections endpoints
+pub mod collections;
+/// Parser and matcher diagnostics
+pub mod dry_run;
 pub mod file_browser;
+/// Media health check endpoints
+pub mod health;
 pub mod history;
+/// IMDb datasets endpoints
+pub mod imdb;
 pub mod intros;
+/// Library entities management
+pub mod libraries;
 /// Liked, watched, custom lists endpoints
 pub mod lists;
+/// Missing and upcoming episodes endpoints
+pub mod missing_episodes;
+/// Show episode numbering endpoints
+pub mod numbering;
+/// Metadata provider response cache endpoints
+pub mod provider_cache;
 /// Resources api endpoints
 pub mod resources;
+/// Scheduled background jobs endpoints
+pub mod scheduler;
 pub mod server;
+/// Library statistics endpoints
+pub mod statistics;
 pub mod subtitles;
 /// Torrent client specific endpoints
 pub mod torrent;
+/// Movie versions endpoints
+pub mod versions;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -50,9 +74,14 @@ pub mod torrent;
         server::fix_movie_metadata,
         server::fix_metadata,
         server::reset_show_metadata,
+        server::reset_season_metadata,
+        server::reset_episode_metadata,
         server::reset_movie_metadata,
         server::reset_metadata,
         server::alter_movie_metadata,
+        server::metadata_locks,
+        server::update_metadata_locks,
+        server::metadata_ratings,
         server::movie_poster,
         server::movie_backdrop,
         server::get_show,
@@ -76,6 +105,8 @@ pub mod torrent;
         server::watch,
         server::watch_episode,
         server::watch_movie,
+        versions::movie_versions_list,
+        versions::set_prime_version,
         server::remove_variant,
         server::search_torrent,
         server::search_content,
@@ -95,6 +126,8 @@ pub mod torrent;
         server::stop_watch_session,
         server::progress,
         server::reconciliate_lib,
+        server::export_nfo,
+        server::import_downloads,
         server::start_direct_stream,
         server::start_hls_stream,
         server::hls_manifest,
@@ -112,6 +145,7 @@ pub mod torrent;
         server::delete_show,
         server::delete_movie,
         server::actor_poster,
+        server::get_actor,
         server::actor_list,
         file_browser::browse_directory,
         file_browser::parent_directory,
@@ -144,6 +178,26 @@ pub mod torrent;
         subtitles::delete_subtitles,
         subtitles::get_subtitles,
         subtitles::reference_external_subtitles,
+        libraries::all_libraries,
+        libraries::get_library,
+        libraries::create_library,
+        libraries::update_library,
+        libraries::delete_library,
+        collections::all_collections,
+        collections::get_collection,
+        collections::collection_poster,
+        collections::collection_backdrop,
+        numbering::show_numbering,
+        numbering::update_show_numbering,
+        missing_episodes::show_missing_episodes,
+        calendar::calendar_entries,
+        calendar::calendar_feed,
+        calendar::refresh_calendar,
+        scheduler::all_jobs,
+        scheduler::update_job,
+        scheduler::run_job,
+        health::health_report,
+        health::start_health_check,
         lists::update_list,
         lists::delete_list,
         lists::create_list,
@@ -159,12 +213,20 @@ pub mod torrent;
         lists::remove_watchlist_item,
         lists::remove_saved_item,
         resources::resources,
+        statistics::library_statistics,
+        dry_run::parser_dry_run,
+        provider_cache::purge_provider_cache,
+        imdb::import_imdb_datasets,
+        imdb::imdb_imports,
+        imdb::imdb_rating,
         ws::ws,
     ),
     components(
         schemas(
             metadata::MovieMetadata,
             metadata::ShowMetadata,
+            metadata::MetadataLocks,
+            metadata::RatingMetadata,
             metadata::EpisodeMetadata,
             metadata::SeasonMetadata,
             metadata::MetadataProvider,
@@ -184,6 +246,53 @@ pub mod torrent;
             server::DetailedVariant,
             server::VideoContentMetadata,
             server::ProviderOrder,
+            versions::MovieVersion,
+            library::libraries::MediaLibrary,
+            library::libraries::LibrarySettings,
+            library::collections::Collection,
+            library::collections::CollectionEntry,
+            library::collections::CollectionDetails,
+            metadata::CollectionMetadata,
+            metadata::CollectionPart,
+            library::organizer::ImportMode,
+            library::numbering::EpisodeNumbering,
+            library::numbering::EpisodeMapping,
+            library::numbering::ShowNumbering,
+            metadata::AbsoluteEpisode,
+            metadata::EpisodeOrdering,
+            library::missing_episodes::SeasonEpisodesReport,
+            library::missing_episodes::ShowEpisodesReport,
+            library::calendar::CalendarEntry,
+            crate::scheduler::JobKind,
+            crate::scheduler::JobStatus,
+            crate::scheduler::JobSchedule,
+            crate::scheduler::ScheduledJob,
+            library::health::HealthIssueKind,
+            library::health::HealthIssue,
+            library::health::VideoHealthReport,
+            library::statistics::Bucket,
+            library::statistics::LibraryStats,
+            library::statistics::GenreCount,
+            library::statistics::ActorCount,
+            library::statistics::GrowthPoint,
+            library::statistics::LibraryStatistics,
+            crate::scan::dry_run::DryRunPayload,
+            crate::scan::dry_run::TokenInfo,
+            crate::scan::dry_run::ComponentTokens,
+            crate::scan::dry_run::LocalMatch,
+            crate::scan::dry_run::MatchCandidate,
+            crate::scan::dry_run::ProviderSearch,
+            crate::scan::dry_run::DryRunResult,
+            provider_cache::ProviderCachePurge,
+            imdb::ImdbImportPayload,
+            imdb::ImdbImport,
+            crate::metadata::imdb::ImdbRating,
+            crate::metadata::imdb::import::ImdbDataset,
+            crate::parser::show::ShowIdent,
+            server::ImportPayload,
+            library::versions::VersionLabel,
+            library::versions::VersionClient,
+            library::versions::VersionPreference,
             history::UpdateHistoryPayload,
             history::ShowSuggestion,
             history::MovieHistory,
@@ -240,6 +349,10 @@ pub mod torrent;
         (name = "Subtitles", description = "Subtitles operations"),
         (name = "Actors", description = "Actors operations"),
         (name = "Resources", description = "Server resources monitoring"),
+        (name = "Libraries", description = "Library folders and their settings"),
+        (name = "Collections", description = "Movie collections and franchises"),
+        (name = "Calendar", description = "Upcoming episodes and movie releases"),
+        (name = "Jobs", description = "Scheduled background jobs"),
     )
 )]
 pub struct OpenApiDoc;
@@ -306,6 +419,47 @@ impl metadata::ShowMetadataProvider for QueryShowProvider {
     }
 }
 
+/// Metadata language requested with the `lang` query parameter or the `Accept-Language` header.
+///
+/// `None` when the client doesn't request any of the supported languages
+pub struct ContentLanguage(pub Option<metadata::Language>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ContentLanguage {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(LanguageQuery { lang }) =
+            Query::<LanguageQuery>::from_request_parts(parts, state).await?;
+        if lang.is_some() {
+            return Ok(Self(lang));
+        }
+        let accept_language = parts
+            .headers
+            .get(axum::http::header::ACCEPT_LANGUAGE)
+            .and_then(|h| h.to_str().ok());
+        Ok(Self(accept_language.and_then(preferred_language)))
+    }
+}
+
+/// Most preferred supported language of the `Accept-Language` header value
+fn preferred_language(accept_language: &str) -> Option<metadata::Language> {
+    let mut languages: Vec<(f32, metadata::Language)> = accept_language
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.), |q| q.parse().ok())?;
+            let primary = tag.split('-').next()?.to_lowercase();
+            Some((quality, primary.parse().ok()?))
+        })
+        .filter(|(quality, _)| *quality > 0.)
+        .collect();
+    languages.sort_by(|a, b| b.0.total_cmp(&a.0));
+    languages.first().map(|(_, lang)| *lang)
+}
+
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct PageQuery {
     pub page: Option<usize>,
@@ -383,6 +537,20 @@ impl<'de> Deserialize<'de> for CursorQuery {
     }
 }
 
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct SpecialsQuery {
+    /// Include specials (season 0). Defaults to `false`.
+    #[serde(default)]
+    pub include_specials: bool,
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct HealthCheckQuery {
+    /// Decode every video in addition to metadata checks. Slow, defaults to `false`.
+    #[serde(default)]
+    pub decode: bool,
+}
+
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct UuidQuery {
     pub id: uuid::Uuid,
@@ -438,6 +606,13 @@ pub struct VariantQuery {
     pub variant: Option<String>,
 }
 
+#[derive(Deserialize, utoipa::IntoParams)]
+pub struct VersionClientQuery {
+    /// Kind of the client, used to pick the default movie version
+    #[param(inline)]
+    pub client: Option<library::versions::VersionClient>,
+}
+
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct StringIdQuery {
     pub id: String,
@@ -460,7 +635,9 @@ pub struct NumberQuery {
 
 #[derive(Deserialize, utoipa::IntoParams)]
 pub struct LanguageQuery {
-    pub lang: Option<String>,
+    /// Metadata language, takes precedence over the `Accept-Language` header
+    #[param(inline)]
+    pub lang: Option<metadata::Language>,
 }
 
 #[derive(Deserialize, utoipa::IntoParams)]
@@ -591,3 +768,24 @@ where
         axum::Json(self.0).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::Language;
+
+    use super::preferred_language;
+
+    #[test]
+    fn accept_language() {
+        assert_eq!(
+            preferred_language("ru-RU,ru;q=0.9,en;q=0.8"),
+            Some(Language::Ru)
+        );
+        assert_eq!(
+            preferred_language("zh-CN, en;q=0.5, de;q=0.7"),
+            Some(Language::De)
+        );
+        assert_eq!(preferred_language("fr;q=0, ja"), Some(Language::Ja));
+        assert_eq!(preferred_language("*"), None);
+    }
+}
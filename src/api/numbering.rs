@@ -0,0 +1,67 @@
+use axum::extract::State;
+
+use crate::{
+    AppError,
+    api::{Json, Path},
+    app_state::AppState,
+    db::Db,
+    library::numbering::{self, ShowNumbering},
+};
+
+/// Get show episode numbering and manual absolute episode mappings
+#[utoipa::path(
+    get,
+    path = "/api/local_show/{id}/numbering",
+    params(
+        ("id", description = "Show id"),
+    ),
+    responses(
+        (status = 200, description = "Show episode numbering", body = ShowNumbering),
+        (status = 404, description = "Show is not found", body = AppError),
+    ),
+    tag = "Shows",
+)]
+pub async fn show_numbering(
+    State(db): State<Db>,
+    Path(id): Path<i64>,
+) -> crate::Result<Json<ShowNumbering>> {
+    Ok(Json(numbering::get(&db, id).await?))
+}
+
+/// Update show episode numbering and replace manual absolute episode mappings.
+///
+/// Numbering and mappings apply to videos that are scanned after the change.
+/// Changed ordering re-fetches metadata of the existing seasons and episodes in the background,
+/// new ordering is saved once its metadata is fetched
+#[utoipa::path(
+    put,
+    path = "/api/local_show/{id}/numbering",
+    params(
+        ("id", description = "Show id"),
+    ),
+    request_body = ShowNumbering,
+    responses(
+        (status = 200, description = "Show episode numbering is updated"),
+        (status = 400, description = "Show metadata is already being refreshed", body = AppError),
+        (status = 404, description = "Show is not found", body = AppError),
+    ),
+    tag = "Shows",
+)]
+pub async fn update_show_numbering(
+    State(app_state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<ShowNumbering>,
+) -> crate::Result<()> {
+    let db = app_state.db;
+    let previous = numbering::get(db, id).await?;
+    let ordering = payload.ordering;
+    let numbering = ShowNumbering {
+        ordering: previous.ordering,
+        ..payload
+    };
+    numbering::update(db, id, &numbering).await?;
+    if previous.ordering != ordering {
+        app_state.reorder_show(id, ordering).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    api::{Json, Query},
+    app_state::AppState,
+    db::Db,
+    metadata::{MetadataProvider, response_cache::ResponseCache},
+};
+
+#[derive(Debug, Deserialize, utoipa::IntoParams)]
+pub struct PurgeQuery {
+    /// Purge responses of this provider only
+    #[param(inline)]
+    pub provider: Option<MetadataProvider>,
+    /// Keep responses that are still served from the cache
+    #[serde(default)]
+    pub expired_only: bool,
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ProviderCachePurge {
+    /// Amount of removed responses
+    pub removed: u64,
+}
+
+/// Remove cached metadata provider responses
+#[utoipa::path(
+    delete,
+    path = "/api/provider_cache",
+    params(PurgeQuery),
+    responses(
+        (status = 200, body = ProviderCachePurge),
+    ),
+    tag = "Metadata",
+)]
+async fn purge_provider_cache(
+    State(db): State<Db>,
+    Query(query): Query<PurgeQuery>,
+) -> crate::Result<Json<ProviderCachePurge>> {
+    let removed = ResponseCache::new(db)
+        .purge(query.provider, query.expired_only)
+        .await?;
+    Ok(Json(ProviderCachePurge { removed }))
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::delete;
+
+    axum::Router::new().route("/", delete(purge_provider_cache))
+}
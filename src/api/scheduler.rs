@@ -0,0 +1,77 @@
+use axum::{extract::State, http::StatusCode};
+use time::OffsetDateTime;
+
+use crate::{
+    AppError,
+    api::{Json, Path},
+    app_state::AppState,
+    db::Db,
+    scheduler::{self, JobKind, JobSchedule, ScheduledJob},
+};
+
+/// Get all scheduled jobs with their last run status
+#[utoipa::path(
+    get,
+    path = "/api/jobs",
+    responses(
+        (status = 200, description = "Scheduled jobs", body = Vec<ScheduledJob>),
+    ),
+    tag = "Jobs",
+)]
+async fn all_jobs(State(db): State<Db>) -> crate::Result<Json<Vec<ScheduledJob>>> {
+    Ok(Json(scheduler::all(&db, OffsetDateTime::now_utc()).await?))
+}
+
+/// Update job schedule
+#[utoipa::path(
+    put,
+    path = "/api/jobs/{kind}",
+    params(
+        ("kind" = JobKind, Path, description = "Job kind"),
+    ),
+    request_body = JobSchedule,
+    responses(
+        (status = 200, description = "Job schedule is updated"),
+        (status = 400, description = "Schedule is invalid", body = AppError),
+    ),
+    tag = "Jobs",
+)]
+async fn update_job(
+    State(db): State<Db>,
+    Path(kind): Path<JobKind>,
+    Json(schedule): Json<JobSchedule>,
+) -> crate::Result<()> {
+    schedule.validate()?;
+    scheduler::update_schedule(&db, kind, &schedule).await?;
+    Ok(())
+}
+
+/// Run the job now regardless of its schedule
+#[utoipa::path(
+    post,
+    path = "/api/jobs/{kind}/run",
+    params(
+        ("kind" = JobKind, Path, description = "Job kind"),
+    ),
+    responses(
+        (status = 202, description = "Job is started"),
+        (status = 400, description = "Job is already running", body = AppError),
+    ),
+    tag = "Jobs",
+)]
+async fn run_job(
+    State(app_state): State<AppState>,
+    Path(kind): Path<JobKind>,
+) -> crate::Result<StatusCode> {
+    scheduler::spawn_job(&app_state, kind)?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::{get, post, put};
+
+    axum::Router::new()
+        .route("/", get(all_jobs))
+        .route("/{kind}", put(update_job))
+        .route("/{kind}/run", post(run_job))
+}
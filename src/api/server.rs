@@ -22,11 +22,15 @@ use tokio_stream::{Stream, StreamExt};
 use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 
-use super::{ContentTypeQuery, OptionalContentTypeQuery, ProviderQuery, StringIdQuery};
-use super::{IdQuery, SearchQuery, VariantQuery};
+use super::{
+    ContentLanguage, ContentTypeQuery, OptionalContentTypeQuery, ProviderQuery, StringIdQuery,
+};
+use super::{IdQuery, SearchQuery, VariantQuery, VersionClientQuery};
+use super::{LanguageQuery, api_data::localization::localize};
 use crate::AppError;
 use crate::api::api_data::LocalDataLookup;
 use crate::api::api_data::api_types::Actor;
+use crate::api::api_data::local_actor::{self, Person};
 use crate::api::api_data::local_movie::Movie;
 use crate::api::api_data::local_show::{Episode, Season, Show};
 use crate::api::{
@@ -50,10 +54,12 @@ use crate::library::media::codec::audio::AudioCodec;
 use crate::library::media::codec::subtitles::SubtitlesCodec;
 use crate::library::media::codec::video::VideoCodec;
 use crate::library::media::container::VideoContainer;
+use crate::library::nfo_export::{ExportTarget, NfoExportTask, NfoExporter};
+use crate::library::organizer;
 use crate::library::{ContentIdentifier, Source, TranscodePayload};
 use crate::metadata::{
-    EpisodeMetadata, MovieMetadata, ParentMediaType, SeasonMetadata, ShowMetadata,
-    metadata_stack::MetadataProvidersStack,
+    EpisodeMetadata, MetadataLocks, MovieMetadata, ParentMediaType, RatingMetadata, SeasonMetadata,
+    ShowMetadata, metadata_stack::MetadataProvidersStack,
 };
 use crate::metadata::{ExternalIdMetadata, MetadataProvider, MetadataSearchResult};
 use crate::progress::{ProgressDispatcher, Task, TaskError, TaskResource};
@@ -549,6 +555,7 @@ pub async fn watch_episode(
     params(
         ("movie_id", description = "movie id"),
         VariantQuery,
+        VersionClientQuery,
     ),
     responses(
         (status = 206, description = "Movie video progressive download stream", content_type = "video/*"),
@@ -560,16 +567,12 @@ pub async fn watch_episode(
 pub async fn watch_movie(
     Path(movie_id): Path<i64>,
     variant: Query<VariantQuery>,
+    Query(VersionClientQuery { client }): Query<VersionClientQuery>,
     State(state): State<AppState>,
     range: Option<TypedHeader<Range>>,
 ) -> crate::Result<impl IntoResponse> {
-    let video_id = sqlx::query!(
-        "SELECT videos.id FROM videos JOIN movies ON movies.metadata_id = videos.metadata_id WHERE movies.id = ?;",
-        movie_id
-    )
-    .fetch_one(&state.db.pool)
-    .await?
-    .id;
+    let video_id =
+        super::versions::default_movie_video(&state, movie_id, client.unwrap_or_default()).await?;
     watch(Path(video_id), variant, State(state), range).await
 }
 
@@ -581,15 +584,18 @@ pub async fn watch_movie(
     ),
     params(
         ContentFilterQuery,
+        LanguageQuery,
     ),
     tag = "Shows",
 )]
 /// All local shows
 pub async fn all_local_shows(
     Query(filter): Query<ContentFilterQuery>,
+    ContentLanguage(lang): ContentLanguage,
     State(db): State<Db>,
 ) -> crate::Result<Json<CursoredResponse<Show>>> {
-    let shows = db.all_shows(filter.into()).await?;
+    let mut shows = db.all_shows(filter.into()).await?;
+    localize(&db, &mut shows, lang).await?;
 
     let cursor = shows.last().and_then(|s| s.local.as_ref()).map(|l| l.id);
 
@@ -601,6 +607,7 @@ pub async fn all_local_shows(
     path = "/api/local_episode/{id}",
     params(
         ("id", description = "Local id"),
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Local episode", body = EpisodeMetadata),
@@ -611,9 +618,12 @@ pub async fn all_local_shows(
 /// Local episode metadata by local episode id
 pub async fn local_episode(
     Path(id): Path<i64>,
+    ContentLanguage(lang): ContentLanguage,
     State(db): State<Db>,
 ) -> crate::Result<Json<Episode>> {
-    Ok(Json(db.get_episode_by_id(id).await?))
+    let mut episode = db.get_episode_by_id(id).await?;
+    localize(&db, std::slice::from_mut(&mut episode), lang).await?;
+    Ok(Json(episode))
 }
 
 #[utoipa::path(
@@ -624,6 +634,7 @@ pub async fn local_episode(
     ),
     params(
         ContentFilterQuery,
+        LanguageQuery,
     ),
     tag = "Movies",
 )]
@@ -631,8 +642,10 @@ pub async fn local_episode(
 pub async fn all_local_movies(
     State(db): State<Db>,
     Query(filter): Query<ContentFilterQuery>,
+    ContentLanguage(lang): ContentLanguage,
 ) -> crate::Result<Json<CursoredResponse<Movie>>> {
-    let movies = db.all_movies(filter.into()).await?;
+    let mut movies = db.all_movies(filter.into()).await?;
+    localize(&db, &mut movies, lang).await?;
     let cursor = movies.last().and_then(|s| s.local.as_ref()).map(|l| l.id);
 
     Ok(Json(CursoredResponse::new(movies, cursor)))
@@ -789,6 +802,7 @@ pub async fn get_video_by_id(
     params(
         ("id", description = "Show id"),
         ProviderQuery,
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Requested show", body = Show),
@@ -800,12 +814,15 @@ pub async fn get_show(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
     Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    ContentLanguage(lang): ContentLanguage,
     Path(id): Path<String>,
 ) -> crate::Result<Json<Show>> {
     let res = if provider.is_local() {
-        db.get_show(id.parse()?).await?
+        let mut show = db.get_show(id.parse()?).await?;
+        localize(&db, std::slice::from_mut(&mut show), lang).await?;
+        show
     } else {
-        let meta = providers.get_show(&id, provider).await?;
+        let meta = providers.get_show(&id, provider, lang).await?;
         Show::extend_with_lookup(meta, LocalDataLookup::new(db)).await?
     };
     Ok(Json(res))
@@ -818,6 +835,7 @@ pub async fn get_show(
     params(
         ("id", description = "Movie id"),
         ProviderQuery,
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Requested movie", body = Movie),
@@ -829,12 +847,15 @@ pub async fn get_movie(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
     Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    ContentLanguage(lang): ContentLanguage,
     Path(id): Path<String>,
 ) -> crate::Result<Json<Movie>> {
     let movie = if provider.is_local() {
-        db.get_movie(id.parse()?).await?
+        let mut movie = db.get_movie(id.parse()?).await?;
+        localize(&db, std::slice::from_mut(&mut movie), lang).await?;
+        movie
     } else {
-        let res = providers.get_movie(&id, provider).await?;
+        let res = providers.get_movie(&id, provider, lang).await?;
         Movie::extend_with_lookup(res, LocalDataLookup::new(db)).await?
     };
     Ok(Json(movie))
@@ -1015,6 +1036,89 @@ pub async fn actor_poster(
     Ok(response)
 }
 
+/// Get actor or crew member details.
+///
+/// Details are fetched from the provider of the person, falling back to IMDb when the provider
+/// doesn't have person details. Local filmography is returned even when the provider is not available
+#[utoipa::path(
+    get,
+    path = "/api/actor/{id}",
+    params(
+        ("id", description = "Actor id"),
+        LanguageQuery,
+    ),
+    responses(
+        (status = 200, description = "Person details", body = Person),
+        (status = 404, body = AppError)
+    ),
+    tag = "Actors",
+)]
+pub async fn get_actor(
+    State(providers): State<&'static MetadataProvidersStack>,
+    State(db): State<Db>,
+    ContentLanguage(lang): ContentLanguage,
+    Path(id): Path<i64>,
+) -> crate::Result<Json<Person>> {
+    let mut builder = db::DbQueryBuilder::default();
+    DbActorsQuery::build(&mut builder);
+    let DbActorsQuery { actor } = builder
+        .push(" where actors.id = ")
+        .push_bind(id)
+        .build_query_as::<DbActorsQuery>()
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or(AppError::not_found("Actor is not found"))?;
+
+    let mut filmography = local_actor::filmography(&db, id).await?;
+    localize(&db, &mut filmography, lang).await?;
+
+    let lookup = match providers.person_provider(actor.external_metadata_provider) {
+        Some(_) => Some((
+            actor.external_metadata_id.as_str(),
+            actor.external_metadata_provider,
+        )),
+        None => actor
+            .imdb_id
+            .as_deref()
+            .map(|imdb_id| (imdb_id, MetadataProvider::Imdb)),
+    };
+    let details = match lookup {
+        Some((person_id, provider)) => providers
+            .get_person(person_id, provider, lang)
+            .await
+            .inspect_err(|e| tracing::warn!("Failed to fetch {provider} person details: {e}"))
+            .ok(),
+        None => None,
+    };
+
+    let mut person = Person {
+        id,
+        name: actor.name,
+        poster: actor.poster,
+        metadata_id: actor.external_metadata_id,
+        metadata_provider: actor.external_metadata_provider,
+        imdb_id: actor.imdb_id,
+        biography: None,
+        birthday: None,
+        deathday: None,
+        place_of_birth: None,
+        known_for_department: None,
+        filmography: Vec::new(),
+        other_works: Vec::new(),
+    };
+    if let Some(details) = details {
+        person.imdb_id = person.imdb_id.or(details.imdb_id);
+        person.biography = details.biography;
+        person.birthday = details.birthday;
+        person.deathday = details.deathday;
+        person.place_of_birth = details.place_of_birth;
+        person.known_for_department = details.known_for_department;
+        person.other_works = local_actor::not_in_library(&db, details.credits).await?;
+    }
+    person.filmography = filmography;
+    Ok(Json(person))
+}
+
 /// Get actor list
 #[utoipa::path(
     get,
@@ -1073,6 +1177,7 @@ pub async fn actor_list(
         ("id", description = "Show id"),
         ("season", description = "Season number"),
         ProviderQuery,
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Desired season metadata", body = Season),
@@ -1084,12 +1189,18 @@ pub async fn get_season(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
     Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    ContentLanguage(lang): ContentLanguage,
     Path((show_id, season)): Path<(String, usize)>,
 ) -> crate::Result<Json<Season>> {
     let res = if provider.is_local() {
-        db.get_season(show_id.parse()?, season).await?
+        let mut season = db.get_season(show_id.parse()?, season).await?;
+        localize(&db, std::slice::from_mut(&mut season), lang).await?;
+        localize(&db, &mut season.episodes, lang).await?;
+        season
     } else {
-        let season = providers.get_season(&show_id, season, provider).await?;
+        let season = providers
+            .get_season(&show_id, season, provider, lang)
+            .await?;
         Season::extend_from_metadata(season, LocalDataLookup::new(db)).await?
     };
     Ok(Json(res))
@@ -1104,6 +1215,7 @@ pub async fn get_season(
         ("season", description = "Season number"),
         ("episode", description = "Episode number"),
         ProviderQuery,
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Desired episode metadata", body = Episode),
@@ -1115,13 +1227,16 @@ pub async fn get_episode(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
     Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    ContentLanguage(lang): ContentLanguage,
     Path((show_id, season, episode)): Path<(String, usize, usize)>,
 ) -> crate::Result<Json<Episode>> {
     let res = if provider.is_local() {
-        db.get_episode(show_id.parse()?, season, episode).await?
+        let mut episode = db.get_episode(show_id.parse()?, season, episode).await?;
+        localize(&db, std::slice::from_mut(&mut episode), lang).await?;
+        episode
     } else {
         let metadata = providers
-            .get_episode(&show_id, season, episode, provider)
+            .get_episode(&show_id, season, episode, provider, lang)
             .await?;
         Episode::extend_from_metadata(metadata, LocalDataLookup::new(db)).await?
     };
@@ -1157,7 +1272,7 @@ pub async fn search_torrent(
         match provider {
             Some(p) => {
                 let lang: config::MetadataLanguage = config::CONFIG.get_value();
-                let fetch_params = crate::metadata::FetchParams { lang: lang.0 };
+                let fetch_params = crate::metadata::FetchParams::new(lang.0);
                 let provider = providers
                     .torrent_index(p)
                     .ok_or(AppError::not_found("Provider is not found"))?;
@@ -1190,6 +1305,9 @@ pub async fn search_torrent(
 #[utoipa::path(
     get,
     path = "/api/search/trending_shows",
+    params(
+        LanguageQuery,
+    ),
     responses(
         (status = 200, description = "List of trending shows", body = Vec<Show>),
     ),
@@ -1198,12 +1316,13 @@ pub async fn search_torrent(
 pub async fn get_trending_shows(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
+    ContentLanguage(lang): ContentLanguage,
 ) -> crate::Result<Json<Vec<Show>>> {
-    let language: config::MetadataLanguage = config::CONFIG.get_value();
+    let lang = lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0);
     let tmdb_api = providers
         .tmdb
         .ok_or(AppError::bad_request("tmdb provider is not available"))?;
-    let trending_shows = tmdb_api.trending_shows(language.0).await?;
+    let trending_shows = tmdb_api.trending_shows(lang).await?;
     let lookup = LocalDataLookup::new(db);
     let res = lookup
         .extend_shows_with_local_data(trending_shows.results.into_iter().map(Into::into).collect())
@@ -1215,6 +1334,9 @@ pub async fn get_trending_shows(
 #[utoipa::path(
     get,
     path = "/api/search/trending_movies",
+    params(
+        LanguageQuery,
+    ),
     responses(
         (status = 200, description = "List of trending shows", body = Vec<Movie>),
     ),
@@ -1223,12 +1345,13 @@ pub async fn get_trending_shows(
 pub async fn get_trending_movies(
     State(providers): State<&'static MetadataProvidersStack>,
     State(db): State<Db>,
+    ContentLanguage(lang): ContentLanguage,
 ) -> crate::Result<Json<Vec<Movie>>> {
-    let language: config::MetadataLanguage = config::CONFIG.get_value();
+    let lang = lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0);
     let tmdb_api = providers
         .tmdb
         .ok_or(AppError::bad_request("tmdb provider is not available"))?;
-    let res = tmdb_api.trending_movies(language.0).await?;
+    let res = tmdb_api.trending_movies(lang).await?;
     let lookup = LocalDataLookup::new(db);
     let res = lookup
         .extend_movies_with_local_data(res.results.into_iter().map(Into::into).collect())
@@ -1242,6 +1365,7 @@ pub async fn get_trending_movies(
     path = "/api/search/content",
     params(
         SearchQuery,
+        LanguageQuery,
     ),
     responses(
         (status = 200, description = "Content search results", body = Vec<MetadataSearchResult>),
@@ -1251,12 +1375,13 @@ pub async fn get_trending_movies(
 #[tracing::instrument(skip_all, fields(query = %query.search))]
 pub async fn search_content(
     Query(query): Query<SearchQuery>,
+    ContentLanguage(lang): ContentLanguage,
     State(providers): State<&'static MetadataProvidersStack>,
 ) -> crate::Result<Json<Vec<MetadataSearchResult>>> {
     if query.search.is_empty() {
         return Ok(Json(Vec::new()));
     }
-    let res = providers.multi_search(&query.search).await?;
+    let res = providers.multi_search(&query.search, lang).await?;
     Ok(Json(res))
 }
 
@@ -1306,6 +1431,75 @@ pub async fn reconciliate_lib(State(app_state): State<AppState>) -> crate::Resul
     Ok(StatusCode::ACCEPTED)
 }
 
+/// Write library metadata and artwork next to the media files
+#[utoipa::path(
+    post,
+    path = "/api/export_nfo",
+    responses(
+        (status = 202, description = "Export is successfully started"),
+        (status = 400, body = AppError, description = "Export is already in progress or disabled"),
+    ),
+    tag = "Metadata",
+)]
+pub async fn export_nfo(State(app_state): State<AppState>) -> crate::Result<StatusCode> {
+    let tasks = app_state.tasks;
+    let exporter = NfoExporter::from_libraries(app_state.db.clone())
+        .await
+        .ok_or(AppError::bad_request(
+            "Nfo export is not enabled for any library",
+        ))?;
+    let folders = exporter.folders().to_vec();
+    let task_id = tasks
+        .nfo_export_tasks
+        .start_task(NfoExportTask { folders }, None)?;
+    tokio::spawn(async move {
+        let progress = ProgressDispatcher::new(&tasks.nfo_export_tasks, task_id);
+        match exporter.export_all(&progress).await {
+            Ok(_) => progress.finish(),
+            Err(err) => {
+                tracing::error!("Nfo export task failed: {err}");
+                progress.error(TaskError::Failure);
+            }
+        };
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[derive(Debug, Deserialize, utoipa::ToSchema)]
+pub struct ImportPayload {
+    /// Folder or file to import
+    #[schema(value_type = String)]
+    pub path: PathBuf,
+}
+
+/// Import downloaded files into the libraries
+///
+/// Files are identified, renamed according to naming templates and placed into the matching library
+#[utoipa::path(
+    post,
+    path = "/api/import",
+    request_body = ImportPayload,
+    responses(
+        (status = 202, description = "Import is successfully started"),
+        (status = 400, body = AppError, description = "Import path does not exist"),
+    ),
+    tag = "Videos",
+)]
+pub async fn import_downloads(
+    State(app_state): State<AppState>,
+    Json(payload): Json<ImportPayload>,
+) -> crate::Result<StatusCode> {
+    if !tokio::fs::try_exists(&payload.path).await.unwrap_or(false) {
+        return Err(AppError::bad_request("Import path does not exist"));
+    }
+    tokio::spawn(async move {
+        if let Err(err) = organizer::import_folder(&app_state, payload.path).await {
+            tracing::error!("Import task failed: {err}");
+        }
+    });
+    Ok(StatusCode::ACCEPTED)
+}
+
 #[derive(Debug, utoipa::ToSchema)]
 pub struct CursoredResponse<T> {
     data: Vec<T>,
@@ -1451,7 +1645,42 @@ pub async fn remove_variant(
     Ok(())
 }
 
+/// Apply manual title and plot edit, fields that are changed get locked
+async fn edit_metadata(
+    db: &Db,
+    metadata_id: Option<i64>,
+    title: Option<&str>,
+    plot: Option<&str>,
+) -> crate::Result<()> {
+    let Some(metadata_id) = metadata_id else {
+        return Err(AppError::not_found("Content is not found"));
+    };
+    let mut tx = db.begin().await?;
+    let current = sqlx::query!("SELECT title, plot FROM metadata WHERE id = ?", metadata_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let title = title.unwrap_or(&current.title);
+    sqlx::query!(
+        "UPDATE metadata SET title = ?, plot = ? WHERE id = ?;",
+        title,
+        plot,
+        metadata_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    let locks = MetadataLocks {
+        title: title != current.title,
+        plot: plot != current.plot.as_deref(),
+        ..Default::default()
+    };
+    tx.add_metadata_locks(metadata_id, locks).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
 /// Update show metadata
+///
+/// Edited fields are locked and kept during metadata refreshes
 #[utoipa::path(
     put,
     path = "/api/show/{id}",
@@ -1470,18 +1699,25 @@ pub async fn alter_show_metadata(
     Path(show_id): Path<i64>,
     Json(metadata): Json<ShowMetadata>,
 ) -> crate::Result<()> {
-    sqlx::query!(
-        "UPDATE metadata SET title = ?, plot = ? WHERE id = (SELECT metadata_id FROM shows WHERE id = ?);",
-        metadata.title,
-        metadata.plot,
-        show_id
+    let metadata_id = sqlx::query_scalar!("SELECT metadata_id FROM shows WHERE id = ?", show_id)
+        .fetch_optional(&db.pool)
+        .await?;
+    edit_metadata(
+        &db,
+        metadata_id,
+        Some(&metadata.title),
+        metadata.plot.as_deref(),
     )
-    .execute(&db.pool)
     .await?;
+    if let Some(exporter) = NfoExporter::from_libraries(db).await {
+        exporter.spawn(ExportTarget::Show(show_id));
+    }
     Ok(())
 }
 
 /// Update season metadata
+///
+/// Edited fields are locked and kept during metadata refreshes
 #[utoipa::path(
     put,
     path = "/api/show/{id}/{season}",
@@ -1501,18 +1737,23 @@ pub async fn alter_season_metadata(
     Path((show_id, season)): Path<(i64, i64)>,
     Json(metadata): Json<SeasonMetadata>,
 ) -> crate::Result<()> {
-    sqlx::query!(
-        "UPDATE metadata SET plot = ? WHERE id = (SELECT metadata_id FROM seasons WHERE show_id = ? AND number = ?);",
-        metadata.plot,
+    let metadata_id = sqlx::query_scalar!(
+        "SELECT metadata_id FROM seasons WHERE show_id = ? AND number = ?",
         show_id,
         season
     )
-    .execute(&db.pool)
+    .fetch_optional(&db.pool)
     .await?;
+    edit_metadata(&db, metadata_id, None, metadata.plot.as_deref()).await?;
+    if let Some(exporter) = NfoExporter::from_libraries(db).await {
+        exporter.spawn(ExportTarget::Season { show_id, season });
+    }
     Ok(())
 }
 
 /// Update episode metadata
+///
+/// Edited fields are locked and kept during metadata refreshes
 #[utoipa::path(
     put,
     path = "/api/show/{id}/{season}/{episode}",
@@ -1533,25 +1774,34 @@ pub async fn alter_episode_metadata(
     Path((show_id, season, episode)): Path<(i64, i64, i64)>,
     Json(metadata): Json<EpisodeMetadata>,
 ) -> crate::Result<()> {
-    sqlx::query!(
-        r#"UPDATE metadata SET title = ?, plot = ?
-        WHERE id = (
-            SELECT episodes.metadata_id FROM episodes
-            JOIN seasons ON seasons.id = episodes.season_id
-            WHERE seasons.show_id = ? AND seasons.number = ? AND episodes.number = ?
-        );"#,
-        metadata.title,
-        metadata.plot,
+    let episode = sqlx::query!(
+        r#"SELECT episodes.id, episodes.metadata_id FROM episodes
+        JOIN seasons ON seasons.id = episodes.season_id
+        WHERE seasons.show_id = ? AND seasons.number = ? AND episodes.number = ?"#,
         show_id,
         season,
         episode
     )
-    .execute(&db.pool)
+    .fetch_optional(&db.pool)
+    .await?;
+    edit_metadata(
+        &db,
+        episode.as_ref().map(|e| e.metadata_id),
+        Some(&metadata.title),
+        metadata.plot.as_deref(),
+    )
     .await?;
+    if let (Some(exporter), Some(episode)) =
+        (NfoExporter::from_libraries(db.clone()).await, episode)
+    {
+        exporter.spawn(ExportTarget::Episode(episode.id));
+    }
     Ok(())
 }
 
 /// Update movie metadata
+///
+/// Edited fields are locked and kept during metadata refreshes
 #[utoipa::path(
     put,
     path = "/api/movie/{id}",
@@ -1570,17 +1820,92 @@ pub async fn alter_movie_metadata(
     Path(id): Path<i64>,
     Json(metadata): Json<MovieMetadata>,
 ) -> crate::Result<()> {
-    sqlx::query!(
-        "UPDATE metadata SET title = ?, plot = ? WHERE id = (SELECT metadata_id FROM movies WHERE id = ?);",
-        metadata.title,
-        metadata.plot,
-        id
+    let metadata_id = sqlx::query_scalar!("SELECT metadata_id FROM movies WHERE id = ?", id)
+        .fetch_optional(&db.pool)
+        .await?;
+    edit_metadata(
+        &db,
+        metadata_id,
+        Some(&metadata.title),
+        metadata.plot.as_deref(),
     )
-    .execute(&db.pool)
     .await?;
+    if let Some(exporter) = NfoExporter::from_libraries(db).await {
+        exporter.spawn(ExportTarget::Movie(id));
+    }
     Ok(())
 }
 
+/// Get metadata fields that are kept during refreshes
+#[utoipa::path(
+    get,
+    path = "/api/metadata/{id}/locks",
+    params(
+        ("id", description = "Metadata id"),
+    ),
+    responses(
+        (status = 200, description = "Metadata locks", body = MetadataLocks),
+        (status = 404, description = "Metadata is not found", body = AppError),
+    ),
+    tag = "Metadata",
+)]
+pub async fn metadata_locks(
+    State(db): State<Db>,
+    Path(metadata_id): Path<i64>,
+) -> crate::Result<Json<MetadataLocks>> {
+    sqlx::query_scalar!("SELECT id FROM metadata WHERE id = ?", metadata_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or(AppError::not_found("Metadata is not found"))?;
+    Ok(Json(db.get_metadata_locks(metadata_id).await?))
+}
+
+/// Set metadata fields that are kept during refreshes
+#[utoipa::path(
+    put,
+    path = "/api/metadata/{id}/locks",
+    params(
+        ("id", description = "Metadata id"),
+    ),
+    request_body = MetadataLocks,
+    responses(
+        (status = 200, description = "Updated metadata locks"),
+        (status = 404, description = "Metadata is not found", body = AppError),
+    ),
+    tag = "Metadata",
+)]
+pub async fn update_metadata_locks(
+    State(db): State<Db>,
+    Path(metadata_id): Path<i64>,
+    Json(locks): Json<MetadataLocks>,
+) -> crate::Result<()> {
+    sqlx::query_scalar!("SELECT id FROM metadata WHERE id = ?", metadata_id)
+        .fetch_optional(&db.pool)
+        .await?
+        .ok_or(AppError::not_found("Metadata is not found"))?;
+    db.set_metadata_locks(metadata_id, locks).await?;
+    Ok(())
+}
+
+/// Get ratings that were imported with the metadata
+#[utoipa::path(
+    get,
+    path = "/api/metadata/{id}/ratings",
+    params(
+        ("id", description = "Metadata id"),
+    ),
+    responses(
+        (status = 200, description = "Metadata ratings", body = Vec<RatingMetadata>),
+    ),
+    tag = "Metadata",
+)]
+pub async fn metadata_ratings(
+    State(db): State<Db>,
+    Path(metadata_id): Path<i64>,
+) -> crate::Result<Json<Vec<RatingMetadata>>> {
+    Ok(Json(db.get_content_ratings(metadata_id).await?))
+}
+
 /// Fix show metadata match
 #[utoipa::path(
     post,
@@ -1593,11 +1918,17 @@ pub async fn alter_movie_metadata(
     responses(
         (status = 200, description = "Fixed show metadata"),
         (status = 404, description = "Show is not found", body = AppError),
+        (status = 409, description = "Requested show is already in the library", body = AppError),
     ),
     tag = "Shows",
 )]
-pub async fn fix_show_metadata() -> crate::Result<()> {
-    unimplemented!("Fixing show metadata is unimplemented");
+pub async fn fix_show_metadata(
+    State(app_state): State<AppState>,
+    Path(show_id): Path<i64>,
+    Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    Query(StringIdQuery { id }): Query<StringIdQuery>,
+) -> crate::Result<()> {
+    app_state.fix_show_metadata(show_id, provider, &id).await
 }
 
 /// Fix movie metadata match
@@ -1612,11 +1943,17 @@ pub async fn fix_show_metadata() -> crate::Result<()> {
     responses(
         (status = 200, description = "Fixed movie metadata"),
         (status = 404, description = "Movie is not found", body = AppError),
+        (status = 409, description = "Requested movie is already in the library", body = AppError),
     ),
     tag = "Movies",
 )]
-pub async fn fix_movie_metadata() -> crate::Result<()> {
-    unimplemented!("Fixing movie metadata is not implemented")
+pub async fn fix_movie_metadata(
+    State(app_state): State<AppState>,
+    Path(movie_id): Path<i64>,
+    Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    Query(StringIdQuery { id }): Query<StringIdQuery>,
+) -> crate::Result<()> {
+    app_state.fix_movie_metadata(movie_id, provider, &id).await
 }
 
 /// Fix metadata match
@@ -1632,62 +1969,183 @@ pub async fn fix_movie_metadata() -> crate::Result<()> {
     responses(
         (status = 200, description = "Fixed metadata match"),
         (status = 404, description = "Content is not found", body = AppError),
+        (status = 409, description = "Requested content is already in the library", body = AppError),
     ),
     tag = "Metadata",
 )]
-pub async fn fix_metadata() -> crate::Result<()> {
-    unimplemented!("fixing metadata is not yet implemented");
+pub async fn fix_metadata(
+    State(app_state): State<AppState>,
+    Path(metadata_id): Path<i64>,
+    Query(ProviderQuery { provider }): Query<ProviderQuery>,
+    Query(StringIdQuery { id }): Query<StringIdQuery>,
+    Query(ContentTypeQuery { content_type }): Query<ContentTypeQuery>,
+) -> crate::Result<()> {
+    match content_type {
+        ParentMediaType::Movie => {
+            let movie_id =
+                sqlx::query_scalar!("SELECT id FROM movies WHERE metadata_id = ?", metadata_id)
+                    .fetch_optional(&app_state.db.pool)
+                    .await?
+                    .ok_or(AppError::not_found("Movie is not found"))?;
+            app_state.fix_movie_metadata(movie_id, provider, &id).await
+        }
+        ParentMediaType::Show => {
+            let show_id =
+                sqlx::query_scalar!("SELECT id FROM shows WHERE metadata_id = ?", metadata_id)
+                    .fetch_optional(&app_state.db.pool)
+                    .await?
+                    .ok_or(AppError::not_found("Show is not found"))?;
+            app_state.fix_show_metadata(show_id, provider, &id).await
+        }
+    }
 }
 
 /// Reset show metadata
+///
+/// Show tree is refreshed from the primary metadata provider in the background, metadata locks are removed
 #[utoipa::path(
     post,
     path = "/api/show/{show_id}/reset_metadata",
     params(
-        ("show_id", description = "Id of the show that needs to be fixed"),
+        ("show_id", description = "Id of the show that needs to be reset"),
     ),
     responses(
-        (status = 200, description = "Successfully reset show metadata"),
+        (status = 202, description = "Show metadata reset is started"),
+        (status = 400, description = "Show does not have ids of the available providers or it is already being reset", body = AppError),
         (status = 404, description = "Show is not found", body = AppError),
     ),
     tag = "Shows",
 )]
-pub async fn reset_show_metadata() -> crate::Result<()> {
-    unimplemented!("Metadata reset is unimplemented");
+pub async fn reset_show_metadata(
+    State(app_state): State<AppState>,
+    Path(show_id): Path<i64>,
+) -> crate::Result<StatusCode> {
+    app_state.reset_show_metadata(show_id).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Reset season metadata
+///
+/// Season and its episodes are refreshed from the primary metadata provider of the show in the background,
+/// metadata locks of the season and its episodes are removed
+#[utoipa::path(
+    post,
+    path = "/api/show/{show_id}/{season}/reset_metadata",
+    params(
+        ("show_id", description = "Id of the show"),
+        ("season", description = "Number of the season that needs to be reset"),
+    ),
+    responses(
+        (status = 202, description = "Season metadata reset is started"),
+        (status = 400, description = "Show does not have ids of the available providers or it is already being reset", body = AppError),
+        (status = 404, description = "Show is not found", body = AppError),
+    ),
+    tag = "Shows",
+)]
+pub async fn reset_season_metadata(
+    State(app_state): State<AppState>,
+    Path((show_id, season)): Path<(i64, usize)>,
+) -> crate::Result<StatusCode> {
+    app_state
+        .reset_season_metadata(show_id, season, None)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+/// Reset episode metadata
+///
+/// Episode is refreshed from the primary metadata provider of the show in the background,
+/// metadata locks of the episode are removed
+#[utoipa::path(
+    post,
+    path = "/api/show/{show_id}/{season}/{episode}/reset_metadata",
+    params(
+        ("show_id", description = "Id of the show"),
+        ("season", description = "Season number"),
+        ("episode", description = "Number of the episode that needs to be reset"),
+    ),
+    responses(
+        (status = 202, description = "Episode metadata reset is started"),
+        (status = 400, description = "Show does not have ids of the available providers or it is already being reset", body = AppError),
+        (status = 404, description = "Show is not found", body = AppError),
+    ),
+    tag = "Shows",
+)]
+pub async fn reset_episode_metadata(
+    State(app_state): State<AppState>,
+    Path((show_id, season, episode)): Path<(i64, usize, usize)>,
+) -> crate::Result<StatusCode> {
+    app_state
+        .reset_season_metadata(show_id, season, Some(episode))
+        .await?;
+    Ok(StatusCode::ACCEPTED)
 }
 
 /// Reset movie metadata
+///
+/// Movie is refreshed from the primary metadata provider in the background, metadata locks are removed
 #[utoipa::path(
     post,
     path = "/api/movie/{movie_id}/reset_metadata",
     params(
-        ("movie_id", description = "Id of the movie that needs to be fixed"),
+        ("movie_id", description = "Id of the movie that needs to be reset"),
     ),
     responses(
-        (status = 200),
+        (status = 202, description = "Movie metadata reset is started"),
+        (status = 400, description = "Movie does not have ids of the available providers or it is already being reset", body = AppError),
+        (status = 404, description = "Movie is not found", body = AppError),
     ),
     tag = "Movies",
 )]
-pub async fn reset_movie_metadata() -> crate::Result<()> {
-    unimplemented!("Metadata reset is unimplemented");
+pub async fn reset_movie_metadata(
+    State(app_state): State<AppState>,
+    Path(movie_id): Path<i64>,
+) -> crate::Result<StatusCode> {
+    app_state.reset_movie_metadata(movie_id).await?;
+    Ok(StatusCode::ACCEPTED)
 }
 
 /// Reset content's metadata
+///
+/// Content is refreshed from the primary metadata provider in the background, metadata locks are removed
 #[utoipa::path(
     post,
     path = "/api/reset_metadata/{metadata_id}",
     params(
-        ("metadata_id", description = "Id of the content that needs to be fixed"),
+        ("metadata_id", description = "Id of the content that needs to be reset"),
         ContentTypeQuery,
     ),
     responses(
-        (status = 200),
+        (status = 202, description = "Metadata reset is started"),
+        (status = 400, description = "Content does not have ids of the available providers or it is already being reset", body = AppError),
         (status = 404, description = "Content is not found", body = AppError),
     ),
     tag = "Metadata",
 )]
-pub async fn reset_metadata() -> crate::Result<()> {
-    unimplemented!("Metadata reset is unimplemented");
+pub async fn reset_metadata(
+    State(app_state): State<AppState>,
+    Path(metadata_id): Path<i64>,
+    Query(ContentTypeQuery { content_type }): Query<ContentTypeQuery>,
+) -> crate::Result<StatusCode> {
+    match content_type {
+        ParentMediaType::Movie => {
+            let movie_id =
+                sqlx::query_scalar!("SELECT id FROM movies WHERE metadata_id = ?", metadata_id)
+                    .fetch_optional(&app_state.db.pool)
+                    .await?
+                    .ok_or(AppError::not_found("Movie is not found"))?;
+            app_state.reset_movie_metadata(movie_id).await?;
+        }
+        ParentMediaType::Show => {
+            let show_id =
+                sqlx::query_scalar!("SELECT id FROM shows WHERE metadata_id = ?", metadata_id)
+                    .fetch_optional(&app_state.db.pool)
+                    .await?
+                    .ok_or(AppError::not_found("Show is not found"))?;
+            app_state.reset_show_metadata(show_id).await?;
+        }
+    }
+    Ok(StatusCode::ACCEPTED)
 }
 
 /// Start transcode video job
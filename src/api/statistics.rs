@@ -0,0 +1,32 @@
+use axum::extract::State;
+
+use crate::{
+    api::Json,
+    app_state::AppState,
+    library::{
+        libraries,
+        statistics::{self, LibraryStatistics},
+    },
+};
+
+/// Library makeup: content counts, stream breakdowns, top genres and actors, growth over time
+#[utoipa::path(
+    get,
+    path = "/api/statistics",
+    responses(
+        (status = 200, body = LibraryStatistics),
+    ),
+    tag = "Resources",
+)]
+async fn library_statistics(
+    State(AppState { db, .. }): State<AppState>,
+) -> crate::Result<Json<LibraryStatistics>> {
+    let media_libraries = libraries::all(db).await?;
+    Ok(Json(statistics::collect(db, &media_libraries).await?))
+}
+
+pub fn router() -> axum::Router<AppState> {
+    use axum::routing::get;
+
+    axum::Router::new().route("/", get(library_statistics))
+}
@@ -0,0 +1,152 @@
+use axum::extract::State;
+use serde::Serialize;
+
+use crate::api::server::DetailedVideo;
+use crate::api::{Json, Path, Query, VersionClientQuery};
+use crate::app_state::AppState;
+use crate::config;
+use crate::db::DbActions;
+use crate::library::versions::{self, VersionCandidate, VersionClient, VersionLabel};
+use crate::library::{ContentIdentifier, Source};
+use crate::{AppError, db::Db};
+
+/// Movie version together with its technical info
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct MovieVersion {
+    /// Human readable version name. Falls back to the file name when nothing is recognized
+    pub label: String,
+    pub version: VersionLabel,
+    /// Version is picked by the user or during the library scan
+    pub is_prime: bool,
+    /// Version will be played by default for the requesting client
+    pub is_default: bool,
+    pub video: DetailedVideo,
+}
+
+/// Movie videos that are present in the library
+async fn movie_versions(
+    state: &AppState,
+    movie_id: i64,
+) -> crate::Result<Vec<(VersionCandidate, Source)>> {
+    let db_videos = sqlx::query!(
+        "SELECT videos.id, videos.is_prime FROM videos
+        JOIN movies ON movies.metadata_id = videos.metadata_id WHERE movies.id = ?",
+        movie_id
+    )
+    .fetch_all(&state.db.pool)
+    .await?;
+
+    let library = state.library.lock().unwrap();
+    let versions = db_videos
+        .into_iter()
+        .filter_map(|db_video| {
+            let file = library.videos.get(&db_video.id)?;
+            let version = match &file.identifier {
+                ContentIdentifier::Movie(identifier) => VersionLabel::new(&identifier.attributes),
+                ContentIdentifier::Show(_) => VersionLabel::default(),
+            };
+            let candidate = VersionCandidate {
+                video_id: db_video.id,
+                version,
+                is_prime: db_video.is_prime,
+            };
+            Some((candidate, file.source.clone()))
+        })
+        .collect();
+    Ok(versions)
+}
+
+/// Pick the movie video that should be played for the client
+pub async fn default_movie_video(
+    state: &AppState,
+    movie_id: i64,
+    client: VersionClient,
+) -> crate::Result<i64> {
+    let versions = movie_versions(state, movie_id).await?;
+    let candidates: Vec<_> = versions.into_iter().map(|(c, _)| c).collect();
+    let policy: config::DefaultVersionPolicy = config::CONFIG.get_value();
+    versions::pick_default(&candidates, policy.preference(client))
+        .ok_or(AppError::not_found("Movie does not have any videos"))
+}
+
+/// List all versions of the movie
+#[utoipa::path(
+    get,
+    path = "/api/local_movie/{movie_id}/versions",
+    params(
+        ("movie_id", description = "Movie id"),
+        VersionClientQuery,
+    ),
+    responses(
+        (status = 200, description = "Movie versions", body = Vec<MovieVersion>),
+    ),
+    tag = "Movies",
+)]
+pub async fn movie_versions_list(
+    Path(movie_id): Path<i64>,
+    Query(VersionClientQuery { client }): Query<VersionClientQuery>,
+    State(state): State<AppState>,
+) -> crate::Result<Json<Vec<MovieVersion>>> {
+    let versions = movie_versions(&state, movie_id).await?;
+    let candidates: Vec<_> = versions.iter().map(|(c, _)| c.clone()).collect();
+    let policy: config::DefaultVersionPolicy = config::CONFIG.get_value();
+    let default =
+        versions::pick_default(&candidates, policy.preference(client.unwrap_or_default()));
+
+    let mut out = Vec::with_capacity(versions.len());
+    for (candidate, source) in versions {
+        let label = candidate.version.label().unwrap_or_else(|| {
+            source
+                .video
+                .path()
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default()
+        });
+        match DetailedVideo::new(state.db.clone(), source).await {
+            Ok(video) => out.push(MovieVersion {
+                label,
+                is_default: default == Some(candidate.video_id),
+                is_prime: candidate.is_prime,
+                version: candidate.version,
+                video,
+            }),
+            Err(e) => {
+                tracing::warn!("Failed to construct detailed video: {e}");
+            }
+        }
+    }
+    Ok(Json(out))
+}
+
+/// Make the video the prime version of the movie
+#[utoipa::path(
+    put,
+    path = "/api/local_movie/{movie_id}/versions/{video_id}/prime",
+    params(
+        ("movie_id", description = "Movie id"),
+        ("video_id", description = "Video id"),
+    ),
+    responses(
+        (status = 200),
+        (status = 404, description = "Video does not belong to the movie", body = AppError),
+    ),
+    tag = "Movies",
+)]
+pub async fn set_prime_version(
+    Path((movie_id, video_id)): Path<(i64, i64)>,
+    State(db): State<Db>,
+) -> crate::Result<()> {
+    let metadata_id = sqlx::query_scalar!(
+        "SELECT movies.metadata_id FROM movies
+        JOIN videos ON videos.metadata_id = movies.metadata_id
+        WHERE movies.id = ? AND videos.id = ?",
+        movie_id,
+        video_id
+    )
+    .fetch_optional(&db.pool)
+    .await?
+    .ok_or(AppError::not_found("Video does not belong to the movie"))?;
+    db.set_prime_video(metadata_id, video_id).await?;
+    Ok(())
+}
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, sync::Mutex};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
 
 use axum::extract::FromRef;
 use tokio::fs;
@@ -12,11 +16,25 @@ use crate::{
     library::{
         ContentIdentifier, Library, Source, TranscodePayload,
         assets::{AssetDir, FileAsset, VariantAsset},
-        explore_movie_dirs, explore_show_dirs,
+        collections, explore_movie_dirs, explore_show_dirs,
+        libraries::{self, LibraryFolders, MediaLibrary},
         media::Video,
+        nfo_export::{ExportTarget, NfoExporter},
+        numbering,
+    },
+    metadata::{
+        EpisodeOrdering, FetchParams, MetadataProvider, ParentMediaType,
+        metadata_api::{
+            PendingInsert,
+            asset_saver::AssetTasks,
+            movie::MovieMetadataApi,
+            refresh::{self, MetadataRefreshProgress, MetadataRefreshTask},
+            show::ShowMetadataApi,
+        },
+        metadata_stack::MetadataProvidersStack,
+        translations::{self, TranslationTask},
     },
-    metadata::{FetchParams, metadata_stack::MetadataProvidersStack},
-    progress::{ProgressDispatcher, TaskResource},
+    progress::{ProgressDispatcher, TaskError, TaskResource},
     scan,
     torrent::TorrentClient,
 };
@@ -35,7 +53,24 @@ pub struct AppState {
 impl AppState {
     pub fn metadata_fetch_params(&self) -> FetchParams {
         let language: config::MetadataLanguage = config::CONFIG.get_value();
-        FetchParams { lang: language.0 }
+        FetchParams::new(language.0)
+    }
+
+    /// Fetch parameters the content is scanned with: language of its library and the show ordering
+    pub async fn content_fetch_params(
+        &self,
+        content_type: ParentMediaType,
+        content_id: i64,
+    ) -> crate::Result<FetchParams> {
+        let mut fetch_params = self.metadata_fetch_params();
+        let library = libraries::content_library(self.db, content_type, content_id).await?;
+        if let Some(lang) = library.and_then(|l| l.settings.metadata_language) {
+            fetch_params.lang = lang;
+        }
+        if content_type == ParentMediaType::Show {
+            fetch_params.ordering = numbering::get(self.db, content_id).await?.ordering;
+        }
+        Ok(fetch_params)
     }
 
     pub fn get_source_by_id(&self, id: i64) -> crate::Result<Source> {
@@ -158,6 +193,222 @@ WHERE seasons.show_id = ?",
         Ok(())
     }
 
+    /// Re-match the show to another show of the metadata provider
+    #[tracing::instrument(skip(self))]
+    pub async fn fix_show_metadata(
+        &self,
+        show_id: i64,
+        provider: MetadataProvider,
+        provider_id: &str,
+    ) -> crate::Result<()> {
+        let Some(provider) = self.providers_stack.show_provider(provider) else {
+            return Err(AppError::not_found(
+                "request metadata provider was not found",
+            ));
+        };
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Show, show_id)
+            .await?;
+        let api = ShowMetadataApi::new(provider, self.db, self.http_client.clone())
+            .with_fetch_params(fetch_params);
+        let pending = api.rematch_show(show_id, provider_id).await?;
+        let config::scan::MaxAssetConcurrency(assets_concurrency) = config::CONFIG.get_value();
+        pending.commit(assets_concurrency).await?;
+        if let Some(exporter) = NfoExporter::from_libraries(self.db.clone()).await {
+            exporter.spawn(ExportTarget::Show(show_id));
+        }
+        Ok(())
+    }
+
+    /// Re-match the movie to another movie of the metadata provider
+    #[tracing::instrument(skip(self))]
+    pub async fn fix_movie_metadata(
+        &self,
+        movie_id: i64,
+        provider: MetadataProvider,
+        provider_id: &str,
+    ) -> crate::Result<()> {
+        let Some(provider) = self.providers_stack.movie_provider(provider) else {
+            return Err(AppError::not_found(
+                "request metadata provider was not found",
+            ));
+        };
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Movie, movie_id)
+            .await?;
+        let api = MovieMetadataApi::new(provider, self.db, self.http_client.clone())
+            .with_fetch_params(fetch_params);
+        let pending = api.rematch_movie(movie_id, provider_id).await?;
+        let config::scan::MaxAssetConcurrency(assets_concurrency) = config::CONFIG.get_value();
+        pending.commit(assets_concurrency).await?;
+        if let Some(exporter) = NfoExporter::from_libraries(self.db.clone()).await {
+            exporter.spawn(ExportTarget::Movie(movie_id));
+        }
+        Ok(())
+    }
+
+    /// Re-fetch metadata of the show seasons and episodes under the new ordering in the background
+    pub async fn reorder_show(&self, show_id: i64, ordering: EpisodeOrdering) -> crate::Result<()> {
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Show, show_id)
+            .await?;
+        let tasks = self.tasks;
+        let task = MetadataRefreshTask {
+            content_type: ParentMediaType::Show,
+            content_id: show_id,
+        };
+        let task_id = tasks.metadata_refresh_tasks.start_task(task, None)?;
+        let db = self.db;
+        let providers = self.providers_stack;
+        tasks.tracker.spawn(async move {
+            let progress = ProgressDispatcher::new(&tasks.metadata_refresh_tasks, task_id);
+            progress.progress(MetadataRefreshProgress::Fetch);
+            match numbering::reorder_show(db, providers, show_id, ordering, fetch_params).await {
+                Ok(_) => {
+                    progress.finish();
+                    if let Some(exporter) = NfoExporter::from_libraries(db.clone()).await {
+                        exporter.spawn(ExportTarget::Show(show_id));
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(show_id, "Failed to reorder show episodes: {e}");
+                    progress.error(TaskError::Failure);
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Reset the show tree to the fresh metadata of its prime provider in the background
+    pub async fn reset_show_metadata(&self, show_id: i64) -> crate::Result<()> {
+        let metadata_id =
+            sqlx::query_scalar!("SELECT metadata_id FROM shows WHERE id = ?", show_id)
+                .fetch_optional(&self.db.pool)
+                .await?
+                .ok_or(AppError::not_found("Show is not found"))?;
+        let (provider, provider_id) = refresh::prime_provider(self.db, metadata_id, |p| {
+            self.providers_stack.show_provider(p)
+        })
+        .await?;
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Show, show_id)
+            .await?;
+        let api = ShowMetadataApi::new(provider, self.db, self.http_client.clone())
+            .with_fetch_params(fetch_params)
+            .with_unlock();
+        let task = MetadataRefreshTask {
+            content_type: ParentMediaType::Show,
+            content_id: show_id,
+        };
+        self.spawn_metadata_refresh(task, async move {
+            api.rematch_show(show_id, &provider_id).await
+        })
+    }
+
+    /// Reset the show season, or a single episode of it, to the fresh metadata
+    /// of the show prime provider in the background
+    pub async fn reset_season_metadata(
+        &self,
+        show_id: i64,
+        season: usize,
+        episode: Option<usize>,
+    ) -> crate::Result<()> {
+        let metadata_id =
+            sqlx::query_scalar!("SELECT metadata_id FROM shows WHERE id = ?", show_id)
+                .fetch_optional(&self.db.pool)
+                .await?
+                .ok_or(AppError::not_found("Show is not found"))?;
+        let (provider, provider_id) = refresh::prime_provider(self.db, metadata_id, |p| {
+            self.providers_stack.show_provider(p)
+        })
+        .await?;
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Show, show_id)
+            .await?;
+        let api = ShowMetadataApi::new(provider, self.db, self.http_client.clone())
+            .with_fetch_params(fetch_params)
+            .with_unlock();
+        let task = MetadataRefreshTask {
+            content_type: ParentMediaType::Show,
+            content_id: show_id,
+        };
+        self.spawn_metadata_refresh(task, async move {
+            api.refresh_season(show_id, &provider_id, season, episode)
+                .await
+        })
+    }
+
+    /// Reset the movie to the fresh metadata of its prime provider in the background
+    pub async fn reset_movie_metadata(&self, movie_id: i64) -> crate::Result<()> {
+        let metadata_id =
+            sqlx::query_scalar!("SELECT metadata_id FROM movies WHERE id = ?", movie_id)
+                .fetch_optional(&self.db.pool)
+                .await?
+                .ok_or(AppError::not_found("Movie is not found"))?;
+        let (provider, provider_id) = refresh::prime_provider(self.db, metadata_id, |p| {
+            self.providers_stack.movie_provider(p)
+        })
+        .await?;
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Movie, movie_id)
+            .await?;
+        let api = MovieMetadataApi::new(provider, self.db, self.http_client.clone())
+            .with_fetch_params(fetch_params)
+            .with_unlock();
+        let task = MetadataRefreshTask {
+            content_type: ParentMediaType::Movie,
+            content_id: movie_id,
+        };
+        self.spawn_metadata_refresh(task, async move {
+            api.rematch_movie(movie_id, &provider_id).await
+        })
+    }
+
+    /// Run the metadata refresh in the background, commit its result with the assets progress
+    /// and export NFO files of the refreshed content
+    fn spawn_metadata_refresh<T: Send + 'static>(
+        &self,
+        task: MetadataRefreshTask,
+        refresh: impl Future<Output = crate::Result<PendingInsert<T>>> + Send + 'static,
+    ) -> crate::Result<()> {
+        let tasks = self.tasks;
+        let db = self.db;
+        let MetadataRefreshTask {
+            content_type,
+            content_id,
+        } = task.clone();
+        let task_id = tasks.metadata_refresh_tasks.start_task(task, None)?;
+        tasks.tracker.spawn(async move {
+            let progress = ProgressDispatcher::new(&tasks.metadata_refresh_tasks, task_id);
+            progress.progress(MetadataRefreshProgress::Fetch);
+            let config::scan::MaxAssetConcurrency(assets_concurrency) = config::CONFIG.get_value();
+            let result = match refresh.await {
+                Ok(pending) => {
+                    refresh::commit_with_progress(pending, assets_concurrency, &progress)
+                        .await
+                        .map_err(Into::into)
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(()) => {
+                    progress.finish();
+                    if let Some(exporter) = NfoExporter::from_libraries(db.clone()).await {
+                        exporter.spawn(match content_type {
+                            ParentMediaType::Show => ExportTarget::Show(content_id),
+                            ParentMediaType::Movie => ExportTarget::Movie(content_id),
+                        });
+                    }
+                }
+                Err(e) => {
+                    tracing::error!(?content_type, content_id, "Failed to reset metadata: {e}");
+                    progress.error(TaskError::Failure);
+                }
+            }
+        });
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn remove_variant(&self, video_id: i64, variant_id: &str) -> crate::Result<()> {
         let asset = VariantAsset::new(video_id, variant_id.to_string());
@@ -341,6 +592,91 @@ WHERE seasons.show_id = ?",
         config: scan::ScanConfig,
     ) -> crate::Result<()> {
         self.partial_refresh().await;
+        let added_videos = self.reconciliate_added(task_id, config).await?;
+        let library_ids: Vec<_> = libraries::all(self.db)
+            .await?
+            .into_iter()
+            .map(|l| l.id)
+            .collect();
+        if let Err(e) = libraries::mark_scanned(self.db, &library_ids).await {
+            tracing::error!("Failed to update libraries scan time: {e}");
+        }
+        self.run_library_jobs(&added_videos).await;
+        self.start_translations_fetch().await;
+        Ok(())
+    }
+
+    /// Scan only the folders of the given libraries
+    #[tracing::instrument(skip(self), fields(%task_id))]
+    pub async fn reconciliate_libraries(
+        &self,
+        task_id: uuid::Uuid,
+        config: scan::ScanConfig,
+        library_ids: &[i64],
+    ) -> crate::Result<()> {
+        let media_libraries: Vec<_> = libraries::all(self.db)
+            .await?
+            .into_iter()
+            .filter(|l| library_ids.contains(&l.id))
+            .collect();
+        self.partial_refresh_libraries(&media_libraries).await;
+        let added_videos = self.reconciliate_added(task_id, config).await?;
+        if let Err(e) = libraries::mark_scanned(self.db, library_ids).await {
+            tracing::error!("Failed to update libraries scan time: {e}");
+        }
+        self.run_library_jobs(&added_videos).await;
+        self.start_translations_fetch().await;
+        Ok(())
+    }
+
+    /// Add videos of the given library subfolders without walking the whole library
+    #[tracing::instrument(skip(self, folders), fields(%task_id))]
+    pub async fn reconciliate_folders(
+        &self,
+        task_id: uuid::Uuid,
+        config: scan::ScanConfig,
+        folders: &[PathBuf],
+    ) -> crate::Result<()> {
+        let LibraryFolders {
+            shows,
+            movies,
+            ignore,
+        } = LibraryFolders::load(self.db).await;
+        let in_any = |roots: &[PathBuf]| -> Vec<PathBuf> {
+            folders
+                .iter()
+                .filter(|f| roots.iter().any(|root| f.starts_with(root)))
+                .cloned()
+                .collect()
+        };
+        let (show_folders, movie_folders) = (in_any(&shows), in_any(&movies));
+        let known: Vec<PathBuf> = {
+            let library = self.library.lock().unwrap();
+            library
+                .videos
+                .values()
+                .map(|f| f.source.video.path().to_owned())
+                .collect()
+        };
+        let mut videos = HashMap::new();
+        explore_show_dirs(show_folders, &ignore, self.db, &mut videos, &known).await;
+        explore_movie_dirs(movie_folders, &ignore, self.db, &mut videos, &known).await;
+        self.library.lock().unwrap().videos.extend(videos);
+        let added_videos = self.reconciliate_added(task_id, config).await?;
+        self.run_library_jobs(&added_videos).await;
+        self.start_translations_fetch().await;
+        Ok(())
+    }
+
+    /// Fetch metadata of the videos that are not in the database yet
+    async fn reconciliate_added(
+        &self,
+        task_id: uuid::Uuid,
+        config: scan::ScanConfig,
+    ) -> crate::Result<Vec<i64>> {
+        if let Some(nfo) = self.providers_stack.nfo {
+            nfo.invalidate().await;
+        }
         let progress = scan::scan_progress::ScanProgressEmitter::new(ProgressDispatcher::new(
             &self.tasks.library_scan_tasks,
             task_id,
@@ -356,20 +692,234 @@ WHERE seasons.show_id = ?",
         .await
     }
 
+    /// Fetch collections of the movies that were added before collections were introduced.
+    ///
+    /// Movies are queued once by the migration and leave the queue after the first attempt
+    pub async fn backfill_collections(&self) -> anyhow::Result<()> {
+        sqlx::query!(
+            "delete from collection_backfill where metadata_id not in (select metadata_id from movies)"
+        )
+        .execute(&self.db.pool)
+        .await?;
+        let movies = sqlx::query!(
+            "select movies.id, movies.metadata_id from collection_backfill
+            join movies on movies.metadata_id = collection_backfill.metadata_id"
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        if movies.is_empty() {
+            return Ok(());
+        }
+        tracing::info!("Fetching collections of {} movies", movies.len());
+        let mut assets = AssetTasks::new(self.http_client.clone());
+        for movie in movies {
+            if self.cancelation_token.is_cancelled() {
+                break;
+            }
+            if let Err(e) = self.backfill_movie_collection(movie.id, &mut assets).await {
+                tracing::warn!(movie_id = movie.id, "Failed to fetch movie collection: {e}");
+            }
+            sqlx::query!(
+                "delete from collection_backfill where metadata_id = ?",
+                movie.metadata_id
+            )
+            .execute(&self.db.pool)
+            .await?;
+        }
+        let config::scan::MaxAssetConcurrency(max_asset_concurrency) = config::CONFIG.get_value();
+        assets.save(max_asset_concurrency, ()).await;
+        Ok(())
+    }
+
+    async fn backfill_movie_collection(
+        &self,
+        movie_id: i64,
+        assets: &mut AssetTasks,
+    ) -> crate::Result<()> {
+        let fetch_params = self
+            .content_fetch_params(ParentMediaType::Movie, movie_id)
+            .await?;
+        let external_ids = self
+            .db
+            .get_external_ids(movie_id, ParentMediaType::Movie)
+            .await?;
+        // Local and nfo metadata never has collections
+        let provider = self
+            .providers_stack
+            .movie_providers()
+            .into_iter()
+            .find_map(|p| {
+                let provider = p.provider_identifier();
+                if matches!(provider, MetadataProvider::Local | MetadataProvider::Nfo) {
+                    return None;
+                }
+                let id = external_ids.iter().find(|id| id.provider == provider)?;
+                Some((p, id.id.clone()))
+            });
+        let Some((provider, id)) = provider else {
+            return Ok(());
+        };
+        let Some(mut collection) = provider.movie(&id, fetch_params).await?.collection else {
+            return Ok(());
+        };
+        if !collections::has_fresh_parts(self.db, &collection).await? {
+            collection = provider
+                .collection(&collection.metadata_id, fetch_params)
+                .await?;
+        }
+        let mut tx = self.db.begin().await?;
+        scan::movie::save_collection(&mut tx, assets, &collection).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Fetch missing and outdated translations to the additional metadata languages in the background
+    async fn start_translations_fetch(&self) {
+        let config::MetadataLanguage(metadata_lang) = config::CONFIG.get_value();
+        let config::MetadataLanguages(mut languages) = config::CONFIG.get_value();
+        languages.retain(|lang| *lang != metadata_lang);
+        // Content of the libraries with their own language is translated to the server language
+        let media_libraries = match libraries::all(self.db).await {
+            Ok(libraries) => libraries,
+            Err(e) => {
+                tracing::error!("Failed to load libraries: {e}");
+                Vec::new()
+            }
+        };
+        if media_libraries.iter().any(|l| {
+            l.settings
+                .metadata_language
+                .is_some_and(|lang| lang != metadata_lang)
+        }) {
+            languages.push(metadata_lang);
+        }
+        if languages.is_empty() {
+            return;
+        }
+        let tasks = self.tasks;
+        let task = TranslationTask {
+            languages: languages.clone(),
+        };
+        let task_id = match tasks.translation_tasks.start_task(task, None) {
+            Ok(task_id) => task_id,
+            Err(e) => {
+                tracing::debug!("Skipping translations fetch: {e}");
+                return;
+            }
+        };
+        let db = self.db;
+        let providers = self.providers_stack;
+        tasks.tracker.spawn(async move {
+            let progress = ProgressDispatcher::new(&tasks.translation_tasks, task_id);
+            match translations::fetch_missing(db, providers, &languages, metadata_lang, &progress)
+                .await
+            {
+                Ok(()) => progress.finish(),
+                Err(e) => {
+                    tracing::error!("Failed to fetch translations: {e}");
+                    progress.error(TaskError::Failure);
+                }
+            }
+        });
+    }
+
+    /// Start post scan jobs enabled in the libraries settings for newly added videos
+    async fn run_library_jobs(&self, added_videos: &[i64]) {
+        let media_libraries = match libraries::all(self.db).await {
+            Ok(libraries) => libraries,
+            Err(e) => {
+                tracing::error!("Failed to load libraries: {e}");
+                return;
+            }
+        };
+        let added: Vec<(i64, PathBuf)> = {
+            let library = self.library.lock().unwrap();
+            added_videos
+                .iter()
+                .filter_map(|id| Some((*id, library.get_source(*id)?.video.path().to_owned())))
+                .collect()
+        };
+        for media_library in &media_libraries {
+            let videos = added
+                .iter()
+                .filter(|(_, path)| media_library.contains(path))
+                .map(|(id, _)| *id);
+            let mut seasons = BTreeSet::new();
+            for video_id in videos {
+                if media_library.settings.generate_previews
+                    && let Err(e) = self.generate_previews(video_id).await
+                {
+                    tracing::warn!(video_id, "Failed to start previews generation: {e}");
+                }
+                if media_library.settings.detect_intros {
+                    let season = sqlx::query!(
+                        "SELECT seasons.show_id, seasons.number FROM videos
+                        JOIN episodes ON episodes.metadata_id = videos.metadata_id
+                        JOIN seasons ON seasons.id = episodes.season_id WHERE videos.id = ?",
+                        video_id
+                    )
+                    .fetch_optional(&self.db.pool)
+                    .await;
+                    if let Ok(Some(season)) = season {
+                        seasons.insert((season.show_id, season.number));
+                    }
+                }
+            }
+            for (show_id, season) in seasons {
+                let app_state = self.clone();
+                self.tasks.tracker.spawn(async move {
+                    if let Err(e) = app_state.detect_intros(show_id, season).await {
+                        tracing::warn!(show_id, season, "Failed to detect intros: {e}");
+                    }
+                });
+            }
+        }
+    }
+
     #[tracing::instrument(skip(self))]
     pub async fn partial_refresh(&self) {
         tracing::info!("Partially refreshing library");
+        let folders = LibraryFolders::load(self.db).await;
+        self.refresh_folders(folders, |_| true).await;
+    }
+
+    /// Partial refresh limited to the folders of the given libraries
+    async fn partial_refresh_libraries(&self, media_libraries: &[MediaLibrary]) {
+        let in_scope = |path: &Path| media_libraries.iter().any(|l| l.contains(path));
+        let LibraryFolders {
+            mut shows,
+            mut movies,
+            ignore,
+        } = LibraryFolders::load(self.db).await;
+        shows.retain(|folder| in_scope(folder));
+        movies.retain(|folder| in_scope(folder));
+        let folders = LibraryFolders {
+            shows,
+            movies,
+            ignore,
+        };
+        self.refresh_folders(folders, in_scope).await;
+    }
+
+    /// Remove missing videos that satisfy `in_scope` and explore the folders for the new ones
+    async fn refresh_folders(&self, folders: LibraryFolders, in_scope: impl Fn(&Path) -> bool) {
         let mut videos = HashMap::new();
         let mut to_remove = Vec::new();
-        let (config::ShowFolders(show_folders), config::MovieFolders(movie_folders)) =
-            config::CONFIG.get_values();
+        let LibraryFolders {
+            shows: show_folders,
+            movies: movie_folders,
+            ignore,
+        } = folders;
         let mut show_paths = Vec::new();
         let mut movie_paths = Vec::new();
         {
             let mut library = self.library.lock().unwrap();
             for (id, file) in &library.videos {
                 let file_path = file.source.video.path();
-                if !file_path.try_exists().unwrap_or(false) {
+                if !in_scope(file_path) {
+                    continue;
+                }
+                if !file_path.try_exists().unwrap_or(false) || ignore.is_path_ignored(file_path) {
                     to_remove.push(*id);
                     continue;
                 }
@@ -396,18 +946,19 @@ WHERE seasons.show_id = ?",
             }
         }
 
+        explore_show_dirs(show_folders, &ignore, self.db, &mut videos, &show_paths).await;
+
+        explore_movie_dirs(movie_folders, &ignore, self.db, &mut videos, &movie_paths).await;
+
+        // Moved videos were relocated during exploration and kept their ids
         let mut tx = self.db.begin().await.unwrap();
-        for absent_id in to_remove {
+        for absent_id in to_remove.into_iter().filter(|id| !videos.contains_key(id)) {
             if let Err(e) = tx.remove_video(absent_id).await {
                 tracing::error!("Failed to remove video: {e}");
             };
         }
         tx.commit().await.unwrap();
 
-        explore_show_dirs(show_folders, self.db, &mut videos, &show_paths).await;
-
-        explore_movie_dirs(movie_folders, self.db, &mut videos, &movie_paths).await;
-
         self.library.lock().unwrap().videos.extend(videos);
     }
 }
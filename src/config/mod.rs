@@ -12,6 +12,10 @@ use utoipa::openapi::RefOr;
 
 use crate::{
     AppError,
+    library::{
+        organizer::ImportMode,
+        versions::{VersionClient, VersionPreference},
+    },
     metadata::{self, MetadataProvider},
     torrent_index::TorrentIndexIdentifier,
 };
@@ -263,6 +267,12 @@ impl ConfigStore {
         store.register_value::<UpnpEnabled>();
         store.register_value::<UpnpTtl>();
         store.register_value::<MetadataLanguage>();
+        store.register_value::<MetadataLanguages>();
+        store.register_value::<DefaultVersionPolicy>();
+        store.register_value::<ImportCompletedDownloads>();
+        store.register_value::<DownloadImportMode>();
+        store.register_value::<ShowNamingTemplate>();
+        store.register_value::<MovieNamingTemplate>();
         store.register_value::<scan::MaxMovieConcurrency>();
         store.register_value::<scan::MaxShowConcurrency>();
         store.register_value::<scan::MaxAssetConcurrency>();
@@ -557,7 +567,13 @@ impl utoipa::PartialSchema for UtoipaConfigSchema {
             .item(UtoipaConfigValue::<scan::MaxShowConcurrency>::schema())
             .item(UtoipaConfigValue::<scan::MaxAssetConcurrency>::schema())
             .item(UtoipaConfigValue::<scan::UseSeasonEpisodes>::schema())
-            .item(UtoipaConfigValue::<MetadataLanguage>::schema());
+            .item(UtoipaConfigValue::<MetadataLanguage>::schema())
+            .item(UtoipaConfigValue::<MetadataLanguages>::schema())
+            .item(UtoipaConfigValue::<DefaultVersionPolicy>::schema())
+            .item(UtoipaConfigValue::<ImportCompletedDownloads>::schema())
+            .item(UtoipaConfigValue::<DownloadImportMode>::schema())
+            .item(UtoipaConfigValue::<ShowNamingTemplate>::schema())
+            .item(UtoipaConfigValue::<MovieNamingTemplate>::schema());
         let array = schema::ArrayBuilder::new().items(schema).build();
         array.into()
     }
@@ -921,6 +937,7 @@ impl Default for DiscoverProvidersOrder {
             MetadataProvider::Local,
             MetadataProvider::Tmdb,
             MetadataProvider::Tvdb,
+            MetadataProvider::Imdb,
         ])
     }
 }
@@ -933,8 +950,10 @@ impl Default for ShowProvidersOrder {
     fn default() -> Self {
         Self(vec![
             MetadataProvider::Local,
+            MetadataProvider::Nfo,
             MetadataProvider::Tmdb,
             MetadataProvider::Tvdb,
+            MetadataProvider::Imdb,
         ])
     }
 }
@@ -947,8 +966,10 @@ impl Default for MovieProvidersOrder {
     fn default() -> Self {
         Self(vec![
             MetadataProvider::Local,
+            MetadataProvider::Nfo,
             MetadataProvider::Tmdb,
             MetadataProvider::Tvdb,
+            MetadataProvider::Imdb,
         ])
     }
 }
@@ -968,6 +989,78 @@ impl Default for TorrentIndexesOrder {
 pub struct MetadataLanguage(pub metadata::Language);
 impl ConfigValue for MetadataLanguage {}
 
+/// Additional languages to fetch titles, plots and posters in during the library scan.
+/// API consumers pick the language with the `Accept-Language` header or `lang` query parameter
+#[derive(Deserialize, Serialize, Clone, Debug, utoipa::ToSchema, Default)]
+pub struct MetadataLanguages(pub Vec<metadata::Language>);
+impl ConfigValue for MetadataLanguages {}
+
+/// Default movie version for each kind of client. Used when movie has multiple video files
+#[derive(Deserialize, Serialize, Clone, Debug, utoipa::ToSchema)]
+pub struct DefaultVersionPolicy(pub Vec<VersionPreference>);
+impl ConfigValue for DefaultVersionPolicy {}
+impl Default for DefaultVersionPolicy {
+    fn default() -> Self {
+        use crate::parser::attributes::{ResolutionAttr, SourceAttr};
+        Self(vec![
+            VersionPreference {
+                client: VersionClient::Mobile,
+                max_resolution: Some(ResolutionAttr::P1080),
+                source: None,
+                editions: Vec::new(),
+            },
+            VersionPreference {
+                client: VersionClient::Tv,
+                max_resolution: None,
+                source: Some(SourceAttr::Remux),
+                editions: Vec::new(),
+            },
+        ])
+    }
+}
+
+impl DefaultVersionPolicy {
+    pub fn preference(&self, client: VersionClient) -> Option<&VersionPreference> {
+        self.0.iter().find(|p| p.client == client)
+    }
+}
+
+/// Import finished torrents into the libraries
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, utoipa::ToSchema)]
+pub struct ImportCompletedDownloads(pub bool);
+impl ConfigValue for ImportCompletedDownloads {}
+
+/// How imported downloads are placed into the libraries. Hardlinks keep torrents seeding
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, utoipa::ToSchema)]
+pub struct DownloadImportMode(pub ImportMode);
+impl ConfigValue for DownloadImportMode {}
+
+/// Path of imported episodes relative to the show library folder.
+/// Available placeholders: `{show}`, `{year}`, `{season}`, `{episode}`, `{title}`, `{ext}`.
+/// Numbers can be zero padded like `{season:02}`
+#[derive(Deserialize, Serialize, Clone, Debug, utoipa::ToSchema)]
+pub struct ShowNamingTemplate(pub String);
+impl ConfigValue for ShowNamingTemplate {}
+impl Default for ShowNamingTemplate {
+    fn default() -> Self {
+        Self(
+            "{show}/Season {season:02}/{show} - S{season:02}E{episode:02} - {title}.{ext}"
+                .to_string(),
+        )
+    }
+}
+
+/// Path of imported movies relative to the movie library folder.
+/// Available placeholders: `{title}`, `{year}`, `{ext}`
+#[derive(Deserialize, Serialize, Clone, Debug, utoipa::ToSchema)]
+pub struct MovieNamingTemplate(pub String);
+impl ConfigValue for MovieNamingTemplate {}
+impl Default for MovieNamingTemplate {
+    fn default() -> Self {
+        Self("{title} ({year})/{title} ({year}).{ext}".to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -22,8 +22,8 @@ use crate::{
     db::query_builders::{DbEpisodeQuery, DbMovieQuery},
     library::assets::{self, AssetDir},
     metadata::{
-        EpisodeMetadata, ExternalIdMetadata, LocaleMetadata, MetadataProvider, MovieMetadata,
-        ParentMediaType, ShowMetadata,
+        CrewJob, EpisodeMetadata, ExternalIdMetadata, LocaleMetadata, MetadataLocks,
+        MetadataProvider, MovieMetadata, ParentMediaType, RatingMetadata, ShowMetadata,
     },
 };
 
@@ -309,11 +309,12 @@ where
         async move {
             let mut conn = self.acquire().await?;
             sqlx::query_scalar!(
-                r#"INSERT INTO roles (actor_id, metadata_id, character)
-                VALUES (?, ?, ?) RETURNING id as "id!";"#,
+                r#"INSERT INTO roles (actor_id, metadata_id, character, job)
+                VALUES (?, ?, ?, ?) RETURNING id as "id!";"#,
                 role.actor_id,
                 role.metadata_id,
                 role.character,
+                role.job,
             )
             .fetch_one(&mut *conn)
             .await
@@ -329,12 +330,13 @@ where
             tracing::debug!("Inserting new video: {}", db_video.path);
             let video_query = sqlx::query!(
                 "INSERT INTO videos
-            (path, size, metadata_id, is_prime)
-            VALUES (?, ?, ?, ?) RETURNING id;",
+            (path, size, metadata_id, is_prime, content_hash)
+            VALUES (?, ?, ?, ?, ?) RETURNING id;",
                 db_video.path,
                 db_video.size,
                 db_video.metadata_id,
                 db_video.is_prime,
+                db_video.content_hash,
             );
             video_query.fetch_one(&mut *conn).await.map(|x| x.id)
         }
@@ -446,6 +448,66 @@ where
         }
     }
 
+    /// Replaces ratings of the metadata row
+    fn replace_content_ratings(
+        self,
+        metadata_id: i64,
+        ratings: &[RatingMetadata],
+    ) -> impl std::future::Future<Output = sqlx::Result<()>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!(
+                "DELETE FROM content_ratings WHERE metadata_id = ?",
+                metadata_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            for rating in ratings {
+                let votes = rating.votes.map(|v| v as i64);
+                sqlx::query!(
+                    "INSERT OR REPLACE INTO content_ratings
+                    (metadata_id, source, value, max_value, votes, is_default)
+                    VALUES (?, ?, ?, ?, ?, ?)",
+                    metadata_id,
+                    rating.source,
+                    rating.value,
+                    rating.max,
+                    votes,
+                    rating.is_default,
+                )
+                .execute(&mut *conn)
+                .await?;
+            }
+            Ok(())
+        }
+    }
+
+    fn get_content_ratings(
+        self,
+        metadata_id: i64,
+    ) -> impl std::future::Future<Output = sqlx::Result<Vec<RatingMetadata>>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            let rows = sqlx::query!(
+                "SELECT source, value, max_value, votes, is_default FROM content_ratings
+                WHERE metadata_id = ? ORDER BY is_default DESC, source",
+                metadata_id
+            )
+            .fetch_all(&mut *conn)
+            .await?;
+            Ok(rows
+                .into_iter()
+                .map(|r| RatingMetadata {
+                    source: r.source,
+                    value: r.value,
+                    max: r.max_value,
+                    votes: r.votes.map(|v| v as u64),
+                    is_default: r.is_default,
+                })
+                .collect())
+        }
+    }
+
     fn insert_intro(
         self,
         intro: DbIntro,
@@ -565,6 +627,9 @@ where
 
     /// Updates a metadata row in place, reusing its id (and therefore preserving
     /// every foreign key that points at it: history, intros, saved list, etc.).
+    ///
+    /// Locked fields keep their current values.
+    /// Translations of the row are marked outdated, they are fetched again after the next library scan.
     fn update_metadata(
         self,
         metadata_id: i64,
@@ -572,14 +637,20 @@ where
     ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
         async move {
             let mut conn = self.acquire().await?;
+            let locks = fetch_metadata_locks(&mut conn, metadata_id).await?;
             sqlx::query!(
                 "UPDATE metadata SET
-                    title = ?, release_date = ?, poster = ?, plot = ?,
+                    title = iif(?, title, ?), release_date = iif(?, release_date, ?),
+                    poster = iif(?, poster, ?), plot = iif(?, plot, ?),
                     original_language = ?, original_title = ?
                 WHERE id = ?;",
+                locks.title,
                 metadata.title,
+                locks.release_date,
                 metadata.release_date,
+                locks.poster,
                 metadata.poster,
+                locks.plot,
                 metadata.plot,
                 metadata.original_language,
                 metadata.original_title,
@@ -587,6 +658,84 @@ where
             )
             .execute(&mut *conn)
             .await?;
+            sqlx::query!(
+                "UPDATE metadata_translations SET outdated = true WHERE metadata_id = ?",
+                metadata_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            Ok(())
+        }
+    }
+
+    fn get_metadata_locks(
+        self,
+        metadata_id: i64,
+    ) -> impl std::future::Future<Output = Result<MetadataLocks, Error>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            fetch_metadata_locks(&mut conn, metadata_id).await
+        }
+    }
+
+    /// Replaces the locks of the metadata row
+    fn set_metadata_locks(
+        self,
+        metadata_id: i64,
+        locks: MetadataLocks,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!(
+                "INSERT OR REPLACE INTO metadata_locks
+                    (metadata_id, title, plot, poster, backdrop, release_date, genres, cast_members)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?);",
+                metadata_id,
+                locks.title,
+                locks.plot,
+                locks.poster,
+                locks.backdrop,
+                locks.release_date,
+                locks.genres,
+                locks.cast,
+            )
+            .execute(&mut *conn)
+            .await?;
+            Ok(())
+        }
+    }
+
+    /// Sets the given locks keeping the ones that are already set
+    fn add_metadata_locks(
+        self,
+        metadata_id: i64,
+        locks: MetadataLocks,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!(
+                "INSERT INTO metadata_locks
+                    (metadata_id, title, plot, poster, backdrop, release_date, genres, cast_members)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (metadata_id) DO UPDATE SET
+                    title = title OR excluded.title,
+                    plot = plot OR excluded.plot,
+                    poster = poster OR excluded.poster,
+                    backdrop = backdrop OR excluded.backdrop,
+                    release_date = release_date OR excluded.release_date,
+                    genres = genres OR excluded.genres,
+                    cast_members = cast_members OR excluded.cast_members;",
+                metadata_id,
+                locks.title,
+                locks.plot,
+                locks.poster,
+                locks.backdrop,
+                locks.release_date,
+                locks.genres,
+                locks.cast,
+            )
+            .execute(&mut *conn)
+            .await?;
             Ok(())
         }
     }
@@ -627,6 +776,36 @@ where
         }
     }
 
+    /// Removes cast roles of a metadata row. Actors are kept
+    fn delete_roles(
+        self,
+        metadata_id: i64,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!("DELETE FROM roles WHERE metadata_id = ?;", metadata_id)
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        }
+    }
+
+    fn delete_content_genres(
+        self,
+        metadata_id: i64,
+    ) -> impl std::future::Future<Output = Result<(), Error>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!(
+                "DELETE FROM content_genres WHERE metadata_id = ?;",
+                metadata_id
+            )
+            .execute(&mut *conn)
+            .await?;
+            Ok(())
+        }
+    }
+
     /// Loads all season nodes of a show in a single query, indexed downstream by
     /// season number. Replaces per-season `get_season_id` lookups.
     fn get_show_season_nodes(
@@ -860,6 +1039,25 @@ where
         }
     }
 
+    /// Mark video as the prime version of its content, unmarking the rest
+    fn set_prime_video(
+        self,
+        metadata_id: i64,
+        video_id: i64,
+    ) -> impl std::future::Future<Output = sqlx::Result<()>> + Send {
+        async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query!(
+                "UPDATE videos SET is_prime = (id = ?) WHERE metadata_id = ?",
+                video_id,
+                metadata_id,
+            )
+            .execute(&mut *conn)
+            .await?;
+            Ok(())
+        }
+    }
+
     fn all_movies(
         self,
         params: ContentFetchParams,
@@ -1334,6 +1532,8 @@ where (actors.external_metadata_provider = ? and actors.external_metadata_id = ?
                         cast: None,
                         genres: None,
                         external_ids: None,
+                        collection: None,
+                        ratings: None,
                     }
                 })
                 .collect())
@@ -1390,6 +1590,7 @@ where (actors.external_metadata_provider = ? and actors.external_metadata_id = ?
                         genres: None,
                         external_ids: None,
                         next_episode_air_date: show.next_episode_air_date.map(Into::into),
+                        ratings: None,
                     }
                 })
                 .collect())
@@ -1480,6 +1681,23 @@ impl<'a> DbActions<'a> for &'a mut Transaction<'static, Sqlite> {}
 impl<'a> DbActions<'a> for &'a Pool<Sqlite> {}
 impl<'a> DbActions<'a> for &'a mut SqliteConnection {}
 
+async fn fetch_metadata_locks(
+    conn: &mut SqliteConnection,
+    metadata_id: i64,
+) -> Result<MetadataLocks, Error> {
+    let locks = sqlx::query_as!(
+        MetadataLocks,
+        r#"SELECT title as "title: bool", plot as "plot: bool", poster as "poster: bool",
+        backdrop as "backdrop: bool", release_date as "release_date: bool",
+        genres as "genres: bool", cast_members as "cast: bool"
+        FROM metadata_locks WHERE metadata_id = ?"#,
+        metadata_id
+    )
+    .fetch_optional(conn)
+    .await?;
+    Ok(locks.unwrap_or_default())
+}
+
 pub type DbTransaction = Transaction<'static, Sqlite>;
 pub type DbQueryBuilder = QueryBuilder<Sqlite>;
 
@@ -1711,6 +1929,7 @@ pub struct DbVideo {
     pub size: i64,
     pub metadata_id: Option<i64>,
     pub scan_date: String,
+    pub content_hash: Option<String>,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Default)]
@@ -1797,10 +2016,12 @@ pub struct DbRole {
     #[sqlx(rename = "role_metadata_id")]
     pub metadata_id: i64,
     pub character: Option<String>,
+    /// Crew job, `None` for the cast roles
+    pub job: Option<CrewJob>,
 }
 
 impl DbRole {
-    pub const SQL: &str = "roles.id as role_id, roles.actor_id as role_actor_id, roles.metadata_id as role_metadata_id, roles.character";
+    pub const SQL: &str = "roles.id as role_id, roles.actor_id as role_actor_id, roles.metadata_id as role_metadata_id, roles.character, roles.job";
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Default)]
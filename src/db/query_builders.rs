@@ -12,7 +12,7 @@ use crate::{
         server::Intro,
     },
     db::{self, DbActor, DbQueryBuilder, ListKind},
-    metadata::{ExternalIdMetadata, Genre, LocaleMetadata, MetadataProvider},
+    metadata::{CrewJob, ExternalIdMetadata, Genre, LocaleMetadata, MetadataProvider},
 };
 
 #[derive(sqlx::FromRow, Debug)]
@@ -109,6 +109,7 @@ pub struct CastQueryJson {
     pub name: String,
     pub poster: Option<String>,
     pub character: Option<String>,
+    pub job: Option<CrewJob>,
     pub imdb_id: Option<String>,
     pub external_metadata_provider: MetadataProvider,
     pub external_metadata_id: String,
@@ -120,6 +121,7 @@ impl CastQueryJson {
 'name', actors.name,
 'poster', actors.poster,
 'character', roles.character,
+'job', roles.job,
 'imdb_id', actors.imdb_id,
 'external_metadata_provider', actors.external_metadata_provider,
 'external_metadata_id', actors.external_metadata_id
@@ -137,6 +139,7 @@ impl From<CastQueryJson> for Actor {
             name,
             poster,
             character,
+            job,
             imdb_id,
             external_metadata_provider,
             external_metadata_id,
@@ -149,6 +152,7 @@ impl From<CastQueryJson> for Actor {
             metadata_provider: external_metadata_provider,
             imdb_id,
             character,
+            job,
             local: Some(local_actor::LocalActorData { id }),
         }
     }
@@ -417,6 +421,7 @@ impl From<DbActorsQuery> for Actor {
             poster: actor.poster,
             imdb_id: actor.imdb_id,
             character: None,
+            job: None,
         }
     }
 }
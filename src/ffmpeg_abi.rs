@@ -62,6 +62,8 @@ pub struct Video {
     pub bit_rate: usize,
     pub width: u32,
     pub height: u32,
+    /// PQ or HLG transfer characteristic
+    pub hdr: bool,
 }
 
 impl Video {
@@ -81,6 +83,7 @@ impl TryFrom<ffmpeg_next::Stream<'_>> for Video {
         let bit_rate;
         let profile;
         let level;
+        let transfer_characteristic;
         unsafe {
             let p = params.as_ptr();
             if p.is_null() {
@@ -91,7 +94,14 @@ impl TryFrom<ffmpeg_next::Stream<'_>> for Video {
             width = (*p).width as u32;
             height = (*p).height as u32;
             level = (*p).level;
+            transfer_characteristic =
+                ffmpeg_next::color::TransferCharacteristic::from((*p).color_trc);
         };
+        let hdr = matches!(
+            transfer_characteristic,
+            ffmpeg_next::color::TransferCharacteristic::SMPTE2084
+                | ffmpeg_next::color::TransferCharacteristic::ARIB_STD_B67
+        );
 
         let raw_profile = ffmpeg_next::codec::Profile::from((params.id(), profile));
         let codec = match (params.id(), raw_profile) {
@@ -126,6 +136,7 @@ impl TryFrom<ffmpeg_next::Stream<'_>> for Video {
             bit_rate,
             width,
             height,
+            hdr,
         })
     }
 }
@@ -522,6 +533,116 @@ pub async fn get_metadata(path: impl AsRef<Path>) -> anyhow::Result<ProbeOutput>
     .expect("never panic")
 }
 
+/// Full decode is expensive, only one file is decoded at a time
+static DECODE_SEMAPHORE: Semaphore = Semaphore::const_new(1);
+
+/// Result of the full decode pass
+#[derive(Debug, Default, Clone)]
+pub struct DecodeReport {
+    /// Packets that failed to demux or decode
+    pub errors: usize,
+    pub first_error: Option<String>,
+    /// Timestamp of the last decoded packet relative to the stream start
+    pub decoded_duration: Duration,
+}
+
+impl DecodeReport {
+    fn push_error(&mut self, error: impl std::fmt::Display) {
+        self.errors += 1;
+        if self.first_error.is_none() {
+            self.first_error = Some(error.to_string());
+        }
+    }
+}
+
+/// Decode every packet of the audio and video streams.
+///
+/// Takes as long as decoding the whole file
+pub async fn decode_check(path: impl AsRef<Path>) -> anyhow::Result<DecodeReport> {
+    fn is_drained(error: &ffmpeg_next::Error) -> bool {
+        matches!(
+            error,
+            ffmpeg_next::Error::Eof
+                | ffmpeg_next::Error::Other {
+                    errno: ffmpeg_next::util::error::EAGAIN
+                }
+        )
+    }
+
+    /// Receive all available frames from the decoder
+    fn drain_decoder(
+        decoder: &mut codec::decoder::Opened,
+        frame: &mut ffmpeg_next::Frame,
+        report: &mut DecodeReport,
+    ) {
+        loop {
+            match decoder.receive_frame(frame) {
+                Ok(()) => {}
+                Err(e) if is_drained(&e) => break,
+                Err(e) => {
+                    report.push_error(e);
+                    break;
+                }
+            }
+        }
+    }
+
+    let path = path.as_ref().to_path_buf();
+    let permit = DECODE_SEMAPHORE.acquire().await.unwrap();
+    tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        let mut input = ffmpeg_next::format::input(&path)?;
+        let mut decoders = std::collections::HashMap::new();
+        for stream in input.streams() {
+            let params = stream.parameters();
+            if !matches!(params.medium(), media::Type::Video | media::Type::Audio) {
+                continue;
+            }
+            let decoder = codec::Context::from_parameters(params)?
+                .decoder()
+                .open()
+                .with_context(|| format!("open decoder of the stream {}", stream.index()))?;
+            // Packet timestamps are offset by the stream start time
+            let start_time = match stream.start_time() {
+                ffmpeg_next::ffi::AV_NOPTS_VALUE => 0,
+                start_time => start_time,
+            };
+            decoders.insert(
+                stream.index(),
+                (decoder, f64::from(stream.time_base()), start_time),
+            );
+        }
+        anyhow::ensure!(!decoders.is_empty(), "file has no audio or video streams");
+
+        let mut report = DecodeReport::default();
+        let mut video_frame = ffmpeg_next::frame::Video::empty();
+        for (stream, packet) in input.packets() {
+            let Some((decoder, time_base, start_time)) = decoders.get_mut(&stream.index()) else {
+                continue;
+            };
+            if let Err(e) = decoder.send_packet(&packet) {
+                report.push_error(e);
+                continue;
+            }
+            // Frames are not inspected, one buffer is reused for all streams
+            drain_decoder(decoder, &mut video_frame, &mut report);
+            if let Some(pts) = packet.pts() {
+                let elapsed = pts.saturating_sub(*start_time);
+                let timestamp = Duration::from_secs_f64((elapsed as f64 * *time_base).max(0.));
+                report.decoded_duration = report.decoded_duration.max(timestamp);
+            }
+        }
+        for (decoder, ..) in decoders.values_mut() {
+            if decoder.send_eof().is_ok() {
+                drain_decoder(decoder, &mut video_frame, &mut report);
+            }
+        }
+        Ok(report)
+    })
+    .await
+    .expect("never panic")
+}
+
 /// h265 encoders
 #[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize, utoipa::ToSchema)]
 #[serde(rename_all = "lowercase")]
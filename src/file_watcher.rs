@@ -4,10 +4,7 @@ use tokio::sync::mpsc::{self};
 
 use notify::{Event, EventKind, RecursiveMode, Watcher};
 
-use crate::{
-    app_state::AppState,
-    config::{self, APP_RESOURCES},
-};
+use crate::{app_state::AppState, config::APP_RESOURCES, library::libraries::LibraryFolders};
 
 #[derive(Debug, Clone)]
 enum EventType {
@@ -53,11 +50,20 @@ impl FileWatcher {
         })?;
 
         let cancellation_token = app_state.cancelation_token.clone();
-        let (mut show_dirs, mut movie_dirs) =
-            config::CONFIG.get_values::<(config::ShowFolders, config::MovieFolders)>();
         let config_path = APP_RESOURCES.config_path.clone();
 
         tokio::spawn(async move {
+            // Folders of the config and of the libraries created with the API
+            let LibraryFolders { shows, movies, .. } = LibraryFolders::load(app_state.db).await;
+            let mut watched = Vec::new();
+            for path in shows.into_iter().chain(movies) {
+                match watcher.watch(&path, RecursiveMode::NonRecursive) {
+                    Ok(()) => watched.push(path),
+                    Err(e) => {
+                        tracing::error!("Failed to add {} to the watcher: {e}", path.display())
+                    }
+                }
+            }
             loop {
                 tokio::select! {
                     Some(event) = notify_rx.recv() => {
@@ -67,14 +73,17 @@ impl FileWatcher {
                                 | EventKind::Create(_)
                                 | EventKind::Modify(_) => {
                                     tracing::debug!("Received watcher event: {:?}", event);
+                                    let LibraryFolders { shows, movies, ignore } =
+                                        LibraryFolders::load(app_state.db).await;
                                     for path in event.paths {
+                                        if ignore.is_path_ignored(&path) {
+                                            tracing::trace!("Skipping ignored path: {}", path.display());
+                                            continue;
+                                        }
                                         if path == config_path {
                                             // load config
                                         }
-                                        if show_dirs.0.contains(&path) {
-                                            app_state.partial_refresh().await;
-                                        }
-                                        if movie_dirs.0.contains(&path) {
+                                        if shows.iter().chain(&movies).any(|dir| path.starts_with(dir)) {
                                             app_state.partial_refresh().await;
                                         }
                                     }
@@ -88,17 +97,17 @@ impl FileWatcher {
                     }
                     Some(command) = command_rx.recv() => {
                         match command {
+                            WatchCommand::Watch(path) if watched.contains(&path) => {},
                             WatchCommand::Watch(path) => {
                                 if let Err(e) = watcher.watch(&path, RecursiveMode::NonRecursive) {
                                     tracing::error!("Failed to add {} to the watcher: {e}", path.display());
                                 } else {
-                                    show_dirs.add(path);
+                                    watched.push(path);
                                 };
                             },
                             WatchCommand::UnWatch(path) => {
                                 let _ = watcher.unwatch(&path);
-                                show_dirs.0.retain(|p| *p != path);
-                                movie_dirs.0.retain(|p| *p != path);
+                                watched.retain(|p| *p != path);
                             },
                         }
                     }
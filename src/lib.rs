@@ -48,6 +48,8 @@ pub mod resources;
 /// 2. New metadata and assets must be saved.
 /// 3. Library items should be linked to their metadata
 pub mod scan;
+/// Background jobs started on a schedule
+pub mod scheduler;
 /// Glue between torrent crate and media server
 pub mod torrent;
 /// Torrent providers
@@ -145,6 +145,7 @@ pub enum PosterContentType {
     Episode,
     Season,
     Actor,
+    Collection,
 }
 impl From<PosterContentType> for AssetContentType {
     fn from(val: PosterContentType) -> Self {
@@ -154,6 +155,7 @@ impl From<PosterContentType> for AssetContentType {
             PosterContentType::Episode => AssetContentType::Episode,
             PosterContentType::Season => AssetContentType::Season,
             PosterContentType::Actor => AssetContentType::Actor,
+            PosterContentType::Collection => AssetContentType::Collection,
         }
     }
 }
@@ -176,12 +178,14 @@ impl FileAsset for BackdropAsset {
 pub enum BackdropContentType {
     Show,
     Movie,
+    Collection,
 }
 impl From<BackdropContentType> for AssetContentType {
     fn from(val: BackdropContentType) -> Self {
         match val {
             BackdropContentType::Show => AssetContentType::Show,
             BackdropContentType::Movie => AssetContentType::Movie,
+            BackdropContentType::Collection => AssetContentType::Collection,
         }
     }
 }
@@ -431,6 +435,7 @@ pub enum AssetContentType {
     Episode,
     Video,
     Actor,
+    Collection,
 }
 
 impl Display for AssetContentType {
@@ -442,6 +447,7 @@ impl Display for AssetContentType {
             AssetContentType::Episode => write!(f, "episode"),
             AssetContentType::Video => write!(f, "video"),
             AssetContentType::Actor => write!(f, "actor"),
+            AssetContentType::Collection => write!(f, "collection"),
         }
     }
 }
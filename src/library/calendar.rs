@@ -0,0 +1,357 @@
+use std::fmt::Write;
+
+use serde::Serialize;
+use time::{Date, format_description::well_known::Iso8601};
+
+use crate::{
+    db::{Db, DbTransaction, ListKind},
+    library::{missing_episodes, numbering},
+    metadata::{FetchParams, LeafMediaType, metadata_stack::MetadataProvidersStack},
+};
+
+/// Aired episodes are kept in the calendar for this amount of days
+const KEEP_AIRED_DAYS: i64 = 7;
+
+/// Episode or movie release
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct CalendarEntry {
+    pub kind: LeafMediaType,
+    /// ISO 8601 release date
+    pub air_date: String,
+    pub title: String,
+    pub poster: Option<String>,
+    pub show_id: Option<i64>,
+    pub show_title: Option<String>,
+    pub season: Option<i64>,
+    pub episode: Option<i64>,
+    pub movie_id: Option<i64>,
+}
+
+/// Shows that are in the watch history or in the watchlist
+async fn tracked_shows(db: &Db) -> sqlx::Result<Vec<i64>> {
+    sqlx::query_scalar!(
+        r#"select shows.id as "id!" from shows
+        join list_items on list_items.metadata_id = shows.metadata_id
+        where list_items.list_id = ?
+        union
+        select seasons.show_id from history
+        join episodes on episodes.metadata_id = history.metadata_id
+        join seasons on seasons.id = episodes.season_id"#,
+        ListKind::WATCH_ID
+    )
+    .fetch_all(&db.pool)
+    .await
+}
+
+async fn save_show_entries(
+    tx: &mut DbTransaction,
+    show_id: i64,
+    next_episode_air_date: Option<time::OffsetDateTime>,
+    entries: &[CalendarEntry],
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "update shows set next_episode_air_date = ? where id = ?",
+        next_episode_air_date,
+        show_id
+    )
+    .execute(&mut **tx)
+    .await?;
+    sqlx::query!("delete from calendar_episodes where show_id = ?", show_id)
+        .execute(&mut **tx)
+        .await?;
+    for entry in entries {
+        sqlx::query!(
+            "insert or replace into calendar_episodes (show_id, season, episode, title, air_date, poster)
+            values (?, ?, ?, ?, ?, ?)",
+            show_id,
+            entry.season,
+            entry.episode,
+            entry.title,
+            entry.air_date,
+            entry.poster,
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Fetch air dates of the latest season of the show
+async fn refresh_show(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    show_id: i64,
+    fetch_params: FetchParams,
+) -> crate::Result<()> {
+    let fetch_params = FetchParams {
+        ordering: numbering::get(db, show_id).await?.ordering,
+        ..fetch_params
+    };
+    let (provider, provider_show_id) = numbering::show_provider(db, providers, show_id).await?;
+    let show = provider.show(&provider_show_id, fetch_params).await?;
+    let since = time::OffsetDateTime::now_utc().date() - time::Duration::days(KEEP_AIRED_DAYS);
+    let mut entries = Vec::new();
+    if let Some(last_season) = show.seasons.iter().flatten().copied().max() {
+        let season = provider
+            .season(&provider_show_id, last_season, fetch_params)
+            .await?;
+        for episode in season.episodes {
+            let Some(air_date) = missing_episodes::air_date(&episode).filter(|d| *d >= since)
+            else {
+                continue;
+            };
+            entries.push(CalendarEntry {
+                kind: LeafMediaType::Episode,
+                air_date: air_date.to_string(),
+                title: episode.title,
+                poster: episode.poster,
+                show_id: Some(show_id),
+                show_title: None,
+                season: Some(last_season as i64),
+                episode: Some(episode.number as i64),
+                movie_id: None,
+            });
+        }
+    }
+    let mut tx = db.begin().await?;
+    save_show_entries(
+        &mut tx,
+        show_id,
+        show.next_episode_air_date.map(Into::into),
+        &entries,
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Refresh air dates of all tracked shows
+pub async fn refresh(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    fetch_params: FetchParams,
+) -> sqlx::Result<()> {
+    let shows = tracked_shows(db).await?;
+    tracing::debug!("Refreshing calendar of {} shows", shows.len());
+    let calendar_shows = sqlx::query_scalar!("select distinct show_id from calendar_episodes")
+        .fetch_all(&db.pool)
+        .await?;
+    for show_id in calendar_shows.into_iter().filter(|id| !shows.contains(id)) {
+        sqlx::query!("delete from calendar_episodes where show_id = ?", show_id)
+            .execute(&db.pool)
+            .await?;
+    }
+    for show_id in shows {
+        if let Err(e) = refresh_show(db, providers, show_id, fetch_params).await {
+            tracing::warn!("Failed to refresh calendar of the show {show_id}: {e}");
+        }
+    }
+    Ok(())
+}
+
+/// Episodes and watchlisted movies released in the given date range, ordered by date
+pub async fn entries(db: &Db, from: Date, to: Date) -> sqlx::Result<Vec<CalendarEntry>> {
+    let from = from.to_string();
+    let to = to.to_string();
+    let episodes = sqlx::query!(
+        "select calendar_episodes.show_id, calendar_episodes.season, calendar_episodes.episode,
+        calendar_episodes.title, calendar_episodes.air_date, calendar_episodes.poster,
+        metadata.title as show_title from calendar_episodes
+        join shows on shows.id = calendar_episodes.show_id
+        join metadata on metadata.id = shows.metadata_id
+        where calendar_episodes.air_date between ? and ?",
+        from,
+        to
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let movies = sqlx::query!(
+        r#"select movies.id, metadata.title, metadata.release_date as "release_date!", metadata.poster
+        from list_items
+        join movies on movies.metadata_id = list_items.metadata_id
+        join metadata on metadata.id = movies.metadata_id
+        where list_items.list_id = ? and metadata.release_date between ? and ?"#,
+        ListKind::WATCH_ID,
+        from,
+        to
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut entries: Vec<_> = episodes
+        .into_iter()
+        .map(|row| CalendarEntry {
+            kind: LeafMediaType::Episode,
+            air_date: row.air_date,
+            title: row.title,
+            poster: row.poster,
+            show_id: Some(row.show_id),
+            show_title: Some(row.show_title),
+            season: Some(row.season),
+            episode: Some(row.episode),
+            movie_id: None,
+        })
+        .chain(movies.into_iter().map(|row| CalendarEntry {
+            kind: LeafMediaType::Movie,
+            air_date: row.release_date,
+            title: row.title,
+            poster: row.poster,
+            show_id: None,
+            show_title: None,
+            season: None,
+            episode: None,
+            movie_id: Some(row.id),
+        }))
+        .collect();
+    entries.sort_by(|a, b| a.air_date.cmp(&b.air_date));
+    Ok(entries)
+}
+
+/// Escape iCalendar text value
+fn escape_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Write content line folded to 75 octets
+fn write_line(out: &mut String, line: &str) {
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            len = 1;
+        }
+        out.push(c);
+        len += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Date in iCalendar basic format: 20261018
+fn basic_date(date: Date) -> String {
+    format!(
+        "{:04}{:02}{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day()
+    )
+}
+
+/// Calendar entries as iCalendar (RFC 5545) feed with all-day events
+pub fn to_ical(entries: &[CalendarEntry], now: time::OffsetDateTime) -> String {
+    let now = now.to_offset(time::UtcOffset::UTC);
+    let stamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        basic_date(now.date()),
+        now.hour(),
+        now.minute(),
+        now.second()
+    );
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//media-server//calendar//EN",
+        "CALSCALE:GREGORIAN",
+        "X-WR-CALNAME:Media server releases",
+    ] {
+        write_line(&mut out, line);
+    }
+    for entry in entries {
+        let Ok(date) = Date::parse(&entry.air_date, &Iso8601::DATE) else {
+            continue;
+        };
+        let (uid, summary) = match entry.kind {
+            LeafMediaType::Episode => {
+                let season = entry.season.unwrap_or_default();
+                let episode = entry.episode.unwrap_or_default();
+                let show = entry.show_title.as_deref().unwrap_or_default();
+                (
+                    format!(
+                        "episode-{}-{season}-{episode}@media-server",
+                        entry.show_id.unwrap_or_default()
+                    ),
+                    format!("{show} S{season:02}E{episode:02} - {}", entry.title),
+                )
+            }
+            LeafMediaType::Movie => (
+                format!("movie-{}@media-server", entry.movie_id.unwrap_or_default()),
+                entry.title.clone(),
+            ),
+        };
+        let start = basic_date(date);
+        let end = basic_date(date.next_day().unwrap_or(date));
+        let mut event = String::new();
+        let _ = write!(
+            event,
+            "BEGIN:VEVENT\nUID:{uid}\nDTSTAMP:{stamp}\nDTSTART;VALUE=DATE:{start}\nDTEND;VALUE=DATE:{end}\nSUMMARY:{}\nEND:VEVENT",
+            escape_text(&summary)
+        );
+        for line in event.split('\n') {
+            write_line(&mut out, line);
+        }
+    }
+    write_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, Time};
+
+    use crate::metadata::LeafMediaType;
+
+    use super::CalendarEntry;
+
+    #[test]
+    fn ical_feed() {
+        let entries = [
+            CalendarEntry {
+                kind: LeafMediaType::Episode,
+                air_date: "2026-10-25".into(),
+                title: "Rain, Snow; Sleet".into(),
+                poster: None,
+                show_id: Some(3),
+                show_title: Some("Frieren".into()),
+                season: Some(2),
+                episode: Some(4),
+                movie_id: None,
+            },
+            CalendarEntry {
+                kind: LeafMediaType::Movie,
+                air_date: "2026-12-31".into(),
+                title: "A".repeat(80),
+                poster: None,
+                show_id: None,
+                show_title: None,
+                season: None,
+                episode: None,
+                movie_id: Some(7),
+            },
+        ];
+        let now = Date::from_calendar_date(2026, Month::October, 18)
+            .unwrap()
+            .with_time(Time::from_hms(12, 30, 0).unwrap())
+            .assume_utc();
+        let ical = super::to_ical(&entries, now);
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        assert!(ical.contains("UID:episode-3-2-4@media-server\r\n"));
+        assert!(ical.contains("DTSTAMP:20261018T123000Z\r\n"));
+        assert!(ical.contains("DTSTART;VALUE=DATE:20261025\r\nDTEND;VALUE=DATE:20261026\r\n"));
+        assert!(ical.contains("SUMMARY:Frieren S02E04 - Rain\\, Snow\\; Sleet\r\n"));
+        assert!(ical.contains("DTSTART;VALUE=DATE:20261231\r\nDTEND;VALUE=DATE:20270101\r\n"));
+        assert!(ical.lines().all(|l| l.len() <= 75));
+        assert!(ical.contains("\r\n A"));
+    }
+}
@@ -0,0 +1,289 @@
+use serde::Serialize;
+
+use crate::{
+    db::{Db, DbTransaction},
+    metadata::{CollectionMetadata, MetadataProvider},
+};
+
+/// Collection parts are fetched again after this amount of days
+const PARTS_TTL_DAYS: u32 = 30;
+
+/// Movie collection with the amount of movies present in the library
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct Collection {
+    pub id: i64,
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub title: String,
+    pub plot: Option<String>,
+    pub poster: Option<String>,
+    pub backdrop: Option<String>,
+    /// Collection movies that are present in the library
+    pub owned: i64,
+    /// All collection movies
+    pub total: i64,
+}
+
+/// Single collection movie, either present in the library or missing
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct CollectionEntry {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub title: String,
+    pub release_date: Option<String>,
+    pub poster: Option<String>,
+    /// Local movie id, empty when movie is missing in the library
+    pub movie_id: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct CollectionDetails {
+    #[serde(flatten)]
+    pub collection: Collection,
+    /// Collection movies ordered by release date
+    pub entries: Vec<CollectionEntry>,
+}
+
+/// Collections that have at least one movie in the library
+pub async fn all(db: &Db) -> sqlx::Result<Vec<Collection>> {
+    sqlx::query_as!(
+        Collection,
+        r#"with owned_parts as (
+          select distinct collection_parts.id from collection_parts
+          join external_ids on external_ids.external_provider = collection_parts.metadata_provider
+          and external_ids.external_id = collection_parts.metadata_id
+          join movies on movies.metadata_id = external_ids.metadata_id
+          join videos on videos.metadata_id = movies.metadata_id
+        )
+        select collections.id as "id!", collections.metadata_id,
+        collections.metadata_provider as "metadata_provider: MetadataProvider",
+        collections.title, collections.plot, collections.poster, collections.backdrop,
+        count(owned_parts.id) as "owned!: i64", count(collection_parts.id) as "total!: i64"
+        from collections
+        left join collection_parts on collection_parts.collection_id = collections.id
+        left join owned_parts on owned_parts.id = collection_parts.id
+        group by collections.id having count(owned_parts.id) > 0
+        order by collections.title"#
+    )
+    .fetch_all(&db.pool)
+    .await
+}
+
+pub async fn get(db: &Db, id: i64) -> sqlx::Result<CollectionDetails> {
+    let collection = sqlx::query_as!(
+        Collection,
+        r#"with owned_parts as (
+          select distinct collection_parts.id from collection_parts
+          join external_ids on external_ids.external_provider = collection_parts.metadata_provider
+          and external_ids.external_id = collection_parts.metadata_id
+          join movies on movies.metadata_id = external_ids.metadata_id
+          join videos on videos.metadata_id = movies.metadata_id
+          where collection_parts.collection_id = ?1
+        )
+        select collections.id as "id!", collections.metadata_id,
+        collections.metadata_provider as "metadata_provider: MetadataProvider",
+        collections.title, collections.plot, collections.poster, collections.backdrop,
+        count(owned_parts.id) as "owned!: i64", count(collection_parts.id) as "total!: i64"
+        from collections
+        left join collection_parts on collection_parts.collection_id = collections.id
+        left join owned_parts on owned_parts.id = collection_parts.id
+        where collections.id = ?1
+        group by collections.id having count(owned_parts.id) > 0"#,
+        id
+    )
+    .fetch_one(&db.pool)
+    .await?;
+    let entries = sqlx::query_as!(
+        CollectionEntry,
+        r#"select collection_parts.metadata_id,
+        collection_parts.metadata_provider as "metadata_provider: MetadataProvider",
+        collection_parts.title, collection_parts.release_date, collection_parts.poster,
+        (select movies.id from external_ids
+          join movies on movies.metadata_id = external_ids.metadata_id
+          join videos on videos.metadata_id = movies.metadata_id
+          where external_ids.external_provider = collection_parts.metadata_provider
+          and external_ids.external_id = collection_parts.metadata_id limit 1) as "movie_id: i64"
+        from collection_parts where collection_parts.collection_id = ?
+        order by collection_parts.release_date is null, collection_parts.release_date"#,
+        id
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(CollectionDetails {
+        collection,
+        entries,
+    })
+}
+
+/// Whether collection parts are saved and were fetched less than [`PARTS_TTL_DAYS`] ago
+pub async fn has_fresh_parts(db: &Db, collection: &CollectionMetadata) -> sqlx::Result<bool> {
+    let cutoff = format!("-{PARTS_TTL_DAYS} days");
+    sqlx::query_scalar!(
+        r#"select exists(select 1 from collections
+        where metadata_provider = ? and metadata_id = ?
+        and parts_updated_at > datetime('now', ?)) as "fresh!: bool""#,
+        collection.metadata_provider,
+        collection.metadata_id,
+        cutoff,
+    )
+    .fetch_one(&db.pool)
+    .await
+}
+
+/// Save collection and replace its parts when they are known.
+///
+/// Returns collection id and whether collection is newly created
+pub async fn save(
+    tx: &mut DbTransaction,
+    collection: &CollectionMetadata,
+) -> sqlx::Result<(i64, bool)> {
+    let existing = sqlx::query_scalar!(
+        "select id from collections where metadata_provider = ? and metadata_id = ?",
+        collection.metadata_provider,
+        collection.metadata_id,
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let (id, is_new) = match existing {
+        Some(id) => (id, false),
+        None => {
+            let id = sqlx::query_scalar!(
+                "insert into collections (metadata_id, metadata_provider, title, plot, poster, backdrop)
+                values (?, ?, ?, ?, ?, ?) returning id",
+                collection.metadata_id,
+                collection.metadata_provider,
+                collection.title,
+                collection.plot,
+                collection.poster,
+                collection.backdrop,
+            )
+            .fetch_one(&mut **tx)
+            .await?;
+            (id, true)
+        }
+    };
+    if !collection.parts.is_empty() {
+        sqlx::query!(
+            "update collections set title = ?, plot = coalesce(?, plot),
+            parts_updated_at = current_timestamp where id = ?",
+            collection.title,
+            collection.plot,
+            id
+        )
+        .execute(&mut **tx)
+        .await?;
+        sqlx::query!("delete from collection_parts where collection_id = ?", id)
+            .execute(&mut **tx)
+            .await?;
+        for part in &collection.parts {
+            sqlx::query!(
+                "insert or ignore into collection_parts
+                (collection_id, metadata_id, metadata_provider, title, release_date, poster)
+                values (?, ?, ?, ?, ?, ?)",
+                id,
+                part.metadata_id,
+                part.metadata_provider,
+                part.title,
+                part.release_date,
+                part.poster,
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok((id, is_new))
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use crate::{
+        db::{DbActions, DbExternalId},
+        metadata::{
+            CollectionMetadata, CollectionPart, MetadataProvider, MovieMetadata,
+            metadata_api::tests::leak_db,
+        },
+    };
+
+    fn part(id: &str, title: &str, release_date: Option<&str>) -> CollectionPart {
+        CollectionPart {
+            metadata_id: id.into(),
+            metadata_provider: MetadataProvider::Tmdb,
+            title: title.into(),
+            release_date: release_date.map(Into::into),
+            poster: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn owned_and_missing_entries(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let collection = CollectionMetadata {
+            metadata_id: "10".into(),
+            metadata_provider: MetadataProvider::Tmdb,
+            title: "Star Wars Collection".into(),
+            parts: vec![
+                part("3", "Unannounced", None),
+                part("2", "The Empire Strikes Back", Some("1980-05-20")),
+                part("1", "Star Wars", Some("1977-05-25")),
+            ],
+            ..Default::default()
+        };
+
+        let mut tx = db.begin().await?;
+        let (id, is_new) = super::save(&mut tx, &collection).await?;
+        assert!(is_new);
+        let movie = MovieMetadata {
+            title: "Star Wars".into(),
+            ..Default::default()
+        };
+        let metadata_id = tx.insert_metadata(&movie.into_db_metadata()).await?;
+        tx.insert_movie(&movie.into_db_movie(metadata_id, Default::default()))
+            .await?;
+        tx.insert_external_id(DbExternalId {
+            external_provider: MetadataProvider::Tmdb,
+            external_id: "1".into(),
+            metadata_id: Some(metadata_id),
+            is_prime: 1,
+            ..Default::default()
+        })
+        .await?;
+        let path = "/movies/Star Wars.mkv";
+        sqlx::query!(
+            "insert into videos (path, size, metadata_id, is_prime) values (?, 1, ?, true)",
+            path,
+            metadata_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        assert!(super::has_fresh_parts(db, &collection).await?);
+        let details = super::get(db, id).await?;
+        assert_eq!(details.collection.owned, 1);
+        assert_eq!(details.collection.total, 3);
+        let titles: Vec<_> = details.entries.iter().map(|e| e.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            ["Star Wars", "The Empire Strikes Back", "Unannounced"]
+        );
+        assert!(details.entries[0].movie_id.is_some());
+        assert!(details.entries[1].movie_id.is_none());
+
+        let mut tx = db.begin().await?;
+        let (same_id, is_new) = super::save(&mut tx, &collection).await?;
+        tx.commit().await?;
+        assert_eq!((same_id, is_new), (id, false));
+        assert_eq!(super::all(db).await?.len(), 1);
+
+        sqlx::query!(
+            "update collections set parts_updated_at = datetime('now', '-60 days') where id = ?",
+            id
+        )
+        .execute(&db.pool)
+        .await?;
+        assert!(!super::has_fresh_parts(db, &collection).await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    db::Db,
+    ffmpeg_abi::{self, DecodeReport, ProbeOutput},
+    library::{Library, media::Video, statistics},
+    progress::{ProgressDispatcher, TaskTrait},
+};
+
+/// Smallest plausible average bitrate of a video file
+const MIN_BITRATE: u64 = 64_000;
+/// File bitrate below this share of the streams bitrate means that data is missing
+const MIN_BITRATE_RATIO: f64 = 0.5;
+/// Decoded duration can be shorter than container duration by this amount
+const TRUNCATION_TOLERANCE: Duration = Duration::from_secs(10);
+/// Check results are written to the database in batches of this size
+const SAVE_BATCH_SIZE: usize = 50;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum HealthIssueKind {
+    /// File can't be opened or probed
+    Unreadable,
+    MissingVideo,
+    MissingAudio,
+    ZeroDuration,
+    /// File is too small for its duration, usually a half-copied file
+    SizeMismatch,
+    /// Decoding stopped before the end of the file
+    Truncated,
+    DecodeErrors,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct HealthIssue {
+    pub kind: HealthIssueKind,
+    pub message: String,
+}
+
+impl HealthIssue {
+    pub fn new(kind: HealthIssueKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Video can't be played at all
+    pub fn is_fatal(&self) -> bool {
+        self.kind == HealthIssueKind::Unreadable
+    }
+}
+
+/// Issues of the video found during its last check
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct VideoHealthReport {
+    pub video_id: i64,
+    pub path: String,
+    pub checked_at: crate::OffsetDateTime,
+    pub issues: Vec<HealthIssue>,
+}
+
+/// Library wide health check
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct HealthCheckTask {
+    /// Full decode pass is performed
+    pub decode: bool,
+}
+
+impl PartialEq for HealthCheckTask {
+    fn eq(&self, _other: &Self) -> bool {
+        // Only one health check can run at a time
+        true
+    }
+}
+
+impl Eq for HealthCheckTask {}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct HealthCheckProgress {
+    pub checked: usize,
+    pub total: usize,
+    /// Videos with at least one issue
+    pub unhealthy: usize,
+}
+
+impl TaskTrait for HealthCheckTask {
+    type Progress = HealthCheckProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> crate::progress::TaskProgress
+    where
+        Self: Sized,
+    {
+        crate::progress::TaskProgress::HealthCheck(status)
+    }
+}
+
+fn size_issue(duration: Duration, file_size: u64, streams_bitrate: u64) -> Option<HealthIssue> {
+    if duration.is_zero() {
+        return None;
+    }
+    let bitrate = (file_size as f64 * 8. / duration.as_secs_f64()) as u64;
+    if bitrate < MIN_BITRATE {
+        return Some(HealthIssue::new(
+            HealthIssueKind::SizeMismatch,
+            format!(
+                "File size {file_size} bytes is too small for {}s duration",
+                duration.as_secs()
+            ),
+        ));
+    }
+    if streams_bitrate > 0 && (bitrate as f64) < streams_bitrate as f64 * MIN_BITRATE_RATIO {
+        return Some(HealthIssue::new(
+            HealthIssueKind::SizeMismatch,
+            format!("File bitrate {bitrate} is much lower than streams bitrate {streams_bitrate}"),
+        ));
+    }
+    None
+}
+
+/// Sanity checks of the container metadata
+pub fn probe_issues(probe: &ProbeOutput, file_size: u64) -> Vec<HealthIssue> {
+    let mut issues = Vec::new();
+    if probe.video_streams().next().is_none() {
+        issues.push(HealthIssue::new(
+            HealthIssueKind::MissingVideo,
+            "File has no video streams",
+        ));
+    }
+    if probe.audio_streams().next().is_none() {
+        issues.push(HealthIssue::new(
+            HealthIssueKind::MissingAudio,
+            "File has no audio streams",
+        ));
+    }
+    let duration = probe.duration();
+    if duration.is_zero() {
+        issues.push(HealthIssue::new(
+            HealthIssueKind::ZeroDuration,
+            "Container duration is zero",
+        ));
+    }
+    let streams_bitrate = probe
+        .default_video()
+        .map(|v| v.bit_rate as u64)
+        .unwrap_or_default()
+        + probe
+            .default_audio()
+            .map(|a| a.bit_rate as u64)
+            .unwrap_or_default();
+    issues.extend(size_issue(duration, file_size, streams_bitrate));
+    issues
+}
+
+/// Issues found during the full decode pass
+pub fn decode_issues(report: &DecodeReport, duration: Duration) -> Vec<HealthIssue> {
+    let mut issues = Vec::new();
+    if report.errors > 0 {
+        let first_error = report.first_error.as_deref().unwrap_or("unknown error");
+        issues.push(HealthIssue::new(
+            HealthIssueKind::DecodeErrors,
+            format!("{} decoding errors, first: {first_error}", report.errors),
+        ));
+    }
+    if duration.saturating_sub(report.decoded_duration) > TRUNCATION_TOLERANCE {
+        issues.push(HealthIssue::new(
+            HealthIssueKind::Truncated,
+            format!(
+                "Decoding stopped at {}s out of {}s",
+                report.decoded_duration.as_secs(),
+                duration.as_secs()
+            ),
+        ));
+    }
+    issues
+}
+
+/// Check the container metadata of the video, cheap enough to run during the library scan
+pub async fn probe_video(video: &Video) -> Vec<HealthIssue> {
+    let probe = match video.metadata().await {
+        Ok(probe) => probe,
+        Err(e) => {
+            return vec![HealthIssue::new(
+                HealthIssueKind::Unreadable,
+                format!("{e:#}"),
+            )];
+        }
+    };
+    let file_size = match video.file_size().await {
+        Ok(size) => size,
+        Err(e) => return vec![HealthIssue::new(HealthIssueKind::Unreadable, e.to_string())],
+    };
+    probe_issues(probe, file_size)
+}
+
+/// Check the video, full decode pass is optional because it takes as long as decoding the file
+pub async fn check_video(video: &Video, decode: bool) -> Vec<HealthIssue> {
+    let mut issues = probe_video(video).await;
+    if decode && !issues.iter().any(HealthIssue::is_fatal) {
+        let duration = video
+            .cached_metadata()
+            .map(|probe| probe.duration())
+            .unwrap_or_default();
+        match ffmpeg_abi::decode_check(video.path()).await {
+            Ok(report) => issues.extend(decode_issues(&report, duration)),
+            Err(e) => issues.push(HealthIssue::new(
+                HealthIssueKind::Unreadable,
+                format!("{e:#}"),
+            )),
+        }
+    }
+    issues
+}
+
+/// Replace issues of the videos with the results of their latest checks
+pub async fn save_all(db: &Db, checks: &[(i64, Vec<HealthIssue>)]) -> sqlx::Result<()> {
+    if checks.is_empty() {
+        return Ok(());
+    }
+    let mut tx = db.begin().await?;
+    for (video_id, issues) in checks {
+        sqlx::query!(
+            "delete from video_health_issues where video_id = ?",
+            video_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        for issue in issues {
+            sqlx::query!(
+                "insert into video_health_issues (video_id, kind, message) values (?, ?, ?)",
+                video_id,
+                issue.kind,
+                issue.message,
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Videos that have issues
+pub async fn report(db: &Db) -> sqlx::Result<Vec<VideoHealthReport>> {
+    let rows = sqlx::query!(
+        r#"select video_health_issues.video_id, videos.path, video_health_issues.checked_at,
+        video_health_issues.kind as "kind: HealthIssueKind", video_health_issues.message
+        from video_health_issues
+        join videos on videos.id = video_health_issues.video_id
+        order by videos.path, video_health_issues.id"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut reports: BTreeMap<String, VideoHealthReport> = BTreeMap::new();
+    for row in rows {
+        let report = reports
+            .entry(row.path.clone())
+            .or_insert_with(|| VideoHealthReport {
+                video_id: row.video_id,
+                path: row.path,
+                checked_at: row.checked_at.into(),
+                issues: Vec::new(),
+            });
+        report.issues.push(HealthIssue {
+            kind: row.kind,
+            message: row.message,
+        });
+    }
+    Ok(reports.into_values().collect())
+}
+
+/// Check every video of the library
+pub async fn check_library(
+    db: &Db,
+    library: &std::sync::Mutex<Library>,
+    decode: bool,
+    progress: ProgressDispatcher<HealthCheckTask>,
+    cancel: CancellationToken,
+) {
+    let sources: Vec<_> = {
+        let library = library.lock().unwrap();
+        library
+            .videos
+            .iter()
+            .map(|(id, file)| (*id, file.source.video.clone()))
+            .collect()
+    };
+    let total = sources.len();
+    let mut unhealthy = 0;
+    let mut checks = Vec::with_capacity(SAVE_BATCH_SIZE);
+    let mut probed = Vec::with_capacity(SAVE_BATCH_SIZE);
+    for (checked, (video_id, video)) in sources.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let issues = check_video(&video, decode).await;
+        if !issues.is_empty() {
+            tracing::warn!(
+                path = %video.path().display(),
+                "Video has {} health issues",
+                issues.len()
+            );
+            unhealthy += 1;
+        }
+        checks.push((video_id, issues));
+        probed.push((video_id, video));
+        if checks.len() >= SAVE_BATCH_SIZE {
+            flush_checks(db, &mut checks, &mut probed).await;
+        }
+        progress.progress(HealthCheckProgress {
+            checked: checked + 1,
+            total,
+            unhealthy,
+        });
+    }
+    flush_checks(db, &mut checks, &mut probed).await;
+    if cancel.is_cancelled() {
+        return;
+    }
+    tracing::info!("Health check found {unhealthy} unhealthy videos out of {total}");
+    progress.finish();
+}
+
+async fn flush_checks(
+    db: &Db,
+    checks: &mut Vec<(i64, Vec<HealthIssue>)>,
+    probed: &mut Vec<(i64, Video)>,
+) {
+    if let Err(e) = save_all(db, checks).await {
+        tracing::error!("Failed to save video health issues: {e}");
+    }
+    let probes: Vec<_> = probed
+        .iter()
+        .filter_map(|(id, video)| Some((*id, video.cached_metadata()?)))
+        .collect();
+    if let Err(e) = statistics::save_probes(db, &probes).await {
+        tracing::error!("Failed to save video probes: {e}");
+    }
+    checks.clear();
+    probed.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::ffmpeg_abi::DecodeReport;
+
+    use super::{HealthIssueKind, decode_issues, size_issue};
+
+    #[test]
+    fn size_mismatch() {
+        let hour = Duration::from_secs(60 * 60);
+        // 1GB hour long video is fine
+        assert_eq!(size_issue(hour, 1_000_000_000, 2_000_000), None);
+        // 10MB is too small for an hour
+        let issue = size_issue(hour, 10_000_000, 0).unwrap();
+        assert_eq!(issue.kind, HealthIssueKind::SizeMismatch);
+        // Streams declare twice the bitrate of the file
+        assert!(size_issue(hour, 500_000_000, 4_000_000).is_some());
+        assert_eq!(size_issue(Duration::ZERO, 0, 0), None);
+    }
+
+    #[test]
+    fn truncated_decode() {
+        let duration = Duration::from_secs(60 * 24);
+        let complete = DecodeReport {
+            decoded_duration: duration - Duration::from_secs(1),
+            ..Default::default()
+        };
+        assert!(decode_issues(&complete, duration).is_empty());
+
+        let truncated = DecodeReport {
+            errors: 3,
+            first_error: Some("Invalid data found when processing input".into()),
+            decoded_duration: Duration::from_secs(60 * 12),
+        };
+        let kinds: Vec<_> = decode_issues(&truncated, duration)
+            .into_iter()
+            .map(|i| i.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [HealthIssueKind::DecodeErrors, HealthIssueKind::Truncated]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+/// Name of the ignore file that is honoured in every library subdirectory
+pub const IGNORE_FILE_NAME: &str = ".mediaignore";
+
+/// Patterns applied to every library root before the library's own patterns
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
+    // Synology thumbnails and recycle bin
+    "@eaDir/",
+    "#recycle/",
+    ".Trash-*/",
+    // Incomplete downloads
+    "*.part",
+    "*.partial",
+    "*.partial.*",
+    "*.!qB",
+    "*.crdownload",
+    "sample.*",
+    "*-sample.*",
+    "*.sample.*",
+];
+
+/// Single gitignore-style pattern.
+///
+/// Unlike git, matching is ASCII case insensitive because media folders often come from Windows shares
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// Directory the pattern is relative to
+    base: PathBuf,
+    pattern: Vec<char>,
+    /// Pattern starts with `!` and re-includes matched paths
+    negated: bool,
+    /// Pattern ends with `/` and matches only directories
+    dir_only: bool,
+    /// Pattern contains `/` and is matched against the whole relative path instead of the file name
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn parse(base: &Path, line: &str) -> Option<Self> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let line = line.strip_prefix("**/").unwrap_or(line);
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+        Some(Self {
+            base: base.to_path_buf(),
+            pattern: line.chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let Ok(relative) = path.strip_prefix(&self.base) else {
+            return false;
+        };
+        if self.anchored {
+            let mut text = Vec::new();
+            for component in relative.components() {
+                if let Component::Normal(name) = component {
+                    if !text.is_empty() {
+                        text.push('/');
+                    }
+                    text.extend(name.to_string_lossy().chars());
+                }
+            }
+            glob_match(&self.pattern, &text)
+        } else {
+            relative.file_name().is_some_and(|name| {
+                let name: Vec<char> = name.to_string_lossy().chars().collect();
+                glob_match(&self.pattern, &name)
+            })
+        }
+    }
+}
+
+/// Match the character against `[...]` class contents
+fn class_match(class: &[char], c: char) -> bool {
+    let (negated, class) = match class.first() {
+        Some('!' | '^') => (true, &class[1..]),
+        _ => (false, class),
+    };
+    let c = c.to_ascii_lowercase();
+    let mut matched = false;
+    let mut i = 0;
+    while i < class.len() {
+        let start = class[i].to_ascii_lowercase();
+        if class.get(i + 1) == Some(&'-') && i + 2 < class.len() {
+            let end = class[i + 2].to_ascii_lowercase();
+            matched |= (start..=end).contains(&c);
+            i += 3;
+        } else {
+            matched |= start == c;
+            i += 1;
+        }
+    }
+    matched != negated
+}
+
+/// Glob matching where `*` and `?` don't cross `/` and `**` matches any number of directories
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `a/**/b` matches `a/b`
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            text.first().is_some_and(|c| *c != '/') && glob_match(&pattern[1..], &text[1..])
+        }
+        Some('[') => {
+            // `]` right after the opening bracket is a part of the class
+            let close = pattern
+                .iter()
+                .skip(2)
+                .position(|c| *c == ']')
+                .map(|i| i + 2);
+            match (close, text.first()) {
+                (Some(close), Some(c)) => {
+                    *c != '/'
+                        && class_match(&pattern[1..close], *c)
+                        && glob_match(&pattern[close + 1..], &text[1..])
+                }
+                (Some(_), None) => false,
+                // Unclosed bracket is a literal
+                (None, Some(c)) => *c == '[' && glob_match(&pattern[1..], &text[1..]),
+                (None, None) => false,
+            }
+        }
+        Some(p) => {
+            text.first().is_some_and(|c| c.eq_ignore_ascii_case(p))
+                && glob_match(&pattern[1..], &text[1..])
+        }
+    }
+}
+
+/// Whether the path or any of its parent directories match [DEFAULT_IGNORE_PATTERNS]
+pub fn is_ignored_by_default(path: &Path) -> bool {
+    let rules: Vec<_> = DEFAULT_IGNORE_PATTERNS
+        .iter()
+        .filter_map(|p| IgnoreRule::parse(Path::new(""), p))
+        .collect();
+    let names: Vec<_> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(Path::new(name)),
+            _ => None,
+        })
+        .collect();
+    names.iter().enumerate().any(|(i, name)| {
+        let is_dir = i + 1 < names.len();
+        rules.iter().any(|rule| rule.matches(name, is_dir))
+    })
+}
+
+/// Ignore rules of the library folders, including `.mediaignore` files found during the walk.
+///
+/// Like in gitignore, the last matching pattern wins and files inside of ignored directories
+/// can't be re-included
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    roots: Vec<PathBuf>,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Add library root with default and custom patterns relative to it
+    pub fn add_root(&mut self, root: &Path, patterns: &[String]) {
+        self.roots.push(root.to_path_buf());
+        let defaults = DEFAULT_IGNORE_PATTERNS.iter().copied();
+        let patterns = defaults.chain(patterns.iter().map(String::as_str));
+        self.rules
+            .extend(patterns.filter_map(|p| IgnoreRule::parse(root, p)));
+    }
+
+    /// Add patterns of the ignore file located in the `base` directory
+    pub fn add_patterns(&mut self, base: &Path, content: &str) {
+        self.rules
+            .extend(content.lines().filter_map(|l| IgnoreRule::parse(base, l)));
+    }
+
+    /// Rules extended with the ignore file of the directory if it has one
+    pub fn enter_dir(self: &Arc<Self>, dir: &Path) -> Arc<Self> {
+        let content = std::fs::read_to_string(dir.join(IGNORE_FILE_NAME));
+        self.with_ignore_file(dir, content.ok())
+    }
+
+    /// Same as [`Self::enter_dir`] for the walks running on the async runtime
+    pub async fn enter_dir_async(self: &Arc<Self>, dir: &Path) -> Arc<Self> {
+        let content = tokio::fs::read_to_string(dir.join(IGNORE_FILE_NAME)).await;
+        self.with_ignore_file(dir, content.ok())
+    }
+
+    fn with_ignore_file(self: &Arc<Self>, dir: &Path, content: Option<String>) -> Arc<Self> {
+        match content {
+            Some(content) => {
+                let mut rules = Self::clone(self);
+                rules.add_patterns(dir, &content);
+                Arc::new(rules)
+            }
+            None => self.clone(),
+        }
+    }
+
+    /// Check the walked entry, ignore files of its parents must be already loaded
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name() == Some(IGNORE_FILE_NAME.as_ref()) {
+            return true;
+        }
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(path, is_dir))
+            .is_some_and(|rule| !rule.negated)
+    }
+
+    /// Check the path outside of a walk, loading ignore files from its library root down to its directory
+    pub fn is_path_ignored(&self, path: &Path) -> bool {
+        let Some(root) = self.roots.iter().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        let Ok(relative) = path.strip_prefix(root) else {
+            return false;
+        };
+        let mut rules = Arc::new(self.clone()).enter_dir(root);
+        let mut dir = root.clone();
+        let mut components = relative.components().peekable();
+        while let Some(component) = components.next() {
+            dir.push(component);
+            let is_last = components.peek().is_none();
+            let is_dir = !is_last || dir.is_dir();
+            if rules.is_ignored(&dir, is_dir) {
+                return true;
+            }
+            if !is_last {
+                rules = rules.enter_dir(&dir);
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{IgnoreRules, glob_match, is_ignored_by_default};
+
+    fn glob(pattern: &str, text: &str) -> bool {
+        let pattern: Vec<char> = pattern.chars().collect();
+        let text: Vec<char> = text.chars().collect();
+        glob_match(&pattern, &text)
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob("*.mkv", "Movie.MKV"));
+        assert!(!glob("*.mkv", "dir/Movie.mkv"));
+        assert!(glob("**/*.mkv", "a/b/Movie.mkv"));
+        assert!(glob("a/**/b", "a/b"));
+        assert!(glob("a/**/b", "a/x/y/b"));
+        assert!(glob("S0?E01*", "S01E01.mkv"));
+        assert!(glob("[ab]*", "Bonus"));
+        assert!(glob("[!ab]*", "cut"));
+        assert!(!glob("[a-c]", "d"));
+        assert!(glob("[", "["));
+    }
+
+    #[test]
+    fn ignore_rules() {
+        let mut rules = IgnoreRules::default();
+        rules.add_root(
+            Path::new("/media/shows"),
+            &[
+                "/Old Shows/".into(),
+                "*.nfo.mkv".into(),
+                "!keep-sample.mkv".into(),
+            ],
+        );
+        rules.add_patterns(Path::new("/media/shows/Show"), "# comment\nspecials/\n");
+
+        let ignored = |path: &str, is_dir| rules.is_ignored(Path::new(path), is_dir);
+        assert!(ignored("/media/shows/@eaDir", true));
+        assert!(ignored("/media/shows/Show/@eaDir", true));
+        assert!(ignored("/media/shows/Show/episode.mkv.part", false));
+        assert!(ignored("/media/shows/Show/show-sample.mkv", false));
+        assert!(!ignored("/media/shows/Show/keep-sample.mkv", false));
+        assert!(ignored("/media/shows/Old Shows", true));
+        // Anchored to the root
+        assert!(!ignored("/media/shows/Show/Old Shows", true));
+        // Only directories
+        assert!(ignored("/media/shows/Show/Specials", true));
+        assert!(!ignored("/media/shows/Show/specials", false));
+        // Rules of the ignore file don't leak outside of its directory
+        assert!(!ignored("/media/shows/Other/specials", true));
+        assert!(!ignored("/media/shows/Show/S01E01.mkv", false));
+        assert!(!ignored("/other/@eaDir", true));
+
+        assert!(is_ignored_by_default(Path::new(
+            "/downloads/@eaDir/Movie.mkv/SYNOVIDEO_VIDEO_SCREENSHOT.jpg"
+        )));
+        assert!(is_ignored_by_default(Path::new(
+            "/downloads/Movie/sample.mkv"
+        )));
+        assert!(!is_ignored_by_default(Path::new(
+            "/downloads/Movie/Movie.mkv"
+        )));
+    }
+}
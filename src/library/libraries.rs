@@ -0,0 +1,442 @@
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config,
+    db::Db,
+    library::ignore::IgnoreRules,
+    metadata::{Language, MetadataProvider, ParentMediaType},
+    scan::ScanConfig,
+};
+
+/// Library with its own folders, metadata and scan settings
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct MediaLibrary {
+    pub id: i64,
+    #[serde(flatten)]
+    pub settings: LibrarySettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LibrarySettings {
+    pub name: String,
+    pub media_type: ParentMediaType,
+    /// Library root folders
+    #[schema(value_type = Vec<String>)]
+    pub paths: Vec<PathBuf>,
+    /// Metadata language, server wide language when empty
+    pub metadata_language: Option<Language>,
+    /// Metadata providers order, server wide order when empty
+    pub provider_order: Option<Vec<MetadataProvider>>,
+    /// Detect intros of newly added episodes after the scan
+    #[serde(default)]
+    pub detect_intros: bool,
+    /// Generate previews of newly added videos after the scan
+    #[serde(default)]
+    pub generate_previews: bool,
+    /// Automatic scan period in minutes
+    pub scan_interval: Option<u32>,
+    /// Gitignore-style patterns relative to the library folders, applied along with `.mediaignore` files
+    #[serde(default)]
+    pub ignore_patterns: Vec<String>,
+    /// Write metadata and artwork back to nfo files and images next to the media
+    #[serde(default)]
+    pub export_nfo: bool,
+}
+
+impl MediaLibrary {
+    pub fn contains(&self, path: &Path) -> bool {
+        self.settings
+            .paths
+            .iter()
+            .any(|root| path.starts_with(root))
+    }
+
+    /// Scan configuration with library overrides applied
+    pub fn scan_config(&self, base: &ScanConfig) -> ScanConfig {
+        let mut config = base.clone();
+        if let Some(lang) = self.settings.metadata_language {
+            config.fetch_params.lang = lang;
+        }
+        if let Some(order) = &self.settings.provider_order {
+            config.provider_order = Some(order.clone());
+        }
+        config
+    }
+}
+
+/// Show and movie folders from both configuration and libraries
+#[derive(Debug, Clone, Default)]
+pub struct LibraryFolders {
+    pub shows: Vec<PathBuf>,
+    pub movies: Vec<PathBuf>,
+    /// Ignore rules of all folders
+    pub ignore: IgnoreRules,
+}
+
+impl LibraryFolders {
+    pub fn new(libraries: &[MediaLibrary]) -> Self {
+        let (config::ShowFolders(mut shows), config::MovieFolders(mut movies)) =
+            config::CONFIG.get_values();
+        let mut ignore = IgnoreRules::default();
+        for path in shows.iter().chain(&movies) {
+            if !libraries.iter().any(|l| l.settings.paths.contains(path)) {
+                ignore.add_root(path, &[]);
+            }
+        }
+        for library in libraries {
+            let folders = match library.settings.media_type {
+                ParentMediaType::Show => &mut shows,
+                ParentMediaType::Movie => &mut movies,
+            };
+            for path in &library.settings.paths {
+                if !folders.contains(path) {
+                    folders.push(path.clone());
+                }
+                ignore.add_root(path, &library.settings.ignore_patterns);
+            }
+        }
+        Self {
+            shows,
+            movies,
+            ignore,
+        }
+    }
+
+    pub async fn load(db: &Db) -> Self {
+        let libraries = all(db).await.unwrap_or_else(|e| {
+            tracing::error!("Failed to load libraries: {e}");
+            Vec::new()
+        });
+        Self::new(&libraries)
+    }
+}
+
+fn parse_provider_order(order: Option<String>) -> Option<Vec<MetadataProvider>> {
+    order.map(|order| {
+        order
+            .split(',')
+            .filter_map(|p| MetadataProvider::from_str(p).ok())
+            .collect()
+    })
+}
+
+fn join_provider_order(order: Option<&[MetadataProvider]>) -> Option<String> {
+    order.map(|order| {
+        order
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    })
+}
+
+pub async fn all(db: &Db) -> sqlx::Result<Vec<MediaLibrary>> {
+    let rows = sqlx::query!(
+        r#"select id, name, media_type as "media_type: ParentMediaType",
+        metadata_language as "metadata_language: Language", provider_order,
+        detect_intros, generate_previews, scan_interval, export_nfo from libraries order by id"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let paths = sqlx::query!("select library_id, path from library_paths order by id")
+        .fetch_all(&db.pool)
+        .await?;
+    let ignore_patterns =
+        sqlx::query!("select library_id, pattern from library_ignore_patterns order by id")
+            .fetch_all(&db.pool)
+            .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| MediaLibrary {
+            id: row.id,
+            settings: LibrarySettings {
+                name: row.name,
+                media_type: row.media_type,
+                paths: paths
+                    .iter()
+                    .filter(|p| p.library_id == row.id)
+                    .map(|p| PathBuf::from(&p.path))
+                    .collect(),
+                metadata_language: row.metadata_language,
+                provider_order: parse_provider_order(row.provider_order),
+                detect_intros: row.detect_intros,
+                generate_previews: row.generate_previews,
+                scan_interval: row.scan_interval.map(|i| i as u32),
+                ignore_patterns: ignore_patterns
+                    .iter()
+                    .filter(|p| p.library_id == row.id)
+                    .map(|p| p.pattern.clone())
+                    .collect(),
+                export_nfo: row.export_nfo,
+            },
+        })
+        .collect())
+}
+
+/// Library of the movie or show, found by the location of its videos
+pub async fn content_library(
+    db: &Db,
+    content_type: ParentMediaType,
+    content_id: i64,
+) -> sqlx::Result<Option<MediaLibrary>> {
+    let path = match content_type {
+        ParentMediaType::Movie => {
+            sqlx::query_scalar!(
+                "select videos.path from videos
+                join movies on movies.metadata_id = videos.metadata_id
+                where movies.id = ? limit 1",
+                content_id
+            )
+            .fetch_optional(&db.pool)
+            .await?
+        }
+        ParentMediaType::Show => {
+            sqlx::query_scalar!(
+                "select videos.path from videos
+                join episodes on episodes.metadata_id = videos.metadata_id
+                join seasons on seasons.id = episodes.season_id
+                where seasons.show_id = ? limit 1",
+                content_id
+            )
+            .fetch_optional(&db.pool)
+            .await?
+        }
+    };
+    let Some(path) = path else {
+        return Ok(None);
+    };
+    Ok(all(db)
+        .await?
+        .into_iter()
+        .find(|library| library.contains(Path::new(&path))))
+}
+
+pub async fn get(db: &Db, id: i64) -> sqlx::Result<MediaLibrary> {
+    all(db)
+        .await?
+        .into_iter()
+        .find(|l| l.id == id)
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+pub async fn insert(db: &Db, settings: &LibrarySettings) -> sqlx::Result<i64> {
+    let mut tx = db.begin().await?;
+    let provider_order = join_provider_order(settings.provider_order.as_deref());
+    let id = sqlx::query_scalar!(
+        "insert into libraries
+        (name, media_type, metadata_language, provider_order, detect_intros, generate_previews,
+        scan_interval, export_nfo)
+        values (?, ?, ?, ?, ?, ?, ?, ?) returning id",
+        settings.name,
+        settings.media_type,
+        settings.metadata_language,
+        provider_order,
+        settings.detect_intros,
+        settings.generate_previews,
+        settings.scan_interval,
+        settings.export_nfo,
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    for path in &settings.paths {
+        let path = path.to_string_lossy();
+        sqlx::query!(
+            "insert into library_paths (library_id, path) values (?, ?)",
+            id,
+            path
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    for pattern in &settings.ignore_patterns {
+        sqlx::query!(
+            "insert into library_ignore_patterns (library_id, pattern) values (?, ?)",
+            id,
+            pattern
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(id)
+}
+
+pub async fn update(db: &Db, id: i64, settings: &LibrarySettings) -> sqlx::Result<()> {
+    let mut tx = db.begin().await?;
+    let provider_order = join_provider_order(settings.provider_order.as_deref());
+    let res = sqlx::query!(
+        "update libraries set name = ?, media_type = ?, metadata_language = ?, provider_order = ?,
+        detect_intros = ?, generate_previews = ?, scan_interval = ?, export_nfo = ? where id = ?",
+        settings.name,
+        settings.media_type,
+        settings.metadata_language,
+        provider_order,
+        settings.detect_intros,
+        settings.generate_previews,
+        settings.scan_interval,
+        settings.export_nfo,
+        id,
+    )
+    .execute(&mut *tx)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    sqlx::query!("delete from library_paths where library_id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query!(
+        "delete from library_ignore_patterns where library_id = ?",
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+    for path in &settings.paths {
+        let path = path.to_string_lossy();
+        sqlx::query!(
+            "insert into library_paths (library_id, path) values (?, ?)",
+            id,
+            path
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    for pattern in &settings.ignore_patterns {
+        sqlx::query!(
+            "insert into library_ignore_patterns (library_id, pattern) values (?, ?)",
+            id,
+            pattern
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete(db: &Db, id: i64) -> sqlx::Result<()> {
+    let res = sqlx::query!("delete from libraries where id = ?", id)
+        .execute(&db.pool)
+        .await?;
+    if res.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+/// Record finished scan of the libraries
+pub async fn mark_scanned(db: &Db, library_ids: &[i64]) -> sqlx::Result<()> {
+    let mut tx = db.begin().await?;
+    for id in library_ids {
+        sqlx::query!(
+            "update libraries set last_scan = current_timestamp where id = ?",
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Libraries which scan schedule has elapsed
+pub async fn scan_due(db: &Db) -> sqlx::Result<Vec<i64>> {
+    sqlx::query_scalar!(
+        r#"select id as "id!" from libraries where scan_interval is not null
+        and (last_scan is null
+        or datetime(last_scan, '+' || scan_interval || ' minutes') <= current_timestamp)"#
+    )
+    .fetch_all(&db.pool)
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use sqlx::SqlitePool;
+
+    use crate::{
+        metadata::{Language, MetadataProvider, ParentMediaType, metadata_api::tests::leak_db},
+        scan::ScanConfig,
+    };
+
+    use super::LibrarySettings;
+
+    fn settings(name: &str, path: &str) -> LibrarySettings {
+        LibrarySettings {
+            name: name.into(),
+            media_type: ParentMediaType::Show,
+            paths: vec![PathBuf::from(path)],
+            metadata_language: Some(Language::Ja),
+            provider_order: Some(vec![MetadataProvider::Tvdb, MetadataProvider::Tmdb]),
+            detect_intros: true,
+            generate_previews: false,
+            scan_interval: Some(60),
+            ignore_patterns: vec!["Extras/".into()],
+            export_nfo: true,
+        }
+    }
+
+    #[sqlx::test]
+    async fn library_crud(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let id = super::insert(db, &settings("Anime", "/media/anime")).await?;
+
+        let library = super::get(db, id).await?;
+        assert_eq!(library.settings.name, "Anime");
+        assert_eq!(library.settings.metadata_language, Some(Language::Ja));
+        assert_eq!(
+            library.settings.provider_order,
+            Some(vec![MetadataProvider::Tvdb, MetadataProvider::Tmdb])
+        );
+        assert!(library.contains(Path::new("/media/anime/Show/S01E01.mkv")));
+        assert_eq!(library.settings.ignore_patterns, ["Extras/"]);
+
+        let config = library.scan_config(&ScanConfig::default());
+        assert_eq!(config.fetch_params.lang, Language::Ja);
+
+        // Folder can belong only to one library
+        assert!(
+            super::insert(db, &settings("Other", "/media/anime"))
+                .await
+                .is_err()
+        );
+
+        let mut updated = settings("Anime", "/media/cartoons");
+        updated.provider_order = None;
+        updated.ignore_patterns.clear();
+        super::update(db, id, &updated).await?;
+        let library = super::get(db, id).await?;
+        assert_eq!(
+            library.settings.paths,
+            vec![PathBuf::from("/media/cartoons")]
+        );
+        assert_eq!(library.settings.provider_order, None);
+        assert!(library.settings.ignore_patterns.is_empty());
+
+        super::delete(db, id).await?;
+        assert!(super::all(db).await?.is_empty());
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn library_scan_schedule(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let anime = super::insert(db, &settings("Anime", "/media/anime")).await?;
+        let cartoons = super::insert(db, &settings("Cartoons", "/media/cartoons")).await?;
+        let mut unscheduled = settings("Movies", "/media/movies");
+        unscheduled.scan_interval = None;
+        super::insert(db, &unscheduled).await?;
+        assert_eq!(super::scan_due(db).await?, [anime, cartoons]);
+
+        super::mark_scanned(db, &[anime]).await?;
+        assert_eq!(super::scan_due(db).await?, [cartoons]);
+        Ok(())
+    }
+}
@@ -40,3 +40,17 @@ impl VideoContainer {
         }
     }
 }
+
+impl std::fmt::Display for VideoContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ext = match self {
+            VideoContainer::Avi => "avi",
+            VideoContainer::Mkv => "mkv",
+            VideoContainer::Mov => "mov",
+            VideoContainer::Mp4 => "mp4",
+            VideoContainer::Ogg => "ogg",
+            VideoContainer::Webm => "webm",
+        };
+        write!(f, "{ext}")
+    }
+}
@@ -53,7 +53,6 @@ impl LazyFFprobeOutput {
             .await
     }
 
-    #[allow(unused)]
     fn try_get(&self) -> Option<&ProbeOutput> {
         self.metadata.get()
     }
@@ -71,29 +70,78 @@ impl Video {
             metadata_id: None,
             is_prime: false,
             scan_date: now.to_string(),
+            content_hash: Some(self.content_hash().await?),
         })
     }
 
+    /// OpenSubtitles compatible hash: file size plus checksum of the first and last 64KiB.
+    ///
+    /// Cheap enough to compute for the whole library, it identifies the file after it was moved.
+    pub async fn content_hash(&self) -> std::io::Result<String> {
+        const CHUNK_SIZE: u64 = 64 * 1024;
+        let mut file = tokio::fs::File::open(&self.path).await?;
+        let size = file.metadata().await?.len();
+        let mut buf = vec![0; CHUNK_SIZE.min(size) as usize];
+        let mut hash = size;
+        for offset in [0, size.saturating_sub(CHUNK_SIZE)] {
+            file.seek(SeekFrom::Start(offset)).await?;
+            file.read_exact(&mut buf).await?;
+            hash = buf.chunks(8).fold(hash, |hash, word| {
+                let mut bytes = [0; 8];
+                bytes[..word.len()].copy_from_slice(word);
+                hash.wrapping_add(u64::from_le_bytes(bytes))
+            });
+        }
+        Ok(format!("{hash:016x}"))
+    }
+
     pub async fn fetch_duration(&self) -> anyhow::Result<std::time::Duration> {
         let metadata = self.metadata().await?;
         Ok(metadata.duration())
     }
 
+    /// Database id of the video, inserting it when the path is unknown.
+    ///
+    /// Unknown paths are first matched by content hash against videos that disappeared from
+    /// their recorded path, so a moved file keeps its id along with everything attached to it.
     pub async fn get_or_insert_id(&self, tx: &mut crate::db::DbTransaction) -> anyhow::Result<i64> {
         let path = self.path().to_string_lossy();
-        let res = sqlx::query!("SELECT id FROM videos WHERE path = ?", path)
-            .fetch_one(&mut **tx)
-            .await;
-        let video_id: Result<i64, anyhow::Error> = match res {
-            Ok(r) => Ok(r.id),
-            Err(sqlx::Error::RowNotFound) => {
-                let db_video = self.into_db_video().await?;
-                let id = tx.insert_video(db_video).await?;
-                Ok(id)
+        let existing = sqlx::query!("SELECT id, content_hash FROM videos WHERE path = ?", path)
+            .fetch_optional(&mut **tx)
+            .await?;
+        if let Some(existing) = existing {
+            // Videos added before fingerprinting was introduced
+            if existing.content_hash.is_none() {
+                match self.content_hash().await {
+                    Ok(hash) => {
+                        sqlx::query!(
+                            "UPDATE videos SET content_hash = ? WHERE id = ?",
+                            hash,
+                            existing.id
+                        )
+                        .execute(&mut **tx)
+                        .await?;
+                    }
+                    Err(e) => tracing::warn!("Failed to compute video content hash: {e}"),
+                }
             }
-            Err(e) => Err(e.into()),
-        };
-        video_id
+            return Ok(existing.id);
+        }
+
+        let db_video = self.into_db_video().await?;
+        if let Some(moved_id) = find_moved_video(tx, &db_video).await? {
+            tracing::info!(id = moved_id, path = %path, "Relocating moved video");
+            sqlx::query!(
+                "UPDATE videos SET path = ?, size = ? WHERE id = ?",
+                db_video.path,
+                db_video.size,
+                moved_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            return Ok(moved_id);
+        }
+        Ok(tx.insert_video(db_video).await?)
     }
 
     pub fn path(&self) -> &Path {
@@ -138,6 +186,11 @@ impl Video {
         self.metadata.get_or_init(self.path()).await
     }
 
+    /// Metadata if it was already evaluated, never probes the file
+    pub fn cached_metadata(&self) -> Option<&ProbeOutput> {
+        self.metadata.try_get()
+    }
+
     /// Get file size in bytes
     pub async fn file_size(&self) -> std::io::Result<u64> {
         tokio::fs::metadata(&self.path).await.map(|m| m.len())
@@ -231,6 +284,29 @@ impl Video {
     }
 }
 
+/// Video row with the same content whose file no longer exists at the recorded path.
+async fn find_moved_video(
+    tx: &mut crate::db::DbTransaction,
+    db_video: &DbVideo,
+) -> anyhow::Result<Option<i64>> {
+    let Some(hash) = &db_video.content_hash else {
+        return Ok(None);
+    };
+    let candidates = sqlx::query!(
+        "SELECT id, path FROM videos WHERE content_hash = ? AND size = ?",
+        hash,
+        db_video.size
+    )
+    .fetch_all(&mut **tx)
+    .await?;
+    for candidate in candidates {
+        if !tokio::fs::try_exists(&candidate.path).await.unwrap_or(true) {
+            return Ok(Some(candidate.id));
+        }
+    }
+    Ok(None)
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Resolution(pub (usize, usize));
 
@@ -390,3 +466,24 @@ impl FromStr for Resolution {
         Ok((x, y).into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Video;
+
+    #[tokio::test]
+    async fn content_hash() {
+        let path = std::env::temp_dir().join(format!("content_hash_{}.mkv", std::process::id()));
+        let mut content = 1u64.to_le_bytes().to_vec();
+        content.extend(2u64.to_le_bytes());
+        content.push(3);
+        tokio::fs::write(&path, &content).await.unwrap();
+        let hash = Video::from_path_unchecked(&path)
+            .content_hash()
+            .await
+            .unwrap();
+        tokio::fs::remove_file(&path).await.unwrap();
+        // size + both (overlapping) windows of the small file
+        assert_eq!(hash, format!("{:016x}", 17 + 2 * (1 + 2 + 3)));
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+use time::{Date, format_description::well_known::Iso8601};
+
+use crate::{
+    db::Db,
+    library::numbering,
+    metadata::{EpisodeMetadata, FetchParams, metadata_stack::MetadataProvidersStack},
+};
+
+/// Episodes of the season that are not present in the library
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct SeasonEpisodesReport {
+    pub number: usize,
+    /// Amount of episodes present in the library
+    pub local: usize,
+    /// Aired episodes that are missing in the library
+    pub missing: Vec<EpisodeMetadata>,
+    /// Episodes that are not aired yet, including the ones without air date
+    pub upcoming: Vec<EpisodeMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ShowEpisodesReport {
+    pub show_id: i64,
+    pub next_episode_air_date: Option<crate::OffsetDateTime>,
+    pub seasons: Vec<SeasonEpisodesReport>,
+}
+
+/// Episode air date, dates without the day are treated as unknown
+pub fn air_date(episode: &EpisodeMetadata) -> Option<Date> {
+    let release_date = episode.release_date.as_deref()?;
+    Date::parse(release_date, &Iso8601::DATE).ok()
+}
+
+fn season_report(
+    number: usize,
+    episodes: Vec<EpisodeMetadata>,
+    local: &HashSet<usize>,
+    today: Date,
+) -> SeasonEpisodesReport {
+    let mut missing = Vec::new();
+    let mut upcoming = Vec::new();
+    for episode in episodes {
+        if local.contains(&episode.number) {
+            continue;
+        }
+        match air_date(&episode) {
+            Some(date) if date <= today => missing.push(episode),
+            _ => upcoming.push(episode),
+        }
+    }
+    SeasonEpisodesReport {
+        number,
+        local: local.len(),
+        missing,
+        upcoming,
+    }
+}
+
+/// Compare provider seasons with local episodes of the show
+pub async fn show_report(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    show_id: i64,
+    fetch_params: FetchParams,
+    include_specials: bool,
+) -> crate::Result<ShowEpisodesReport> {
+    let show_numbering = numbering::get(db, show_id).await?;
+    let fetch_params = FetchParams {
+        ordering: show_numbering.ordering,
+        ..fetch_params
+    };
+    let next_episode_air_date = sqlx::query_scalar!(
+        "select next_episode_air_date from shows where id = ?",
+        show_id
+    )
+    .fetch_one(&db.pool)
+    .await?
+    .map(Into::into);
+    let local_episodes = sqlx::query!(
+        "select distinct seasons.number as season, episodes.number as episode from episodes
+        join seasons on seasons.id = episodes.season_id
+        join videos on videos.metadata_id = episodes.metadata_id
+        where seasons.show_id = ?",
+        show_id
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut local: BTreeMap<usize, HashSet<usize>> = BTreeMap::new();
+    for row in local_episodes {
+        local
+            .entry(row.season as usize)
+            .or_default()
+            .insert(row.episode as usize);
+    }
+
+    let (provider, provider_show_id) = numbering::show_provider(db, providers, show_id).await?;
+    let show = provider.show(&provider_show_id, fetch_params).await?;
+    let mut season_numbers = show
+        .seasons
+        .unwrap_or_else(|| local.keys().copied().collect());
+    season_numbers.retain(|n| include_specials || *n > 0);
+    season_numbers.sort_unstable();
+
+    let today = time::OffsetDateTime::now_utc().date();
+    let mut seasons = Vec::with_capacity(season_numbers.len());
+    for number in season_numbers {
+        let season = match provider
+            .season(&provider_show_id, number, fetch_params)
+            .await
+        {
+            Ok(season) => season,
+            Err(e) => {
+                tracing::warn!("Failed to fetch season {number} of the show {show_id}: {e}");
+                continue;
+            }
+        };
+        let local = local.remove(&number).unwrap_or_default();
+        seasons.push(season_report(number, season.episodes, &local, today));
+    }
+    Ok(ShowEpisodesReport {
+        show_id,
+        next_episode_air_date,
+        seasons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use time::{Date, Month};
+
+    use crate::metadata::EpisodeMetadata;
+
+    fn episode(number: usize, release_date: Option<&str>) -> EpisodeMetadata {
+        EpisodeMetadata {
+            number,
+            release_date: release_date.map(Into::into),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn missing_and_upcoming_episodes() {
+        let episodes = vec![
+            episode(1, Some("2026-01-01")),
+            episode(2, Some("2026-01-08")),
+            episode(3, Some("2026-10-18")),
+            episode(4, Some("2026-10-25")),
+            episode(5, Some("2026")),
+            episode(6, None),
+        ];
+        let local = HashSet::from([1]);
+        let today = Date::from_calendar_date(2026, Month::October, 18).unwrap();
+        let report = super::season_report(1, episodes, &local, today);
+        let numbers = |episodes: &[EpisodeMetadata]| -> Vec<usize> {
+            episodes.iter().map(|e| e.number).collect()
+        };
+        assert_eq!(report.local, 1);
+        assert_eq!(numbers(&report.missing), [2, 3]);
+        assert_eq!(numbers(&report.upcoming), [4, 5, 6]);
+    }
+}
@@ -16,6 +16,8 @@ use crate::{
     parser::{movie::MovieIdentifier, show::ShowIdentifier, walk_movie_dirs, walk_show_dirs},
 };
 
+use self::ignore::IgnoreRules;
+
 use self::assets::{
     AssetDir, PreviewAsset, PreviewsDirAsset, SubtitleAsset, SubtitlesDirAsset, VideoAssetsDir,
 };
@@ -23,8 +25,30 @@ use self::assets::{VariantAsset, VariantsDirAsset};
 
 /// Saved local assets like posters
 pub mod assets;
+/// Release calendar of the watched and watchlisted content
+pub mod calendar;
+/// Movie collections (franchises) with owned and missing movies
+pub mod collections;
+/// Verification of corrupted, truncated and unplayable videos
+pub mod health;
+/// Gitignore-style exclude patterns and `.mediaignore` files
+pub mod ignore;
+/// Library entities with their own folders, metadata and scan settings
+pub mod libraries;
 /// Library videos and it's components
 pub mod media;
+/// Missing and upcoming episodes of the shows
+pub mod missing_episodes;
+/// Metadata and artwork export into nfo files next to the media
+pub mod nfo_export;
+/// Absolute episode numbering and manual episode mappings of shows
+pub mod numbering;
+/// Import of downloads into the libraries with configurable naming
+pub mod organizer;
+/// Aggregated library statistics: codecs, resolutions, runtime and growth
+pub mod statistics;
+/// Named movie versions and default version selection
+pub mod versions;
 
 pub const EXTRAS_FOLDERS: &[&str] = &[
     "behind the scenes",
@@ -57,17 +81,18 @@ pub fn is_format_supported(path: &impl AsRef<Path>) -> bool {
     let supports_extension = path
         .extension()
         .is_some_and(|ex| VideoContainer::try_from(ex).is_ok());
-    !is_extra && supports_extension
+    !is_extra && supports_extension && !ignore::is_ignored_by_default(&path)
 }
 
 #[tracing::instrument(level = "debug", skip_all, fields(folders = folders.len()))]
 pub async fn explore_show_dirs(
     folders: Vec<PathBuf>,
+    ignore: &IgnoreRules,
     db: &crate::db::Db,
     library: &mut HashMap<i64, LibraryFile>,
     exclude: &[PathBuf],
 ) {
-    let videos = walk_show_dirs(folders);
+    let videos = walk_show_dirs(folders, ignore);
     let mut tx = db.begin().await.expect("transaction to begin");
     for (video, identifier) in videos {
         let path = video.path();
@@ -93,11 +118,12 @@ pub async fn explore_show_dirs(
 #[tracing::instrument(level = "debug", skip_all, fields(folders = folders.len()))]
 pub async fn explore_movie_dirs(
     folders: Vec<PathBuf>,
+    ignore: &IgnoreRules,
     db: &crate::db::Db,
     library: &mut HashMap<i64, LibraryFile>,
     exclude: &[PathBuf],
 ) {
-    let videos = walk_movie_dirs(folders).await;
+    let videos = walk_movie_dirs(folders, ignore).await;
     let mut tx = db.begin().await.expect("transaction begin");
     for (video, identifier) in videos {
         let path = video.path();
@@ -288,20 +314,25 @@ impl Source {
 }
 
 impl<T: Media> LibraryItem<T> {
-    // Identification in this
-    pub async fn from_path(path: PathBuf, db: &crate::db::Db) -> Result<Self, anyhow::Error> {
-        let video = Video::from_path(&path).await?;
-        let file_name = path.file_name().context("get filename")?;
-        let identifier = match T::identify(file_name) {
-            Ok(val) => val,
+    /// Identify the content by the file name, container title tag is used when the name is not enough
+    pub async fn identify(video: &Video) -> anyhow::Result<T> {
+        let file_name = video.path().file_name().context("get filename")?;
+        match T::identify(file_name) {
+            Ok(val) => Ok(val),
             Err(_) => {
                 let metadata = video.metadata().await?;
                 metadata
                     .tag_title()
                     .and_then(|metadata_title| T::identify(metadata_title).ok())
-                    .context("Try to identify content from container metadata")?
+                    .context("Try to identify content from container metadata")
             }
-        };
+        }
+    }
+
+    // Identification in this
+    pub async fn from_path(path: PathBuf, db: &crate::db::Db) -> Result<Self, anyhow::Error> {
+        let video = Video::from_path(&path).await?;
+        let identifier = Self::identify(&video).await?;
         let mut tx = db.begin().await?;
         let source = Source::from_video(video, &mut tx).await?;
         tx.commit().await?;
@@ -318,12 +349,13 @@ impl Library {
     pub async fn init_from_folders(
         show_dirs: Vec<PathBuf>,
         movie_dirs: Vec<PathBuf>,
+        ignore: &IgnoreRules,
         db: &Db,
     ) -> Self {
         let mut videos = HashMap::new();
-        explore_show_dirs(show_dirs, db, &mut videos, &[]).await;
+        explore_show_dirs(show_dirs, ignore, db, &mut videos, &[]).await;
 
-        explore_movie_dirs(movie_dirs, db, &mut videos, &[]).await;
+        explore_movie_dirs(movie_dirs, ignore, db, &mut videos, &[]).await;
         Self { videos }
     }
 
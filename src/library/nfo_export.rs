@@ -0,0 +1,502 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::{
+    db::{Db, DbActions},
+    library::{
+        assets::{BackdropAsset, BackdropContentType, FileAsset, PosterAsset, PosterContentType},
+        libraries,
+    },
+    metadata::{
+        ExternalIdMetadata, Genre, MetadataProvider, imdb,
+        nfo::{EXPORT_MARKER, NfoActor, NfoContent, NfoEpisode, NfoRating},
+    },
+    progress::{ProgressDispatcher, TaskTrait},
+    scan::local_artwork,
+};
+
+/// Export of the library metadata into nfo files and images next to the media
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct NfoExportTask {
+    /// Library folders that are exported
+    #[schema(value_type = Vec<String>)]
+    pub folders: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct NfoExportProgress {
+    pub exported: usize,
+    pub total: usize,
+}
+
+impl TaskTrait for NfoExportTask {
+    type Progress = NfoExportProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> crate::progress::TaskProgress
+    where
+        Self: Sized,
+    {
+        crate::progress::TaskProgress::NfoExport(status)
+    }
+}
+
+/// Content that should be exported after its metadata is changed
+#[derive(Debug, Clone, Copy)]
+pub enum ExportTarget {
+    Movie(i64),
+    Show(i64),
+    Season { show_id: i64, season: i64 },
+    Episode(i64),
+}
+
+/// Writes metadata from the database and saved assets next to the media files.
+///
+/// Nfo files are rewritten only when they were written by the server, images are written only
+/// when they do not exist yet to keep custom nfo files and artwork untouched.
+#[derive(Debug, Clone)]
+pub struct NfoExporter {
+    db: Db,
+    folders: Vec<PathBuf>,
+}
+
+impl NfoExporter {
+    pub fn new(db: Db, folders: Vec<PathBuf>) -> Self {
+        Self { db, folders }
+    }
+
+    /// Exporter for the folders of the libraries with nfo export. `None` if export is disabled
+    /// for every library
+    pub async fn from_libraries(db: Db) -> Option<Self> {
+        let media_libraries = match libraries::all(&db).await {
+            Ok(libraries) => libraries,
+            Err(e) => {
+                tracing::error!("Failed to load libraries: {e}");
+                return None;
+            }
+        };
+        let folders: Vec<_> = media_libraries
+            .into_iter()
+            .filter(|l| l.settings.export_nfo)
+            .flat_map(|l| l.settings.paths)
+            .collect();
+        (!folders.is_empty()).then(|| Self::new(db, folders))
+    }
+
+    /// Folders the exporter writes to
+    pub fn folders(&self) -> &[PathBuf] {
+        &self.folders
+    }
+
+    /// Export the content in the background
+    pub fn spawn(self, target: ExportTarget) {
+        tokio::spawn(async move {
+            let result = match target {
+                ExportTarget::Movie(id) => self.export_movie(id).await,
+                ExportTarget::Show(id) => self.export_show(id).await,
+                ExportTarget::Season { show_id, season } => {
+                    self.export_season(show_id, season).await
+                }
+                ExportTarget::Episode(id) => self.export_episode(id).await,
+            };
+            if let Err(e) = result {
+                tracing::warn!(?target, "Failed to export nfo: {e}");
+            }
+        });
+    }
+
+    /// Export every movie and show with all seasons and episodes
+    pub async fn export_all(
+        &self,
+        progress: &ProgressDispatcher<NfoExportTask>,
+    ) -> anyhow::Result<()> {
+        let movies = sqlx::query_scalar!("SELECT id FROM movies")
+            .fetch_all(&self.db.pool)
+            .await?;
+        let shows = sqlx::query_scalar!("SELECT id FROM shows")
+            .fetch_all(&self.db.pool)
+            .await?;
+        let total = movies.len() + shows.len();
+        let mut exported = 0;
+
+        for movie_id in movies {
+            if let Err(e) = self.export_movie(movie_id).await {
+                tracing::warn!(movie_id, "Failed to export movie nfo: {e}");
+            }
+            exported += 1;
+            progress.progress(NfoExportProgress { exported, total });
+        }
+
+        for show_id in shows {
+            let export = async {
+                self.export_show(show_id).await?;
+                let seasons =
+                    sqlx::query_scalar!("SELECT number FROM seasons WHERE show_id = ?", show_id)
+                        .fetch_all(&self.db.pool)
+                        .await?;
+                for season in seasons {
+                    self.export_season(show_id, season).await?;
+                }
+                let episodes = sqlx::query_scalar!(
+                    "SELECT episodes.id FROM episodes
+                    JOIN seasons ON seasons.id = episodes.season_id
+                    WHERE seasons.show_id = ?",
+                    show_id
+                )
+                .fetch_all(&self.db.pool)
+                .await?;
+                for episode_id in episodes {
+                    self.export_episode(episode_id).await?;
+                }
+                anyhow::Ok(())
+            };
+            if let Err(e) = export.await {
+                tracing::warn!(show_id, "Failed to export show nfo: {e}");
+            }
+            exported += 1;
+            progress.progress(NfoExportProgress { exported, total });
+        }
+        Ok(())
+    }
+
+    pub async fn export_movie(&self, movie_id: i64) -> anyhow::Result<()> {
+        let movie = sqlx::query!(
+            "SELECT movies.duration, metadata.id as metadata_id, metadata.title, metadata.plot,
+            metadata.release_date, metadata.poster, movies.backdrop FROM movies
+            JOIN metadata ON metadata.id = movies.metadata_id WHERE movies.id = ?",
+            movie_id
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+        let videos = self.video_paths(movie.metadata_id).await?;
+        if videos.is_empty() {
+            return Ok(());
+        }
+
+        let content = NfoContent {
+            title: Some(movie.title),
+            plot: movie.plot,
+            year: release_year(movie.release_date.as_deref()),
+            premiered: movie.release_date,
+            runtime: (movie.duration > 0).then_some(movie.duration as u64 / 60),
+            genres: self.genres(movie.metadata_id).await?,
+            cast: self.cast(movie.metadata_id).await?,
+            unique_ids: self.unique_ids(movie.metadata_id).await?,
+            ratings: self.ratings(movie.metadata_id).await?,
+            poster: movie.poster.filter(|p| is_remote(p)),
+            fanart: movie.backdrop.filter(|p| is_remote(p)),
+            ..Default::default()
+        };
+        let xml = content.to_xml("movie");
+        let poster = PosterAsset::new(movie_id, PosterContentType::Movie);
+        let backdrop = BackdropAsset::new(movie_id, BackdropContentType::Movie);
+
+        for video in videos {
+            let (Some(root), Some(dir), Some(stem)) = (
+                self.library_root(&video),
+                video.parent(),
+                video.file_stem().map(|s| s.to_string_lossy()),
+            ) else {
+                continue;
+            };
+            write_nfo(&video.with_extension("nfo"), &xml).await?;
+            // Movies that share the library root must not share artwork
+            let (poster_name, fanart_name) = match dir == root {
+                true => (format!("{stem}-poster.jpg"), format!("{stem}-fanart.jpg")),
+                false => ("poster.jpg".to_owned(), "fanart.jpg".to_owned()),
+            };
+            copy_asset(&poster, &dir.join(poster_name)).await?;
+            copy_asset(&backdrop, &dir.join(fanart_name)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn export_show(&self, show_id: i64) -> anyhow::Result<()> {
+        let Some(show_dir) = self
+            .show_video(show_id)
+            .await?
+            .and_then(|video| local_artwork::show_dir(&video, &self.folders))
+        else {
+            return Ok(());
+        };
+        let show = sqlx::query!(
+            "SELECT metadata.id as metadata_id, metadata.title, metadata.plot,
+            metadata.release_date, metadata.poster, shows.backdrop FROM shows
+            JOIN metadata ON metadata.id = shows.metadata_id WHERE shows.id = ?",
+            show_id
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        let content = NfoContent {
+            title: Some(show.title),
+            plot: show.plot,
+            year: release_year(show.release_date.as_deref()),
+            premiered: show.release_date,
+            genres: self.genres(show.metadata_id).await?,
+            cast: self.cast(show.metadata_id).await?,
+            unique_ids: self.unique_ids(show.metadata_id).await?,
+            ratings: self.ratings(show.metadata_id).await?,
+            poster: show.poster.filter(|p| is_remote(p)),
+            fanart: show.backdrop.filter(|p| is_remote(p)),
+            ..Default::default()
+        };
+        write_nfo(&show_dir.join("tvshow.nfo"), &content.to_xml("tvshow")).await?;
+        copy_asset(
+            &PosterAsset::new(show_id, PosterContentType::Show),
+            &show_dir.join("poster.jpg"),
+        )
+        .await?;
+        copy_asset(
+            &BackdropAsset::new(show_id, BackdropContentType::Show),
+            &show_dir.join("fanart.jpg"),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn export_season(&self, show_id: i64, season: i64) -> anyhow::Result<()> {
+        let Some(show_dir) = self
+            .show_video(show_id)
+            .await?
+            .and_then(|video| local_artwork::show_dir(&video, &self.folders))
+        else {
+            return Ok(());
+        };
+        let row = sqlx::query!(
+            "SELECT seasons.id, metadata.title, metadata.plot, metadata.release_date,
+            metadata.poster FROM seasons
+            JOIN metadata ON metadata.id = seasons.metadata_id
+            WHERE seasons.show_id = ? AND seasons.number = ?",
+            show_id,
+            season
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        copy_asset(
+            &PosterAsset::new(row.id, PosterContentType::Season),
+            &show_dir.join(format!("season{season:02}-poster.jpg")),
+        )
+        .await?;
+
+        // season.nfo can only be placed into the dedicated season folder
+        let season_dirs = sqlx::query_scalar!(
+            "SELECT videos.path FROM videos
+            JOIN episodes ON episodes.metadata_id = videos.metadata_id
+            WHERE episodes.season_id = ?",
+            row.id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        let season_dir = season_dirs
+            .iter()
+            .filter_map(|path| Path::new(path).parent())
+            .find(|dir| *dir != show_dir && dir.starts_with(&show_dir));
+        if let Some(season_dir) = season_dir {
+            let content = NfoContent {
+                title: Some(row.title),
+                plot: row.plot,
+                year: release_year(row.release_date.as_deref()),
+                premiered: row.release_date,
+                season_number: Some(season as usize),
+                poster: row.poster.filter(|p| is_remote(p)),
+                ..Default::default()
+            };
+            write_nfo(&season_dir.join("season.nfo"), &content.to_xml("season")).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn export_episode(&self, episode_id: i64) -> anyhow::Result<()> {
+        let episode = sqlx::query!(
+            "SELECT episodes.number, episodes.duration, seasons.number as season_number,
+            metadata.id as metadata_id, metadata.title, metadata.plot, metadata.release_date,
+            metadata.poster FROM episodes
+            JOIN seasons ON seasons.id = episodes.season_id
+            JOIN metadata ON metadata.id = episodes.metadata_id
+            WHERE episodes.id = ?",
+            episode_id
+        )
+        .fetch_one(&self.db.pool)
+        .await?;
+
+        let content = NfoEpisode {
+            title: Some(episode.title),
+            season: Some(episode.season_number as usize),
+            episode: Some(episode.number as usize),
+            plot: episode.plot,
+            aired: episode.release_date,
+            runtime: (episode.duration > 0).then_some(episode.duration as u64 / 60),
+            thumb: episode.poster.filter(|p| is_remote(p)),
+            cast: self.cast(episode.metadata_id).await?,
+        };
+        let xml = content.to_xml();
+        let poster = PosterAsset::new(episode_id, PosterContentType::Episode);
+        for video in self.video_paths(episode.metadata_id).await? {
+            if self.library_root(&video).is_none() {
+                continue;
+            }
+            write_nfo(&video.with_extension("nfo"), &xml).await?;
+            if let Some(stem) = video.file_stem() {
+                let thumb = format!("{}-thumb.jpg", stem.to_string_lossy());
+                copy_asset(&poster, &video.with_file_name(thumb)).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Library folder with enabled export that contains the path
+    fn library_root(&self, path: &Path) -> Option<&Path> {
+        self.folders
+            .iter()
+            .find(|folder| path.starts_with(folder))
+            .map(PathBuf::as_path)
+    }
+
+    /// Any video of the show
+    async fn show_video(&self, show_id: i64) -> anyhow::Result<Option<PathBuf>> {
+        let path = sqlx::query_scalar!(
+            "SELECT videos.path FROM videos
+            JOIN episodes ON episodes.metadata_id = videos.metadata_id
+            JOIN seasons ON seasons.id = episodes.season_id
+            WHERE seasons.show_id = ? LIMIT 1",
+            show_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+        Ok(path.map(PathBuf::from))
+    }
+
+    async fn video_paths(&self, metadata_id: i64) -> anyhow::Result<Vec<PathBuf>> {
+        let paths =
+            sqlx::query_scalar!("SELECT path FROM videos WHERE metadata_id = ?", metadata_id)
+                .fetch_all(&self.db.pool)
+                .await?;
+        Ok(paths.into_iter().map(PathBuf::from).collect())
+    }
+
+    async fn genres(&self, metadata_id: i64) -> anyhow::Result<Vec<Genre>> {
+        let genres = sqlx::query_scalar!(
+            "SELECT genre_id FROM content_genres WHERE metadata_id = ?",
+            metadata_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(genres
+            .into_iter()
+            .filter_map(|id| Genre::try_from(id).ok())
+            .collect())
+    }
+
+    async fn cast(&self, metadata_id: i64) -> anyhow::Result<Vec<NfoActor>> {
+        let cast = sqlx::query!(
+            "SELECT actors.name, actors.poster, roles.character FROM roles
+            JOIN actors ON actors.id = roles.actor_id WHERE roles.metadata_id = ? AND roles.job IS NULL",
+            metadata_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(cast
+            .into_iter()
+            .map(|actor| NfoActor {
+                name: actor.name,
+                role: actor.character,
+                thumb: actor.poster.filter(|p| is_remote(p)),
+            })
+            .collect())
+    }
+
+    /// Ratings saved with the metadata. IMDb rating from the imported datasets replaces the saved one
+    async fn ratings(&self, metadata_id: i64) -> anyhow::Result<Vec<NfoRating>> {
+        let mut ratings: Vec<NfoRating> = self
+            .db
+            .get_content_ratings(metadata_id)
+            .await?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        let imdb_id = sqlx::query_scalar!(
+            "SELECT external_id FROM external_ids WHERE metadata_id = ? AND external_provider = 'imdb'",
+            metadata_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+        let Some(imdb_id) = imdb_id else {
+            return Ok(ratings);
+        };
+        if let Some(rating) = imdb::rating(&self.db, &imdb_id).await? {
+            ratings.retain(|r| r.name != "imdb");
+            let has_default = ratings.iter().any(|r| r.is_default);
+            ratings.push(NfoRating {
+                name: "imdb".to_owned(),
+                value: rating.average_rating,
+                max: Some(10.),
+                votes: Some(rating.num_votes as u64),
+                is_default: !has_default,
+            });
+        }
+        Ok(ratings)
+    }
+
+    /// Ids of the public providers. Local and nfo ids only make sense for this server
+    async fn unique_ids(&self, metadata_id: i64) -> anyhow::Result<Vec<ExternalIdMetadata>> {
+        let ids = sqlx::query!(
+            r#"SELECT external_provider as "external_provider: MetadataProvider", external_id
+            FROM external_ids WHERE metadata_id = ? ORDER BY is_prime DESC"#,
+            metadata_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(ids
+            .into_iter()
+            .filter(|id| {
+                matches!(
+                    id.external_provider,
+                    MetadataProvider::Tmdb
+                        | MetadataProvider::Tvdb
+                        | MetadataProvider::Imdb
+                        | MetadataProvider::Anilist
+                        | MetadataProvider::Mal
+                )
+            })
+            .map(|id| ExternalIdMetadata {
+                provider: id.external_provider,
+                id: id.external_id,
+            })
+            .collect())
+    }
+}
+
+fn release_year(release_date: Option<&str>) -> Option<u16> {
+    release_date?.get(..4)?.parse().ok()
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// Copy saved asset to the destination unless the destination already exists
+async fn copy_asset(asset: &impl FileAsset, destination: &Path) -> anyhow::Result<()> {
+    let source = asset.path();
+    if !source.try_exists()? || destination.try_exists()? {
+        return Ok(());
+    }
+    tokio::fs::copy(source, destination).await?;
+    Ok(())
+}
+
+/// Write the nfo unless the destination is an nfo file that was not written by the server
+async fn write_nfo(destination: &Path, xml: &str) -> anyhow::Result<()> {
+    match tokio::fs::read_to_string(destination).await {
+        Ok(existing) if !existing.contains(EXPORT_MARKER) => {
+            tracing::debug!("Keeping custom nfo file {}", destination.display());
+            return Ok(());
+        }
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    tokio::fs::write(destination, xml).await?;
+    Ok(())
+}
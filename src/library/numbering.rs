@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppError,
+    db::{Db, DbActions, DbExternalId},
+    metadata::{
+        AbsoluteEpisode, EpisodeOrdering, FetchParams, MetadataProvider, ParentMediaType,
+        ShowMetadataProvider, metadata_stack::MetadataProvidersStack,
+    },
+    parser::show::ShowIdentifier,
+};
+
+use super::LibraryItem;
+
+/// Which numbering the show seasons follow
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum EpisodeNumbering {
+    /// Absolute episode numbers are translated into provider seasons
+    #[default]
+    Provider,
+    /// Season and episode numbers are taken from files as is
+    File,
+}
+
+/// Manual absolute (scene) episode number mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct EpisodeMapping {
+    pub absolute_number: i64,
+    pub season: i64,
+    pub episode: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ShowNumbering {
+    pub numbering: EpisodeNumbering,
+    /// Provider ordering used to fetch seasons and episodes
+    #[serde(default)]
+    pub ordering: EpisodeOrdering,
+    /// Mappings that take precedence over provider absolute ordering
+    #[serde(default)]
+    pub mappings: Vec<EpisodeMapping>,
+}
+
+pub async fn get(db: &Db, show_id: i64) -> sqlx::Result<ShowNumbering> {
+    let show = sqlx::query!(
+        r#"select episode_numbering as "episode_numbering: EpisodeNumbering",
+        episode_ordering as "episode_ordering: EpisodeOrdering" from shows where id = ?"#,
+        show_id
+    )
+    .fetch_one(&db.pool)
+    .await?;
+    let mappings = sqlx::query_as!(
+        EpisodeMapping,
+        "select absolute_number, season, episode from episode_mappings
+        where show_id = ? order by absolute_number",
+        show_id
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(ShowNumbering {
+        numbering: show.episode_numbering,
+        ordering: show.episode_ordering,
+        mappings,
+    })
+}
+
+pub async fn update(db: &Db, show_id: i64, numbering: &ShowNumbering) -> sqlx::Result<()> {
+    let mut tx = db.begin().await?;
+    let res = sqlx::query!(
+        "update shows set episode_numbering = ?, episode_ordering = ? where id = ?",
+        numbering.numbering,
+        numbering.ordering,
+        show_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    sqlx::query!("delete from episode_mappings where show_id = ?", show_id)
+        .execute(&mut *tx)
+        .await?;
+    for mapping in &numbering.mappings {
+        sqlx::query!(
+            "insert or replace into episode_mappings (show_id, absolute_number, season, episode)
+            values (?, ?, ?, ?)",
+            show_id,
+            mapping.absolute_number,
+            mapping.season,
+            mapping.episode,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// First remote show provider the local show has an id for
+pub async fn show_provider(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    show_id: i64,
+) -> crate::Result<(&'static (dyn ShowMetadataProvider + Send + Sync), String)> {
+    let external_ids = db.get_external_ids(show_id, ParentMediaType::Show).await?;
+    providers
+        .show_providers()
+        .into_iter()
+        .filter(|p| p.provider_identifier() != MetadataProvider::Local)
+        .find_map(|p| {
+            let id = external_ids
+                .iter()
+                .find(|id| id.provider == p.provider_identifier())?;
+            Some((p, id.id.clone()))
+        })
+        .ok_or(AppError::not_found(
+            "Show does not have ids of the metadata providers",
+        ))
+}
+
+/// Re-fetch metadata of the local seasons and episodes under the given ordering.
+///
+/// Local seasons and episodes keep their numbers, so videos, history and intros stay linked
+/// while their metadata is replaced with the one from the new ordering.
+/// Ordering is saved along with the metadata, so a failed reorder keeps the previous one.
+/// Returns the amount of updated episodes
+pub async fn reorder_show(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    show_id: i64,
+    ordering: EpisodeOrdering,
+    fetch_params: FetchParams,
+) -> crate::Result<usize> {
+    let fetch_params = FetchParams {
+        ordering,
+        ..fetch_params
+    };
+    let (provider, provider_show_id) = show_provider(db, providers, show_id).await?;
+    let provider_identifier = provider.provider_identifier();
+
+    let local_seasons = sqlx::query!(
+        "select id, metadata_id, number from seasons where show_id = ? order by number",
+        show_id
+    )
+    .fetch_all(&db.pool)
+    .await?;
+
+    // Seasons are fetched before the transaction is opened to avoid locking the database
+    // for the duration of rate limited provider requests
+    let mut seasons = Vec::with_capacity(local_seasons.len());
+    for local_season in local_seasons {
+        match provider
+            .season(
+                &provider_show_id,
+                local_season.number as usize,
+                fetch_params,
+            )
+            .await
+        {
+            Ok(season) => seasons.push((local_season, season)),
+            Err(e) => {
+                tracing::warn!(
+                    "Season {} is not available in {ordering:?} ordering: {e}",
+                    local_season.number
+                );
+            }
+        };
+    }
+
+    let mut tx = db.begin().await?;
+    // Episode ids are relinked after all metadata is updated because orderings can swap them
+    let mut episode_ids = Vec::new();
+    for (local_season, season) in &seasons {
+        tx.update_metadata(local_season.metadata_id, &season.into_db_metadata())
+            .await?;
+        let local_episodes = sqlx::query!(
+            "select id, metadata_id, number from episodes where season_id = ?",
+            local_season.id
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for local_episode in local_episodes {
+            let Some(episode) = season
+                .episodes
+                .iter()
+                .find(|e| e.number == local_episode.number as usize)
+            else {
+                continue;
+            };
+            tx.update_metadata(local_episode.metadata_id, &episode.into_db_metadata())
+                .await?;
+            sqlx::query!(
+                "delete from external_ids where metadata_id = ? and external_provider = ?",
+                local_episode.metadata_id,
+                provider_identifier,
+            )
+            .execute(&mut *tx)
+            .await?;
+            episode_ids.push((local_episode.metadata_id, episode.metadata_id.clone()));
+        }
+    }
+    let updated = episode_ids.len();
+    for (metadata_id, external_id) in episode_ids {
+        let res = tx
+            .try_insert_external_id(DbExternalId {
+                external_provider: provider_identifier,
+                external_id,
+                metadata_id: Some(metadata_id),
+                ..Default::default()
+            })
+            .await;
+        if let Err(e) = res {
+            tracing::error!("Failed to relink episode external id: {e}");
+        }
+    }
+    sqlx::query!(
+        "update shows set episode_ordering = ? where id = ?",
+        ordering,
+        show_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    tracing::info!("Updated {updated} episodes of the show {show_id} using {ordering:?} ordering");
+    Ok(updated)
+}
+
+/// Absolute episode number to season and episode lookup
+#[derive(Debug, Clone, Default)]
+pub struct AbsoluteOrdering {
+    episodes: HashMap<usize, (usize, usize)>,
+}
+
+impl AbsoluteOrdering {
+    /// Manual mappings override provider ordering
+    pub fn new(provider_episodes: &[AbsoluteEpisode], mappings: &[EpisodeMapping]) -> Self {
+        let mut episodes: HashMap<_, _> = provider_episodes
+            .iter()
+            .map(|e| (e.absolute_number, (e.season, e.episode)))
+            .collect();
+        for mapping in mappings {
+            episodes.insert(
+                mapping.absolute_number as usize,
+                (mapping.season as usize, mapping.episode as usize),
+            );
+        }
+        Self { episodes }
+    }
+
+    pub fn contains(&self, absolute_number: usize) -> bool {
+        self.episodes.contains_key(&absolute_number)
+    }
+
+    /// Replace absolute numbers of the videos with provider season and episode.
+    ///
+    /// Videos that are not part of the ordering are left untouched
+    pub fn apply(&self, videos: &mut [LibraryItem<ShowIdentifier>]) {
+        for video in videos.iter_mut().filter(|v| v.identifier.absolute) {
+            let absolute_number = video.identifier.episode as usize;
+            if let Some((season, episode)) = self.episodes.get(&absolute_number) {
+                tracing::trace!(
+                    "Mapped absolute episode {absolute_number} to S{season:02}E{episode:02}"
+                );
+                video.identifier.season = *season as u16;
+                video.identifier.episode = *episode as u16;
+                video.identifier.absolute = false;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::AbsoluteEpisode;
+
+    use super::{AbsoluteOrdering, EpisodeMapping};
+
+    #[test]
+    fn mappings_override_provider_ordering() {
+        let provider: Vec<_> = (1..=30)
+            .map(|n| AbsoluteEpisode {
+                absolute_number: n,
+                season: if n <= 24 { 1 } else { 2 },
+                episode: if n <= 24 { n } else { n - 24 },
+            })
+            .collect();
+        let mappings = [EpisodeMapping {
+            absolute_number: 30,
+            season: 0,
+            episode: 1,
+        }];
+        let ordering = AbsoluteOrdering::new(&provider, &mappings);
+        assert_eq!(ordering.episodes.get(&25), Some(&(2, 1)));
+        assert_eq!(ordering.episodes.get(&30), Some(&(0, 1)));
+        assert!(!ordering.contains(31));
+    }
+}
@@ -0,0 +1,646 @@
+use std::{
+    path::{Component, Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+
+use crate::{
+    app_state::AppState,
+    config,
+    db::DbActions,
+    library::{is_format_supported, libraries::LibraryFolders},
+    metadata::metadata_stack::MetadataProvidersStack,
+    progress::{ProgressDispatcher, TaskError, TaskTrait},
+    scan::{LibraryScanTask, ScanConfig},
+    torrent::{DownloadState, ProgressEvent, TorrentContent, TorrentStateChange, resolve_files},
+    utils,
+};
+
+/// How long to wait for the running library scan before starting a new one
+const SCAN_RETRY: Duration = Duration::from_secs(5);
+
+/// How imported files are placed into the library
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Hardlink files to keep torrents seeding without using extra space.
+    /// Files are copied when hardlink is not possible, for example across filesystems,
+    /// such files are reported in the import progress
+    #[default]
+    Hardlink,
+    Copy,
+    Move,
+}
+
+/// Import of downloaded files into the libraries
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct ImportTask {
+    /// Folder or file that is imported
+    #[schema(value_type = String)]
+    pub source: PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct ImportProgress {
+    pub imported: usize,
+    pub total: usize,
+    /// Files that were copied because they could not be hardlinked
+    pub copied: usize,
+}
+
+impl TaskTrait for ImportTask {
+    type Progress = ImportProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> crate::progress::TaskProgress
+    where
+        Self: Sized,
+    {
+        crate::progress::TaskProgress::Import(status)
+    }
+}
+
+/// Video file that is going to be imported
+#[derive(Debug, Clone)]
+struct ImportFile {
+    source: PathBuf,
+    /// Path relative to the imported folder, used to group files into releases
+    relative: PathBuf,
+    size: u64,
+    /// Index of the file in the torrent
+    torrent_idx: Option<i64>,
+}
+
+/// Value of the naming template placeholder
+#[derive(Debug, Clone, Copy)]
+pub enum TemplateValue<'a> {
+    Text(&'a str),
+    Number(usize),
+}
+
+fn sanitize(value: &str) -> String {
+    value
+        .chars()
+        .filter_map(|c| match c {
+            '/' | '\\' | ':' => Some('-'),
+            '*' | '?' | '"' | '<' | '>' | '|' => None,
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+/// Render naming template like `{show}/Season {season:02}/{show} - S{season:02}E{episode:02}.{ext}`
+///
+/// Placeholder values never introduce new folders, only `/` of the template itself does.
+/// Empty brackets left by missing values are removed.
+pub fn render_template(
+    template: &str,
+    values: &[(&str, TemplateValue)],
+) -> anyhow::Result<PathBuf> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .context("unclosed placeholder")?;
+        let placeholder = &rest[start + 1..end];
+        let (key, width) = match placeholder.split_once(':') {
+            Some((key, width)) => (key, width.parse().context("invalid placeholder width")?),
+            None => (placeholder, 0),
+        };
+        let (_, value) = values
+            .iter()
+            .find(|(k, _)| *k == key)
+            .with_context(|| format!("unknown placeholder {key}"))?;
+        match value {
+            TemplateValue::Text(text) => out.push_str(&sanitize(text)),
+            TemplateValue::Number(number) => out.push_str(&format!("{number:0width$}")),
+        }
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    let mut path = PathBuf::new();
+    for component in out.split('/') {
+        let component = component.replace("()", "").replace("[]", "");
+        let component = component.split_whitespace().collect::<Vec<_>>().join(" ");
+        let component = component.trim_end_matches('.');
+        if matches!(
+            Path::new(component).components().next(),
+            Some(Component::Normal(_))
+        ) {
+            path.push(component);
+        }
+    }
+    anyhow::ensure!(
+        path.components().next().is_some(),
+        "template renders empty path"
+    );
+    Ok(path)
+}
+
+fn release_year(date: Option<&str>) -> &str {
+    date.and_then(|d| d.get(..4)).unwrap_or_default()
+}
+
+/// Resolve metadata of the single release and plan the library path of its videos
+async fn plan_release(
+    providers_stack: &'static MetadataProvidersStack,
+    folders: &LibraryFolders,
+    files: &[ImportFile],
+) -> Vec<(usize, PathBuf)> {
+    let (config::ShowNamingTemplate(show_template), config::MovieNamingTemplate(movie_template)) =
+        config::CONFIG.get_values();
+    let relative: Vec<_> = files.iter().map(|f| (f.relative.clone(), f.size)).collect();
+    let contents = resolve_files(providers_stack, &relative, None).await;
+    let root = |folders: &[PathBuf]| {
+        folders
+            .iter()
+            .find(|f| f.try_exists().unwrap_or(false))
+            .cloned()
+    };
+    let extension = |idx: usize| {
+        files[idx]
+            .source
+            .extension()
+            .map(|e| e.to_string_lossy().to_string())
+            .unwrap_or_default()
+    };
+
+    let mut planned = Vec::new();
+    match contents.content {
+        Some(TorrentContent::Show(show)) => {
+            let Some(root) = root(&folders.shows) else {
+                tracing::warn!("Could not find show library folder for the import");
+                return planned;
+            };
+            let year = release_year(show.show_metadata.release_date.as_deref());
+            for episode in show.seasons.values().flatten() {
+                let ext = extension(episode.file_idx);
+                let values = [
+                    ("show", TemplateValue::Text(&show.show_metadata.title)),
+                    ("year", TemplateValue::Text(year)),
+                    (
+                        "season",
+                        TemplateValue::Number(episode.metadata.season_number),
+                    ),
+                    ("episode", TemplateValue::Number(episode.metadata.number)),
+                    ("title", TemplateValue::Text(&episode.metadata.title)),
+                    ("ext", TemplateValue::Text(&ext)),
+                ];
+                match render_template(&show_template, &values) {
+                    Ok(path) => planned.push((episode.file_idx, root.join(path))),
+                    Err(e) => tracing::error!("Failed to render show naming template: {e}"),
+                }
+            }
+        }
+        Some(TorrentContent::Movie(movies)) => {
+            let Some(root) = root(&folders.movies) else {
+                tracing::warn!("Could not find movie library folder for the import");
+                return planned;
+            };
+            for movie in movies {
+                let ext = extension(movie.file_idx);
+                let values = [
+                    ("title", TemplateValue::Text(&movie.metadata.title)),
+                    (
+                        "year",
+                        TemplateValue::Text(release_year(movie.metadata.release_date.as_deref())),
+                    ),
+                    ("ext", TemplateValue::Text(&ext)),
+                ];
+                match render_template(&movie_template, &values) {
+                    Ok(path) => planned.push((movie.file_idx, root.join(path))),
+                    Err(e) => tracing::error!("Failed to render movie naming template: {e}"),
+                }
+            }
+        }
+        None => {
+            tracing::warn!("Could not identify content of the imported files");
+        }
+    }
+    planned
+}
+
+/// Place the file into the library according to import mode.
+///
+/// Returns the mode that was actually used, hardlink falls back to copy
+async fn place_file(
+    source: &Path,
+    destination: &Path,
+    mode: ImportMode,
+) -> std::io::Result<ImportMode> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    match mode {
+        ImportMode::Hardlink => {
+            if let Err(e) = fs::hard_link(source, destination).await {
+                tracing::warn!(
+                    "Failed to hardlink {}, copying it instead: {e}",
+                    source.display()
+                );
+                fs::copy(source, destination).await?;
+                return Ok(ImportMode::Copy);
+            }
+        }
+        ImportMode::Copy => {
+            fs::copy(source, destination).await?;
+        }
+        ImportMode::Move => {
+            if fs::rename(source, destination).await.is_err() {
+                fs::copy(source, destination).await?;
+                fs::remove_file(source).await?;
+            }
+        }
+    }
+    Ok(mode)
+}
+
+/// Group files into releases by the first component of their relative path
+fn group_releases(files: Vec<ImportFile>) -> Vec<Vec<ImportFile>> {
+    let mut releases: Vec<Vec<ImportFile>> = Vec::new();
+    for file in files {
+        let release = file.relative.components().next();
+        match releases
+            .iter_mut()
+            .find(|r| r[0].relative.components().next() == release)
+        {
+            Some(release) => release.push(file),
+            None => releases.push(vec![file]),
+        }
+    }
+    releases
+}
+
+/// Place files into the libraries and return their library paths.
+///
+/// Files are grouped into releases by the first component of their relative path
+/// since every release is identified as a single show or a set of movies.
+async fn import_files(
+    app_state: &AppState,
+    files: Vec<ImportFile>,
+    progress: &ProgressDispatcher<ImportTask>,
+) -> Vec<(ImportFile, PathBuf)> {
+    let config::DownloadImportMode(mode) = config::CONFIG.get_value();
+    let folders = LibraryFolders::load(app_state.db).await;
+    let total = files.len();
+    let mut imported = Vec::new();
+    let mut copied = 0;
+
+    for release in group_releases(files) {
+        let (in_library, release): (Vec<_>, Vec<_>) = release.into_iter().partition(|f| {
+            folders
+                .shows
+                .iter()
+                .chain(&folders.movies)
+                .any(|root| f.source.starts_with(root))
+        });
+        // Files that are already inside a library are only scanned
+        imported.extend(in_library.into_iter().map(|f| {
+            let path = f.source.clone();
+            (f, path)
+        }));
+
+        for (idx, destination) in plan_release(app_state.providers_stack, &folders, &release).await
+        {
+            let file = &release[idx];
+            if let Ok(existing) = fs::metadata(&destination).await {
+                // Same file was imported before
+                if existing.len() == file.size {
+                    imported.push((file.clone(), destination));
+                } else {
+                    tracing::warn!(
+                        "Skipping import of {}, {} already exists",
+                        file.source.display(),
+                        destination.display()
+                    );
+                }
+                continue;
+            }
+            match place_file(&file.source, &destination, mode).await {
+                Ok(used_mode) => {
+                    if used_mode != mode {
+                        copied += 1;
+                    }
+                    tracing::info!(
+                        "Imported {} to {}",
+                        file.source.display(),
+                        destination.display()
+                    );
+                    imported.push((file.clone(), destination));
+                }
+                Err(e) => tracing::error!("Failed to import {}: {e}", file.source.display()),
+            }
+            progress.progress(ImportProgress {
+                imported: imported.len(),
+                total,
+                copied,
+            });
+        }
+    }
+    imported
+}
+
+/// Scan library folders of the imported files to pick them up
+async fn scan_imported(app_state: &AppState, paths: &[PathBuf]) -> anyhow::Result<()> {
+    let mut folders: Vec<PathBuf> = paths
+        .iter()
+        .filter_map(|p| p.parent())
+        .map(Path::to_path_buf)
+        .collect();
+    folders.sort();
+    folders.dedup();
+    let tasks = app_state.tasks;
+    let config = ScanConfig::new_from_server_configuration();
+    let task_id = loop {
+        match tasks
+            .library_scan_tasks
+            .start_task(LibraryScanTask::new(config.clone()), None)
+        {
+            Ok(id) => break id,
+            // Running scan could miss imported files
+            Err(TaskError::Duplicate) => tokio::time::sleep(SCAN_RETRY).await,
+            Err(e) => return Err(e.into()),
+        }
+    };
+    match app_state
+        .reconciliate_folders(task_id, config, &folders)
+        .await
+    {
+        Ok(_) => {
+            tasks.library_scan_tasks.finish_task(task_id);
+            Ok(())
+        }
+        Err(e) => {
+            tasks
+                .library_scan_tasks
+                .error_task(task_id, TaskError::Failure);
+            Err(e.into())
+        }
+    }
+}
+
+/// Import and scan files, reporting progress as the import task
+async fn run_import(
+    app_state: &AppState,
+    source: PathBuf,
+    files: Vec<ImportFile>,
+) -> anyhow::Result<Vec<(ImportFile, PathBuf)>> {
+    let tasks = app_state.tasks;
+    let task_id = tasks.import_tasks.start_task(ImportTask { source }, None)?;
+    let progress = ProgressDispatcher::new(&tasks.import_tasks, task_id);
+    let imported = import_files(app_state, files, &progress).await;
+    let paths: Vec<_> = imported.iter().map(|(_, path)| path.clone()).collect();
+    if !paths.is_empty()
+        && let Err(e) = scan_imported(app_state, &paths).await
+    {
+        progress.error(TaskError::Failure);
+        return Err(e);
+    }
+    progress.finish();
+    Ok(imported)
+}
+
+/// Videos of the imported folder.
+///
+/// Paths are relative to the folder itself, so every top level entry of the folder is a release
+async fn folder_files(folder: &Path) -> anyhow::Result<Vec<ImportFile>> {
+    let walk_folder = folder.to_owned();
+    let paths = tokio::task::spawn_blocking(move || {
+        if walk_folder.is_file() {
+            Ok(vec![walk_folder])
+        } else {
+            utils::walk_recursive(&walk_folder, Some(is_format_supported))
+        }
+    })
+    .await
+    .expect("blocking task panicked")?;
+    // Single file is imported as its own release
+    let base = if folder.is_file() {
+        folder.parent().unwrap_or(folder)
+    } else {
+        folder
+    };
+    let mut files = Vec::with_capacity(paths.len());
+    for source in paths.into_iter().filter(is_format_supported) {
+        let size = fs::metadata(&source).await?.len();
+        let relative = source.strip_prefix(base).unwrap_or(&source).to_owned();
+        files.push(ImportFile {
+            source,
+            relative,
+            size,
+            torrent_idx: None,
+        });
+    }
+    Ok(files)
+}
+
+/// Import every video from the folder into the libraries
+pub async fn import_folder(app_state: &AppState, folder: PathBuf) -> anyhow::Result<usize> {
+    let files = folder_files(&folder).await?;
+    let imported = run_import(app_state, folder, files).await?;
+    Ok(imported.len())
+}
+
+/// Whether the torrent was already handled by the importer
+async fn is_torrent_imported(app_state: &AppState, torrent_id: i64) -> sqlx::Result<bool> {
+    sqlx::query_scalar!(
+        r#"select exists(select 1 from torrent_imports where torrent_id = ?1)
+        or exists(select 1 from torrent_files
+        where torrent_id = ?1 and metadata_id is not null) as "imported!: bool""#,
+        torrent_id
+    )
+    .fetch_one(&app_state.db.pool)
+    .await
+}
+
+/// Import finished torrent files and link them to the library content.
+///
+/// Torrent is marked as imported even if none of its files ended up in the library,
+/// so it is not imported again on every seeding state change
+pub async fn import_torrent(app_state: &AppState, info_hash: &[u8; 20]) -> anyhow::Result<()> {
+    let db = app_state.db;
+    let torrent = db.pool.get_torrent_by_info_hash(info_hash).await?;
+    let torrent_id = torrent.id.context("torrent id")?;
+    if is_torrent_imported(app_state, torrent_id).await? {
+        return Ok(());
+    }
+    let info = torrent::Info::from_bytes(&torrent.bencoded_info)?;
+    let save_location = PathBuf::from(&torrent.save_location);
+
+    let mut files = Vec::new();
+    for file in db.pool.torrent_files(torrent_id).await? {
+        let relative = PathBuf::from(&file.relative_path);
+        let source = save_location.join(&relative);
+        if file.priority == 0 || !is_format_supported(&source) {
+            continue;
+        }
+        let Ok(metadata) = fs::metadata(&source).await else {
+            continue;
+        };
+        files.push(ImportFile {
+            source,
+            relative,
+            size: metadata.len(),
+            torrent_idx: Some(file.idx),
+        });
+    }
+
+    let imported = run_import(app_state, save_location.join(&info.name), files).await?;
+
+    let mut tx = db.begin().await?;
+    for (file, path) in imported {
+        let Some(idx) = file.torrent_idx else {
+            continue;
+        };
+        let path = path.to_string_lossy();
+        sqlx::query!(
+            "update torrent_files set metadata_id = (select metadata_id from videos where path = ?)
+            where torrent_id = ? and idx = ?",
+            path,
+            torrent_id,
+            idx
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    sqlx::query!(
+        "insert or ignore into torrent_imports (torrent_id) values (?)",
+        torrent_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Import torrents once they finish downloading
+pub async fn run_download_importer(app_state: AppState) {
+    let mut progress = app_state.torrent_client.progress_broadcast.subscribe();
+    loop {
+        let progress = tokio::select! {
+            progress = progress.recv() => progress,
+            _ = app_state.cancelation_token.cancelled() => break,
+        };
+        let progress = match progress {
+            Ok(progress) => progress,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        };
+        let config::ImportCompletedDownloads(enabled) = config::CONFIG.get_value();
+        if !enabled {
+            continue;
+        }
+        for torrent in &progress.changed_torrents {
+            let finished = torrent.events.iter().any(|e| {
+                matches!(
+                    e,
+                    ProgressEvent::State(TorrentStateChange {
+                        state: DownloadState::Seeding
+                    })
+                )
+            });
+            if !finished {
+                continue;
+            }
+            let app_state = app_state.clone();
+            let info_hash = torrent.info_hash;
+            tokio::spawn(async move {
+                if let Err(e) = import_torrent(&app_state, &info_hash).await {
+                    tracing::error!(
+                        "Failed to import torrent {}: {e}",
+                        utils::stringify_info_hash(&info_hash)
+                    );
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{TemplateValue, folder_files, group_releases, render_template};
+
+    #[test]
+    fn naming_template() {
+        let values = [
+            ("show", TemplateValue::Text("Who: Are You?")),
+            ("year", TemplateValue::Text("")),
+            ("season", TemplateValue::Number(1)),
+            ("episode", TemplateValue::Number(12)),
+            ("title", TemplateValue::Text("Pilot/Part 1")),
+            ("ext", TemplateValue::Text("mkv")),
+        ];
+        let path = render_template(
+            "{show} ({year})/Season {season:02}/{show} - S{season:02}E{episode:02} - {title}.{ext}",
+            &values,
+        )
+        .unwrap();
+        assert_eq!(
+            path,
+            PathBuf::from("Who- Are You/Season 01/Who- Are You - S01E12 - Pilot-Part 1.mkv")
+        );
+
+        assert!(render_template("{show}/{unknown}", &values).is_err());
+        assert!(render_template("{show", &values).is_err());
+        assert_eq!(
+            render_template("../{show}.{ext}", &values).unwrap(),
+            PathBuf::from("Who- Are You.mkv")
+        );
+    }
+
+    #[tokio::test]
+    async fn folder_releases() {
+        let folder = std::env::temp_dir().join(format!("organizer_{}", std::process::id()));
+        for file in [
+            "Show S01/Show S01E01.mkv",
+            "Show S01/Show S01E02.mkv",
+            "Movie (2020)/Movie (2020).mkv",
+            "Other Movie (2021).mkv",
+        ] {
+            let path = folder.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, []).unwrap();
+        }
+
+        let files = folder_files(&folder).await.unwrap();
+        let mut releases: Vec<_> = group_releases(files)
+            .into_iter()
+            .map(|release| {
+                (
+                    release[0]
+                        .relative
+                        .components()
+                        .next()
+                        .unwrap()
+                        .as_os_str()
+                        .to_owned(),
+                    release.len(),
+                )
+            })
+            .collect();
+        releases.sort();
+        assert_eq!(
+            releases,
+            vec![
+                ("Movie (2020)".into(), 1),
+                ("Other Movie (2021).mkv".into(), 1),
+                ("Show S01".into(), 2),
+            ]
+        );
+
+        let single = folder_files(&folder.join("Other Movie (2021).mkv"))
+            .await
+            .unwrap();
+        assert_eq!(single[0].relative, PathBuf::from("Other Movie (2021).mkv"));
+        std::fs::remove_dir_all(&folder).unwrap();
+    }
+}
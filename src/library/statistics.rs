@@ -0,0 +1,387 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde::Serialize;
+
+use crate::{
+    db::Db,
+    ffmpeg_abi::ProbeOutput,
+    library::{libraries::MediaLibrary, media::container::VideoContainer},
+    metadata::Genre,
+};
+
+/// Amount of entries in top genres and actors
+const TOP_LIMIT: i64 = 10;
+
+/// Items grouped by the same value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct Bucket {
+    pub key: String,
+    pub count: usize,
+    /// Total size of the videos in bytes
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, utoipa::ToSchema)]
+pub struct LibraryStats {
+    pub library_id: i64,
+    pub name: String,
+    pub movies: usize,
+    pub shows: usize,
+    pub episodes: usize,
+    pub videos: usize,
+    /// Total size of the videos in bytes
+    pub size: u64,
+    /// Total runtime of the content in seconds
+    pub runtime: u64,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct GenreCount {
+    pub genre: Genre,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ActorCount {
+    pub id: i64,
+    pub name: String,
+    pub poster: Option<String>,
+    /// Movies and shows the actor appears in
+    pub count: i64,
+}
+
+/// Videos added during the month
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct GrowthPoint {
+    /// Month in `YYYY-MM` format
+    pub month: String,
+    pub videos: i64,
+    pub size: i64,
+    /// Videos added up to the end of the month
+    pub total_videos: i64,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct LibraryStatistics {
+    pub libraries: Vec<LibraryStats>,
+    pub video_codecs: Vec<Bucket>,
+    pub audio_codecs: Vec<Bucket>,
+    pub resolutions: Vec<Bucket>,
+    /// Videos split into `hdr` and `sdr`
+    pub dynamic_range: Vec<Bucket>,
+    pub containers: Vec<Bucket>,
+    /// Videos that were not probed yet and are missing from the stream breakdowns.
+    /// Videos are probed during the scan and the health check
+    pub unprobed_videos: usize,
+    pub top_genres: Vec<GenreCount>,
+    pub top_actors: Vec<ActorCount>,
+    pub growth: Vec<GrowthPoint>,
+}
+
+/// Counts values and sizes, larger buckets first
+#[derive(Debug, Default)]
+struct Breakdown(HashMap<String, (usize, u64)>);
+
+impl Breakdown {
+    fn add(&mut self, key: impl ToString, size: u64) {
+        let entry = self.0.entry(key.to_string()).or_default();
+        entry.0 += 1;
+        entry.1 += size;
+    }
+
+    fn into_buckets(self) -> Vec<Bucket> {
+        let mut buckets: Vec<_> = self
+            .0
+            .into_iter()
+            .map(|(key, (count, size))| Bucket { key, count, size })
+            .collect();
+        buckets.sort_unstable_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+        buckets
+    }
+}
+
+/// Common name of the resolution, width is checked too because of cropped widescreen videos
+fn resolution_label(width: u32, height: u32) -> &'static str {
+    match (width, height) {
+        (w, h) if w >= 3200 || h >= 2000 => "2160p",
+        (w, h) if w >= 2200 || h >= 1300 => "1440p",
+        (w, h) if w >= 1600 || h >= 900 => "1080p",
+        (w, h) if w >= 1100 || h >= 650 => "720p",
+        (w, h) if w >= 700 || h >= 450 => "480p",
+        _ => "sd",
+    }
+}
+
+#[derive(Debug)]
+struct VideoRow {
+    path: String,
+    size: i64,
+    content_type: Option<String>,
+    /// movies.id or shows.id
+    content_id: Option<i64>,
+    episode_id: Option<i64>,
+    duration: Option<i64>,
+}
+
+async fn library_stats(
+    db: &Db,
+    media_libraries: &[MediaLibrary],
+) -> sqlx::Result<Vec<LibraryStats>> {
+    let rows = sqlx::query_as!(
+        VideoRow,
+        r#"select videos.path, videos.size, metadata.content_type,
+        coalesce(movies.id, seasons.show_id) as "content_id: i64",
+        episodes.id as "episode_id: i64",
+        coalesce(movies.duration, episodes.duration) as "duration: i64"
+        from videos
+        left join metadata on metadata.id = videos.metadata_id
+        left join movies on movies.metadata_id = videos.metadata_id
+        left join episodes on episodes.metadata_id = videos.metadata_id
+        left join seasons on seasons.id = episodes.season_id"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut stats: Vec<_> = media_libraries
+        .iter()
+        .map(|library| LibraryStats {
+            library_id: library.id,
+            name: library.settings.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut seen = vec![(HashSet::new(), HashSet::new(), HashSet::new()); stats.len()];
+    for row in rows {
+        let path = std::path::Path::new(&row.path);
+        let Some(idx) = media_libraries.iter().position(|l| l.contains(path)) else {
+            continue;
+        };
+        let library = &mut stats[idx];
+        let (movies, shows, episodes) = &mut seen[idx];
+        library.videos += 1;
+        library.size += row.size as u64;
+        let is_new = match (row.content_type.as_deref(), row.content_id, row.episode_id) {
+            (Some("movie"), Some(id), _) => {
+                let is_new = movies.insert(id);
+                if is_new {
+                    library.movies += 1;
+                }
+                is_new
+            }
+            (Some("episode"), Some(show_id), Some(id)) => {
+                if shows.insert(show_id) {
+                    library.shows += 1;
+                }
+                let is_new = episodes.insert(id);
+                if is_new {
+                    library.episodes += 1;
+                }
+                is_new
+            }
+            _ => false,
+        };
+        // Multiple versions of the same content are counted once
+        if is_new {
+            library.runtime += row.duration.unwrap_or_default() as u64;
+        }
+    }
+    Ok(stats)
+}
+
+async fn top_genres(db: &Db) -> sqlx::Result<Vec<GenreCount>> {
+    let rows = sqlx::query!(
+        r#"select genre_id, count(distinct metadata_id) as "count!: i64" from content_genres
+        where metadata_id in (select metadata_id from movies union select metadata_id from shows)
+        group by genre_id order by 2 desc limit ?"#,
+        TOP_LIMIT,
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let genre = Genre::try_from(row.genre_id).ok()?;
+            Some(GenreCount {
+                genre,
+                count: row.count,
+            })
+        })
+        .collect())
+}
+
+async fn top_actors(db: &Db) -> sqlx::Result<Vec<ActorCount>> {
+    sqlx::query_as!(
+        ActorCount,
+        r#"select actors.id, actors.name, actors.poster,
+        count(distinct roles.metadata_id) as "count!: i64" from roles
+        join actors on actors.id = roles.actor_id
+        where roles.job is null
+        and roles.metadata_id in (select metadata_id from movies union select metadata_id from shows)
+        group by actors.id order by 4 desc, actors.name limit ?"#,
+        TOP_LIMIT,
+    )
+    .fetch_all(&db.pool)
+    .await
+}
+
+async fn growth(db: &Db) -> sqlx::Result<Vec<GrowthPoint>> {
+    let rows = sqlx::query!(
+        r#"select substr(scan_date, 1, 7) as "month!: String", count(*) as "videos!: i64",
+        coalesce(sum(size), 0) as "size!: i64" from videos
+        where scan_date is not null group by 1 order by 1"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    let mut total_videos = 0;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            total_videos += row.videos;
+            GrowthPoint {
+                month: row.month,
+                videos: row.videos,
+                size: row.size,
+                total_videos,
+            }
+        })
+        .collect())
+}
+
+/// Remember stream summaries of the probed videos
+pub async fn save_probes(db: &Db, probes: &[(i64, &ProbeOutput)]) -> sqlx::Result<()> {
+    if probes.is_empty() {
+        return Ok(());
+    }
+    let mut tx = db.begin().await?;
+    for &(video_id, probe) in probes {
+        let video = probe.default_video();
+        let video_codec = video.map(|v| v.codec.to_string());
+        let width = video.map(|v| v.width);
+        let height = video.map(|v| v.height);
+        let hdr = video.is_some_and(|v| v.hdr);
+        let audio_codec = probe.default_audio().map(|a| a.codec.to_string());
+        sqlx::query!(
+            "insert or replace into video_probes (video_id, video_codec, audio_codec, width, height, hdr)
+            values (?, ?, ?, ?, ?, ?)",
+            video_id,
+            video_codec,
+            audio_codec,
+            width,
+            height,
+            hdr,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Aggregate statistics of the library.
+///
+/// Stream breakdowns use probe results saved in the database, files are never touched here
+pub async fn collect(
+    db: &Db,
+    media_libraries: &[MediaLibrary],
+) -> anyhow::Result<LibraryStatistics> {
+    let videos = sqlx::query!(
+        r#"select videos.path, videos.size, video_probes.video_id as "probed?: i64",
+        video_probes.video_codec, video_probes.audio_codec,
+        video_probes.width as "width: u32", video_probes.height as "height: u32",
+        video_probes.hdr as "hdr?: bool"
+        from videos left join video_probes on video_probes.video_id = videos.id"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+
+    let mut video_codecs = Breakdown::default();
+    let mut audio_codecs = Breakdown::default();
+    let mut resolutions = Breakdown::default();
+    let mut dynamic_range = Breakdown::default();
+    let mut containers = Breakdown::default();
+    let mut unprobed_videos = 0;
+    for video in videos {
+        let size = video.size as u64;
+        if let Some(container) = Path::new(&video.path)
+            .extension()
+            .and_then(|ext| VideoContainer::try_from(ext).ok())
+        {
+            containers.add(container, size);
+        }
+        if video.probed.is_none() {
+            unprobed_videos += 1;
+            continue;
+        }
+        if let Some(codec) = video.video_codec {
+            video_codecs.add(codec, size);
+        }
+        if let (Some(width), Some(height)) = (video.width, video.height) {
+            resolutions.add(resolution_label(width, height), size);
+            let hdr = video.hdr.unwrap_or_default();
+            dynamic_range.add(if hdr { "hdr" } else { "sdr" }, size);
+        }
+        if let Some(codec) = video.audio_codec {
+            audio_codecs.add(codec, size);
+        }
+    }
+
+    Ok(LibraryStatistics {
+        libraries: library_stats(db, media_libraries).await?,
+        video_codecs: video_codecs.into_buckets(),
+        audio_codecs: audio_codecs.into_buckets(),
+        resolutions: resolutions.into_buckets(),
+        dynamic_range: dynamic_range.into_buckets(),
+        containers: containers.into_buckets(),
+        unprobed_videos,
+        top_genres: top_genres(db).await?,
+        top_actors: top_actors(db).await?,
+        growth: growth(db).await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Breakdown, Bucket, resolution_label};
+
+    #[test]
+    fn resolution_labels() {
+        assert_eq!(resolution_label(3840, 2160), "2160p");
+        // Cropped widescreen
+        assert_eq!(resolution_label(3840, 1600), "2160p");
+        assert_eq!(resolution_label(1920, 800), "1080p");
+        assert_eq!(resolution_label(1280, 720), "720p");
+        assert_eq!(resolution_label(720, 480), "480p");
+        assert_eq!(resolution_label(320, 240), "sd");
+    }
+
+    #[test]
+    fn breakdown_order() {
+        let mut breakdown = Breakdown::default();
+        breakdown.add("hevc", 10);
+        breakdown.add("h264", 5);
+        breakdown.add("h264", 5);
+        breakdown.add("av1", 1);
+        assert_eq!(
+            breakdown.into_buckets(),
+            [
+                Bucket {
+                    key: "h264".into(),
+                    count: 2,
+                    size: 10
+                },
+                Bucket {
+                    key: "av1".into(),
+                    count: 1,
+                    size: 1
+                },
+                Bucket {
+                    key: "hevc".into(),
+                    count: 1,
+                    size: 10
+                },
+            ]
+        );
+    }
+}
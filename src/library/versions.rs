@@ -0,0 +1,231 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::parser::attributes::{Attributes, ResolutionAttr, SourceAttr, Tag};
+
+/// Kind of the client that is going to play the movie
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionClient {
+    #[default]
+    Web,
+    Mobile,
+    Tv,
+}
+
+/// Rule that picks the default movie version for the client
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct VersionPreference {
+    pub client: VersionClient,
+    /// Versions above this resolution are picked only when nothing else is available
+    pub max_resolution: Option<ResolutionAttr>,
+    /// Preferred release source, for example `remux`
+    pub source: Option<SourceAttr>,
+    /// Preferred editions, for example `directors_cut`
+    #[serde(default)]
+    pub editions: Vec<Tag>,
+}
+
+/// Movie version described by the file name attributes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct VersionLabel {
+    pub resolution: Option<ResolutionAttr>,
+    pub source: Option<SourceAttr>,
+    pub editions: Vec<Tag>,
+}
+
+impl VersionLabel {
+    pub fn new(attributes: &Attributes) -> Self {
+        Self {
+            resolution: attributes.resolution,
+            source: attributes.source,
+            editions: attributes
+                .tags
+                .iter()
+                .copied()
+                .filter(Tag::is_edition)
+                .collect(),
+        }
+    }
+
+    /// Human readable label like `4K Remux · Director's Cut`
+    pub fn label(&self) -> Option<String> {
+        let technical: Vec<_> = [
+            self.resolution.map(resolution_name),
+            self.source.map(source_name),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        let mut parts = Vec::with_capacity(self.editions.len() + 1);
+        if !technical.is_empty() {
+            parts.push(technical.join(" "));
+        }
+        parts.extend(
+            self.editions
+                .iter()
+                .filter_map(edition_name)
+                .map(String::from),
+        );
+        (!parts.is_empty()).then(|| parts.join(" · "))
+    }
+}
+
+/// Movie video that can be picked as the default version
+#[derive(Debug, Clone)]
+pub struct VersionCandidate {
+    pub video_id: i64,
+    pub version: VersionLabel,
+    pub is_prime: bool,
+}
+
+/// Pick the default version among movie videos.
+///
+/// Without preference the version marked as prime wins, then the one with the highest resolution.
+/// Preference ranks versions that fit `max_resolution` first, then ones with the preferred source and editions.
+pub fn pick_default(
+    candidates: &[VersionCandidate],
+    preference: Option<&VersionPreference>,
+) -> Option<i64> {
+    candidates
+        .iter()
+        .max_by_key(|candidate| {
+            let version = &candidate.version;
+            let (fits, source_match, editions_match) = match preference {
+                Some(preference) => (
+                    preference
+                        .max_resolution
+                        .zip(version.resolution)
+                        .is_none_or(|(max, resolution)| resolution <= max),
+                    preference.source.is_some() && preference.source == version.source,
+                    preference
+                        .editions
+                        .iter()
+                        .filter(|e| version.editions.contains(e))
+                        .count(),
+                ),
+                None => (true, false, 0),
+            };
+            (
+                fits,
+                source_match,
+                editions_match,
+                candidate.is_prime,
+                version.resolution,
+                Reverse(candidate.video_id),
+            )
+        })
+        .map(|candidate| candidate.video_id)
+}
+
+fn resolution_name(resolution: ResolutionAttr) -> &'static str {
+    match resolution {
+        ResolutionAttr::P480 => "480p",
+        ResolutionAttr::P720 => "720p",
+        ResolutionAttr::P1080 => "1080p",
+        ResolutionAttr::P2160 => "4K",
+    }
+}
+
+fn source_name(source: SourceAttr) -> &'static str {
+    match source {
+        SourceAttr::Web => "WEB",
+        SourceAttr::WebDl => "WEB-DL",
+        SourceAttr::WebRip => "WEBRip",
+        SourceAttr::BluRay => "BluRay",
+        SourceAttr::Remux => "Remux",
+        SourceAttr::Dvd => "DVD",
+        SourceAttr::Telesync => "TS",
+    }
+}
+
+fn edition_name(tag: &Tag) -> Option<&'static str> {
+    Some(match tag {
+        Tag::Extended => "Extended",
+        Tag::Remastered => "Remastered",
+        Tag::DirectorsCut => "Director's Cut",
+        Tag::Theatrical => "Theatrical",
+        Tag::Unrated => "Unrated",
+        Tag::Imax => "IMAX",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(
+        video_id: i64,
+        resolution: ResolutionAttr,
+        source: Option<SourceAttr>,
+        is_prime: bool,
+    ) -> VersionCandidate {
+        VersionCandidate {
+            video_id,
+            version: VersionLabel {
+                resolution: Some(resolution),
+                source,
+                editions: Vec::new(),
+            },
+            is_prime,
+        }
+    }
+
+    #[test]
+    fn labels() {
+        let version = VersionLabel {
+            resolution: Some(ResolutionAttr::P2160),
+            source: Some(SourceAttr::Remux),
+            editions: vec![Tag::DirectorsCut],
+        };
+        assert_eq!(
+            Some("4K Remux · Director's Cut"),
+            version.label().as_deref()
+        );
+        let version = VersionLabel {
+            editions: vec![Tag::Extended],
+            ..Default::default()
+        };
+        assert_eq!(Some("Extended"), version.label().as_deref());
+        assert_eq!(None, VersionLabel::default().label());
+    }
+
+    #[test]
+    fn prime_wins_without_preference() {
+        let candidates = [
+            candidate(1, ResolutionAttr::P2160, None, false),
+            candidate(2, ResolutionAttr::P1080, None, true),
+        ];
+        assert_eq!(Some(2), pick_default(&candidates, None));
+        let candidates = [
+            candidate(1, ResolutionAttr::P1080, None, false),
+            candidate(2, ResolutionAttr::P2160, None, false),
+        ];
+        assert_eq!(Some(2), pick_default(&candidates, None));
+    }
+
+    #[test]
+    fn preference_overrides_prime() {
+        let candidates = [
+            candidate(1, ResolutionAttr::P2160, Some(SourceAttr::Remux), true),
+            candidate(2, ResolutionAttr::P1080, Some(SourceAttr::WebDl), false),
+            candidate(3, ResolutionAttr::P720, Some(SourceAttr::WebDl), false),
+        ];
+        let mobile = VersionPreference {
+            client: VersionClient::Mobile,
+            max_resolution: Some(ResolutionAttr::P1080),
+            source: None,
+            editions: Vec::new(),
+        };
+        assert_eq!(Some(2), pick_default(&candidates, Some(&mobile)));
+        let tv = VersionPreference {
+            client: VersionClient::Tv,
+            max_resolution: None,
+            source: Some(SourceAttr::Remux),
+            editions: Vec::new(),
+        };
+        assert_eq!(Some(1), pick_default(&candidates, Some(&tv)));
+    }
+}
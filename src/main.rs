@@ -8,8 +8,11 @@ use media_server::app_state::AppState;
 use media_server::config::{self, APP_RESOURCES, AppResources, Args, ConfigFile};
 use media_server::db::Db;
 use media_server::library::Library;
+use media_server::library::libraries::LibraryFolders;
+use media_server::library::organizer;
 use media_server::metadata::metadata_stack::MetadataProvidersStack;
 use media_server::progress::TaskResource;
+use media_server::scheduler;
 use media_server::torrent::TorrentClient;
 use media_server::tracing::init_tracer;
 use media_server::upnp::Upnp;
@@ -55,13 +58,8 @@ async fn main() {
         Err(err) => Some(err),
     };
 
-    let (
-        config::OtelEndpoint(otel_endpoint),
-        config::Port(port),
-        config::ShowFolders(show_dirs),
-        config::MovieFolders(movie_dirs),
-        config::WebUiPath(web_ui_path),
-    ) = config::CONFIG.get_values();
+    let (config::OtelEndpoint(otel_endpoint), config::Port(port), config::WebUiPath(web_ui_path)) =
+        config::CONFIG.get_values();
     let _guard = init_tracer(otel_endpoint.as_deref());
 
     match dotenv_path {
@@ -91,11 +89,17 @@ async fn main() {
 
         let db = Box::leak(Box::new(db));
 
-        let library = Library::init_from_folders(show_dirs, movie_dirs, db).await;
+        let LibraryFolders {
+            shows: show_dirs,
+            movies: movie_dirs,
+            ignore,
+        } = LibraryFolders::load(db).await;
+        let library = Library::init_from_folders(show_dirs, movie_dirs, &ignore, db).await;
         let library = Box::leak(Box::new(Mutex::new(library)));
 
         let mut providers_stack = MetadataProvidersStack::new();
-        providers_stack.setup_providers(&http_client);
+        providers_stack.setup_providers(&http_client, db);
+        providers_stack.register_imdb(db).await;
         let providers_stack = Box::leak(Box::new(providers_stack));
 
         let tasks = TaskResource::new(cancellation_token.clone());
@@ -121,6 +125,16 @@ async fn main() {
 
         #[cfg(feature = "windows-tray")]
         tokio::spawn(media_server::tray::spawn_tray_icon(app_state.clone()));
+        tokio::spawn(scheduler::run_scheduler(app_state.clone()));
+        tokio::spawn(organizer::run_download_importer(app_state.clone()));
+        tracker.spawn({
+            let app_state = app_state.clone();
+            async move {
+                if let Err(e) = app_state.backfill_collections().await {
+                    tracing::error!("Failed to backfill movie collections: {e}");
+                }
+            }
+        });
         // tokio::spawn(watch::monitor_library(app_state.clone(), media_folders));
         // tokio::spawn(watch::monitor_config(app_state.configuration, config_path));
 
@@ -137,9 +151,29 @@ async fn main() {
                 "/local_movie/{movie_id}/watch",
                 get(api::server::watch_movie),
             )
+            .route(
+                "/local_movie/{movie_id}/versions",
+                get(api::versions::movie_versions_list),
+            )
+            .route(
+                "/local_movie/{movie_id}/versions/{video_id}/prime",
+                put(api::versions::set_prime_version),
+            )
             .route("/local_movies", get(api::server::all_local_movies))
             .route("/local_season/{id}", delete(api::server::delete_season))
             .route("/local_show/{id}", delete(api::server::delete_show))
+            .route(
+                "/local_show/{id}/numbering",
+                get(api::numbering::show_numbering),
+            )
+            .route(
+                "/local_show/{id}/numbering",
+                put(api::numbering::update_show_numbering),
+            )
+            .route(
+                "/local_show/{id}/missing_episodes",
+                get(api::missing_episodes::show_missing_episodes),
+            )
             .route("/external_ids/{id}", get(api::server::external_ids))
             .route("/movie/{movie_id}", get(api::server::get_movie))
             .route("/movie/{movie_id}", put(api::server::alter_movie_metadata))
@@ -173,6 +207,10 @@ async fn main() {
                 "/show/{show_id}/{season}/detect_intros",
                 post(api::intros::detect_intros),
             )
+            .route(
+                "/show/{show_id}/{season}/reset_metadata",
+                post(api::server::reset_season_metadata),
+            )
             .route(
                 "/season/{season_id}/poster",
                 get(api::server::season_poster),
@@ -205,6 +243,10 @@ async fn main() {
                 "/show/{show_id}/{season}/{episode}/poster",
                 get(api::server::episode_poster),
             )
+            .route(
+                "/show/{show_id}/{season}/{episode}/reset_metadata",
+                post(api::server::reset_episode_metadata),
+            )
             .route("/variants", get(api::server::get_all_variants))
             .route("/video/by_content", get(api::server::contents_video))
             .route("/video/{id}", get(api::server::get_video_by_id))
@@ -231,6 +273,12 @@ async fn main() {
                 "/metadata/{id}/history",
                 put(api::history::update_metadata_history),
             )
+            .route("/metadata/{id}/locks", get(api::server::metadata_locks))
+            .route(
+                "/metadata/{id}/locks",
+                put(api::server::update_metadata_locks),
+            )
+            .route("/metadata/{id}/ratings", get(api::server::metadata_ratings))
             .route("/video/{id}/transcode", post(api::server::transcode_video))
             .route(
                 "/watch/direct/start/{id}",
@@ -262,6 +310,7 @@ async fn main() {
             )
             .route("/actor/{id}/poster", get(api::server::actor_poster))
             .route("/actor/list", get(api::server::actor_list))
+            .route("/actor/{id}", get(api::server::get_actor))
             .route("/subtitles/{id}", delete(api::subtitles::delete_subtitles))
             .route("/subtitles/{id}", get(api::subtitles::get_subtitles))
             .route("/torrent/search", get(api::server::search_torrent))
@@ -351,6 +400,8 @@ async fn main() {
             .route("/tasks/progress", get(api::server::progress))
             .route("/ws", any(ws::ws))
             .route("/scan", post(api::server::reconciliate_lib))
+            .route("/export_nfo", post(api::server::export_nfo))
+            .route("/import", post(api::server::import_downloads))
             .route(
                 "/fix_metadata/{metadata_id}",
                 post(api::server::fix_metadata),
@@ -388,7 +439,16 @@ async fn main() {
                 "/api",
                 server_api
                     .nest("/lists", api::lists::router())
-                    .nest("/resources", api::resources::router()),
+                    .nest("/libraries", api::libraries::router())
+                    .nest("/collections", api::collections::router())
+                    .nest("/calendar", api::calendar::router())
+                    .nest("/jobs", api::scheduler::router())
+                    .nest("/media_health", api::health::router())
+                    .nest("/resources", api::resources::router())
+                    .nest("/statistics", api::statistics::router())
+                    .nest("/parser", api::dry_run::router())
+                    .nest("/provider_cache", api::provider_cache::router())
+                    .nest("/imdb", api::imdb::router()),
             )
             .nest("/debug", debug_api)
             .merge(
@@ -0,0 +1,645 @@
+use std::time::Duration;
+
+use reqwest::{
+    Client, Method, Request, Url,
+    header::{ACCEPT, CONTENT_TYPE, HeaderMap, HeaderValue},
+};
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::json;
+
+use super::{
+    EpisodeMetadata, ExternalIdMetadata, FetchParams, Genre, Language, LocaleMetadata,
+    MetadataProvider, MovieMetadata, MovieMetadataProvider, PersonMetadata, ProviderIdentifier,
+    RoleMetadata, SeasonMetadata, ShowMetadata, ShowMetadataProvider,
+    request_client::LimitedRequestClient,
+    response_cache::{CacheKind, ResponseCache},
+};
+
+/// Fields of the media that are requested in every query
+const MEDIA_FRAGMENT: &str = "
+fragment media on Media {
+  id
+  idMal
+  status
+  title { romaji english native }
+  description(asHtml: false)
+  startDate { year month day }
+  episodes
+  duration
+  genres
+  coverImage { extraLarge }
+  bannerImage
+  countryOfOrigin
+  nextAiringEpisode { airingAt episode }
+}";
+
+/// Details query, includes episodes and the japanese voice cast
+const MEDIA_QUERY: &str = "
+query ($id: Int) {
+  Media(id: $id, type: ANIME) {
+    ...media
+    airingSchedule(perPage: 50) { nodes { airingAt episode } }
+    streamingEpisodes { title thumbnail }
+    characters(sort: [ROLE, RELEVANCE], perPage: 25) {
+      edges {
+        node { name { full } image { large } }
+        voiceActors(language: JAPANESE) { id name { full } image { large } }
+      }
+    }
+  }
+}";
+
+/// AniList treats every season (cour) of the anime as a separate media.
+///
+/// Shows of this provider always have the single season with all episodes of the media.
+#[derive(Debug)]
+pub struct AnilistApi {
+    base_url: Url,
+    client: LimitedRequestClient,
+}
+
+impl AnilistApi {
+    const API_URL: &'static str = "https://graphql.anilist.co";
+    /// AniList allows 90 requests per minute, but lowers the limit when under load
+    const RATE_LIMIT: usize = 30;
+
+    /// Responses are stored in the persistent `cache`
+    pub fn new(http_client: Client, cache: ResponseCache) -> Self {
+        let json = HeaderValue::from_static("application/json");
+        let headers = HeaderMap::from_iter([(CONTENT_TYPE, json.clone()), (ACCEPT, json)]);
+        let client = LimitedRequestClient::new(
+            http_client,
+            headers,
+            Self::RATE_LIMIT,
+            Duration::from_secs(60),
+        )
+        .with_cache(cache, MetadataProvider::Anilist);
+        Self {
+            base_url: Url::parse(Self::API_URL).expect("url to parse"),
+            client,
+        }
+    }
+
+    fn graphql_request(&self, query: &str, variables: serde_json::Value) -> Request {
+        let body = json!({ "query": format!("{query}\n{MEDIA_FRAGMENT}"), "variables": variables });
+        let mut req = Request::new(Method::POST, self.base_url.clone());
+        *req.body_mut() = Some(body.to_string().into());
+        req
+    }
+
+    async fn query<T: DeserializeOwned + Send + 'static>(
+        &self,
+        req: Request,
+        kind: impl Fn(&T) -> CacheKind + Send + Sync + 'static,
+    ) -> crate::Result<T> {
+        let res: AnilistResponse<T> = self
+            .client
+            .cached_request_by(req, move |res: &AnilistResponse<T>| kind(&res.data))
+            .await?;
+        Ok(res.data)
+    }
+
+    async fn media(&self, id: usize, kind: CacheKind) -> crate::Result<AnilistMedia> {
+        let req = self.graphql_request(MEDIA_QUERY, json!({ "id": id }));
+        let data: AnilistMediaData = self
+            .query(req, move |data: &AnilistMediaData| {
+                match data.media.status.as_deref() {
+                    Some("FINISHED") if kind == CacheKind::Show => CacheKind::EndedShow,
+                    _ => kind,
+                }
+            })
+            .await?;
+        Ok(data.media)
+    }
+
+    /// Search anime, `formats` is the GraphQL filter like `format_in: [MOVIE]`
+    async fn search(&self, query: &str, formats: &str) -> crate::Result<Vec<AnilistMedia>> {
+        let search_query = format!(
+            "
+query ($search: String) {{
+  Page(perPage: 20) {{
+    media(search: $search, type: ANIME, isAdult: false, sort: SEARCH_MATCH, {formats}) {{
+      ...media
+    }}
+  }}
+}}"
+        );
+        let req = self.graphql_request(&search_query, json!({ "search": query }));
+        let data: AnilistPageData = self.query(req, |_: &_| CacheKind::Search).await?;
+        Ok(data.page.media)
+    }
+}
+
+impl ProviderIdentifier for AnilistApi {
+    fn provider_identifier(&self) -> MetadataProvider {
+        MetadataProvider::Anilist
+    }
+}
+
+#[async_trait::async_trait]
+impl MovieMetadataProvider for AnilistApi {
+    async fn movie(&self, metadata_id: &str, params: FetchParams) -> crate::Result<MovieMetadata> {
+        let media = self.media(metadata_id.parse()?, CacheKind::Movie).await?;
+        Ok(media.into_movie(params.lang))
+    }
+
+    async fn movie_search(
+        &self,
+        query: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<Vec<MovieMetadata>> {
+        let movies = self.search(query, "format_in: [MOVIE]").await?;
+        Ok(movies
+            .into_iter()
+            .map(|m| m.into_movie(fetch_params.lang))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl ShowMetadataProvider for AnilistApi {
+    async fn show(
+        &self,
+        metadata_show_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<ShowMetadata> {
+        let media = self
+            .media(metadata_show_id.parse()?, CacheKind::Show)
+            .await?;
+        Ok(media.into_show(fetch_params.lang))
+    }
+
+    /// Only the first season exists, later seasons are separate AniList media that are not
+    /// linked to the show. Libraries that use AniList should keep every season in its own show
+    /// or rely on absolute episode numbers.
+    async fn season(
+        &self,
+        metadata_show_id: &str,
+        season: usize,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<SeasonMetadata> {
+        if season != 1 {
+            return Err(crate::AppError::not_found(
+                "AniList shows have only the first season, sequels are separate shows",
+            ));
+        }
+        let media = self
+            .media(metadata_show_id.parse()?, CacheKind::Show)
+            .await?;
+        Ok(media.into_season())
+    }
+
+    async fn episode(
+        &self,
+        metadata_show_id: &str,
+        season: usize,
+        episode: usize,
+        fetch_params: FetchParams,
+    ) -> crate::Result<EpisodeMetadata> {
+        self.season(metadata_show_id, season, fetch_params)
+            .await?
+            .episodes
+            .into_iter()
+            .find(|e| e.number == episode)
+            .ok_or(crate::AppError::not_found("Episode is not found"))
+    }
+
+    async fn show_search(
+        &self,
+        query: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<Vec<ShowMetadata>> {
+        let shows = self.search(query, "format_not_in: [MOVIE, MUSIC]").await?;
+        Ok(shows
+            .into_iter()
+            .map(|s| s.into_show(fetch_params.lang))
+            .collect())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistResponse<T> {
+    data: T,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistMediaData {
+    #[serde(rename = "Media")]
+    media: AnilistMedia,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistPageData {
+    #[serde(rename = "Page")]
+    page: AnilistPage,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistPage {
+    media: Vec<AnilistMedia>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistMedia {
+    id: usize,
+    id_mal: Option<usize>,
+    status: Option<String>,
+    title: AnilistTitle,
+    description: Option<String>,
+    start_date: Option<AnilistDate>,
+    episodes: Option<usize>,
+    /// Episode duration in minutes
+    duration: Option<u64>,
+    #[serde(default)]
+    genres: Vec<String>,
+    cover_image: Option<AnilistCoverImage>,
+    banner_image: Option<String>,
+    /// ISO 3166-1 alpha-2 country code
+    country_of_origin: Option<String>,
+    next_airing_episode: Option<AnilistAiringEpisode>,
+    airing_schedule: Option<AnilistNodes<AnilistAiringEpisode>>,
+    streaming_episodes: Option<Vec<AnilistStreamingEpisode>>,
+    characters: Option<AnilistCharacters>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistTitle {
+    romaji: Option<String>,
+    english: Option<String>,
+    native: Option<String>,
+}
+
+impl AnilistTitle {
+    /// English title is rarely available for lesser known titles, romaji is used when it is missing.
+    /// Romaji is preferred for other languages since AniList doesn't have their translations
+    fn localized(&self, lang: Language) -> String {
+        let (preferred, fallback) = match lang {
+            Language::En => (&self.english, &self.romaji),
+            Language::Ja => (&self.native, &self.romaji),
+            _ => (&self.romaji, &self.english),
+        };
+        preferred
+            .as_ref()
+            .or(fallback.as_ref())
+            .or(self.native.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistDate {
+    year: Option<i32>,
+    month: Option<u8>,
+    day: Option<u8>,
+}
+
+impl AnilistDate {
+    /// Full date when it is known, otherwise only the year
+    fn release_date(&self) -> Option<String> {
+        match (self.year?, self.month, self.day) {
+            (year, Some(month), Some(day)) => Some(format!("{year:04}-{month:02}-{day:02}")),
+            (year, _, _) => Some(year.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistCoverImage {
+    extra_large: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistAiringEpisode {
+    /// Unix timestamp
+    airing_at: i64,
+    episode: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistNodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistStreamingEpisode {
+    /// Usually formatted like `Episode 1 - Title`
+    title: Option<String>,
+    thumbnail: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistCharacters {
+    edges: Vec<AnilistCharacterEdge>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AnilistCharacterEdge {
+    node: AnilistCharacter,
+    #[serde(default)]
+    voice_actors: Vec<AnilistStaff>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistCharacter {
+    name: AnilistName,
+    image: Option<AnilistImage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistStaff {
+    id: usize,
+    name: AnilistName,
+    image: Option<AnilistImage>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistName {
+    full: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct AnilistImage {
+    large: Option<String>,
+}
+
+/// Remove html tags that are left in descriptions even without `asHtml`
+fn strip_html(description: &str) -> String {
+    let mut out = String::with_capacity(description.len());
+    let mut in_tag = false;
+    for c in description.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.trim().to_string()
+}
+
+fn genre(name: &str) -> Option<Genre> {
+    match name {
+        "Action" => Some(Genre::Action),
+        "Adventure" => Some(Genre::Adventure),
+        "Comedy" => Some(Genre::Comedy),
+        "Drama" => Some(Genre::Drama),
+        "Fantasy" => Some(Genre::Fantasy),
+        "Horror" => Some(Genre::Horror),
+        "Music" => Some(Genre::Music),
+        "Mystery" => Some(Genre::Mystery),
+        "Romance" => Some(Genre::Romance),
+        "Sci-Fi" => Some(Genre::ScienceFiction),
+        "Thriller" => Some(Genre::Thriller),
+        _ => None,
+    }
+}
+
+fn unix_date(timestamp: i64) -> Option<time::OffsetDateTime> {
+    time::OffsetDateTime::from_unix_timestamp(timestamp).ok()
+}
+
+/// Parse `Episode 12 - Title` into the episode number and title
+fn parse_streaming_title(title: &str) -> Option<(usize, &str)> {
+    let rest = title.strip_prefix("Episode ")?;
+    let (number, title) = rest.split_once(" - ").unwrap_or((rest, ""));
+    Some((number.trim().parse().ok()?, title.trim()))
+}
+
+impl AnilistMedia {
+    fn poster(&self) -> Option<String> {
+        self.cover_image.as_ref()?.extra_large.clone()
+    }
+
+    fn plot(&self) -> Option<String> {
+        self.description.as_deref().map(strip_html)
+    }
+
+    fn genres(&self) -> Vec<Genre> {
+        std::iter::once(Genre::Animation)
+            .chain(self.genres.iter().filter_map(|g| genre(g)))
+            .collect()
+    }
+
+    fn locale_metadata(&self) -> Option<LocaleMetadata> {
+        let original_language = match self.country_of_origin.as_deref()? {
+            "JP" => "ja",
+            "KR" => "ko",
+            "CN" | "TW" => "zh",
+            _ => return None,
+        };
+        Some(LocaleMetadata {
+            original_title: self.title.native.clone()?,
+            original_language: original_language.to_string(),
+        })
+    }
+
+    fn external_ids(&self) -> Vec<ExternalIdMetadata> {
+        self.id_mal
+            .map(|id| ExternalIdMetadata {
+                provider: MetadataProvider::Mal,
+                id: id.to_string(),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    /// Voice actors of the characters, playing the character is their role
+    fn cast(&self) -> Option<Vec<PersonMetadata>> {
+        let characters = self.characters.as_ref()?;
+        let cast = characters
+            .edges
+            .iter()
+            .filter_map(|edge| {
+                let actor = edge.voice_actors.first()?;
+                Some(PersonMetadata {
+                    metadata_id: actor.id.to_string(),
+                    metadata_provider: MetadataProvider::Anilist,
+                    person_poster: actor.image.as_ref().and_then(|i| i.large.clone()),
+                    name: actor.name.full.clone()?,
+                    imdb_id: None,
+                    role: edge.node.name.full.clone().map(|character| RoleMetadata {
+                        character,
+                        poster: edge.node.image.as_ref().and_then(|i| i.large.clone()),
+                    }),
+                    job: None,
+                })
+            })
+            .collect();
+        Some(cast)
+    }
+
+    /// Released episodes amount. Airing shows usually don't have the total amount
+    fn episodes_amount(&self) -> Option<usize> {
+        self.episodes.or_else(|| {
+            self.next_airing_episode
+                .as_ref()
+                .map(|next| next.episode.saturating_sub(1))
+        })
+    }
+
+    fn into_movie(self, lang: Language) -> MovieMetadata {
+        MovieMetadata {
+            metadata_id: self.id.to_string(),
+            metadata_provider: MetadataProvider::Anilist,
+            poster: self.poster(),
+            backdrop: self.banner_image.clone(),
+            plot: self.plot(),
+            release_date: self.start_date.as_ref().and_then(|d| d.release_date()),
+            runtime: self
+                .duration
+                .map(|minutes| Duration::from_secs(minutes * 60).into()),
+            title: self.title.localized(lang),
+            locale_metadata: self.locale_metadata(),
+            cast: self.cast(),
+            genres: Some(self.genres()),
+            external_ids: Some(self.external_ids()),
+            collection: None,
+            ratings: None,
+        }
+    }
+
+    fn into_show(self, lang: Language) -> ShowMetadata {
+        ShowMetadata {
+            metadata_id: self.id.to_string(),
+            metadata_provider: MetadataProvider::Anilist,
+            poster: self.poster(),
+            backdrop: self.banner_image.clone(),
+            plot: self.plot(),
+            seasons: Some(vec![1]),
+            episodes_amount: self.episodes_amount(),
+            release_date: self.start_date.as_ref().and_then(|d| d.release_date()),
+            title: self.title.localized(lang),
+            locale_metadata: self.locale_metadata(),
+            cast: self.cast(),
+            genres: Some(self.genres()),
+            next_episode_air_date: self
+                .next_airing_episode
+                .as_ref()
+                .and_then(|next| unix_date(next.airing_at))
+                .map(Into::into),
+            external_ids: Some(self.external_ids()),
+            ratings: None,
+        }
+    }
+
+    fn into_season(self) -> SeasonMetadata {
+        let schedule = self
+            .airing_schedule
+            .as_ref()
+            .map(|s| s.nodes.as_slice())
+            .unwrap_or_default();
+        let streaming: Vec<_> = self
+            .streaming_episodes
+            .iter()
+            .flatten()
+            .filter_map(|e| {
+                let (number, title) = parse_streaming_title(e.title.as_deref()?)?;
+                Some((number, title, e.thumbnail.clone()))
+            })
+            .collect();
+        let amount = self
+            .episodes_amount()
+            .or_else(|| schedule.iter().map(|e| e.episode).max())
+            .unwrap_or_default();
+        let episodes = (1..=amount)
+            .map(|number| {
+                let streaming = streaming.iter().find(|(n, ..)| *n == number);
+                let title = streaming
+                    .map(|(_, title, _)| title.to_string())
+                    .filter(|title| !title.is_empty())
+                    .unwrap_or_else(|| format!("Episode {number}"));
+                let release_date = schedule
+                    .iter()
+                    .find(|e| e.episode == number)
+                    .and_then(|e| unix_date(e.airing_at))
+                    .map(|date| date.date().to_string());
+                EpisodeMetadata {
+                    metadata_id: format!("{}:1:{number}", self.id),
+                    metadata_provider: MetadataProvider::Anilist,
+                    release_date,
+                    number,
+                    title,
+                    plot: None,
+                    season_number: 1,
+                    runtime: self
+                        .duration
+                        .map(|minutes| Duration::from_secs(minutes * 60).into()),
+                    poster: streaming.and_then(|(.., thumbnail)| thumbnail.clone()),
+                    cast: None,
+                }
+            })
+            .collect();
+        SeasonMetadata {
+            metadata_id: format!("{}:1", self.id),
+            metadata_provider: MetadataProvider::Anilist,
+            release_date: self.start_date.as_ref().and_then(|d| d.release_date()),
+            title: Some("Season 1".to_string()),
+            episodes,
+            plot: self.plot(),
+            poster: self.poster(),
+            number: 1,
+            cast: self.cast(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::{Language, MetadataProvider};
+
+    use super::{AnilistMediaData, AnilistResponse};
+
+    const MEDIA: &str = r#"{"data": {"Media": {
+        "id": 21, "idMal": 21, "status": "FINISHED",
+        "title": {"romaji": "Shingeki no Kyojin", "english": "Attack on Titan", "native": "進撃の巨人"},
+        "description": "Several hundred years ago, humans were nearly exterminated.<br><br>\n(Source: Kodansha)",
+        "startDate": {"year": 2013, "month": 4, "day": 7},
+        "episodes": 2, "duration": 24, "genres": ["Action", "Drama", "Mecha"],
+        "coverImage": {"extraLarge": "https://example.com/cover.jpg"}, "bannerImage": null,
+        "countryOfOrigin": "JP", "nextAiringEpisode": null,
+        "airingSchedule": {"nodes": [{"airingAt": 1365343200, "episode": 1}]},
+        "streamingEpisodes": [{"title": "Episode 2 - That Day", "thumbnail": "https://example.com/2.jpg"}],
+        "characters": {"edges": [{
+            "node": {"name": {"full": "Eren Yeager"}, "image": {"large": null}},
+            "voiceActors": [{"id": 95672, "name": {"full": "Yuki Kaji"}, "image": null}]
+        }]}
+    }}}"#;
+
+    #[test]
+    fn anilist_media() {
+        let res: AnilistResponse<AnilistMediaData> = serde_json::from_str(MEDIA).unwrap();
+        let media = res.data.media;
+        assert_eq!(media.title.localized(Language::En), "Attack on Titan");
+        assert_eq!(media.title.localized(Language::Ja), "進撃の巨人");
+        assert_eq!(media.title.localized(Language::Ru), "Shingeki no Kyojin");
+
+        let show = media.clone().into_show(Language::En);
+        assert_eq!(show.release_date.as_deref(), Some("2013-04-07"));
+        assert_eq!(
+            show.plot.as_deref(),
+            Some("Several hundred years ago, humans were nearly exterminated.\n(Source: Kodansha)")
+        );
+        assert_eq!(show.locale_metadata.unwrap().original_language, "ja");
+        let external_ids = show.external_ids.unwrap();
+        assert_eq!(external_ids.len(), 1);
+        assert_eq!(external_ids[0].provider, MetadataProvider::Mal);
+        let cast = show.cast.unwrap();
+        assert_eq!(cast[0].name, "Yuki Kaji");
+        assert_eq!(cast[0].role.as_ref().unwrap().character, "Eren Yeager");
+
+        let season = media.into_season();
+        assert_eq!(season.episodes.len(), 2);
+        assert_eq!(season.episodes[0].title, "Episode 1");
+        assert_eq!(
+            season.episodes[0].release_date.as_deref(),
+            Some("2013-04-07")
+        );
+        assert_eq!(season.episodes[1].title, "That Day");
+        assert_eq!(season.episodes[1].release_date, None);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use flate2::read::MultiGzDecoder;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    db::{Db, DbQueryBuilder, DbTransaction},
+    progress::{ProgressDispatcher, TaskError, TaskProgress, TaskTrait},
+};
+
+/// Rows inserted with a single statement, keeps the amount of bound values below the SQLite limit
+const BATCH_SIZE: usize = 1000;
+/// Progress is reported once per this amount of read rows
+const PROGRESS_ROWS: u64 = 100_000;
+/// Staging rows are committed once per this amount of read rows to let other writers through
+const COMMIT_ROWS: u64 = 50_000;
+/// Titles added to the search index with a single transaction
+const SEARCH_INDEX_CHUNK: i64 = 100_000;
+
+type Row = Vec<Option<String>>;
+
+/// IMDb non-commercial dataset dump
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum ImdbDataset {
+    TitleBasics,
+    TitleEpisode,
+    TitleRatings,
+    TitlePrincipals,
+    NameBasics,
+}
+
+impl ImdbDataset {
+    /// Datasets in import order. Later datasets keep only rows that reference earlier ones
+    pub const ALL: [Self; 5] = [
+        Self::TitleBasics,
+        Self::TitleEpisode,
+        Self::TitleRatings,
+        Self::TitlePrincipals,
+        Self::NameBasics,
+    ];
+
+    /// Name of the uncompressed dump file
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Self::TitleBasics => "title.basics.tsv",
+            Self::TitleEpisode => "title.episode.tsv",
+            Self::TitleRatings => "title.ratings.tsv",
+            Self::TitlePrincipals => "title.principals.tsv",
+            Self::NameBasics => "name.basics.tsv",
+        }
+    }
+
+    /// Dump columns, table columns follow the same order
+    fn header(self) -> &'static [&'static str] {
+        match self {
+            Self::TitleBasics => &[
+                "tconst",
+                "titleType",
+                "primaryTitle",
+                "originalTitle",
+                "isAdult",
+                "startYear",
+                "endYear",
+                "runtimeMinutes",
+                "genres",
+            ],
+            Self::TitleEpisode => &["tconst", "parentTconst", "seasonNumber", "episodeNumber"],
+            Self::TitleRatings => &["tconst", "averageRating", "numVotes"],
+            Self::TitlePrincipals => &[
+                "tconst",
+                "ordering",
+                "nconst",
+                "category",
+                "job",
+                "characters",
+            ],
+            Self::NameBasics => &[
+                "nconst",
+                "primaryName",
+                "birthYear",
+                "deathYear",
+                "primaryProfession",
+                "knownForTitles",
+            ],
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            Self::TitleBasics => "imdb_titles",
+            Self::TitleEpisode => "imdb_episodes",
+            Self::TitleRatings => "imdb_ratings",
+            Self::TitlePrincipals => "imdb_principals",
+            Self::NameBasics => "imdb_names",
+        }
+    }
+
+    fn columns(self) -> &'static str {
+        match self {
+            Self::TitleBasics => {
+                "tconst, title_type, primary_title, original_title, is_adult, start_year, end_year, runtime_minutes, genres"
+            }
+            Self::TitleEpisode => "tconst, parent_tconst, season_number, episode_number",
+            Self::TitleRatings => "tconst, average_rating, num_votes",
+            Self::TitlePrincipals => "tconst, ordering, nconst, category, job, characters",
+            Self::NameBasics => {
+                "nconst, primary_name, birth_year, death_year, primary_profession, known_for_titles"
+            }
+        }
+    }
+
+    /// Condition on the `VALUES` columns (`column1`, `column2`, ...) that imported rows satisfy
+    fn filter(self) -> &'static str {
+        match self {
+            Self::TitleBasics => {
+                "column2 IN ('movie', 'tvMovie', 'tvSeries', 'tvMiniSeries', 'tvEpisode') AND column5 = '0'"
+            }
+            Self::TitleEpisode => "EXISTS (SELECT 1 FROM imdb_titles WHERE tconst = column2)",
+            Self::TitleRatings => "EXISTS (SELECT 1 FROM imdb_titles WHERE tconst = column1)",
+            Self::TitlePrincipals => {
+                "EXISTS (SELECT 1 FROM imdb_titles WHERE tconst = column1 AND title_type != 'tvEpisode')"
+            }
+            Self::NameBasics => "EXISTS (SELECT 1 FROM imdb_principals WHERE nconst = column1)",
+        }
+    }
+}
+
+/// Path of the dataset dump in the directory, gzipped dumps are preferred
+pub fn dataset_path(directory: &Path, dataset: ImdbDataset) -> Option<PathBuf> {
+    let plain = directory.join(dataset.file_name());
+    let gzipped = directory.join(format!("{}.gz", dataset.file_name()));
+    [gzipped, plain].into_iter().find(|path| path.is_file())
+}
+
+fn open_dump(path: &Path) -> anyhow::Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path).with_context(|| format!("open {}", path.display()))?;
+    Ok(match path.extension().is_some_and(|e| e == "gz") {
+        true => Box::new(BufReader::new(MultiGzDecoder::new(file))),
+        false => Box::new(BufReader::new(file)),
+    })
+}
+
+/// Split the TSV line, `\N` is the null value of the dumps
+fn parse_row(line: &str, columns: usize) -> Option<Row> {
+    let row: Row = line
+        .split('\t')
+        .map(|value| (value != "\\N").then(|| value.to_owned()))
+        .collect();
+    (row.len() == columns).then_some(row)
+}
+
+/// Read the dump in batches. Runs on the blocking thread
+fn read_rows(
+    reader: impl BufRead,
+    dataset: ImdbDataset,
+    tx: mpsc::Sender<anyhow::Result<Vec<Row>>>,
+) {
+    let header = dataset.header();
+    let mut lines = reader.lines();
+    match lines.next() {
+        Some(Ok(line)) if line.trim_end().split('\t').eq(header.iter().copied()) => {}
+        Some(Err(e)) => {
+            let _ = tx.blocking_send(Err(e.into()));
+            return;
+        }
+        _ => {
+            let _ = tx.blocking_send(Err(anyhow::anyhow!(
+                "{} has unexpected header",
+                dataset.file_name()
+            )));
+            return;
+        }
+    }
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for line in lines {
+        let line = match line {
+            Ok(line) => line,
+            Err(e) => {
+                let _ = tx.blocking_send(Err(e.into()));
+                return;
+            }
+        };
+        match parse_row(line.trim_end_matches('\r'), header.len()) {
+            Some(row) => batch.push(row),
+            None => tracing::warn!("Skipping malformed {} row", dataset.file_name()),
+        }
+        if batch.len() == BATCH_SIZE
+            && tx
+                .blocking_send(Ok(std::mem::replace(
+                    &mut batch,
+                    Vec::with_capacity(BATCH_SIZE),
+                )))
+                .is_err()
+        {
+            return;
+        }
+    }
+    if !batch.is_empty() {
+        let _ = tx.blocking_send(Ok(batch));
+    }
+}
+
+async fn insert_batch(
+    tx: &mut DbTransaction,
+    table: &str,
+    dataset: ImdbDataset,
+    batch: Vec<Row>,
+) -> sqlx::Result<()> {
+    let mut query = DbQueryBuilder::new(format!(
+        "INSERT OR REPLACE INTO {table} ({}) SELECT * FROM (",
+        dataset.columns()
+    ));
+    query.push_values(batch, |mut b, row| {
+        for value in row {
+            b.push_bind(value);
+        }
+    });
+    query.push(format!(") WHERE {}", dataset.filter()));
+    query.build().execute(&mut **tx).await?;
+    Ok(())
+}
+
+/// Search index covers movies and shows, episodes are found through their shows.
+///
+/// Index is filled in chunks, titles are missing from search results until their chunk is committed
+async fn fill_search_index(db: &Db) -> sqlx::Result<()> {
+    let mut last_rowid = 0;
+    loop {
+        let mut tx = db.begin().await?;
+        sqlx::query!(
+            "INSERT INTO imdb_titles_fts(rowid, primary_title, original_title)
+            SELECT rowid, primary_title, original_title FROM imdb_titles
+            WHERE rowid > ? AND title_type != 'tvEpisode' ORDER BY rowid LIMIT ?",
+            last_rowid,
+            SEARCH_INDEX_CHUNK
+        )
+        .execute(&mut *tx)
+        .await?;
+        let next = sqlx::query_scalar!(
+            r#"SELECT max(rowid) as "rowid: i64" FROM imdb_titles_fts WHERE rowid > ?"#,
+            last_rowid
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        match next {
+            Some(rowid) => last_rowid = rowid,
+            None => break,
+        }
+    }
+    Ok(())
+}
+
+/// Schema statement of the staging table or its index.
+///
+/// Staging index names alternate between imports because indexes keep their names when
+/// the staging table is renamed to the dataset table
+fn staging_sql(sql: &str, table: &str, staging: &str) -> String {
+    const PLACEHOLDER: &str = "\0";
+    sql.replace(staging, PLACEHOLDER)
+        .replace(table, staging)
+        .replace(PLACEHOLDER, table)
+}
+
+/// Recreate empty staging table with the schema and indexes of the dataset table
+async fn create_staging(db: &Db, table: &str, staging: &str) -> anyhow::Result<()> {
+    let schema = sqlx::query_scalar!(
+        r#"SELECT sql as "sql!" FROM sqlite_master WHERE tbl_name = ? AND sql IS NOT NULL
+        ORDER BY type DESC"#,
+        table
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    anyhow::ensure!(!schema.is_empty(), "{table} table does not exist");
+    let mut tx = db.begin().await?;
+    DbQueryBuilder::new(format!("DROP TABLE IF EXISTS {staging}"))
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    for sql in schema {
+        DbQueryBuilder::new(staging_sql(&sql, table, staging))
+            .build()
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Load the dump into the staging table, committing every [COMMIT_ROWS] rows
+async fn load_staging(
+    db: &Db,
+    staging: &str,
+    dataset: ImdbDataset,
+    mut batch_rx: mpsc::Receiver<anyhow::Result<Vec<Row>>>,
+    mut on_progress: impl FnMut(u64),
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let mut tx = db.begin().await?;
+    let mut read = 0;
+    while let Some(batch) = batch_rx.recv().await {
+        if cancel.is_cancelled() {
+            return Err(TaskError::Canceled.into());
+        }
+        let batch = batch?;
+        let previous = read;
+        read += batch.len() as u64;
+        insert_batch(&mut tx, staging, dataset, batch).await?;
+        if previous / COMMIT_ROWS != read / COMMIT_ROWS {
+            tx.commit().await?;
+            tx = db.begin().await?;
+        }
+        if previous / PROGRESS_ROWS != read / PROGRESS_ROWS {
+            on_progress(read);
+        }
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Replace the dataset table with the rows of the dump.
+///
+/// Rows are loaded into a staging table with periodic commits, so the database is never locked
+/// for the whole import. Staging table replaces the dataset table once the dump is read.
+/// `on_progress` is called with the amount of rows read so far every [PROGRESS_ROWS] rows.
+/// Returns the amount of rows in the table after the import
+pub async fn import_dataset(
+    db: &Db,
+    dataset: ImdbDataset,
+    reader: impl BufRead + Send + 'static,
+    on_progress: impl FnMut(u64),
+    cancel: &CancellationToken,
+) -> anyhow::Result<i64> {
+    let table = dataset.table();
+    let staging = format!("{table}_staging");
+    create_staging(db, table, &staging).await?;
+
+    let (batch_tx, batch_rx) = mpsc::channel(4);
+    let reader = tokio::task::spawn_blocking(move || read_rows(reader, dataset, batch_tx));
+    let loaded = load_staging(db, &staging, dataset, batch_rx, on_progress, cancel).await;
+    if let Err(e) = loaded {
+        // Stops the reader that waits for the next batch
+        reader.abort();
+        let mut drop_staging = DbQueryBuilder::new(format!("DROP TABLE IF EXISTS {staging}"));
+        if let Err(e) = drop_staging.build().execute(&db.pool).await {
+            tracing::warn!("Failed to drop {staging} table: {e}");
+        }
+        return Err(e);
+    }
+    reader.await?;
+
+    let rows: i64 = DbQueryBuilder::new(format!("SELECT COUNT(*) FROM {staging}"))
+        .build_query_scalar()
+        .fetch_one(&db.pool)
+        .await?;
+    let mut tx = db.begin().await?;
+    DbQueryBuilder::new(format!("DROP TABLE {table}"))
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    DbQueryBuilder::new(format!("ALTER TABLE {staging} RENAME TO {table}"))
+        .build()
+        .execute(&mut *tx)
+        .await?;
+    if dataset == ImdbDataset::TitleBasics {
+        // Index rowids point to the rows of the dropped table
+        sqlx::query!("INSERT INTO imdb_titles_fts(imdb_titles_fts) VALUES('delete-all')")
+            .execute(&mut *tx)
+            .await?;
+    }
+    sqlx::query!(
+        "INSERT OR REPLACE INTO imdb_imports (dataset, rows) VALUES (?, ?)",
+        dataset,
+        rows
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    if dataset == ImdbDataset::TitleBasics {
+        fill_search_index(db).await?;
+    }
+    Ok(rows)
+}
+
+/// Import of the IMDb dataset dumps located in the directory
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ImdbImportTask {
+    #[schema(value_type = String)]
+    pub directory: PathBuf,
+}
+
+impl PartialEq for ImdbImportTask {
+    fn eq(&self, _other: &Self) -> bool {
+        // Only one import can run at a time
+        true
+    }
+}
+
+impl Eq for ImdbImportTask {}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct ImdbImportProgress {
+    /// Dataset that is being imported
+    pub dataset: ImdbDataset,
+    /// Rows of the dataset read so far
+    pub rows: u64,
+}
+
+impl TaskTrait for ImdbImportTask {
+    type Progress = ImdbImportProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> TaskProgress
+    where
+        Self: Sized,
+    {
+        TaskProgress::ImdbImport(status)
+    }
+}
+
+/// Import every dataset dump found in the directory.
+///
+/// Missing dumps are skipped, tables of their datasets are left untouched
+pub async fn import_directory(
+    db: &Db,
+    directory: &Path,
+    progress: ProgressDispatcher<ImdbImportTask>,
+    cancel: CancellationToken,
+) {
+    for dataset in ImdbDataset::ALL {
+        let Some(path) = dataset_path(directory, dataset) else {
+            tracing::info!("Skipping missing {} dump", dataset.file_name());
+            continue;
+        };
+        let reader = match open_dump(&path) {
+            Ok(reader) => reader,
+            Err(e) => {
+                tracing::error!("Failed to open IMDb dump: {e}");
+                progress.error(TaskError::Failure);
+                return;
+            }
+        };
+        progress.progress(ImdbImportProgress { dataset, rows: 0 });
+        let on_progress = |rows| progress.progress(ImdbImportProgress { dataset, rows });
+        match import_dataset(db, dataset, reader, on_progress, &cancel).await {
+            Ok(rows) => tracing::info!("Imported {rows} rows of {}", dataset.file_name()),
+            Err(e) => {
+                tracing::error!("Failed to import {}: {e}", dataset.file_name());
+                let err = match cancel.is_cancelled() {
+                    true => TaskError::Canceled,
+                    false => TaskError::Failure,
+                };
+                progress.error(err);
+                return;
+            }
+        }
+    }
+    progress.finish();
+}
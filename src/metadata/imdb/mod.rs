@@ -0,0 +1,697 @@
+//! Offline metadata provider backed by the imported IMDb datasets.
+//!
+//! Datasets have no plots and artwork, so the provider is best used for matching and ratings
+//! alongside providers that have them.
+
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::{AppError, db::Db};
+
+use super::{
+    CreditMetadata, CrewJob, DiscoverMetadataProvider, EpisodeMetadata, ExternalIdMetadata,
+    FetchParams, Genre, MetadataProvider, MetadataSearchResult, MovieMetadata,
+    MovieMetadataProvider, ParentMediaType, PersonDetailsMetadata, PersonMetadata,
+    PersonMetadataProvider, ProviderIdentifier, RoleMetadata, SeasonMetadata, ShowMetadata,
+    ShowMetadataProvider,
+};
+
+/// Importer of the IMDb non-commercial TSV datasets
+pub mod import;
+
+/// Search results of a single query
+const SEARCH_LIMIT: i64 = 20;
+
+const MOVIE_TYPES: [&str; 2] = ["movie", "tvMovie"];
+const SHOW_TYPES: [&str; 2] = ["tvSeries", "tvMiniSeries"];
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ImdbRating {
+    /// Weighted average of the user ratings on the 1-10 scale
+    pub average_rating: f64,
+    pub num_votes: i64,
+}
+
+/// Rating of the title from the imported ratings dataset
+pub async fn rating(db: &Db, tconst: &str) -> sqlx::Result<Option<ImdbRating>> {
+    sqlx::query_as!(
+        ImdbRating,
+        "SELECT average_rating, num_votes FROM imdb_ratings WHERE tconst = ?",
+        tconst
+    )
+    .fetch_optional(&db.pool)
+    .await
+}
+
+/// Titles dataset is imported, other datasets are optional
+pub async fn is_imported(db: &Db) -> sqlx::Result<bool> {
+    let imported = sqlx::query_scalar!(
+        r#"SELECT EXISTS (SELECT 1 FROM imdb_imports WHERE dataset = 'title_basics') as "imported!: bool""#
+    )
+    .fetch_one(&db.pool)
+    .await?;
+    Ok(imported)
+}
+
+#[derive(Debug)]
+struct ImdbTitle {
+    tconst: String,
+    title_type: String,
+    primary_title: String,
+    start_year: Option<i64>,
+    runtime_minutes: Option<i64>,
+    genres: Option<String>,
+}
+
+impl ImdbTitle {
+    fn release_date(&self) -> Option<String> {
+        self.start_year.map(|year| year.to_string())
+    }
+
+    fn runtime(&self) -> Option<crate::MediaDuration> {
+        self.runtime_minutes
+            .map(|minutes| Duration::from_secs(minutes as u64 * 60).into())
+    }
+
+    fn genres(&self) -> Vec<Genre> {
+        self.genres
+            .iter()
+            .flat_map(|genres| genres.split(','))
+            .filter_map(genre)
+            .collect()
+    }
+
+    fn external_ids(&self) -> Vec<ExternalIdMetadata> {
+        vec![ExternalIdMetadata {
+            provider: MetadataProvider::Imdb,
+            id: self.tconst.clone(),
+        }]
+    }
+
+    fn into_movie(self, cast: Option<Vec<PersonMetadata>>) -> MovieMetadata {
+        MovieMetadata {
+            release_date: self.release_date(),
+            runtime: self.runtime(),
+            genres: Some(self.genres()),
+            external_ids: Some(self.external_ids()),
+            metadata_id: self.tconst,
+            metadata_provider: MetadataProvider::Imdb,
+            title: self.primary_title,
+            cast,
+            ..Default::default()
+        }
+    }
+
+    fn into_show(
+        self,
+        seasons: Option<Vec<usize>>,
+        episodes_amount: Option<usize>,
+        cast: Option<Vec<PersonMetadata>>,
+    ) -> ShowMetadata {
+        ShowMetadata {
+            release_date: self.release_date(),
+            genres: Some(self.genres()),
+            external_ids: Some(self.external_ids()),
+            metadata_id: self.tconst,
+            metadata_provider: MetadataProvider::Imdb,
+            title: self.primary_title,
+            seasons,
+            episodes_amount,
+            cast,
+            ..Default::default()
+        }
+    }
+}
+
+/// Crew job of the principal category, `None` for the cast
+fn crew_job(category: &str) -> Option<CrewJob> {
+    match category {
+        "director" => Some(CrewJob::Director),
+        "writer" => Some(CrewJob::Writer),
+        "composer" => Some(CrewJob::Composer),
+        _ => None,
+    }
+}
+
+/// Department of the first primary profession
+fn known_for_department(professions: &str) -> Option<String> {
+    let department = match professions.split(',').next()? {
+        "actor" | "actress" | "self" => "Acting",
+        "director" => "Directing",
+        "writer" => "Writing",
+        "composer" | "soundtrack" | "music_department" => "Sound",
+        "producer" => "Production",
+        _ => return None,
+    };
+    Some(department.to_owned())
+}
+
+fn genre(name: &str) -> Option<Genre> {
+    let genre = match name {
+        "Action" => Genre::Action,
+        "Adventure" => Genre::Adventure,
+        "Animation" => Genre::Animation,
+        "Comedy" => Genre::Comedy,
+        "Crime" => Genre::Crime,
+        "Documentary" => Genre::Documentary,
+        "Drama" => Genre::Drama,
+        "Family" => Genre::Family,
+        "Fantasy" => Genre::Fantasy,
+        "History" => Genre::History,
+        "Horror" => Genre::Horror,
+        "Music" | "Musical" => Genre::Music,
+        "Mystery" => Genre::Mystery,
+        "News" => Genre::News,
+        "Reality-TV" => Genre::Reality,
+        "Romance" => Genre::Romance,
+        "Sci-Fi" => Genre::ScienceFiction,
+        "Talk-Show" => Genre::Talk,
+        "Thriller" => Genre::Thriller,
+        "War" => Genre::War,
+        "Western" => Genre::Western,
+        _ => return None,
+    };
+    Some(genre)
+}
+
+/// Metadata provider that queries the local copy of the IMDb datasets
+#[derive(Debug, Clone)]
+pub struct ImdbProvider {
+    db: Db,
+}
+
+impl ImdbProvider {
+    pub fn new(db: Db) -> Self {
+        Self { db }
+    }
+
+    async fn title(&self, tconst: &str, types: [&str; 2]) -> crate::Result<ImdbTitle> {
+        let title = sqlx::query_as!(
+            ImdbTitle,
+            "SELECT tconst, title_type, primary_title, start_year, runtime_minutes, genres
+            FROM imdb_titles WHERE tconst = ? AND title_type IN (?, ?)",
+            tconst,
+            types[0],
+            types[1]
+        )
+        .fetch_optional(&self.db.pool)
+        .await?;
+        title.ok_or(AppError::not_found("Title is not found in IMDb dataset"))
+    }
+
+    /// Movies and shows matching the query, most voted first
+    async fn search(&self, query: &str) -> crate::Result<Vec<ImdbTitle>> {
+        let query = format!("\"{}\"", query.trim().replace('"', ""));
+        let titles = sqlx::query_as!(
+            ImdbTitle,
+            "SELECT imdb_titles.tconst, imdb_titles.title_type, imdb_titles.primary_title,
+            imdb_titles.start_year, imdb_titles.runtime_minutes, imdb_titles.genres
+            FROM imdb_titles_fts
+            JOIN imdb_titles ON imdb_titles.rowid = imdb_titles_fts.rowid
+            LEFT JOIN imdb_ratings ON imdb_ratings.tconst = imdb_titles.tconst
+            WHERE imdb_titles_fts MATCH ?
+            ORDER BY imdb_ratings.num_votes DESC LIMIT ?",
+            query,
+            SEARCH_LIMIT
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(titles)
+    }
+
+    /// Cast followed by the crew members
+    async fn cast(&self, tconst: &str) -> crate::Result<Vec<PersonMetadata>> {
+        let cast = sqlx::query!(
+            "SELECT imdb_principals.nconst, imdb_principals.category, imdb_principals.characters,
+            imdb_names.primary_name
+            FROM imdb_principals JOIN imdb_names ON imdb_names.nconst = imdb_principals.nconst
+            WHERE imdb_principals.tconst = ?
+            AND imdb_principals.category IN ('actor', 'actress', 'self', 'director', 'writer', 'composer')
+            ORDER BY imdb_principals.category IN ('director', 'writer', 'composer'), imdb_principals.ordering",
+            tconst
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(cast
+            .into_iter()
+            .map(|person| {
+                let characters: Vec<String> = person
+                    .characters
+                    .and_then(|c| serde_json::from_str(&c).ok())
+                    .unwrap_or_default();
+                PersonMetadata {
+                    metadata_id: person.nconst.clone(),
+                    metadata_provider: MetadataProvider::Imdb,
+                    person_poster: None,
+                    name: person.primary_name,
+                    imdb_id: Some(person.nconst),
+                    role: (!characters.is_empty()).then(|| RoleMetadata {
+                        character: characters.join(" / "),
+                        poster: None,
+                    }),
+                    job: crew_job(&person.category),
+                }
+            })
+            .collect())
+    }
+
+    async fn show_episodes(&self, tconst: &str) -> crate::Result<(Vec<usize>, usize)> {
+        let seasons = sqlx::query!(
+            r#"SELECT season_number as "season_number!", COUNT(*) as "episodes!: i64"
+            FROM imdb_episodes WHERE parent_tconst = ? AND season_number IS NOT NULL
+            GROUP BY season_number ORDER BY season_number"#,
+            tconst
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        let episodes = seasons.iter().map(|s| s.episodes as usize).sum();
+        Ok((
+            seasons
+                .into_iter()
+                .map(|s| s.season_number as usize)
+                .collect(),
+            episodes,
+        ))
+    }
+
+    async fn season_episodes(
+        &self,
+        show_id: &str,
+        season: usize,
+        episode: Option<usize>,
+    ) -> crate::Result<Vec<EpisodeMetadata>> {
+        let season = season as i64;
+        let episode = episode.map(|e| e as i64);
+        let episodes = sqlx::query!(
+            r#"SELECT imdb_episodes.tconst, imdb_episodes.episode_number as "episode_number!",
+            imdb_titles.primary_title, imdb_titles.start_year, imdb_titles.runtime_minutes
+            FROM imdb_episodes JOIN imdb_titles ON imdb_titles.tconst = imdb_episodes.tconst
+            WHERE imdb_episodes.parent_tconst = ? AND imdb_episodes.season_number = ?
+            AND imdb_episodes.episode_number IS NOT NULL
+            AND (?3 IS NULL OR imdb_episodes.episode_number = ?3)
+            ORDER BY imdb_episodes.episode_number"#,
+            show_id,
+            season,
+            episode
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        Ok(episodes
+            .into_iter()
+            .map(|episode| EpisodeMetadata {
+                metadata_id: episode.tconst,
+                metadata_provider: MetadataProvider::Imdb,
+                release_date: episode.start_year.map(|year| year.to_string()),
+                number: episode.episode_number as usize,
+                title: episode.primary_title,
+                season_number: season as usize,
+                runtime: episode
+                    .runtime_minutes
+                    .map(|minutes| Duration::from_secs(minutes as u64 * 60).into()),
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+impl ProviderIdentifier for ImdbProvider {
+    fn provider_identifier(&self) -> MetadataProvider {
+        MetadataProvider::Imdb
+    }
+}
+
+#[async_trait::async_trait]
+impl MovieMetadataProvider for ImdbProvider {
+    async fn movie(
+        &self,
+        movie_metadata_id: &str,
+        _params: FetchParams,
+    ) -> crate::Result<MovieMetadata> {
+        let title = self.title(movie_metadata_id, MOVIE_TYPES).await?;
+        let cast = self.cast(movie_metadata_id).await?;
+        Ok(title.into_movie(Some(cast)))
+    }
+
+    async fn movie_search(
+        &self,
+        query: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<Vec<MovieMetadata>> {
+        let titles = self.search(query).await?;
+        Ok(titles
+            .into_iter()
+            .filter(|t| MOVIE_TYPES.contains(&t.title_type.as_str()))
+            .map(|t| t.into_movie(None))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl ShowMetadataProvider for ImdbProvider {
+    async fn show(&self, show_id: &str, _fetch_params: FetchParams) -> crate::Result<ShowMetadata> {
+        let title = self.title(show_id, SHOW_TYPES).await?;
+        let (seasons, episodes_amount) = self.show_episodes(show_id).await?;
+        let cast = self.cast(show_id).await?;
+        Ok(title.into_show(Some(seasons), Some(episodes_amount), Some(cast)))
+    }
+
+    async fn season(
+        &self,
+        show_id: &str,
+        season: usize,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<SeasonMetadata> {
+        let episodes = self.season_episodes(show_id, season, None).await?;
+        if episodes.is_empty() {
+            return Err(AppError::not_found("Season is not found in IMDb dataset"));
+        }
+        Ok(SeasonMetadata {
+            metadata_id: format!("{show_id}:{season}"),
+            metadata_provider: MetadataProvider::Imdb,
+            release_date: episodes.iter().find_map(|e| e.release_date.clone()),
+            episodes,
+            number: season,
+            ..Default::default()
+        })
+    }
+
+    async fn episode(
+        &self,
+        show_id: &str,
+        season: usize,
+        episode: usize,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<EpisodeMetadata> {
+        self.season_episodes(show_id, season, Some(episode))
+            .await?
+            .pop()
+            .ok_or(AppError::not_found("Episode is not found in IMDb dataset"))
+    }
+
+    async fn show_search(
+        &self,
+        query: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<Vec<ShowMetadata>> {
+        let titles = self.search(query).await?;
+        Ok(titles
+            .into_iter()
+            .filter(|t| SHOW_TYPES.contains(&t.title_type.as_str()))
+            .map(|t| t.into_show(None, None, None))
+            .collect())
+    }
+}
+
+#[async_trait::async_trait]
+impl PersonMetadataProvider for ImdbProvider {
+    async fn person(
+        &self,
+        person_id: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<PersonDetailsMetadata> {
+        let person = sqlx::query!(
+            "SELECT nconst, primary_name, birth_year, death_year, primary_profession
+            FROM imdb_names WHERE nconst = ?",
+            person_id
+        )
+        .fetch_optional(&self.db.pool)
+        .await?
+        .ok_or(AppError::not_found("Person is not found in IMDb dataset"))?;
+        let credits = sqlx::query!(
+            "SELECT imdb_titles.tconst, imdb_titles.title_type, imdb_titles.primary_title,
+            imdb_titles.start_year, imdb_principals.category, imdb_principals.characters
+            FROM imdb_principals JOIN imdb_titles ON imdb_titles.tconst = imdb_principals.tconst
+            WHERE imdb_principals.nconst = ?
+            ORDER BY imdb_titles.start_year DESC, imdb_principals.ordering",
+            person_id
+        )
+        .fetch_all(&self.db.pool)
+        .await?;
+        let credits = credits
+            .into_iter()
+            .filter_map(|credit| {
+                let content_type = match credit.title_type.as_str() {
+                    t_type if MOVIE_TYPES.contains(&t_type) => ParentMediaType::Movie,
+                    t_type if SHOW_TYPES.contains(&t_type) => ParentMediaType::Show,
+                    _ => return None,
+                };
+                let job = crew_job(&credit.category);
+                if job.is_none()
+                    && !["actor", "actress", "self"].contains(&credit.category.as_str())
+                {
+                    return None;
+                }
+                let characters: Vec<String> = credit
+                    .characters
+                    .and_then(|c| serde_json::from_str(&c).ok())
+                    .unwrap_or_default();
+                Some(CreditMetadata {
+                    metadata_id: credit.tconst,
+                    metadata_provider: MetadataProvider::Imdb,
+                    content_type,
+                    title: credit.primary_title,
+                    release_date: credit.start_year.map(|year| year.to_string()),
+                    poster: None,
+                    character: (!characters.is_empty()).then(|| characters.join(" / ")),
+                    job,
+                })
+            })
+            .collect();
+        Ok(PersonDetailsMetadata {
+            metadata_id: person.nconst.clone(),
+            metadata_provider: MetadataProvider::Imdb,
+            name: person.primary_name,
+            poster: None,
+            biography: None,
+            birthday: person.birth_year.map(|year| year.to_string()),
+            deathday: person.death_year.map(|year| year.to_string()),
+            place_of_birth: None,
+            known_for_department: person
+                .primary_profession
+                .as_deref()
+                .and_then(known_for_department),
+            imdb_id: Some(person.nconst),
+            credits,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl DiscoverMetadataProvider for ImdbProvider {
+    async fn multi_search(
+        &self,
+        query: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<Vec<MetadataSearchResult>> {
+        let titles = self.search(query).await?;
+        Ok(titles
+            .into_iter()
+            .filter_map(|t| match t.title_type.as_str() {
+                t_type if MOVIE_TYPES.contains(&t_type) => Some(t.into_movie(None).into()),
+                t_type if SHOW_TYPES.contains(&t_type) => {
+                    Some(t.into_show(None, None, None).into())
+                }
+                _ => None,
+            })
+            .collect())
+    }
+
+    async fn external_ids(
+        &self,
+        content_id: &str,
+        content_hint: ParentMediaType,
+    ) -> crate::Result<Vec<ExternalIdMetadata>> {
+        let types = match content_hint {
+            ParentMediaType::Movie => MOVIE_TYPES,
+            ParentMediaType::Show => SHOW_TYPES,
+        };
+        // Datasets don't reference other providers
+        self.title(content_id, types).await?;
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use sqlx::SqlitePool;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        db::Db,
+        metadata::{
+            CrewJob, DiscoverMetadataProvider, FetchParams, Genre, MetadataProvider,
+            MovieMetadataProvider, ParentMediaType, PersonMetadataProvider, ShowMetadataProvider,
+        },
+    };
+
+    use super::{
+        ImdbProvider,
+        import::{ImdbDataset, import_dataset},
+        rating,
+    };
+
+    const TITLE_BASICS: &str = "tconst\ttitleType\tprimaryTitle\toriginalTitle\tisAdult\tstartYear\tendYear\truntimeMinutes\tgenres
+tt0133093\tmovie\tThe Matrix\tThe Matrix\t0\t1999\t\\N\t136\tAction,Sci-Fi
+tt0234215\tmovie\tThe Matrix Reloaded\tThe Matrix Reloaded\t0\t2003\t\\N\t138\tAction,Sci-Fi
+tt0106062\tvideoGame\tThe Matrix Game\tThe Matrix Game\t0\t2003\t\\N\t\\N\tAction
+tt0903747\ttvSeries\tBreaking Bad\tBreaking Bad\t0\t2008\t2013\t49\tCrime,Drama,Thriller
+tt0959621\ttvEpisode\tPilot\tPilot\t0\t2008\t\\N\t58\tCrime,Drama
+tt1054724\ttvEpisode\tCat's in the Bag...\tCat's in the Bag...\t0\t2008\t\\N\t48\tCrime,Drama
+tt1232244\ttvEpisode\tSeven Thirty-Seven\tSeven Thirty-Seven\t0\t2009\t\\N\t47\tCrime,Drama
+";
+    const TITLE_EPISODE: &str = "tconst\tparentTconst\tseasonNumber\tepisodeNumber
+tt0959621\ttt0903747\t1\t1
+tt1054724\ttt0903747\t1\t2
+tt1232244\ttt0903747\t2\t1
+tt9999999\ttt8888888\t1\t1
+";
+    const TITLE_RATINGS: &str = "tconst\taverageRating\tnumVotes
+tt0133093\t8.7\t2100000
+tt0234215\t7.2\t650000
+tt0903747\t9.5\t2200000
+";
+    const TITLE_PRINCIPALS: &str = "tconst\tordering\tnconst\tcategory\tjob\tcharacters
+tt0133093\t1\tnm0000206\tactor\t\\N\t[\"Neo\"]
+tt0133093\t2\tnm0905154\tdirector\t\\N\t\\N
+tt0903747\t1\tnm0186505\tactor\t\\N\t[\"Walter White\"]
+tt0959621\t1\tnm0186505\tactor\t\\N\t[\"Walter White\"]
+";
+    const NAME_BASICS: &str =
+        "nconst\tprimaryName\tbirthYear\tdeathYear\tprimaryProfession\tknownForTitles
+nm0000206\tKeanu Reeves\t1964\t\\N\tactor,producer\ttt0133093
+nm0905154\tLana Wachowski\t1965\t\\N\twriter,director\ttt0133093
+nm0186505\tBryan Cranston\t1956\t\\N\tactor,producer\ttt0903747
+nm0000001\tFred Astaire\t1899\t1987\tactor\ttt0050419
+";
+
+    async fn import_fixtures(db: &Db) -> anyhow::Result<()> {
+        let cancel = CancellationToken::new();
+        for (dataset, tsv) in [
+            (ImdbDataset::TitleBasics, TITLE_BASICS),
+            (ImdbDataset::TitleEpisode, TITLE_EPISODE),
+            (ImdbDataset::TitleRatings, TITLE_RATINGS),
+            (ImdbDataset::TitlePrincipals, TITLE_PRINCIPALS),
+            (ImdbDataset::NameBasics, NAME_BASICS),
+        ] {
+            import_dataset(db, dataset, Cursor::new(tsv), |_| {}, &cancel).await?;
+        }
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn imdb_import(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        import_fixtures(&db).await?;
+        let imports = sqlx::query!(
+            r#"SELECT dataset as "dataset: ImdbDataset", rows FROM imdb_imports ORDER BY rowid"#
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        let rows: Vec<_> = imports.iter().map(|i| (i.dataset, i.rows)).collect();
+        assert_eq!(
+            rows,
+            [
+                // Video game is skipped
+                (ImdbDataset::TitleBasics, 6),
+                // Episode of the unknown show is skipped
+                (ImdbDataset::TitleEpisode, 3),
+                (ImdbDataset::TitleRatings, 3),
+                // Episode principals are skipped
+                (ImdbDataset::TitlePrincipals, 3),
+                // Only people of the imported principals
+                (ImdbDataset::NameBasics, 3),
+            ]
+        );
+
+        let wrong_header = Cursor::new("tconst\tprimaryTitle\ntt0133093\tThe Matrix\n");
+        let cancel = CancellationToken::new();
+        let result =
+            import_dataset(&db, ImdbDataset::TitleBasics, wrong_header, |_| {}, &cancel).await;
+        assert!(result.is_err());
+        // Failed import keeps the previous data
+        let rating = rating(&db, "tt0133093").await?.unwrap();
+        assert_eq!(rating.average_rating, 8.7);
+        assert_eq!(rating.num_votes, 2100000);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn imdb_provider(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        import_fixtures(&db).await?;
+        let provider = ImdbProvider::new(db);
+        let params = FetchParams::default();
+
+        let movies = provider.movie_search("matrix", params).await?;
+        let ids: Vec<_> = movies.iter().map(|m| m.metadata_id.as_str()).collect();
+        assert_eq!(ids, ["tt0133093", "tt0234215"]);
+
+        let movie = provider.movie("tt0133093", params).await?;
+        assert_eq!(movie.title, "The Matrix");
+        assert_eq!(movie.release_date.as_deref(), Some("1999"));
+        assert_eq!(movie.runtime.unwrap().0.as_secs(), 136 * 60);
+        assert_eq!(
+            movie.genres,
+            Some(vec![Genre::Action, Genre::ScienceFiction])
+        );
+        let cast = movie.cast.unwrap();
+        assert_eq!(cast.len(), 2);
+        assert_eq!(cast[0].name, "Keanu Reeves");
+        assert_eq!(cast[0].role.as_ref().unwrap().character, "Neo");
+        assert_eq!(cast[0].job, None);
+        assert_eq!(cast[1].name, "Lana Wachowski");
+        assert_eq!(cast[1].job, Some(CrewJob::Director));
+        assert!(provider.movie("tt0903747", params).await.is_err());
+
+        let show = provider.show("tt0903747", params).await?;
+        assert_eq!(show.title, "Breaking Bad");
+        assert_eq!(show.seasons, Some(vec![1, 2]));
+        assert_eq!(show.episodes_amount, Some(3));
+        let season = provider.season("tt0903747", 1, params).await?;
+        let episodes: Vec<_> = season.episodes.iter().map(|e| e.number).collect();
+        assert_eq!(episodes, [1, 2]);
+        let episode = provider.episode("tt0903747", 2, 1, params).await?;
+        assert_eq!(episode.title, "Seven Thirty-Seven");
+        assert_eq!(episode.metadata_id, "tt1232244");
+        assert!(provider.episode("tt0903747", 2, 2, params).await.is_err());
+        assert!(provider.season("tt0903747", 3, params).await.is_err());
+
+        let shows = provider.show_search("breaking bad", params).await?;
+        assert_eq!(shows.len(), 1);
+        let results = provider.multi_search("the", params).await?;
+        let results: Vec<_> = results
+            .iter()
+            .map(|r| (r.metadata_id.as_str(), r.content_type))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("tt0133093", ParentMediaType::Movie),
+                ("tt0234215", ParentMediaType::Movie)
+            ]
+        );
+        assert!(
+            provider
+                .external_ids("tt0903747", ParentMediaType::Show)
+                .await?
+                .is_empty()
+        );
+        assert_eq!(
+            movies[0].external_ids.as_ref().unwrap()[0].provider,
+            MetadataProvider::Imdb
+        );
+
+        let person = provider.person("nm0905154", params).await?;
+        assert_eq!(person.name, "Lana Wachowski");
+        assert_eq!(person.birthday.as_deref(), Some("1965"));
+        assert_eq!(person.known_for_department.as_deref(), Some("Writing"));
+        assert_eq!(person.credits.len(), 1);
+        assert_eq!(person.credits[0].metadata_id, "tt0133093");
+        assert_eq!(person.credits[0].job, Some(CrewJob::Director));
+        assert!(provider.person("nm9999999", params).await.is_err());
+        Ok(())
+    }
+}
@@ -2,6 +2,7 @@ pub mod asset_saver;
 pub mod batch;
 pub mod movie;
 pub mod reconcile;
+pub mod refresh;
 pub mod show;
 
 #[cfg(test)]
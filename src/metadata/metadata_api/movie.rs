@@ -3,17 +3,20 @@ use std::time::Duration;
 use tokio::task::JoinSet;
 
 use crate::{
-    config,
+    AppError, AppErrorKind, config,
     db::{Db, DbActions, DbExternalId, DbTransaction, LocalContentId},
-    library::assets::{BackdropAsset, BackdropContentType, PosterAsset, PosterContentType},
+    library::{
+        assets::{BackdropAsset, BackdropContentType, PosterAsset, PosterContentType},
+        collections,
+    },
     metadata::{
-        ExternalIdMetadata, FetchParams, MovieMetadata, MovieMetadataProvider,
+        ExternalIdMetadata, FetchParams, MovieMetadata, MovieMetadataProvider, ParentMediaType,
         metadata_api::asset_saver::AssetTasks,
     },
-    scan::{AssetKind, AssetSaveTask, AssetTaskSource, insert_roles},
+    scan::{AssetKind, AssetSaveTask, AssetTaskSource, insert_roles, movie::save_collection},
 };
 
-use super::{MetadataLookup, PendingInsert};
+use super::{MetadataLookup, PendingInsert, refresh};
 
 /// Resolves a movie against a single metadata provider, reusing local database metadata when
 /// it already exists and fetching from the provider otherwise.
@@ -23,6 +26,7 @@ pub struct MovieMetadataApi<T> {
     fetch_params: FetchParams,
     db: &'static Db,
     http_client: reqwest::Client,
+    unlock: bool,
 }
 
 #[cfg(test)]
@@ -38,26 +42,41 @@ impl MovieMetadataApi<crate::metadata::metadata_api::tests::provider_mock::MockP
             db,
             fetch_params,
             http_client: reqwest::Client::new(),
+            unlock: false,
         }
     }
 }
 
 impl<T> MovieMetadataApi<T>
 where
-    T: MovieMetadataProvider,
+    T: MovieMetadataProvider + Send + Sync,
 {
     pub fn new(provider: T, db: &'static Db, http_client: reqwest::Client) -> Self {
         let config::MetadataLanguage(lang) = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang };
+        let fetch_params = FetchParams::new(lang);
 
         Self {
             provider,
             db,
             fetch_params,
             http_client,
+            unlock: false,
         }
     }
 
+    /// Use language and ordering of the content instead of the server wide ones
+    pub fn with_fetch_params(mut self, fetch_params: FetchParams) -> Self {
+        self.fetch_params = fetch_params;
+        self
+    }
+
+    /// Remove metadata locks of the refreshed content in the write transaction of the refresh,
+    /// so locked fields are overwritten too
+    pub fn with_unlock(mut self) -> Self {
+        self.unlock = true;
+        self
+    }
+
     pub async fn search_movie_title(
         &self,
         title: &str,
@@ -166,8 +185,6 @@ where
         tx: &mut DbTransaction,
         assets: &mut AssetTasks,
     ) -> sqlx::Result<LocalContentId> {
-        let poster = metadata.poster.clone();
-        let backdrop = metadata.backdrop.clone();
         let metadata_id = tx.insert_metadata(&metadata.into_db_metadata()).await?;
         let movie_id = tx
             .insert_movie(&metadata.into_db_movie(
@@ -175,48 +192,167 @@ where
                 metadata.runtime.as_ref().map_or(Duration::ZERO, |v| v.0),
             ))
             .await?;
-        if let Some(cast) = metadata.cast {
-            insert_roles(tx, metadata_id, cast, assets).await?;
+        let local = LocalContentId {
+            id: movie_id,
+            metadata_id,
+        };
+        insert_movie_relations(metadata, local, tx, assets).await?;
+        Ok(local)
+    }
+
+    /// Re-match the local movie to the provider's movie `id`.
+    ///
+    /// Metadata row is updated in place, so videos, history and list items that hang off the
+    /// metadata id stay linked. External ids, genres and cast are replaced with the new ones.
+    /// Collection of the new movie is saved, the previous one stops listing the movie
+    /// because collections are matched by external ids.
+    /// Locked fields keep their current values.
+    pub async fn rematch_movie(
+        &self,
+        movie_id: i64,
+        id: &str,
+    ) -> crate::Result<PendingInsert<LocalContentId>> {
+        let metadata_id =
+            sqlx::query_scalar!("SELECT metadata_id FROM movies WHERE id = ?", movie_id)
+                .fetch_optional(&self.db.pool)
+                .await?
+                .ok_or(AppError::not_found("Movie is not found"))?;
+        let mut metadata = self.provider.movie(id, self.fetch_params).await?;
+        if let Some(local) = self
+            .db
+            .crossreference_movie(metadata.metadata_provider, &metadata.metadata_id)
+            .await?
+            && local.id != movie_id
+        {
+            return Err(AppError::new(
+                "Requested movie already exists in the library",
+                AppErrorKind::Duplicate,
+            ));
         }
-        tx.insert_external_id(DbExternalId {
-            id: None,
-            external_provider: metadata.metadata_provider,
-            external_id: metadata.metadata_id,
-            metadata_id: Some(metadata_id),
-            is_prime: true.into(),
-        })
-        .await?;
-        for ext_id in metadata.external_ids.iter().flatten() {
-            tx.try_insert_external_id(DbExternalId {
-                external_provider: ext_id.provider,
-                external_id: ext_id.id.clone(),
-                metadata_id: Some(metadata_id),
-                is_prime: (metadata.metadata_provider == ext_id.provider).into(),
-                ..Default::default()
-            })
+        // Stale parts are refetched, same as during the scan
+        if let Some(collection) = metadata.collection.as_mut()
+            && !collections::has_fresh_parts(self.db, collection).await?
+        {
+            match self
+                .provider
+                .collection(&collection.metadata_id, self.fetch_params)
+                .await
+            {
+                Ok(full) => *collection = full,
+                Err(e) => tracing::warn!("Failed to fetch movie collection: {e}"),
+            }
+        }
+
+        let local = LocalContentId {
+            id: movie_id,
+            metadata_id,
+        };
+        let mut tx = self.db.pool.begin_with("BEGIN IMMEDIATE").await?;
+        if self.unlock {
+            refresh::unlock(&mut tx, ParentMediaType::Movie, movie_id).await?;
+        }
+        let mut assets = AssetTasks::new(self.http_client.clone());
+        tx.update_metadata(metadata_id, &metadata.into_db_metadata())
+            .await?;
+        let duration = metadata.runtime.as_ref().map(|v| v.0.as_secs() as i64);
+        // Locked fields are dropped from the fresh metadata so they are never written
+        let locks = tx.get_metadata_locks(metadata_id).await?;
+        if locks.backdrop {
+            metadata.backdrop = None;
+        } else {
+            let backdrop = metadata.backdrop.clone();
+            sqlx::query!(
+                "UPDATE movies SET backdrop = ? WHERE id = ?",
+                backdrop,
+                movie_id
+            )
+            .execute(&mut *tx)
             .await?;
         }
-        for genre in metadata.genres.into_iter().flatten() {
-            let _ = tx.insert_content_genre(metadata_id, genre.into()).await;
+        sqlx::query!(
+            "UPDATE movies SET duration = coalesce(?, duration) WHERE id = ?",
+            duration,
+            movie_id
+        )
+        .execute(&mut *tx)
+        .await?;
+        if locks.poster {
+            metadata.poster = None;
         }
-        if let Some(url) = poster {
-            let task_source = AssetTaskSource::Url(url);
-            assets.push(AssetSaveTask {
-                kind: AssetKind::Poster(PosterAsset::new(movie_id, PosterContentType::Movie)),
-                source: task_source,
-            });
+        if locks.genres {
+            metadata.genres = None;
+        } else {
+            tx.delete_content_genres(metadata_id).await?;
         }
-        if let Some(url) = backdrop {
-            assets.push(AssetSaveTask {
-                kind: AssetKind::Backdrop(BackdropAsset::new(movie_id, BackdropContentType::Movie)),
-                source: AssetTaskSource::Url(url),
-            });
+        if locks.cast {
+            metadata.cast = None;
+        } else {
+            tx.delete_roles(metadata_id).await?;
         }
-        Ok(LocalContentId {
-            id: movie_id,
-            metadata_id,
+        if let Some(collection) = &metadata.collection {
+            save_collection(&mut tx, &mut assets, collection).await?;
+        }
+        tx.delete_external_ids(metadata_id).await?;
+        insert_movie_relations(metadata, local, &mut tx, &mut assets).await?;
+        Ok(PendingInsert {
+            content: local,
+            tx,
+            assets,
+        })
+    }
+}
+
+/// Inserts cast, external ids, ratings and genres of the movie and queues its assets
+async fn insert_movie_relations(
+    metadata: MovieMetadata,
+    local: LocalContentId,
+    tx: &mut DbTransaction,
+    assets: &mut AssetTasks,
+) -> sqlx::Result<()> {
+    let LocalContentId {
+        id: movie_id,
+        metadata_id,
+    } = local;
+    if let Some(cast) = metadata.cast {
+        insert_roles(tx, metadata_id, cast, assets).await?;
+    }
+    tx.insert_external_id(DbExternalId {
+        id: None,
+        external_provider: metadata.metadata_provider,
+        external_id: metadata.metadata_id,
+        metadata_id: Some(metadata_id),
+        is_prime: true.into(),
+    })
+    .await?;
+    for ext_id in metadata.external_ids.iter().flatten() {
+        tx.try_insert_external_id(DbExternalId {
+            external_provider: ext_id.provider,
+            external_id: ext_id.id.clone(),
+            metadata_id: Some(metadata_id),
+            is_prime: (metadata.metadata_provider == ext_id.provider).into(),
+            ..Default::default()
         })
+        .await?;
+    }
+    tx.replace_content_ratings(metadata_id, metadata.ratings.as_deref().unwrap_or_default())
+        .await?;
+    for genre in metadata.genres.into_iter().flatten() {
+        let _ = tx.insert_content_genre(metadata_id, genre.into()).await;
+    }
+    if let Some(url) = metadata.poster {
+        let task_source = AssetTaskSource::Url(url);
+        assets.push(AssetSaveTask {
+            kind: AssetKind::Poster(PosterAsset::new(movie_id, PosterContentType::Movie)),
+            source: task_source,
+        });
+    }
+    if let Some(url) = metadata.backdrop {
+        assets.push(AssetSaveTask {
+            kind: AssetKind::Backdrop(BackdropAsset::new(movie_id, BackdropContentType::Movie)),
+            source: AssetTaskSource::Url(url),
+        });
     }
+    Ok(())
 }
 
 pub(super) struct BatchResult<S> {
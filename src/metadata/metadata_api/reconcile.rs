@@ -5,7 +5,8 @@
 //! their metadata rows are updated *in place* — the content/metadata ids are
 //! reused, so every foreign key pointing at them (history, intros, saved list,
 //! watch progress) survives. External ids are recreated (old deleted, fresh
-//! inserted). Local nodes that are absent from the fresh tree are left untouched.
+//! inserted). Local nodes that are absent from the fresh tree are left untouched,
+//! as well as the fields that are locked by manual edits.
 //!
 //! The caller supplies an already-resolved fresh tree (resolved against the
 //! corrected provider id, so every node is [`MetadataLookup::New`]). This keeps
@@ -13,13 +14,17 @@
 //!
 //! [`flush_show_tree`]: super::show::ShowMetadataApi::flush_show_tree
 
+use std::collections::HashMap;
+
 use anyhow::bail;
 
 use crate::{
     db::{Db, DbActions, DbExternalId, DbTransaction},
     library::assets::{BackdropAsset, BackdropContentType, PosterAsset, PosterContentType},
-    metadata::{ExternalIdMetadata, metadata_api::asset_saver::AssetTasks},
-    scan::{AssetKind, AssetSaveTask, AssetTaskSource},
+    metadata::{
+        ExternalIdMetadata, MetadataProvider, PersonMetadata, metadata_api::asset_saver::AssetTasks,
+    },
+    scan::{AssetKind, AssetSaveTask, AssetTaskSource, insert_roles},
 };
 
 use super::{
@@ -57,14 +62,30 @@ where
     };
 
     // Show: update the metadata row in place and recreate its external ids.
-    let poster = show_meta.poster.clone();
-    let backdrop = show_meta.backdrop.clone();
+    // Locked fields are left as they are.
+    let locks = tx.get_metadata_locks(show_metadata_id).await?;
+    let poster = show_meta.poster.clone().filter(|_| !locks.poster);
+    let backdrop = show_meta.backdrop.clone().filter(|_| !locks.backdrop);
     tx.update_metadata(show_metadata_id, &show_meta.into_db_metadata())
         .await?;
-    tx.update_show_backdrop(old_show_id, backdrop.clone())
-        .await?;
-    recreate_external_ids(tx, show_metadata_id, show_meta.external_ids).await;
-    for genre in show_meta.genres.into_iter().flatten() {
+    if !locks.backdrop {
+        tx.update_show_backdrop(old_show_id, backdrop.clone())
+            .await?;
+    }
+    recreate_external_ids(
+        tx,
+        show_metadata_id,
+        show_meta.metadata_provider,
+        show_meta.external_ids,
+    )
+    .await;
+    tx.replace_content_ratings(
+        show_metadata_id,
+        show_meta.ratings.as_deref().unwrap_or_default(),
+    )
+    .await?;
+    let genres = show_meta.genres.filter(|_| !locks.genres);
+    for genre in genres.into_iter().flatten() {
         // INSERT OR IGNORE: safe to re-run on refresh.
         let _ = tx
             .insert_content_genre(show_metadata_id, genre.into())
@@ -85,26 +106,47 @@ where
 
     let mut written_seasons = Vec::new();
     for season in fresh.seasons {
-        let ResolvedSeason {
-            number: season_number,
-            lookup,
-            episodes,
-        } = season;
+        if let Some(written) =
+            reconcile_season(tx, asset_tasks, &old_tree, old_show_id, season).await?
+        {
+            written_seasons.push(written);
+        }
+    }
 
-        let MetadataLookup::New {
-            metadata: season_meta,
-        } = lookup
-        else {
-            tracing::warn!(
-                season = season_number,
-                "Skipping non-fresh season in reconcile"
-            );
-            continue;
-        };
+    Ok(WrittenShow {
+        show_id: old_show_id,
+        metadata_id: show_metadata_id,
+        seasons: written_seasons,
+    })
+}
+
+/// Reconciles a single fresh season onto the local show `show_id`.
+///
+/// Local season lookup keeps the season as it is and reconciles only its episodes.
+/// Returns `None` when the season has no metadata
+pub(super) async fn reconcile_season<T>(
+    tx: &mut DbTransaction,
+    asset_tasks: &mut AssetTasks,
+    old_tree: &LocalTree,
+    show_id: i64,
+    season: ResolvedSeason<T>,
+) -> anyhow::Result<Option<WrittenSeason<T>>>
+where
+    T: HasSource,
+{
+    let ResolvedSeason {
+        number: season_number,
+        lookup,
+        episodes,
+    } = season;
 
-        let (season_id, season_metadata_id) = match old_tree.seasons.get(&season_number) {
+    let (season_id, season_metadata_id) = match lookup {
+        MetadataLookup::New {
+            metadata: season_meta,
+        } => match old_tree.seasons.get(&season_number) {
             Some(local) => {
-                let poster = season_meta.poster.clone();
+                let locks = tx.get_metadata_locks(local.metadata_id).await?;
+                let poster = season_meta.poster.clone().filter(|_| !locks.poster);
                 tx.update_metadata(local.metadata_id, &season_meta.into_db_metadata())
                     .await?;
                 queue_simple_poster(asset_tasks, local.id, PosterContentType::Season, poster);
@@ -114,38 +156,46 @@ where
                 let poster = season_meta.poster.clone();
                 let metadata_id = tx.insert_metadata(&season_meta.into_db_metadata()).await?;
                 let season_id = tx
-                    .insert_season(season_meta.into_db_season(metadata_id, old_show_id))
+                    .insert_season(season_meta.into_db_season(metadata_id, show_id))
                     .await?;
                 queue_simple_poster(asset_tasks, season_id, PosterContentType::Season, poster);
                 (season_id, metadata_id)
             }
-        };
+        },
+        // Season itself is not refreshed, only its episodes
+        MetadataLookup::Local(local) => (local.id, local.metadata_id),
+        MetadataLookup::Missing => {
+            tracing::warn!(
+                season = season_number,
+                "Skipping season without metadata in reconcile"
+            );
+            return Ok(None);
+        }
+    };
 
-        let mut written_episodes = Vec::new();
-        for episode in episodes {
-            let ResolvedEpisode {
-                number: episode_number,
-                lookup,
-                duration,
-                items,
-            } = episode;
-
-            let MetadataLookup::New { metadata: ep_meta } = lookup else {
-                tracing::warn!(
-                    season = season_number,
-                    episode = episode_number,
-                    "Skipping non-fresh episode in reconcile"
-                );
-                continue;
-            };
+    let mut written_episodes = Vec::new();
+    for episode in episodes {
+        let ResolvedEpisode {
+            number: episode_number,
+            lookup,
+            duration,
+            items,
+        } = episode;
+
+        let MetadataLookup::New { metadata: ep_meta } = lookup else {
+            tracing::warn!(
+                season = season_number,
+                episode = episode_number,
+                "Skipping non-fresh episode in reconcile"
+            );
+            continue;
+        };
 
-            let poster = ep_meta.poster.clone();
-            let source = items.first().and_then(|i| i.fallback_source());
+        let poster = ep_meta.poster.clone();
+        let source = items.first().and_then(|i| i.fallback_source());
 
-            let (episode_id, episode_metadata_id) = match old_tree
-                .episodes
-                .get(&(season_number, episode_number))
-            {
+        let (episode_id, episode_metadata_id) =
+            match old_tree.episodes.get(&(season_number, episode_number)) {
                 Some(local) => {
                     tx.update_metadata(local.metadata_id, &ep_meta.into_db_metadata())
                         .await?;
@@ -159,7 +209,9 @@ where
                         })
                         .await?;
                     }
-                    queue_episode_poster(asset_tasks, local.id, poster, source);
+                    if !tx.get_metadata_locks(local.metadata_id).await?.poster {
+                        queue_episode_poster(asset_tasks, local.id, poster, source);
+                    }
                     (local.id, local.metadata_id)
                 }
                 None => {
@@ -183,33 +235,30 @@ where
                 }
             };
 
-            written_episodes.push(WrittenEpisode {
-                episode_id,
-                metadata_id: episode_metadata_id,
-                number: episode_number,
-                items,
-            });
-        }
-
-        written_seasons.push(WrittenSeason {
-            season_id,
-            metadata_id: season_metadata_id,
-            number: season_number,
-            episodes: written_episodes,
+        written_episodes.push(WrittenEpisode {
+            episode_id,
+            metadata_id: episode_metadata_id,
+            number: episode_number,
+            items,
         });
     }
 
-    Ok(WrittenShow {
-        show_id: old_show_id,
-        metadata_id: show_metadata_id,
-        seasons: written_seasons,
-    })
+    Ok(Some(WrittenSeason {
+        season_id,
+        metadata_id: season_metadata_id,
+        number: season_number,
+        episodes: written_episodes,
+    }))
 }
 
 /// Deletes every external id of a metadata row, then inserts the fresh set.
+///
+/// Id of the provider the tree was resolved from is marked as prime, same as during the scan.
+/// Without it the refreshed show loses its prime id and the next reset picks an arbitrary provider.
 async fn recreate_external_ids(
     tx: &mut DbTransaction,
     metadata_id: i64,
+    prime_provider: MetadataProvider,
     external_ids: Option<Vec<ExternalIdMetadata>>,
 ) {
     if let Err(e) = tx.delete_external_ids(metadata_id).await {
@@ -222,6 +271,7 @@ async fn recreate_external_ids(
                 external_provider: ext.provider,
                 external_id: ext.id,
                 metadata_id: Some(metadata_id),
+                is_prime: (ext.provider == prime_provider).into(),
                 ..Default::default()
             })
             .await
@@ -244,3 +294,90 @@ fn queue_simple_poster(
         });
     }
 }
+
+/// Cast of a freshly resolved tree, taken out of it before reconciliation.
+///
+/// Reconcile keeps local roles, while re-matching has to replace them because they belong
+/// to the previous match. Only the nodes that were freshly resolved get their roles replaced.
+#[derive(Debug, Default)]
+pub struct FreshCast {
+    show: Option<Vec<PersonMetadata>>,
+    seasons: HashMap<usize, Option<Vec<PersonMetadata>>>,
+    episodes: HashMap<(usize, usize), Option<Vec<PersonMetadata>>>,
+}
+
+impl FreshCast {
+    pub fn take<T>(fresh: &mut ResolvedShow<T>) -> Self {
+        let mut cast = Self::default();
+        if let MetadataLookup::New { metadata } = &mut fresh.show_lookup {
+            cast.show = metadata.cast.take();
+        }
+        for season in &mut fresh.seasons {
+            cast.take_season(season);
+        }
+        cast
+    }
+
+    pub fn take_season<T>(&mut self, season: &mut ResolvedSeason<T>) {
+        if let MetadataLookup::New { metadata } = &mut season.lookup {
+            self.seasons.insert(season.number, metadata.cast.take());
+        }
+        for episode in &mut season.episodes {
+            if let MetadataLookup::New { metadata } = &mut episode.lookup {
+                self.episodes
+                    .insert((season.number, episode.number), metadata.cast.take());
+            }
+        }
+    }
+
+    /// Replace roles of every written node with the fresh cast
+    pub async fn replace<T>(
+        mut self,
+        tx: &mut DbTransaction,
+        asset_tasks: &mut AssetTasks,
+        written: &WrittenShow<T>,
+    ) -> sqlx::Result<()> {
+        replace_roles(tx, asset_tasks, written.metadata_id, self.show.take()).await?;
+        for season in &written.seasons {
+            self.replace_season(tx, asset_tasks, season).await?;
+        }
+        Ok(())
+    }
+
+    /// Replace roles of the written season and its episodes
+    pub async fn replace_season<T>(
+        &mut self,
+        tx: &mut DbTransaction,
+        asset_tasks: &mut AssetTasks,
+        season: &WrittenSeason<T>,
+    ) -> sqlx::Result<()> {
+        if let Some(season_cast) = self.seasons.remove(&season.number) {
+            replace_roles(tx, asset_tasks, season.metadata_id, season_cast).await?;
+        }
+        for episode in &season.episodes {
+            if let Some(episode_cast) = self.episodes.remove(&(season.number, episode.number)) {
+                replace_roles(tx, asset_tasks, episode.metadata_id, episode_cast).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Drops the roles of the metadata row and inserts the new cast if there is one.
+///
+/// Roles are kept when the cast is locked
+pub async fn replace_roles(
+    tx: &mut DbTransaction,
+    asset_tasks: &mut AssetTasks,
+    metadata_id: i64,
+    cast: Option<Vec<PersonMetadata>>,
+) -> sqlx::Result<()> {
+    if tx.get_metadata_locks(metadata_id).await?.cast {
+        return Ok(());
+    }
+    tx.delete_roles(metadata_id).await?;
+    if let Some(cast) = cast {
+        insert_roles(tx, metadata_id, cast, asset_tasks).await?;
+    }
+    Ok(())
+}
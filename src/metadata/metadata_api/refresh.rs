@@ -0,0 +1,179 @@
+//! Reset of the local content to the fresh metadata of its prime provider.
+//!
+//! Reset is a re-match to the same provider id. Metadata locks are removed in the write
+//! transaction of the re-match, so manual metadata edits are overwritten while everything that
+//! hangs off the metadata ids stays linked. Locks are kept if the re-match fails.
+
+use std::sync::atomic::{self, AtomicUsize};
+
+use serde::Serialize;
+
+use crate::{
+    AppError,
+    db::{Db, DbTransaction},
+    metadata::{MetadataProvider, ParentMediaType},
+    progress::{ProgressDispatcher, TaskProgress, TaskTrait},
+};
+
+use super::{PendingInsert, asset_saver::AssetsProgressSink};
+
+/// Metadata reset of a show tree or a movie
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct MetadataRefreshTask {
+    pub content_type: ParentMediaType,
+    /// Id of the show or movie
+    pub content_id: i64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase", tag = "stage")]
+pub enum MetadataRefreshProgress {
+    /// Fetching metadata from the provider
+    Fetch,
+    /// Downloading posters and backdrops
+    Assets {
+        total: usize,
+        saved: usize,
+        failed: usize,
+    },
+}
+
+impl TaskTrait for MetadataRefreshTask {
+    type Progress = MetadataRefreshProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> TaskProgress
+    where
+        Self: Sized,
+    {
+        TaskProgress::MetadataRefresh(status)
+    }
+}
+
+/// Provider id of the content that metadata is refreshed from.
+///
+/// Prime id is preferred, other ids are used when the prime provider is not available
+pub async fn prime_provider<P>(
+    db: &Db,
+    metadata_id: i64,
+    available: impl Fn(MetadataProvider) -> Option<P>,
+) -> crate::Result<(P, String)> {
+    let ids = sqlx::query!(
+        r#"SELECT external_provider as "provider: MetadataProvider", external_id FROM external_ids
+        WHERE metadata_id = ? ORDER BY is_prime DESC, id"#,
+        metadata_id
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    ids.into_iter()
+        .filter(|id| !id.provider.is_local())
+        .find_map(|id| Some((available(id.provider)?, id.external_id)))
+        .ok_or(AppError::bad_request(
+            "Content does not have ids of the available metadata providers",
+        ))
+}
+
+/// Remove metadata locks of the movie or the whole show tree
+pub async fn unlock(
+    tx: &mut DbTransaction,
+    content_type: ParentMediaType,
+    content_id: i64,
+) -> sqlx::Result<()> {
+    match content_type {
+        ParentMediaType::Movie => {
+            sqlx::query!(
+                "DELETE FROM metadata_locks WHERE metadata_id = (SELECT metadata_id FROM movies WHERE id = ?)",
+                content_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        ParentMediaType::Show => {
+            sqlx::query!(
+                r#"DELETE FROM metadata_locks WHERE metadata_id IN (
+                    SELECT metadata_id FROM shows WHERE id = ?1
+                    UNION SELECT metadata_id FROM seasons WHERE show_id = ?1
+                    UNION SELECT episodes.metadata_id FROM episodes
+                    JOIN seasons ON seasons.id = episodes.season_id WHERE seasons.show_id = ?1
+                )"#,
+                content_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Remove metadata locks of the show season with its episodes, or of a single episode
+pub async fn unlock_season(
+    tx: &mut DbTransaction,
+    show_id: i64,
+    season: usize,
+    episode: Option<usize>,
+) -> sqlx::Result<()> {
+    let season = season as i64;
+    let episode = episode.map(|e| e as i64);
+    sqlx::query!(
+        r#"DELETE FROM metadata_locks WHERE metadata_id IN (
+            SELECT metadata_id FROM seasons WHERE show_id = ?1 AND number = ?2 AND ?3 IS NULL
+            UNION SELECT episodes.metadata_id FROM episodes
+            JOIN seasons ON seasons.id = episodes.season_id
+            WHERE seasons.show_id = ?1 AND seasons.number = ?2
+            AND (?3 IS NULL OR episodes.number = ?3)
+        )"#,
+        show_id,
+        season,
+        episode
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+struct AssetsProgress<'a> {
+    dispatch: &'a ProgressDispatcher<MetadataRefreshTask>,
+    total: usize,
+    saved: AtomicUsize,
+    failed: AtomicUsize,
+}
+
+impl AssetsProgress<'_> {
+    fn send(&self) {
+        self.dispatch.progress(MetadataRefreshProgress::Assets {
+            total: self.total,
+            saved: self.saved.load(atomic::Ordering::Relaxed),
+            failed: self.failed.load(atomic::Ordering::Relaxed),
+        });
+    }
+}
+
+impl AssetsProgressSink for AssetsProgress<'_> {
+    fn dispatch_success(&self) {
+        self.saved.fetch_add(1, atomic::Ordering::Relaxed);
+        self.send();
+    }
+
+    fn dispatch_fail(&self) {
+        self.failed.fetch_add(1, atomic::Ordering::Relaxed);
+        self.send();
+    }
+}
+
+/// Commit refreshed metadata and save its assets reporting the progress
+pub async fn commit_with_progress<T>(
+    pending: PendingInsert<T>,
+    max_concurrency: usize,
+    dispatch: &ProgressDispatcher<MetadataRefreshTask>,
+) -> sqlx::Result<()> {
+    let PendingInsert { tx, assets, .. } = pending;
+    tx.commit().await?;
+    let progress = AssetsProgress {
+        dispatch,
+        total: assets.len(),
+        saved: AtomicUsize::new(0),
+        failed: AtomicUsize::new(0),
+    };
+    progress.send();
+    assets.save(max_concurrency, progress).await;
+    Ok(())
+}
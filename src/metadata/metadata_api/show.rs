@@ -36,20 +36,24 @@ use anyhow::bail;
 use tokio::task::JoinSet;
 
 use crate::{
-    config,
+    AppError, AppErrorKind, config,
     db::{Db, DbActions, DbExternalId, DbTransaction, LocalContentId},
     library::{
         Source,
         assets::{BackdropAsset, BackdropContentType, PosterAsset, PosterContentType},
     },
     metadata::{
-        EpisodeMetadata, ExternalIdMetadata, FetchParams, SeasonMetadata, ShowMetadata,
-        ShowMetadataProvider, metadata_api::asset_saver::AssetTasks,
+        EpisodeMetadata, ExternalIdMetadata, FetchParams, ParentMediaType, SeasonMetadata,
+        ShowMetadata, ShowMetadataProvider, metadata_api::asset_saver::AssetTasks,
     },
     scan::{AssetKind, AssetSaveTask, AssetTaskSource, insert_roles},
 };
 
-use super::{MetadataLookup, PendingInsert};
+use super::{
+    MetadataLookup, PendingInsert,
+    reconcile::{FreshCast, reconcile_season, reconcile_show_tree},
+    refresh,
+};
 
 /// A leaf carried through the resolve/write pipeline. The resolver only ever asks a
 /// leaf whether it has a local video, used both for episode duration and for poster
@@ -309,6 +313,7 @@ pub struct ShowMetadataApi<T> {
     fetch_params: FetchParams,
     db: &'static Db,
     http_client: reqwest::Client,
+    unlock: bool,
 }
 
 #[cfg(test)]
@@ -323,6 +328,7 @@ impl ShowMetadataApi<crate::metadata::metadata_api::tests::provider_mock::MockPr
             fetch_params,
             db,
             http_client: reqwest::Client::new(),
+            unlock: false,
         }
     }
 }
@@ -333,16 +339,30 @@ where
 {
     pub fn new(provider: T, db: &'static Db, http_client: reqwest::Client) -> Self {
         let config::MetadataLanguage(lang) = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang };
+        let fetch_params = FetchParams::new(lang);
 
         Self {
             provider,
             db,
             fetch_params,
             http_client,
+            unlock: false,
         }
     }
 
+    /// Use language and ordering of the content instead of the server wide ones
+    pub fn with_fetch_params(mut self, fetch_params: FetchParams) -> Self {
+        self.fetch_params = fetch_params;
+        self
+    }
+
+    /// Remove metadata locks of the refreshed content in the write transaction of the refresh,
+    /// so locked fields are overwritten too
+    pub fn with_unlock(mut self) -> Self {
+        self.unlock = true;
+        self
+    }
+
     pub async fn search_show_title(
         &self,
         title: &str,
@@ -379,25 +399,178 @@ where
         &self,
         id: &str,
     ) -> anyhow::Result<MetadataLookup<ShowMetadata>> {
-        let mut show = self.provider.show(id, self.fetch_params).await?;
+        let show = self.fetch_show(id).await?;
         match self
             .db
             .crossreference_show(show.metadata_provider, &show.metadata_id)
             .await
         {
             Ok(Some(local)) => Ok(MetadataLookup::Local(local)),
-            Ok(None) | Err(_) => {
-                let external_ids = show.external_ids.get_or_insert_default();
-                external_ids.insert(
-                    0,
-                    ExternalIdMetadata {
-                        provider: show.metadata_provider,
-                        id: show.metadata_id.clone(),
-                    },
-                );
-                Ok(MetadataLookup::New { metadata: show })
+            Ok(None) | Err(_) => Ok(MetadataLookup::New { metadata: show }),
+        }
+    }
+
+    /// Fetch show from the provider bypassing local metadata.
+    ///
+    /// The provider's own id is put first in the external ids
+    async fn fetch_show(&self, id: &str) -> crate::Result<ShowMetadata> {
+        let mut show = self.provider.show(id, self.fetch_params).await?;
+        let external_ids = show.external_ids.get_or_insert_default();
+        external_ids.insert(
+            0,
+            ExternalIdMetadata {
+                provider: show.metadata_provider,
+                id: show.metadata_id.clone(),
+            },
+        );
+        Ok(show)
+    }
+
+    /// Re-match the local show to the provider's show `id`.
+    ///
+    /// Every local season and episode is resolved against the new show and written over the
+    /// local tree in place, so videos, history, intros and list items that hang off the metadata
+    /// ids stay linked. Genres and cast are replaced with the new ones.
+    /// Nodes the new show doesn't have and locked fields keep their current metadata,
+    /// but external ids of such nodes are removed because they point to the previous match.
+    pub async fn rematch_show(
+        &self,
+        show_id: i64,
+        id: &str,
+    ) -> crate::Result<PendingInsert<WrittenShow<EpisodeNumber>>> {
+        let show_metadata_id =
+            sqlx::query_scalar!("SELECT metadata_id FROM shows WHERE id = ?", show_id)
+                .fetch_optional(&self.db.pool)
+                .await?
+                .ok_or(AppError::not_found("Show is not found"))?;
+        let metadata = self.fetch_show(id).await?;
+        if let Some(local) = self
+            .db
+            .crossreference_show(metadata.metadata_provider, &metadata.metadata_id)
+            .await?
+            && local.id != show_id
+        {
+            return Err(AppError::new(
+                "Requested show already exists in the library",
+                AppErrorKind::Duplicate,
+            ));
+        }
+
+        let local_tree = LocalTree::load(self.db, show_id).await?;
+        let mut numbers: HashMap<usize, Vec<usize>> = local_tree
+            .seasons
+            .keys()
+            .map(|season| (*season, Vec::new()))
+            .collect();
+        for (season, episode) in local_tree.episodes.keys() {
+            numbers.entry(*season).or_default().push(*episode);
+        }
+        let mut fresh = self
+            .fetch_show_tree(MetadataLookup::New { metadata }, numbers)
+            .await?;
+        let cast = FreshCast::take(&mut fresh);
+
+        let mut tx = self.db.pool.begin_with("BEGIN IMMEDIATE").await?;
+        if self.unlock {
+            refresh::unlock(&mut tx, ParentMediaType::Show, show_id).await?;
+        }
+        let mut assets = AssetTasks::new(self.http_client.clone());
+        if !tx.get_metadata_locks(show_metadata_id).await?.genres {
+            tx.delete_content_genres(show_metadata_id).await?;
+        }
+        let written = reconcile_show_tree(self.db, &mut tx, &mut assets, show_id, fresh).await?;
+        cast.replace(&mut tx, &mut assets, &written).await?;
+        for (number, season) in &local_tree.seasons {
+            if !written.seasons.iter().any(|s| s.number == *number) {
+                tx.delete_external_ids(season.metadata_id).await?;
             }
         }
+        for ((season_number, number), episode) in &local_tree.episodes {
+            let is_written = written
+                .seasons
+                .iter()
+                .filter(|s| s.number == *season_number)
+                .flat_map(|s| &s.episodes)
+                .any(|e| e.number == *number);
+            if !is_written {
+                tx.delete_external_ids(episode.metadata_id).await?;
+            }
+        }
+        Ok(PendingInsert {
+            content: written,
+            tx,
+            assets,
+        })
+    }
+
+    /// Refresh the local season, or a single episode of it, from the provider's show `id`.
+    ///
+    /// Nodes are written over the local ones in place like during the re-match,
+    /// show metadata and other seasons are left untouched.
+    pub async fn refresh_season(
+        &self,
+        show_id: i64,
+        id: &str,
+        season: usize,
+        episode: Option<usize>,
+    ) -> crate::Result<PendingInsert<WrittenSeason<EpisodeNumber>>> {
+        let local_tree = LocalTree::load(self.db, show_id).await?;
+        let Some(local_season) = local_tree.seasons.get(&season).copied() else {
+            return Err(AppError::not_found("Season is not found"));
+        };
+        let mut fresh = match episode {
+            Some(episode) => {
+                if !local_tree.episodes.contains_key(&(season, episode)) {
+                    return Err(AppError::not_found("Episode is not found"));
+                }
+                let input = EpisodeInput {
+                    number: episode,
+                    items: Vec::new(),
+                };
+                ResolvedSeason {
+                    number: season,
+                    lookup: MetadataLookup::Local(local_season),
+                    episodes: vec![self.resolve_episode(None, Some(id), season, input).await],
+                }
+            }
+            None => {
+                let mut episodes: Vec<_> = local_tree
+                    .episodes
+                    .keys()
+                    .filter(|(season_number, _)| *season_number == season)
+                    .map(|(_, number)| EpisodeInput {
+                        number: *number,
+                        items: Vec::new(),
+                    })
+                    .collect();
+                episodes.sort_unstable_by_key(|e| e.number);
+                let input = SeasonInput {
+                    number: season,
+                    episodes,
+                };
+                self.resolve_season(None, Some(id), input).await
+            }
+        };
+        let mut cast = FreshCast::default();
+        cast.take_season(&mut fresh);
+
+        let mut tx = self.db.pool.begin_with("BEGIN IMMEDIATE").await?;
+        if self.unlock {
+            refresh::unlock_season(&mut tx, show_id, season, episode).await?;
+        }
+        let mut assets = AssetTasks::new(self.http_client.clone());
+        let written = reconcile_season(&mut tx, &mut assets, &local_tree, show_id, fresh)
+            .await?
+            .filter(|written| episode.is_none() || !written.episodes.is_empty())
+            .ok_or(AppError::not_found(
+                "Metadata provider does not have the requested season or episode",
+            ))?;
+        cast.replace_season(&mut tx, &mut assets, &written).await?;
+        Ok(PendingInsert {
+            content: written,
+            tx,
+            assets,
+        })
     }
 
     /// Resolve the full season/episode tree for a show.
@@ -533,6 +706,9 @@ where
                         tracing::error!(provider = %ext.provider, "Failed to insert external id: {e}");
                     }
                 }
+                if let Some(ratings) = &metadata.ratings {
+                    tx.replace_content_ratings(metadata_id, ratings).await?;
+                }
                 for genre in metadata.genres.into_iter().flatten() {
                     let _ = tx.insert_content_genre(metadata_id, genre.into()).await;
                 }
@@ -211,3 +211,317 @@ async fn reconcile_updates_in_place(pool: SqlitePool) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+mod rematch {
+    use super::provider_mock::{MockProvider, MovieKey, ShowTreeBuilder};
+    use super::*;
+    use crate::{AppErrorKind, metadata::metadata_api::movie::MovieMetadataApi};
+
+    #[sqlx::test]
+    async fn rematch_show_keeps_links(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let saved = ShowTreeBuilder::new(1).season(1, 1..=3).save(db).await?;
+        let s1e1 = *saved.episode(1, 1).expect("s1e1 was seeded");
+        let s1e3 = *saved.episode(1, 3).expect("s1e3 was seeded");
+        db.insert_history(DbHistory {
+            id: None,
+            time: 42,
+            is_finished: false,
+            update_time: Some(time::OffsetDateTime::now_utc().into()),
+            metadata_id: s1e1.metadata_id,
+        })
+        .await?;
+        db.insert_content_genre(saved.metadata_id, 18).await?;
+
+        let corrected = ShowTreeBuilder::new(2).season(1, 1..=2);
+        let corrected_meta = corrected.show_key().show_metadata();
+        let api = ShowMetadataApi::new_test(MockProvider::new([corrected], []), db);
+        let written = api
+            .rematch_show(saved.content_id, &corrected_meta.metadata_id)
+            .await?;
+        written.commit(1).await?;
+
+        let show_title: String =
+            sqlx::query_scalar!("SELECT title FROM metadata WHERE id = ?", saved.metadata_id)
+                .fetch_one(&db.pool)
+                .await?;
+        assert_eq!(show_title, corrected_meta.title);
+        let show_ext = sqlx::query!(
+            r#"SELECT external_id, is_prime FROM external_ids WHERE metadata_id = ?"#,
+            saved.metadata_id
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        assert_eq!(show_ext.len(), 1);
+        assert_eq!(show_ext[0].external_id, corrected_meta.metadata_id);
+        assert_eq!(show_ext[0].is_prime, 1);
+
+        // Genres of the old match are gone
+        let genres: i64 = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM content_genres WHERE metadata_id = ?",
+            saved.metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert_eq!(genres, 0);
+
+        let episode_ext: String = sqlx::query_scalar!(
+            "SELECT external_id FROM external_ids WHERE metadata_id = ?",
+            s1e1.metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert_eq!(episode_ext, "show2S1E1");
+        let history_count: i64 = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM history WHERE metadata_id = ?",
+            s1e1.metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert_eq!(history_count, 1);
+        // Episode that the new show doesn't have is kept without ids of the old show
+        let episode_count: i64 = sqlx::query_scalar!("SELECT COUNT(*) FROM episodes")
+            .fetch_one(&db.pool)
+            .await?;
+        assert_eq!(episode_count, 3);
+        let stale_ids: i64 = sqlx::query_scalar!(
+            "SELECT COUNT(*) FROM external_ids WHERE metadata_id = ?",
+            s1e3.metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert_eq!(stale_ids, 0);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn refresh_single_season(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let saved = ShowTreeBuilder::new(1).season(1, 1..=2).save(db).await?;
+        let show_id = saved.key.show_metadata().metadata_id;
+        let (s1e1, s1e2) = (
+            *saved.episode(1, 1).expect("s1e1 was seeded"),
+            *saved.episode(1, 2).expect("s1e2 was seeded"),
+        );
+        sqlx::query!("UPDATE metadata SET title = 'Edited'")
+            .execute(&db.pool)
+            .await?;
+        let title = |metadata_id: i64| {
+            sqlx::query_scalar!("SELECT title FROM metadata WHERE id = ?", metadata_id)
+                .fetch_one(&db.pool)
+        };
+        let provider = MockProvider::new([ShowTreeBuilder::new(1).season(1, 1..=2)], []);
+        let api = ShowMetadataApi::new_test(provider, db);
+
+        api.refresh_season(saved.content_id, &show_id, 1, Some(2))
+            .await?
+            .commit(1)
+            .await?;
+        assert_eq!(title(s1e1.metadata_id).await?, "Edited");
+        assert_eq!(title(s1e2.metadata_id).await?, "Episode 2");
+
+        api.refresh_season(saved.content_id, &show_id, 1, None)
+            .await?
+            .commit(1)
+            .await?;
+        assert_eq!(title(s1e1.metadata_id).await?, "Episode 1");
+        // Show metadata is not touched
+        assert_eq!(title(saved.metadata_id).await?, "Edited");
+
+        let Err(err) = api
+            .refresh_season(saved.content_id, &show_id, 2, None)
+            .await
+        else {
+            panic!("season that is not in the library can't be refreshed");
+        };
+        assert_matches!(err.kind, AppErrorKind::NotFound);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rematch_show_to_existing_show(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let saved = ShowTreeBuilder::new(1).season(1, 1..=1).save(db).await?;
+        let other = ShowTreeBuilder::new(2).season(1, 1..=1);
+        other.save(db).await?;
+        let other_id = other.show_key().show_metadata().metadata_id;
+        let api = ShowMetadataApi::new_test(MockProvider::new([other], []), db);
+
+        let Err(err) = api.rematch_show(saved.content_id, &other_id).await else {
+            panic!("show must not be matched to another local show");
+        };
+        assert_matches!(err.kind, AppErrorKind::Duplicate);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn rematch_movie_in_place(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = leak_db(pool);
+        let (wrong, correct) = (MovieKey::new(1), MovieKey::new(2));
+        let api = MovieMetadataApi::new_test(MockProvider::new([], [wrong, correct]), db);
+        let pending = api
+            .get_or_insert_movie(&wrong.external_metadata().metadata_id)
+            .await?;
+        let local = pending.content;
+        pending.commit(1).await?;
+
+        let correct_meta = correct.external_metadata();
+        let pending = api
+            .rematch_movie(local.id, &correct_meta.metadata_id)
+            .await?;
+        assert_eq!(pending.content.metadata_id, local.metadata_id);
+        pending.commit(1).await?;
+
+        let title: String =
+            sqlx::query_scalar!("SELECT title FROM metadata WHERE id = ?", local.metadata_id)
+                .fetch_one(&db.pool)
+                .await?;
+        assert_eq!(title, correct_meta.title);
+        let external_ids: Vec<String> = sqlx::query_scalar!(
+            "SELECT external_id FROM external_ids WHERE metadata_id = ?",
+            local.metadata_id
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        assert_eq!(external_ids, vec![correct_meta.metadata_id]);
+        let movie_count: i64 = sqlx::query_scalar!("SELECT COUNT(*) FROM movies")
+            .fetch_one(&db.pool)
+            .await?;
+        assert_eq!(movie_count, 1);
+        Ok(())
+    }
+}
+
+#[sqlx::test]
+async fn refresh_prime_provider(pool: SqlitePool) -> anyhow::Result<()> {
+    use crate::{db::DbExternalId, metadata::MetadataProvider};
+
+    use super::refresh::prime_provider;
+
+    let db = leak_db(pool);
+    let saved = provider_mock::ShowTreeBuilder::new(1)
+        .season(1, 1..=1)
+        .save(db)
+        .await?;
+    db.insert_external_id(DbExternalId {
+        external_provider: MetadataProvider::Tvdb,
+        external_id: "tvdb1".into(),
+        metadata_id: Some(saved.metadata_id),
+        is_prime: 1,
+        ..Default::default()
+    })
+    .await?;
+
+    let (provider, id) = prime_provider(db, saved.metadata_id, Some).await?;
+    assert_eq!(provider, MetadataProvider::Tvdb);
+    assert_eq!(id, "tvdb1");
+
+    // Falls back to the other ids when the prime provider is unavailable
+    let (provider, id) = prime_provider(db, saved.metadata_id, |p| {
+        (p != MetadataProvider::Tvdb).then_some(p)
+    })
+    .await?;
+    assert_eq!(provider, MetadataProvider::Tmdb);
+    assert_eq!(id, saved.key.show_metadata().metadata_id);
+
+    assert!(
+        prime_provider(db, saved.metadata_id, |_| None::<()>)
+            .await
+            .is_err()
+    );
+    Ok(())
+}
+
+#[sqlx::test]
+async fn rematch_keeps_locked_fields(pool: SqlitePool) -> anyhow::Result<()> {
+    use crate::metadata::{MetadataLocks, metadata_api::movie::MovieMetadataApi};
+    use provider_mock::{MockProvider, MovieKey};
+
+    let db = leak_db(pool);
+    let (wrong, correct) = (MovieKey::new(1), MovieKey::new(2));
+    let api = MovieMetadataApi::new_test(MockProvider::new([], [wrong, correct]), db);
+    let pending = api
+        .get_or_insert_movie(&wrong.external_metadata().metadata_id)
+        .await?;
+    let local = pending.content;
+    pending.commit(1).await?;
+    db.add_metadata_locks(
+        local.metadata_id,
+        MetadataLocks {
+            title: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    let correct_meta = correct.external_metadata();
+    api.rematch_movie(local.id, &correct_meta.metadata_id)
+        .await?
+        .commit(1)
+        .await?;
+
+    let row = sqlx::query!(
+        "SELECT title, plot FROM metadata WHERE id = ?",
+        local.metadata_id
+    )
+    .fetch_one(&db.pool)
+    .await?;
+    assert_eq!(row.title, wrong.external_metadata().title);
+    assert_eq!(row.plot, correct_meta.plot);
+
+    // Locks are merged with the existing ones
+    db.add_metadata_locks(
+        local.metadata_id,
+        MetadataLocks {
+            plot: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+    let locks = db.get_metadata_locks(local.metadata_id).await?;
+    assert!(locks.title && locks.plot && !locks.poster);
+    Ok(())
+}
+
+#[sqlx::test]
+async fn reset_unlocks_with_the_refresh(pool: SqlitePool) -> anyhow::Result<()> {
+    use crate::metadata::{MetadataLocks, metadata_api::movie::MovieMetadataApi};
+    use provider_mock::{MockProvider, MovieKey};
+
+    let db = leak_db(pool);
+    let key = MovieKey::new(1);
+    let api = MovieMetadataApi::new_test(MockProvider::new([], [key]), db).with_unlock();
+    let metadata_id = &key.external_metadata().metadata_id;
+    let pending = api.get_or_insert_movie(metadata_id).await?;
+    let local = pending.content;
+    pending.commit(1).await?;
+    sqlx::query!(
+        "UPDATE metadata SET title = 'Edited' WHERE id = ?",
+        local.metadata_id
+    )
+    .execute(&db.pool)
+    .await?;
+    db.add_metadata_locks(
+        local.metadata_id,
+        MetadataLocks {
+            title: true,
+            ..Default::default()
+        },
+    )
+    .await?;
+
+    // Dropped refresh keeps the locks
+    drop(api.rematch_movie(local.id, metadata_id).await?);
+    assert!(db.get_metadata_locks(local.metadata_id).await?.title);
+
+    api.rematch_movie(local.id, metadata_id)
+        .await?
+        .commit(1)
+        .await?;
+    let title = sqlx::query_scalar!("SELECT title FROM metadata WHERE id = ?", local.metadata_id)
+        .fetch_one(&db.pool)
+        .await?;
+    assert_eq!(title, key.external_metadata().title);
+    assert!(!db.get_metadata_locks(local.metadata_id).await?.title);
+    Ok(())
+}
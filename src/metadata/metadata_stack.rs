@@ -1,10 +1,14 @@
-use std::{sync::Mutex, time::Duration};
+use std::{
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
 
 use anyhow::Context;
 use serde::{Serialize, ser::SerializeStruct};
 
 use crate::{
     config,
+    db::Db,
     torrent_index::{
         TorrentIndex, TorrentIndexIdentifier, TorrentMetadata, nyaa::NyaaApi,
         rutracker::ProvodRuTrackerAdapter, tpb::TpbApi,
@@ -12,14 +16,25 @@ use crate::{
 };
 
 use super::{
-    DiscoverMetadataProvider, EpisodeMetadata, ExternalIdMetadata, FetchParams, MetadataProvider,
-    MetadataSearchResult, MovieMetadata, MovieMetadataProvider, ParentMediaType, SeasonMetadata,
-    ShowMetadata, ShowMetadataProvider, tmdb_api::TmdbApi, tvdb_api::TvdbApi,
+    DiscoverMetadataProvider, EpisodeMetadata, ExternalIdMetadata, FetchParams, Language,
+    MetadataProvider, MetadataSearchResult, MovieMetadata, MovieMetadataProvider, ParentMediaType,
+    PersonDetailsMetadata, PersonMetadataProvider, SeasonMetadata, ShowMetadata,
+    ShowMetadataProvider,
+    anilist_api::AnilistApi,
+    imdb::{self, ImdbProvider},
+    nfo::NfoProvider,
+    response_cache::ResponseCache,
+    tmdb_api::TmdbApi,
+    tvdb_api::TvdbApi,
 };
 
 pub struct MetadataProvidersStack {
     pub tmdb: Option<&'static TmdbApi>,
     pub tvdb: Option<&'static TvdbApi>,
+    pub nfo: Option<&'static NfoProvider>,
+    /// Registered once the IMDb datasets are imported
+    pub imdb: OnceLock<&'static ImdbProvider>,
+    pub anilist: Option<&'static AnilistApi>,
     pub tpb: Option<&'static TpbApi>,
     pub nyaa: Option<&'static NyaaApi>,
     pub rutracker: Option<&'static ProvodRuTrackerAdapter>,
@@ -75,6 +90,9 @@ impl MetadataProvidersStack {
         Self {
             tvdb: None,
             tmdb: None,
+            nfo: None,
+            imdb: OnceLock::new(),
+            anilist: None,
             tpb: None,
             nyaa: None,
             rutracker: None,
@@ -86,9 +104,10 @@ impl MetadataProvidersStack {
     }
 
     #[tracing::instrument(skip_all)]
-    pub fn setup_providers(&mut self, http_client: &reqwest::Client) {
+    pub fn setup_providers(&mut self, http_client: &reqwest::Client, db: &Db) {
+        let cache = ResponseCache::new(db.clone());
         let (config::TmdbKey(tmdb_key), config::TvdbKey(tvdb_key)) = config::CONFIG.get_values();
-        match TmdbApi::new(http_client.clone(), tmdb_key) {
+        match TmdbApi::new(http_client.clone(), tmdb_key, cache.clone()) {
             Ok(tmdb_api) => {
                 let tmdb_api: &'static _ = Box::leak(Box::new(tmdb_api));
                 self.tmdb = Some(tmdb_api);
@@ -112,21 +131,47 @@ impl MetadataProvidersStack {
             Err(e) => tracing::warn!("Failed to initialize RuTracker api: {e}"),
         }
 
-        match TvdbApi::new(http_client.clone(), tvdb_key.as_deref()) {
+        match TvdbApi::new(http_client.clone(), tvdb_key.as_deref(), cache.clone()) {
             Ok(tvdb_api) => {
                 let tvdb_api: &'static _ = Box::leak(Box::new(tvdb_api));
                 self.tvdb = Some(tvdb_api);
             }
             Err(e) => tracing::warn!("Failed to initialize TVDB api: {e}"),
         }
+
+        let nfo_provider = Box::leak(Box::new(NfoProvider::new(db.clone())));
+        self.nfo = Some(nfo_provider);
+
+        let anilist_api = Box::leak(Box::new(AnilistApi::new(http_client.clone(), cache)));
+        self.anilist = Some(anilist_api);
         self.apply_config_order();
     }
 
+    /// Register IMDb provider if its datasets are imported, providers order is reapplied
+    /// to include it
+    #[tracing::instrument(skip_all)]
+    pub async fn register_imdb(&self, db: &Db) {
+        if self.imdb.get().is_some() {
+            return;
+        }
+        match imdb::is_imported(db).await {
+            Ok(true) => {
+                let imdb_provider = Box::leak(Box::new(ImdbProvider::new(db.clone())));
+                if self.imdb.set(imdb_provider).is_ok() {
+                    self.apply_config_order();
+                }
+            }
+            Ok(false) => tracing::debug!("IMDb datasets are not imported"),
+            Err(e) => tracing::warn!("Failed to check IMDb imports: {e}"),
+        }
+    }
+
+    /// Reorder providers stacks using the configured orders, each stack reads its own setting
     #[tracing::instrument(skip_all)]
     pub fn apply_config_order(&self) {
         let (
             config::DiscoverProvidersOrder(discover_order),
-            config::DiscoverProvidersOrder(show_order),
+            config::ShowProvidersOrder(show_order),
             config::MovieProvidersOrder(movie_order),
             config::TorrentIndexesOrder(torrent_order),
         ) = config::CONFIG.get_values();
@@ -140,7 +185,7 @@ impl MetadataProvidersStack {
     pub async fn search_movie(&self, query: &str) -> anyhow::Result<Vec<MovieMetadata>> {
         let movie_providers = { self.movie_providers_stack.lock().unwrap().clone() };
         let lang: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: lang.0 };
+        let fetch_params = FetchParams::new(lang.0);
         let mut out = Vec::new();
         let handles: Vec<_> = movie_providers
             .into_iter()
@@ -163,7 +208,7 @@ impl MetadataProvidersStack {
         let show_providers = { self.show_providers_stack.lock().unwrap().clone() };
         let mut out = Vec::new();
         let lang: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: lang.0 };
+        let fetch_params = FetchParams::new(lang.0);
         let handles: Vec<_> = show_providers
             .into_iter()
             .map(|p| {
@@ -181,11 +226,16 @@ impl MetadataProvidersStack {
     }
 
     #[tracing::instrument(level = "debug", skip(self))]
-    pub async fn multi_search(&self, query: &str) -> anyhow::Result<Vec<MetadataSearchResult>> {
+    pub async fn multi_search(
+        &self,
+        query: &str,
+        lang: Option<Language>,
+    ) -> anyhow::Result<Vec<MetadataSearchResult>> {
         let discover_providers = { self.discover_providers_stack.lock().unwrap().clone() };
         let mut out = Vec::with_capacity(discover_providers.len());
-        let lang: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: lang.0 };
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
         let handles: Vec<_> = discover_providers
             .into_iter()
             .map(|p| {
@@ -207,6 +257,7 @@ impl MetadataProvidersStack {
         &self,
         movie_id: &str,
         provider: MetadataProvider,
+        lang: Option<Language>,
     ) -> crate::Result<MovieMetadata> {
         let movie_providers = { self.movie_providers_stack.lock().unwrap().clone() };
         let provider = movie_providers
@@ -214,25 +265,44 @@ impl MetadataProvidersStack {
             .find(|p| p.provider_identifier() == provider)
             .context("provider is not supported")?;
 
-        let language: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: language.0 };
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
 
         provider.movie(movie_id, fetch_params).await
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_person(
+        &self,
+        person_id: &str,
+        provider: MetadataProvider,
+        lang: Option<Language>,
+    ) -> crate::Result<PersonDetailsMetadata> {
+        let provider = self
+            .person_provider(provider)
+            .context("provider is not supported")?;
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
+        provider.person(person_id, fetch_params).await
+    }
+
     #[tracing::instrument(level = "debug", skip(self))]
     pub async fn get_show(
         &self,
         show_id: &str,
         provider: MetadataProvider,
+        lang: Option<Language>,
     ) -> crate::Result<ShowMetadata> {
         let show_providers = { self.show_providers_stack.lock().unwrap().clone() };
         let provider = show_providers
             .into_iter()
             .find(|p| p.provider_identifier() == provider)
             .context("provider is not supported")?;
-        let language: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: language.0 };
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
         provider.show(show_id, fetch_params).await
     }
 
@@ -242,14 +312,16 @@ impl MetadataProvidersStack {
         show_id: &str,
         season: usize,
         provider: MetadataProvider,
+        lang: Option<Language>,
     ) -> crate::Result<SeasonMetadata> {
         let show_providers = { self.show_providers_stack.lock().unwrap().clone() };
         let provider = show_providers
             .into_iter()
             .find(|p| p.provider_identifier() == provider)
             .context("provider is not supported")?;
-        let language: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: language.0 };
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
         provider.season(show_id, season, fetch_params).await
     }
 
@@ -260,14 +332,16 @@ impl MetadataProvidersStack {
         season: usize,
         episode: usize,
         provider: MetadataProvider,
+        lang: Option<Language>,
     ) -> crate::Result<EpisodeMetadata> {
         let show_providers = { self.show_providers_stack.lock().unwrap().clone() };
         let provider = show_providers
             .into_iter()
             .find(|p| p.provider_identifier() == provider)
             .context("provider is not supported")?;
-        let language: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: language.0 };
+        let fetch_params = FetchParams::new(
+            lang.unwrap_or_else(|| config::CONFIG.get_value::<config::MetadataLanguage>().0),
+        );
         provider
             .episode(show_id, season, episode, fetch_params)
             .await
@@ -297,7 +371,7 @@ impl MetadataProvidersStack {
         let torrent_indexes = { self.torrent_indexes_stack.lock().unwrap().clone() };
         let mut out = Vec::new();
         let lang: config::MetadataLanguage = config::CONFIG.get_value();
-        let fetch_params = FetchParams { lang: lang.0 };
+        let fetch_params = FetchParams::new(lang.0);
         let handles: Vec<_> = torrent_indexes
             .into_iter()
             .map(|p| {
@@ -400,6 +474,34 @@ impl MetadataProvidersStack {
         self.show_providers_stack.lock().unwrap().clone()
     }
 
+    /// Movie providers in the given order, configured order when `None`
+    pub fn movie_providers_in(
+        &self,
+        order: Option<&[MetadataProvider]>,
+    ) -> Vec<&'static (dyn MovieMetadataProvider + Send + Sync)> {
+        match order {
+            Some(order) => order
+                .iter()
+                .filter_map(|p| self.movie_provider(*p))
+                .collect(),
+            None => self.movie_providers(),
+        }
+    }
+
+    /// Show providers in the given order, configured order when `None`
+    pub fn show_providers_in(
+        &self,
+        order: Option<&[MetadataProvider]>,
+    ) -> Vec<&'static (dyn ShowMetadataProvider + Send + Sync)> {
+        match order {
+            Some(order) => order
+                .iter()
+                .filter_map(|p| self.show_provider(*p))
+                .collect(),
+            None => self.show_providers(),
+        }
+    }
+
     pub fn torrent_indexes(&self) -> Vec<&'static (dyn TorrentIndex + Send + Sync)> {
         self.torrent_indexes_stack.lock().unwrap().clone()
     }
@@ -416,7 +518,13 @@ impl MetadataProvidersStack {
             MetadataProvider::Tvdb => self
                 .tvdb
                 .map(|p| p as &(dyn DiscoverMetadataProvider + Send + Sync)),
-            MetadataProvider::Imdb => None,
+            MetadataProvider::Imdb => self
+                .imdb
+                .get()
+                .map(|p| *p as &(dyn DiscoverMetadataProvider + Send + Sync)),
+            MetadataProvider::Nfo => None,
+            MetadataProvider::Anilist => None,
+            MetadataProvider::Mal => None,
         }
     }
 
@@ -432,7 +540,17 @@ impl MetadataProvidersStack {
             MetadataProvider::Tvdb => self
                 .tvdb
                 .map(|p| p as &(dyn MovieMetadataProvider + Send + Sync)),
-            MetadataProvider::Imdb => None,
+            MetadataProvider::Imdb => self
+                .imdb
+                .get()
+                .map(|p| *p as &(dyn MovieMetadataProvider + Send + Sync)),
+            MetadataProvider::Nfo => self
+                .nfo
+                .map(|p| p as &(dyn MovieMetadataProvider + Send + Sync)),
+            MetadataProvider::Anilist => self
+                .anilist
+                .map(|p| p as &(dyn MovieMetadataProvider + Send + Sync)),
+            MetadataProvider::Mal => None,
         }
     }
 
@@ -448,7 +566,37 @@ impl MetadataProvidersStack {
             MetadataProvider::Tvdb => self
                 .tvdb
                 .map(|p| p as &(dyn ShowMetadataProvider + Send + Sync)),
-            MetadataProvider::Imdb => None,
+            MetadataProvider::Imdb => self
+                .imdb
+                .get()
+                .map(|p| *p as &(dyn ShowMetadataProvider + Send + Sync)),
+            MetadataProvider::Nfo => self
+                .nfo
+                .map(|p| p as &(dyn ShowMetadataProvider + Send + Sync)),
+            MetadataProvider::Anilist => self
+                .anilist
+                .map(|p| p as &(dyn ShowMetadataProvider + Send + Sync)),
+            MetadataProvider::Mal => None,
+        }
+    }
+
+    pub fn person_provider(
+        &self,
+        provider: MetadataProvider,
+    ) -> Option<&'static (dyn PersonMetadataProvider + Send + Sync)> {
+        match provider {
+            MetadataProvider::Tmdb => self
+                .tmdb
+                .map(|p| p as &(dyn PersonMetadataProvider + Send + Sync)),
+            MetadataProvider::Imdb => self
+                .imdb
+                .get()
+                .map(|p| *p as &(dyn PersonMetadataProvider + Send + Sync)),
+            MetadataProvider::Local
+            | MetadataProvider::Tvdb
+            | MetadataProvider::Nfo
+            | MetadataProvider::Anilist
+            | MetadataProvider::Mal => None,
         }
     }
 
@@ -3,8 +3,14 @@ use std::{fmt::Display, num::NonZero, str::FromStr, time::Duration};
 use crate::db::{DbContentType, DbEpisode, DbMetadata, DbMovie, DbSeason, DbShow};
 use serde::{Deserialize, Serialize};
 
+/// AniList GraphQL API agent
+pub mod anilist_api;
+/// Offline provider of the IMDb datasets
+pub mod imdb;
 pub mod metadata_api;
 pub mod metadata_stack;
+/// Kodi style nfo sidecar files reader
+pub mod nfo;
 /// Fallback service for different metadata providers.
 ///
 /// Allows to access metadata providers and torrent indexes with using user authorization.
@@ -16,16 +22,23 @@ pub mod metadata_stack;
 pub mod provod_agent;
 /// Rate limited request client
 pub mod request_client;
+/// Persistent cache of provider responses
+pub mod response_cache;
 /// Tmdb API agent
 pub mod tmdb_api;
+/// Metadata in the additional languages
+pub mod translations;
 /// Tvdb API agent
 #[allow(unused)]
 pub mod tvdb_api;
 
 pub const METADATA_CACHE_SIZE: NonZero<usize> = NonZero::new(20).unwrap();
 
-#[derive(Debug, Clone, Copy, Default, utoipa::ToSchema, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(
+    Debug, Clone, Copy, Default, utoipa::ToSchema, Eq, PartialEq, Serialize, Deserialize, sqlx::Type,
+)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
 pub enum Language {
     #[default]
     En,
@@ -49,6 +62,23 @@ impl Display for Language {
     }
 }
 
+impl FromStr for Language {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> std::prelude::v1::Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Self::En),
+            "es" => Ok(Self::Es),
+            "de" => Ok(Self::De),
+            "fr" => Ok(Self::Fr),
+            "ru" => Ok(Self::Ru),
+            "ja" => Ok(Self::Ja),
+            "sr" => Ok(Self::Sr),
+            _ => Err(anyhow::anyhow!("{s} is not supported metadata language")),
+        }
+    }
+}
+
 impl Language {
     fn as_str(&self) -> &'static str {
         match self {
@@ -66,6 +96,44 @@ impl Language {
 #[derive(Debug, Default, Clone, Copy, serde::Serialize, utoipa::ToSchema)]
 pub struct FetchParams {
     pub lang: Language,
+    /// Providers without alternate orderings always use aired order
+    pub ordering: EpisodeOrdering,
+}
+
+impl FetchParams {
+    pub fn new(lang: Language) -> Self {
+        Self {
+            lang,
+            ordering: EpisodeOrdering::default(),
+        }
+    }
+}
+
+/// Order in which show seasons and episodes are numbered
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    utoipa::ToSchema,
+    sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum EpisodeOrdering {
+    /// Original air order
+    #[default]
+    Aired,
+    Dvd,
+    /// Single season with absolute episode numbers
+    Absolute,
+    /// Alternate (story) order
+    Story,
 }
 
 pub trait ProviderIdentifier {
@@ -96,6 +164,17 @@ pub trait MovieMetadataProvider: ProviderIdentifier {
         query: &str,
         fetch_params: FetchParams,
     ) -> crate::Result<Vec<MovieMetadata>>;
+
+    /// Query for movie collection with all its parts
+    async fn collection(
+        &self,
+        _collection_id: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<CollectionMetadata> {
+        Err(crate::AppError::not_found(
+            "Provider does not support collections",
+        ))
+    }
 }
 
 #[async_trait::async_trait]
@@ -115,6 +194,14 @@ impl<T: MovieMetadataProvider + Send + Sync + ?Sized> MovieMetadataProvider for
     ) -> crate::Result<Vec<MovieMetadata>> {
         (**self).movie_search(query, fetch_params).await
     }
+
+    async fn collection(
+        &self,
+        collection_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<CollectionMetadata> {
+        (**self).collection(collection_id, fetch_params).await
+    }
 }
 
 /// This trait must be implemented by all show metadata providers
@@ -149,6 +236,33 @@ pub trait ShowMetadataProvider: ProviderIdentifier {
         query: &str,
         fetch_params: FetchParams,
     ) -> crate::Result<Vec<ShowMetadata>>;
+
+    /// Absolute episode ordering of the show.
+    ///
+    /// By default episodes of all regular seasons are numbered one after another
+    async fn absolute_episodes(
+        &self,
+        show_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<Vec<AbsoluteEpisode>> {
+        let show = self.show(show_id, fetch_params).await?;
+        let mut seasons = show.seasons.unwrap_or_default();
+        seasons.sort_unstable();
+        let mut episodes = Vec::new();
+        // Specials are not part of absolute ordering
+        for season_number in seasons.into_iter().filter(|s| *s > 0) {
+            let mut season = self.season(show_id, season_number, fetch_params).await?;
+            season.episodes.sort_unstable_by_key(|e| e.number);
+            for episode in season.episodes {
+                episodes.push(AbsoluteEpisode {
+                    absolute_number: episodes.len() + 1,
+                    season: season_number,
+                    episode: episode.number,
+                });
+            }
+        }
+        Ok(episodes)
+    }
 }
 
 #[async_trait::async_trait]
@@ -185,6 +299,14 @@ impl<T: ShowMetadataProvider + Send + Sync + ?Sized> ShowMetadataProvider for &T
     ) -> crate::Result<Vec<ShowMetadata>> {
         (**self).show_search(query, fetch_params).await
     }
+
+    async fn absolute_episodes(
+        &self,
+        show_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<Vec<AbsoluteEpisode>> {
+        (**self).absolute_episodes(show_id, fetch_params).await
+    }
 }
 
 /// This trait must be implemented by all metadata providers with discovery capabilities
@@ -205,6 +327,17 @@ pub trait DiscoverMetadataProvider: ProviderIdentifier {
     ) -> crate::Result<Vec<ExternalIdMetadata>>;
 }
 
+/// This trait must be implemented by all metadata providers with person details
+#[async_trait::async_trait]
+pub trait PersonMetadataProvider: ProviderIdentifier {
+    /// Query for person details and works
+    async fn person(
+        &self,
+        person_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<PersonDetailsMetadata>;
+}
+
 // types
 
 #[derive(
@@ -228,6 +361,14 @@ pub enum MetadataProvider {
     Tmdb,
     Tvdb,
     Imdb,
+    /// Kodi style nfo files next to the library videos
+    Nfo,
+    /// AniList anime database, opt-in with the library providers order.
+    ///
+    /// Each season of the anime is a separate show
+    Anilist,
+    /// MyAnimeList, its ids are only known from the other providers
+    Mal,
 }
 
 impl MetadataProvider {
@@ -251,6 +392,9 @@ impl FromStr for MetadataProvider {
             "tmdb" => Ok(Self::Tmdb),
             "tvdb" => Ok(Self::Tvdb),
             "imdb" => Ok(Self::Imdb),
+            "nfo" => Ok(Self::Nfo),
+            "anilist" => Ok(Self::Anilist),
+            "mal" => Ok(Self::Mal),
             _ => Err(anyhow::anyhow!(
                 "{s} is not recognized as metadata provider"
             )),
@@ -265,13 +409,19 @@ impl Display for MetadataProvider {
             MetadataProvider::Tmdb => write!(f, "tmdb"),
             MetadataProvider::Tvdb => write!(f, "tvdb"),
             MetadataProvider::Imdb => write!(f, "imdb"),
+            MetadataProvider::Nfo => write!(f, "nfo"),
+            MetadataProvider::Anilist => write!(f, "anilist"),
+            MetadataProvider::Mal => write!(f, "mal"),
         }
     }
 }
 
 /// Leaf node type of the any content tree
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, utoipa::ToSchema)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
 #[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
 pub enum ParentMediaType {
     Movie,
     Show,
@@ -330,6 +480,44 @@ pub struct MovieMetadata {
     pub cast: Option<Vec<PersonMetadata>>,
     pub genres: Option<Vec<Genre>>,
     pub external_ids: Option<Vec<ExternalIdMetadata>>,
+    /// Collection (franchise) the movie belongs to
+    pub collection: Option<CollectionMetadata>,
+    pub ratings: Option<Vec<RatingMetadata>>,
+}
+
+/// Rating of the content from a single source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RatingMetadata {
+    /// Rating source, for example `imdb` or `themoviedb`
+    pub source: String,
+    pub value: f64,
+    /// Top of the rating scale, 10 when it is not known
+    pub max: Option<f64>,
+    pub votes: Option<u64>,
+    /// Rating that is preferred over the other sources
+    pub is_default: bool,
+}
+
+/// Movie collection (franchise) like all movies of a single series
+#[derive(Debug, Clone, Serialize, Deserialize, Default, utoipa::ToSchema)]
+pub struct CollectionMetadata {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub title: String,
+    pub plot: Option<String>,
+    pub poster: Option<String>,
+    pub backdrop: Option<String>,
+    /// Movies of the collection. Empty when only collection membership is known
+    pub parts: Vec<CollectionPart>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CollectionPart {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub title: String,
+    pub release_date: Option<String>,
+    pub poster: Option<String>,
 }
 
 /// The unified show data structure from any show provider
@@ -350,6 +538,7 @@ pub struct ShowMetadata {
     pub genres: Option<Vec<Genre>>,
     pub next_episode_air_date: Option<crate::OffsetDateTime>,
     pub external_ids: Option<Vec<ExternalIdMetadata>>,
+    pub ratings: Option<Vec<RatingMetadata>>,
 }
 
 /// The unified season data structure from any show provider
@@ -388,6 +577,7 @@ pub struct LocaleMetadata {
     pub original_language: String,
 }
 
+/// Credited person. Cast lists hold the crew members after the actors
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct PersonMetadata {
     pub metadata_id: String,
@@ -396,6 +586,54 @@ pub struct PersonMetadata {
     pub name: String,
     pub imdb_id: Option<String>,
     pub role: Option<RoleMetadata>,
+    /// Crew job of the person, `None` for the actors
+    pub job: Option<CrewJob>,
+}
+
+/// Crew jobs that are kept with the credits
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum CrewJob {
+    Director,
+    Writer,
+    Composer,
+}
+
+/// Person details from the metadata provider
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct PersonDetailsMetadata {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub name: String,
+    pub poster: Option<String>,
+    pub biography: Option<String>,
+    /// Birth date, or only the year when provider doesn't know the date
+    pub birthday: Option<String>,
+    /// Death date, or only the year when provider doesn't know the date
+    pub deathday: Option<String>,
+    pub place_of_birth: Option<String>,
+    /// Department the person is known for, for example `Acting` or `Directing`
+    pub known_for_department: Option<String>,
+    pub imdb_id: Option<String>,
+    /// Movies and shows of the person
+    pub credits: Vec<CreditMetadata>,
+}
+
+/// Movie or show the person is credited in
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct CreditMetadata {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub content_type: ParentMediaType,
+    pub title: String,
+    pub release_date: Option<String>,
+    pub poster: Option<String>,
+    pub character: Option<String>,
+    /// Crew job of the person, `None` for the acting credits
+    pub job: Option<CrewJob>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
@@ -404,6 +642,29 @@ pub struct RoleMetadata {
     pub poster: Option<String>,
 }
 
+/// Fields of the local metadata that refreshes and re-matches leave untouched.
+///
+/// Manual edits lock the fields they change
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(default)]
+pub struct MetadataLocks {
+    pub title: bool,
+    pub plot: bool,
+    pub poster: bool,
+    pub backdrop: bool,
+    pub release_date: bool,
+    pub genres: bool,
+    pub cast: bool,
+}
+
+/// Position of the episode in the show's absolute ordering
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct AbsoluteEpisode {
+    pub absolute_number: usize,
+    pub season: usize,
+    pub episode: usize,
+}
+
 #[derive(Debug, Clone, Eq, Hash, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct ExternalIdMetadata {
     pub provider: MetadataProvider,
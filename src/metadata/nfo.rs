@@ -0,0 +1,1125 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use quick_xml::{
+    Reader, Writer,
+    escape::resolve_predefined_entity,
+    events::{BytesDecl, BytesStart, BytesText, Event},
+};
+use tokio::sync::Mutex;
+
+use crate::{AppError, MediaDuration, db::Db, library::libraries::LibraryFolders, parser};
+
+use super::{
+    EpisodeMetadata, ExternalIdMetadata, FetchParams, Genre, MetadataProvider, MovieMetadata,
+    MovieMetadataProvider, PersonMetadata, ProviderIdentifier, RatingMetadata, RoleMetadata,
+    SeasonMetadata, ShowMetadata, ShowMetadataProvider,
+};
+
+/// Comment that marks nfo files written by the server, files without it are never overwritten
+pub const EXPORT_MARKER: &str = " Written by media-server ";
+
+/// How long the library nfo index stays valid before the next lookup walks library folders again.
+///
+/// Library scans invalidate the index, so it is only rebuilt once per scan
+const INDEX_TTL: Duration = Duration::from_secs(30 * 60);
+
+/// Metadata provider that reads Kodi style `.nfo` files placed next to the library videos.
+///
+/// Movies are identified by the nfo file path, shows by the directory containing `tvshow.nfo`.
+#[derive(Debug)]
+pub struct NfoProvider {
+    db: Db,
+    index: Mutex<Option<(Instant, Arc<NfoIndex>)>>,
+}
+
+/// Nfo files found in the library folders
+#[derive(Debug, Default)]
+struct NfoIndex {
+    /// Lowercase movie title -> nfo path
+    movies: HashMap<String, PathBuf>,
+    /// Lowercase show title -> show directory
+    shows: HashMap<String, PathBuf>,
+    /// (show directory, season) -> `season.nfo` path
+    seasons: HashMap<(PathBuf, usize), PathBuf>,
+    /// (show directory, season, episode) -> episode nfo path
+    episodes: HashMap<(PathBuf, usize, usize), PathBuf>,
+}
+
+impl NfoIndex {
+    async fn build(db: &Db) -> Self {
+        let LibraryFolders {
+            shows: show_folders,
+            movies: movie_folders,
+            ignore,
+        } = LibraryFolders::load(db).await;
+        let movies = parser::walk_movie_dirs(movie_folders, &ignore).await;
+        let build = tokio::task::spawn_blocking(move || {
+            let mut index = Self::default();
+            for (video, identifier) in movies {
+                if let Some(nfo) = movie_nfo_path(video.path()) {
+                    index
+                        .movies
+                        .entry(identifier.title.to_lowercase())
+                        .or_insert(nfo);
+                }
+            }
+
+            for (video, identifier) in parser::walk_show_dirs(show_folders.clone(), &ignore) {
+                let Some(show_dir) = show_dir(video.path(), &show_folders) else {
+                    continue;
+                };
+                if !index.shows.values().any(|dir| *dir == show_dir) {
+                    let nfo_title = std::fs::read_to_string(show_dir.join("tvshow.nfo"))
+                        .ok()
+                        .and_then(|xml| NfoContent::parse(&xml, "tvshow").ok())
+                        .and_then(|show| show.title);
+                    if let Some(title) = nfo_title {
+                        index
+                            .shows
+                            .entry(title.to_lowercase())
+                            .or_insert(show_dir.clone());
+                    }
+                }
+                index
+                    .shows
+                    .entry(identifier.title.to_lowercase())
+                    .or_insert(show_dir.clone());
+
+                let season = identifier.season as usize;
+                let episode = identifier.episode as usize;
+                if let Some(season_dir) = video.path().parent() {
+                    let season_nfo = season_dir.join("season.nfo");
+                    if season_dir != show_dir && season_nfo.is_file() {
+                        index
+                            .seasons
+                            .entry((show_dir.clone(), season))
+                            .or_insert(season_nfo);
+                    }
+                }
+                let episode_nfo = video.path().with_extension("nfo");
+                if episode_nfo.is_file() {
+                    index
+                        .episodes
+                        .entry((show_dir, season, episode))
+                        .or_insert(episode_nfo);
+                }
+            }
+            index
+        });
+        build.await.unwrap_or_default()
+    }
+
+    fn has_movie(&self, nfo: &Path) -> bool {
+        self.movies.values().any(|p| p == nfo)
+    }
+
+    fn has_show(&self, show_dir: &Path) -> bool {
+        self.shows.values().any(|p| p == show_dir)
+    }
+}
+
+/// `<video>.nfo` or `movie.nfo` in the video directory
+fn movie_nfo_path(video: &Path) -> Option<PathBuf> {
+    let sidecar = video.with_extension("nfo");
+    if sidecar.is_file() {
+        return Some(sidecar);
+    }
+    let movie_nfo = video.parent()?.join("movie.nfo");
+    movie_nfo.is_file().then_some(movie_nfo)
+}
+
+/// Closest directory with `tvshow.nfo` that is located inside one of the show folders
+fn show_dir(video: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let root = roots.iter().find(|root| video.starts_with(root))?;
+    video
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| dir.starts_with(root))
+        .find(|dir| dir.join("tvshow.nfo").is_file())
+        .map(Path::to_path_buf)
+}
+
+impl NfoProvider {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            index: Mutex::default(),
+        }
+    }
+
+    /// Drop the index, next lookup walks library folders again
+    pub async fn invalidate(&self) {
+        *self.index.lock().await = None;
+    }
+
+    async fn index(&self) -> Arc<NfoIndex> {
+        let mut index = self.index.lock().await;
+        if let Some((built_at, index)) = index.as_ref()
+            && built_at.elapsed() < INDEX_TTL
+        {
+            return index.clone();
+        }
+        let fresh = Arc::new(NfoIndex::build(&self.db).await);
+        *index = Some((Instant::now(), fresh.clone()));
+        fresh
+    }
+
+    async fn read_movie(&self, nfo: &Path) -> crate::Result<MovieMetadata> {
+        let xml = tokio::fs::read_to_string(nfo).await?;
+        let movie = NfoContent::parse(&xml, "movie")?;
+        Ok(movie.into_movie_metadata(nfo.to_string_lossy().to_string()))
+    }
+
+    async fn read_show(&self, show_dir: &Path, index: &NfoIndex) -> crate::Result<ShowMetadata> {
+        let xml = tokio::fs::read_to_string(show_dir.join("tvshow.nfo")).await?;
+        let show = NfoContent::parse(&xml, "tvshow")?;
+        let mut seasons: Vec<_> = index
+            .episodes
+            .keys()
+            .filter(|(dir, ..)| dir == show_dir)
+            .map(|(_, season, _)| *season)
+            .collect();
+        seasons.sort_unstable();
+        seasons.dedup();
+        Ok(show.into_show_metadata(show_dir.to_string_lossy().to_string(), seasons))
+    }
+}
+
+impl ProviderIdentifier for NfoProvider {
+    fn provider_identifier(&self) -> MetadataProvider {
+        MetadataProvider::Nfo
+    }
+}
+
+#[async_trait::async_trait]
+impl MovieMetadataProvider for NfoProvider {
+    async fn movie(
+        &self,
+        movie_metadata_id: &str,
+        _params: FetchParams,
+    ) -> crate::Result<MovieMetadata> {
+        let nfo = PathBuf::from(movie_metadata_id);
+        if !self.index().await.has_movie(&nfo) {
+            return Err(AppError::not_found("Movie nfo is not found"));
+        }
+        self.read_movie(&nfo).await
+    }
+
+    async fn movie_search(
+        &self,
+        query: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<Vec<MovieMetadata>> {
+        let index = self.index().await;
+        let Some(nfo) = index.movies.get(&query.to_lowercase()) else {
+            return Ok(Vec::new());
+        };
+        Ok(vec![self.read_movie(nfo).await?])
+    }
+}
+
+#[async_trait::async_trait]
+impl ShowMetadataProvider for NfoProvider {
+    async fn show(&self, show_id: &str, _fetch_params: FetchParams) -> crate::Result<ShowMetadata> {
+        let show_dir = PathBuf::from(show_id);
+        let index = self.index().await;
+        if !index.has_show(&show_dir) {
+            return Err(AppError::not_found("Show nfo is not found"));
+        }
+        self.read_show(&show_dir, &index).await
+    }
+
+    async fn season(
+        &self,
+        show_id: &str,
+        season: usize,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<SeasonMetadata> {
+        let show_dir = PathBuf::from(show_id);
+        let index = self.index().await;
+        let season_nfo = index.seasons.get(&(show_dir.clone(), season));
+        let mut episode_nfos: Vec<_> = index
+            .episodes
+            .iter()
+            .filter(|((dir, s, _), _)| *dir == show_dir && *s == season)
+            .map(|((.., episode), nfo)| (*episode, nfo))
+            .collect();
+        if season_nfo.is_none() && episode_nfos.is_empty() {
+            return Err(AppError::not_found("Season nfo is not found"));
+        }
+        episode_nfos.sort_unstable_by_key(|(episode, _)| *episode);
+
+        let mut episodes = Vec::with_capacity(episode_nfos.len());
+        for (episode, nfo) in episode_nfos {
+            match read_episode(nfo, season, episode).await {
+                Ok(metadata) => episodes.push(metadata),
+                Err(e) => tracing::warn!(path = %nfo.display(), "Failed to read episode nfo: {e}"),
+            }
+        }
+
+        let season_content = match season_nfo {
+            Some(nfo) => {
+                let xml = tokio::fs::read_to_string(nfo).await?;
+                NfoContent::parse(&xml, "season")?
+            }
+            None => NfoContent::default(),
+        };
+        Ok(SeasonMetadata {
+            metadata_id: season_nfo
+                .map(|nfo| nfo.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("{show_id}:{season}")),
+            metadata_provider: MetadataProvider::Nfo,
+            release_date: season_content.release_date(),
+            title: season_content.title,
+            episodes,
+            plot: season_content.plot,
+            poster: season_content.poster,
+            number: season,
+            cast: None,
+        })
+    }
+
+    async fn episode(
+        &self,
+        show_id: &str,
+        season: usize,
+        episode: usize,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<EpisodeMetadata> {
+        let index = self.index().await;
+        let nfo = index
+            .episodes
+            .get(&(PathBuf::from(show_id), season, episode))
+            .ok_or(AppError::not_found("Episode nfo is not found"))?;
+        read_episode(nfo, season, episode).await
+    }
+
+    async fn show_search(
+        &self,
+        query: &str,
+        _fetch_params: FetchParams,
+    ) -> crate::Result<Vec<ShowMetadata>> {
+        let index = self.index().await;
+        let Some(show_dir) = index.shows.get(&query.to_lowercase()) else {
+            return Ok(Vec::new());
+        };
+        Ok(vec![self.read_show(show_dir, &index).await?])
+    }
+}
+
+async fn read_episode(nfo: &Path, season: usize, episode: usize) -> crate::Result<EpisodeMetadata> {
+    let xml = tokio::fs::read_to_string(nfo).await?;
+    let episodes = NfoEpisode::parse_all(&xml)?;
+    // Multi episode files contain one <episodedetails> per episode, missing numbers are taken
+    // from the file name
+    let nfo_episode = episodes
+        .into_iter()
+        .find(|e| e.season.is_none_or(|s| s == season) && e.episode.is_none_or(|e| e == episode))
+        .ok_or(AppError::not_found(
+            "Nfo does not contain the episode details",
+        ))?;
+    Ok(EpisodeMetadata {
+        metadata_id: nfo.to_string_lossy().to_string(),
+        metadata_provider: MetadataProvider::Nfo,
+        release_date: nfo_episode.aired,
+        number: episode,
+        title: nfo_episode
+            .title
+            .unwrap_or_else(|| format!("Episode {episode}")),
+        plot: nfo_episode.plot,
+        season_number: season,
+        runtime: nfo_episode.runtime.map(runtime_duration),
+        poster: nfo_episode.thumb,
+        cast: (!nfo_episode.cast.is_empty()).then(|| people(nfo_episode.cast)),
+    })
+}
+
+/// Parsed `<movie>`, `<tvshow>` or `<season>` document
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NfoContent {
+    pub title: Option<String>,
+    pub plot: Option<String>,
+    pub year: Option<u16>,
+    pub premiered: Option<String>,
+    /// Season number of the `<season>` document
+    pub season_number: Option<usize>,
+    /// Runtime in minutes
+    pub runtime: Option<u64>,
+    pub genres: Vec<Genre>,
+    pub cast: Vec<NfoActor>,
+    pub ratings: Vec<NfoRating>,
+    pub unique_ids: Vec<ExternalIdMetadata>,
+    pub poster: Option<String>,
+    pub fanart: Option<String>,
+}
+
+/// Parsed `<episodedetails>` document
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NfoEpisode {
+    pub title: Option<String>,
+    pub season: Option<usize>,
+    pub episode: Option<usize>,
+    pub plot: Option<String>,
+    pub aired: Option<String>,
+    /// Runtime in minutes
+    pub runtime: Option<u64>,
+    pub thumb: Option<String>,
+    pub cast: Vec<NfoActor>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NfoActor {
+    pub name: String,
+    pub role: Option<String>,
+    pub thumb: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NfoRating {
+    /// Rating source, for example `imdb` or `themoviedb`
+    pub name: String,
+    pub value: f64,
+    pub max: Option<f64>,
+    pub votes: Option<u64>,
+    pub is_default: bool,
+}
+
+impl From<NfoRating> for RatingMetadata {
+    fn from(rating: NfoRating) -> Self {
+        Self {
+            source: rating.name,
+            value: rating.value,
+            max: rating.max,
+            votes: rating.votes,
+            is_default: rating.is_default,
+        }
+    }
+}
+
+impl From<RatingMetadata> for NfoRating {
+    fn from(rating: RatingMetadata) -> Self {
+        Self {
+            name: rating.source,
+            value: rating.value,
+            max: rating.max,
+            votes: rating.votes,
+            is_default: rating.is_default,
+        }
+    }
+}
+
+impl NfoContent {
+    /// Parse nfo document with the given root element name
+    pub fn parse(xml: &str, root: &str) -> anyhow::Result<Self> {
+        let elements = XmlElement::parse_document(xml)?;
+        let root = elements
+            .iter()
+            .find(|e| e.name == root)
+            .with_context(|| format!("nfo does not contain <{root}> element"))?;
+        let is_show = root.name == "tvshow";
+
+        let mut unique_ids: Vec<ExternalIdMetadata> = Vec::new();
+        let mut push_id = |provider: MetadataProvider, id: &str| {
+            if !unique_ids.iter().any(|e| e.provider == provider) {
+                unique_ids.push(ExternalIdMetadata {
+                    provider,
+                    id: id.to_owned(),
+                });
+            }
+        };
+        for unique_id in root.children_named("uniqueid") {
+            let provider = match unique_id
+                .attribute("type")
+                .map(str::to_lowercase)
+                .as_deref()
+            {
+                Some("tmdb") => MetadataProvider::Tmdb,
+                Some("imdb") => MetadataProvider::Imdb,
+                Some("tvdb") => MetadataProvider::Tvdb,
+                Some("anilist") => MetadataProvider::Anilist,
+                Some("mal") => MetadataProvider::Mal,
+                _ => continue,
+            };
+            if let Some(id) = unique_id.value() {
+                push_id(provider, id);
+            }
+        }
+        // Legacy id tags
+        for (tag, provider) in [
+            ("tmdbid", MetadataProvider::Tmdb),
+            ("imdbid", MetadataProvider::Imdb),
+            ("tvdbid", MetadataProvider::Tvdb),
+        ] {
+            if let Some(id) = root.child_value(tag) {
+                push_id(provider, id);
+            }
+        }
+        if let Some(id) = root.child_value("id") {
+            if id.starts_with("tt") {
+                push_id(MetadataProvider::Imdb, id);
+            } else if is_show && id.chars().all(|c| c.is_ascii_digit()) {
+                push_id(MetadataProvider::Tvdb, id);
+            }
+        }
+
+        let poster = root
+            .children_named("thumb")
+            .filter(|t| t.attribute("type").is_none_or(|t| t != "season"))
+            .filter(|t| t.attribute("aspect").is_none_or(|a| a == "poster"))
+            .find_map(|t| t.value().and_then(remote_url));
+        let fanart = root
+            .child("fanart")
+            .into_iter()
+            .flat_map(|f| f.children_named("thumb"))
+            .find_map(|t| t.value().and_then(remote_url));
+
+        Ok(Self {
+            title: root.child_value("title").map(String::from),
+            plot: root
+                .child_value("plot")
+                .or_else(|| root.child_value("outline"))
+                .map(String::from),
+            year: root.child_value("year").and_then(|y| y.parse().ok()),
+            premiered: root
+                .child_value("premiered")
+                .or_else(|| root.child_value("releasedate"))
+                .map(String::from),
+            season_number: root
+                .child_value("seasonnumber")
+                .and_then(|s| s.parse().ok()),
+            runtime: root.child_value("runtime").and_then(|r| r.parse().ok()),
+            genres: root
+                .children_named("genre")
+                .filter_map(XmlElement::value)
+                .flat_map(|g| g.split('/'))
+                .filter_map(genre)
+                .collect(),
+            cast: actors(root),
+            ratings: ratings(root),
+            unique_ids,
+            poster,
+            fanart,
+        })
+    }
+
+    /// Serialize into nfo document with the given root element name
+    pub fn to_xml(&self, root: &str) -> String {
+        write_document(|writer| {
+            writer.create_element(root).write_inner_content(|w| {
+                write_value(w, "title", self.title.as_deref())?;
+                write_value(w, "plot", self.plot.as_deref())?;
+                write_value(w, "year", self.year)?;
+                write_value(w, "premiered", self.premiered.as_deref())?;
+                write_value(w, "seasonnumber", self.season_number)?;
+                write_value(w, "runtime", self.runtime)?;
+                for genre in &self.genres {
+                    write_value(w, "genre", Some(genre_name(*genre)))?;
+                }
+                if !self.ratings.is_empty() {
+                    w.create_element("ratings").write_inner_content(|w| {
+                        for rating in &self.ratings {
+                            let mut element = w
+                                .create_element("rating")
+                                .with_attribute(("name", rating.name.as_str()))
+                                .with_attribute(("default", bool_str(rating.is_default)));
+                            if let Some(max) = rating.max {
+                                element = element.with_attribute(("max", max.to_string().as_str()));
+                            }
+                            element.write_inner_content(|w| {
+                                write_value(w, "value", Some(rating.value))?;
+                                write_value(w, "votes", rating.votes)
+                            })?;
+                        }
+                        Ok(())
+                    })?;
+                }
+                for (i, unique_id) in self.unique_ids.iter().enumerate() {
+                    w.create_element("uniqueid")
+                        .with_attribute(("type", unique_id.provider.to_string().as_str()))
+                        .with_attribute(("default", bool_str(i == 0)))
+                        .write_text_content(BytesText::new(&unique_id.id))?;
+                }
+                if let Some(poster) = &self.poster {
+                    w.create_element("thumb")
+                        .with_attribute(("aspect", "poster"))
+                        .write_text_content(BytesText::new(poster))?;
+                }
+                if let Some(fanart) = &self.fanart {
+                    w.create_element("fanart")
+                        .write_inner_content(|w| write_value(w, "thumb", Some(fanart)))?;
+                }
+                write_actors(w, &self.cast)
+            })?;
+            Ok(())
+        })
+    }
+
+    fn release_date(&self) -> Option<String> {
+        self.premiered
+            .clone()
+            .or_else(|| self.year.map(|y| y.to_string()))
+    }
+
+    fn into_movie_metadata(self, metadata_id: String) -> MovieMetadata {
+        MovieMetadata {
+            metadata_id,
+            metadata_provider: MetadataProvider::Nfo,
+            release_date: self.release_date(),
+            poster: self.poster,
+            backdrop: self.fanart,
+            plot: self.plot,
+            runtime: self.runtime.map(runtime_duration),
+            title: self.title.unwrap_or_default(),
+            locale_metadata: None,
+            cast: (!self.cast.is_empty()).then(|| people(self.cast)),
+            genres: (!self.genres.is_empty()).then_some(self.genres),
+            external_ids: Some(self.unique_ids),
+            collection: None,
+            ratings: Some(self.ratings.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    fn into_show_metadata(self, metadata_id: String, seasons: Vec<usize>) -> ShowMetadata {
+        ShowMetadata {
+            metadata_id,
+            metadata_provider: MetadataProvider::Nfo,
+            release_date: self.release_date(),
+            poster: self.poster,
+            backdrop: self.fanart,
+            plot: self.plot,
+            seasons: (!seasons.is_empty()).then_some(seasons),
+            episodes_amount: None,
+            title: self.title.unwrap_or_default(),
+            locale_metadata: None,
+            cast: (!self.cast.is_empty()).then(|| people(self.cast)),
+            genres: (!self.genres.is_empty()).then_some(self.genres),
+            next_episode_air_date: None,
+            external_ids: Some(self.unique_ids),
+            ratings: Some(self.ratings.into_iter().map(Into::into).collect()),
+        }
+    }
+}
+
+impl NfoEpisode {
+    /// Parse all `<episodedetails>` elements of the episode nfo
+    pub fn parse_all(xml: &str) -> anyhow::Result<Vec<Self>> {
+        let elements = XmlElement::parse_document(xml)?;
+        Ok(elements
+            .iter()
+            .filter(|e| e.name == "episodedetails")
+            .map(|e| Self {
+                title: e.child_value("title").map(String::from),
+                season: e.child_value("season").and_then(|s| s.parse().ok()),
+                episode: e.child_value("episode").and_then(|s| s.parse().ok()),
+                plot: e.child_value("plot").map(String::from),
+                aired: e.child_value("aired").map(String::from),
+                runtime: e.child_value("runtime").and_then(|r| r.parse().ok()),
+                thumb: e
+                    .children_named("thumb")
+                    .find_map(|t| t.value().and_then(remote_url)),
+                cast: actors(e),
+            })
+            .collect())
+    }
+
+    /// Serialize into `<episodedetails>` document
+    pub fn to_xml(&self) -> String {
+        write_document(|writer| {
+            writer
+                .create_element("episodedetails")
+                .write_inner_content(|w| {
+                    write_value(w, "title", self.title.as_deref())?;
+                    write_value(w, "season", self.season)?;
+                    write_value(w, "episode", self.episode)?;
+                    write_value(w, "plot", self.plot.as_deref())?;
+                    write_value(w, "aired", self.aired.as_deref())?;
+                    write_value(w, "runtime", self.runtime)?;
+                    write_value(w, "thumb", self.thumb.as_deref())?;
+                    write_actors(w, &self.cast)
+                })?;
+            Ok(())
+        })
+    }
+}
+
+type XmlWriter = Writer<Vec<u8>>;
+
+fn write_document(f: impl FnOnce(&mut XmlWriter) -> std::io::Result<()>) -> String {
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 4);
+    writer
+        .write_event(Event::Decl(BytesDecl::new(
+            "1.0",
+            Some("UTF-8"),
+            Some("yes"),
+        )))
+        .and_then(|_| writer.write_event(Event::Comment(BytesText::from_escaped(EXPORT_MARKER))))
+        .and_then(|_| f(&mut writer))
+        .expect("writes into vec are infallible");
+    String::from_utf8(writer.into_inner()).expect("xml writer produces utf-8")
+}
+
+fn write_value(
+    writer: &mut XmlWriter,
+    name: &str,
+    value: Option<impl ToString>,
+) -> std::io::Result<()> {
+    if let Some(value) = value {
+        writer
+            .create_element(name)
+            .write_text_content(BytesText::new(&value.to_string()))?;
+    }
+    Ok(())
+}
+
+fn write_actors(writer: &mut XmlWriter, cast: &[NfoActor]) -> std::io::Result<()> {
+    for actor in cast {
+        writer.create_element("actor").write_inner_content(|w| {
+            write_value(w, "name", Some(&actor.name))?;
+            write_value(w, "role", actor.role.as_deref())?;
+            write_value(w, "thumb", actor.thumb.as_deref())
+        })?;
+    }
+    Ok(())
+}
+
+fn bool_str(value: bool) -> &'static str {
+    if value { "true" } else { "false" }
+}
+
+fn actors(element: &XmlElement) -> Vec<NfoActor> {
+    element
+        .children_named("actor")
+        .filter_map(|actor| {
+            Some(NfoActor {
+                name: actor.child_value("name")?.to_owned(),
+                role: actor.child_value("role").map(String::from),
+                thumb: actor.child_value("thumb").and_then(remote_url),
+            })
+        })
+        .collect()
+}
+
+fn ratings(element: &XmlElement) -> Vec<NfoRating> {
+    let ratings: Vec<_> = element
+        .child("ratings")
+        .into_iter()
+        .flat_map(|r| r.children_named("rating"))
+        .filter_map(|rating| {
+            Some(NfoRating {
+                name: rating.attribute("name").unwrap_or("default").to_owned(),
+                value: rating.child_value("value")?.parse().ok()?,
+                max: rating.attribute("max").and_then(|m| m.parse().ok()),
+                votes: rating.child_value("votes").and_then(parse_votes),
+                is_default: rating.attribute("default") == Some("true"),
+            })
+        })
+        .collect();
+    if !ratings.is_empty() {
+        return ratings;
+    }
+    // Legacy single <rating> tag
+    element
+        .child_value("rating")
+        .and_then(|r| r.parse().ok())
+        .map(|value| NfoRating {
+            name: "default".to_owned(),
+            value,
+            max: None,
+            votes: element.child_value("votes").and_then(parse_votes),
+            is_default: true,
+        })
+        .into_iter()
+        .collect()
+}
+
+fn parse_votes(votes: &str) -> Option<u64> {
+    votes.replace([',', '.', ' '], "").parse().ok()
+}
+
+fn people(cast: Vec<NfoActor>) -> Vec<PersonMetadata> {
+    cast.into_iter()
+        .map(|actor| PersonMetadata {
+            metadata_id: actor.name.clone(),
+            metadata_provider: MetadataProvider::Nfo,
+            person_poster: actor.thumb,
+            name: actor.name,
+            imdb_id: None,
+            role: actor.role.map(|character| RoleMetadata {
+                character,
+                poster: None,
+            }),
+            job: None,
+        })
+        .collect()
+}
+
+fn runtime_duration(minutes: u64) -> MediaDuration {
+    MediaDuration(Duration::from_secs(minutes * 60))
+}
+
+/// Only remote artwork is used, local files are not reachable by the asset saver
+fn remote_url(url: &str) -> Option<String> {
+    (url.starts_with("http://") || url.starts_with("https://")).then(|| url.to_owned())
+}
+
+fn genre_name(genre: Genre) -> &'static str {
+    match genre {
+        Genre::Action => "Action",
+        Genre::Adventure => "Adventure",
+        Genre::Fantasy => "Fantasy",
+        Genre::History => "History",
+        Genre::Horror => "Horror",
+        Genre::Music => "Music",
+        Genre::Mystery => "Mystery",
+        Genre::Romance => "Romance",
+        Genre::ScienceFiction => "Science Fiction",
+        Genre::Thriller => "Thriller",
+        Genre::War => "War",
+        Genre::Western => "Western",
+        Genre::ActionAdventure => "Action & Adventure",
+        Genre::Animation => "Animation",
+        Genre::Comedy => "Comedy",
+        Genre::Crime => "Crime",
+        Genre::Documentary => "Documentary",
+        Genre::Drama => "Drama",
+        Genre::Family => "Family",
+        Genre::Kids => "Kids",
+        Genre::News => "News",
+        Genre::Reality => "Reality",
+        Genre::SciFiFantasy => "Sci-Fi & Fantasy",
+        Genre::Soap => "Soap",
+        Genre::Talk => "Talk",
+    }
+}
+
+fn genre(name: &str) -> Option<Genre> {
+    let genre = match name.trim().to_lowercase().as_str() {
+        "action" => Genre::Action,
+        "adventure" => Genre::Adventure,
+        "fantasy" => Genre::Fantasy,
+        "history" => Genre::History,
+        "horror" => Genre::Horror,
+        "music" => Genre::Music,
+        "mystery" => Genre::Mystery,
+        "romance" => Genre::Romance,
+        "science fiction" | "sci-fi" | "scifi" => Genre::ScienceFiction,
+        "thriller" => Genre::Thriller,
+        "war" => Genre::War,
+        "western" => Genre::Western,
+        "action & adventure" => Genre::ActionAdventure,
+        "animation" => Genre::Animation,
+        "comedy" => Genre::Comedy,
+        "crime" => Genre::Crime,
+        "documentary" => Genre::Documentary,
+        "drama" => Genre::Drama,
+        "family" => Genre::Family,
+        "kids" => Genre::Kids,
+        "news" => Genre::News,
+        "reality" => Genre::Reality,
+        "sci-fi & fantasy" => Genre::SciFiFantasy,
+        "soap" => Genre::Soap,
+        "talk" => Genre::Talk,
+        _ => return None,
+    };
+    Some(genre)
+}
+
+/// Minimal element tree. Nfo files are often hand edited so the tree does not care about
+/// element order or repeated elements
+#[derive(Debug, Default)]
+struct XmlElement {
+    name: String,
+    attributes: Vec<(String, String)>,
+    text: String,
+    children: Vec<XmlElement>,
+}
+
+impl XmlElement {
+    fn new(start: &BytesStart) -> anyhow::Result<Self> {
+        let name = String::from_utf8_lossy(start.local_name().as_ref()).to_lowercase();
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            let attribute = attribute?;
+            let key = String::from_utf8_lossy(attribute.key.local_name().as_ref()).to_lowercase();
+            attributes.push((key, attribute.unescape_value()?.into_owned()));
+        }
+        Ok(Self {
+            name,
+            attributes,
+            ..Default::default()
+        })
+    }
+
+    /// Root elements of the document. Text outside of root elements (like trailing urls) is ignored
+    fn parse_document(xml: &str) -> anyhow::Result<Vec<Self>> {
+        let mut reader = Reader::from_str(xml);
+        let mut stack: Vec<Self> = Vec::new();
+        let mut roots = Vec::new();
+
+        fn attach(stack: &mut [XmlElement], roots: &mut Vec<XmlElement>, element: XmlElement) {
+            match stack.last_mut() {
+                Some(parent) => parent.children.push(element),
+                None => roots.push(element),
+            }
+        }
+
+        loop {
+            match reader.read_event()? {
+                Event::Start(start) => stack.push(Self::new(&start)?),
+                Event::Empty(start) => {
+                    let element = Self::new(&start)?;
+                    attach(&mut stack, &mut roots, element);
+                }
+                Event::End(_) => {
+                    if let Some(element) = stack.pop() {
+                        attach(&mut stack, &mut roots, element);
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&text.xml10_content()?);
+                    }
+                }
+                Event::CData(data) => {
+                    if let Some(element) = stack.last_mut() {
+                        element.text.push_str(&data.decode()?);
+                    }
+                }
+                Event::GeneralRef(reference) => {
+                    if let Some(element) = stack.last_mut() {
+                        if let Some(char) = reference.resolve_char_ref()? {
+                            element.text.push(char);
+                        } else if let Some(entity) = resolve_predefined_entity(&reference.decode()?)
+                        {
+                            element.text.push_str(entity);
+                        }
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(roots)
+    }
+
+    fn child(&self, name: &str) -> Option<&Self> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Self> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Trimmed text content, `None` if empty
+    fn value(&self) -> Option<&str> {
+        let text = self.text.trim();
+        (!text.is_empty()).then_some(text)
+    }
+
+    fn child_value(&self, name: &str) -> Option<&str> {
+        self.child(name)?.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::metadata::{ExternalIdMetadata, Genre, MetadataProvider};
+
+    use super::{EXPORT_MARKER, NfoContent, NfoEpisode, NfoRating, read_episode};
+
+    #[test]
+    fn parse_movie() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<movie>
+    <title>The Matrix</title>
+    <ratings>
+        <rating name="imdb" max="10" default="true">
+            <value>8.7</value>
+            <votes>1,234,567</votes>
+        </rating>
+    </ratings>
+    <plot>Neo &amp; Trinity &#x2014; the plot</plot>
+    <thumb aspect="banner">https://example.com/banner.jpg</thumb>
+    <thumb aspect="poster">https://example.com/poster.jpg</thumb>
+    <fanart>
+        <thumb>https://example.com/fanart.jpg</thumb>
+    </fanart>
+    <runtime>136</runtime>
+    <uniqueid type="imdb" default="true">tt0133093</uniqueid>
+    <genre>Action</genre>
+    <uniqueid type="tmdb">603</uniqueid>
+    <genre>Science Fiction</genre>
+    <year>1999</year>
+    <actor>
+        <name>Keanu Reeves</name>
+        <role>Neo</role>
+        <thumb>https://example.com/keanu.jpg</thumb>
+    </actor>
+    <actor>
+        <name>Carrie-Anne Moss</name>
+    </actor>
+</movie>
+https://www.themoviedb.org/movie/603
+"#;
+        let movie = NfoContent::parse(xml, "movie").unwrap();
+        assert_eq!(movie.title.as_deref(), Some("The Matrix"));
+        assert_eq!(movie.plot.as_deref(), Some("Neo & Trinity — the plot"));
+        assert_eq!(movie.year, Some(1999));
+        assert_eq!(movie.runtime, Some(136));
+        assert_eq!(movie.genres, vec![Genre::Action, Genre::ScienceFiction]);
+        assert_eq!(
+            movie.poster.as_deref(),
+            Some("https://example.com/poster.jpg")
+        );
+        assert_eq!(
+            movie.fanart.as_deref(),
+            Some("https://example.com/fanart.jpg")
+        );
+        assert_eq!(movie.cast.len(), 2);
+        assert_eq!(movie.cast[0].role.as_deref(), Some("Neo"));
+        assert_eq!(
+            movie.ratings,
+            vec![NfoRating {
+                name: "imdb".into(),
+                value: 8.7,
+                max: Some(10.0),
+                votes: Some(1234567),
+                is_default: true,
+            }]
+        );
+        let metadata = movie.clone().into_movie_metadata("movie.nfo".into());
+        let ratings = metadata.ratings.unwrap();
+        assert_eq!(ratings[0].source, "imdb");
+        assert_eq!(ratings[0].value, 8.7);
+        assert_eq!(ratings[0].votes, Some(1234567));
+        assert_eq!(
+            movie.unique_ids,
+            vec![
+                ExternalIdMetadata {
+                    provider: MetadataProvider::Imdb,
+                    id: "tt0133093".into(),
+                },
+                ExternalIdMetadata {
+                    provider: MetadataProvider::Tmdb,
+                    id: "603".into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_legacy_show() {
+        let xml = r#"<tvshow>
+    <title>Breaking Bad</title>
+    <rating>9.5</rating>
+    <id>81189</id>
+    <genre>Crime / Drama</genre>
+    <premiered>2008-01-20</premiered>
+</tvshow>"#;
+        let show = NfoContent::parse(xml, "tvshow").unwrap();
+        assert_eq!(show.genres, vec![Genre::Crime, Genre::Drama]);
+        assert_eq!(show.release_date().as_deref(), Some("2008-01-20"));
+        assert_eq!(show.ratings.len(), 1);
+        assert_eq!(show.ratings[0].value, 9.5);
+        assert!(show.ratings[0].is_default);
+        assert_eq!(
+            show.unique_ids,
+            vec![ExternalIdMetadata {
+                provider: MetadataProvider::Tvdb,
+                id: "81189".into(),
+            }]
+        );
+        assert!(NfoContent::parse(xml, "movie").is_err());
+    }
+
+    #[test]
+    fn write_roundtrip() {
+        let movie = NfoContent {
+            title: Some("Tom & Jerry".into()),
+            plot: Some("<b>Cat</b> chases mouse".into()),
+            year: Some(2021),
+            runtime: Some(101),
+            genres: vec![Genre::Animation, Genre::SciFiFantasy],
+            unique_ids: vec![ExternalIdMetadata {
+                provider: MetadataProvider::Tmdb,
+                id: "587807".into(),
+            }],
+            poster: Some("https://example.com/poster.jpg".into()),
+            ratings: vec![NfoRating {
+                name: "themoviedb".into(),
+                value: 7.5,
+                max: Some(10.0),
+                votes: Some(1200),
+                is_default: true,
+            }],
+            ..Default::default()
+        };
+        let xml = movie.to_xml("movie");
+        assert!(xml.contains(EXPORT_MARKER));
+        assert_eq!(NfoContent::parse(&xml, "movie").unwrap(), movie);
+        let episode = NfoEpisode {
+            title: Some("Pilot".into()),
+            season: Some(1),
+            episode: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            NfoEpisode::parse_all(&episode.to_xml()).unwrap(),
+            vec![episode]
+        );
+    }
+
+    #[test]
+    fn parse_multi_episode() {
+        let xml = r#"<episodedetails>
+    <title>Pilot</title>
+    <season>1</season>
+    <episode>1</episode>
+    <aired>2008-01-20</aired>
+</episodedetails>
+<episodedetails>
+    <title><![CDATA[Cat's in the Bag...]]></title>
+    <season>1</season>
+    <episode>2</episode>
+</episodedetails>"#;
+        let episodes = NfoEpisode::parse_all(xml).unwrap();
+        assert_eq!(episodes.len(), 2);
+        assert_eq!(episodes[0].aired.as_deref(), Some("2008-01-20"));
+        assert_eq!(episodes[1].title.as_deref(), Some("Cat's in the Bag..."));
+        assert_eq!(episodes[1].episode, Some(2));
+    }
+
+    #[tokio::test]
+    async fn read_requested_episode() {
+        let path = std::env::temp_dir().join(format!("read_episode_{}.nfo", std::process::id()));
+        let xml = r#"<episodedetails>
+    <title>Pilot</title>
+    <season>1</season>
+    <episode>1</episode>
+</episodedetails>
+<episodedetails>
+    <title>Cat's in the Bag...</title>
+    <season>1</season>
+    <episode>2</episode>
+</episodedetails>"#;
+        tokio::fs::write(&path, xml).await.unwrap();
+        let second = read_episode(&path, 1, 2).await;
+        let missing = read_episode(&path, 1, 3).await;
+        tokio::fs::remove_file(&path).await.unwrap();
+        assert_eq!(second.unwrap().title, "Cat's in the Bag...");
+        assert!(missing.is_err());
+    }
+}
@@ -6,7 +6,25 @@ use serde::de::DeserializeOwned;
 use tokio::sync::{Semaphore, mpsc, oneshot};
 use tokio_util::sync::CancellationToken;
 
-use crate::AppError;
+use crate::{AppError, AppErrorKind};
+
+use super::{
+    MetadataProvider,
+    response_cache::{CacheKind, Freshness, ResponseCache, unix_now},
+};
+
+tokio::task_local! {
+    /// Set while cached requests must not write into the response cache
+    static READ_ONLY_CACHE: ();
+}
+
+/// Run the future with the response cache in read only mode.
+///
+/// Cached responses are still served, fresh responses are not stored and stale ones are not
+/// revalidated
+pub async fn with_read_only_cache<F: Future>(f: F) -> F::Output {
+    READ_ONLY_CACHE.scope((), f).await
+}
 
 /// Request that is send to limited request client
 #[derive(Debug)]
@@ -23,6 +41,17 @@ struct LimitedRequest {
 #[derive(Debug, Clone)]
 pub struct LimitedRequestClient {
     request_tx: mpsc::Sender<LimitedRequest>,
+    /// Persistent response cache and the provider responses are stored under
+    cache: Option<(ResponseCache, MetadataProvider)>,
+}
+
+/// Key of the cached response. Requests with the body (e.g. GraphQL queries) share the url
+fn cache_key(req: &Request) -> String {
+    let url = req.url().to_string();
+    match req.body().and_then(|b| b.as_bytes()) {
+        Some(body) => format!("{url}\n{}", String::from_utf8_lossy(body)),
+        None => url,
+    }
 }
 
 impl LimitedRequestClient {
@@ -82,7 +111,16 @@ impl LimitedRequestClient {
                 });
             }
         });
-        Self { request_tx: tx }
+        Self {
+            request_tx: tx,
+            cache: None,
+        }
+    }
+
+    /// Store responses of [Self::cached_request] in the persistent cache
+    pub fn with_cache(mut self, cache: ResponseCache, provider: MetadataProvider) -> Self {
+        self.cache = Some((cache, provider));
+        self
     }
 
     pub async fn request<T>(&self, req: Request) -> crate::Result<T>
@@ -102,6 +140,110 @@ impl LimitedRequestClient {
         }
     }
 
+    /// Same as [Self::request], but the response is served from the persistent cache when possible.
+    ///
+    /// Stale responses are served while being revalidated in the background.
+    /// Expired responses are refetched and served only when the provider fails to respond.
+    pub async fn cached_request<T>(&self, req: Request, kind: CacheKind) -> crate::Result<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        self.cached_request_by(req, move |_: &T| kind).await
+    }
+
+    /// Cached request with the cache kind that depends on the response (e.g. ended shows)
+    pub async fn cached_request_by<T>(
+        &self,
+        req: Request,
+        kind: impl Fn(&T) -> CacheKind + Send + Sync + 'static,
+    ) -> crate::Result<T>
+    where
+        T: DeserializeOwned + Send + 'static,
+    {
+        let Some((cache, provider)) = &self.cache else {
+            return self.request(req).await;
+        };
+        let url = cache_key(&req);
+        let cached = cache.get(*provider, &url).await.unwrap_or_else(|e| {
+            tracing::warn!(url, "Failed to read cached response: {e}");
+            None
+        });
+        let cached = cached.and_then(|cached| {
+            let value = serde_json::from_str::<T>(&cached.body).ok()?;
+            Some((cached.freshness(unix_now()), value))
+        });
+        let read_only = READ_ONLY_CACHE.try_with(|_| ()).is_ok();
+        match cached {
+            Some((Freshness::Fresh, value)) => return Ok(value),
+            Some((Freshness::Stale, value)) => {
+                if !read_only
+                    && let Some(req) = req.try_clone()
+                    && cache.begin_revalidation(*provider, &url)
+                {
+                    let client = self.clone();
+                    let cache = cache.clone();
+                    let provider = *provider;
+                    tokio::spawn(async move {
+                        let url = cache_key(&req);
+                        if let Err(e) = client.fetch_and_cache::<T>(req, &kind).await {
+                            tracing::warn!(url, "Failed to revalidate cached response: {e}");
+                        }
+                        cache.end_revalidation(provider, &url);
+                    });
+                }
+                return Ok(value);
+            }
+            Some((Freshness::Expired, _)) | None => {}
+        }
+        let fetched = if read_only {
+            self.request(req).await
+        } else {
+            self.fetch_and_cache(req, &kind).await
+        };
+        match fetched {
+            Ok(value) => Ok(value),
+            Err(e) if e.kind == AppErrorKind::NotFound => Err(e),
+            Err(e) => match cached {
+                Some((_, value)) => {
+                    tracing::warn!(url, "Serving expired cached response: {e}");
+                    Ok(value)
+                }
+                None => Err(e),
+            },
+        }
+    }
+
+    async fn fetch_and_cache<T>(
+        &self,
+        req: Request,
+        kind: &impl Fn(&T) -> CacheKind,
+    ) -> crate::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let url = cache_key(&req);
+        let response = self.request_raw(req).await?;
+        let body = response
+            .text()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read response body: {e}"))?;
+        let value = match serde_json::from_str(&body) {
+            Ok(value) => value,
+            Err(e) => {
+                tracing::error!(url, "Failed to deserialize fetch response: {e}");
+                return Err(AppError::internal_error(
+                    "failed to deserialize response json body",
+                ));
+            }
+        };
+        if let Some((cache, provider)) = &self.cache
+            && let Err(e) = cache.store(*provider, &url, kind(&value), &body).await
+        {
+            tracing::warn!(url, "Failed to cache response: {e}");
+        }
+        Ok(value)
+    }
+
     pub async fn request_raw(&self, req: Request) -> crate::Result<Response> {
         let (tx, rx) = oneshot::channel::<Result<Response, reqwest::Error>>();
         let cancellation_token = CancellationToken::new();
@@ -0,0 +1,244 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::db::Db;
+
+use super::MetadataProvider;
+
+/// How long stale responses are served while they are revalidated in the background.
+///
+/// Responses that are older than that are refetched before being served
+pub const STALE_WHILE_REVALIDATE: Duration = Duration::from_secs(60 * 60 * 24 * 7);
+
+/// Kind of the cached provider response, decides for how long the response stays fresh
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheKind {
+    /// Trending lists change every day
+    Trending,
+    Search,
+    Movie,
+    Collection,
+    /// Show that is still airing
+    Show,
+    /// Show that is ended or canceled, it rarely receives metadata updates
+    EndedShow,
+    Season,
+    Episode,
+    ExternalIds,
+    Person,
+}
+
+impl CacheKind {
+    pub const fn ttl(self) -> Duration {
+        const HOUR: u64 = 60 * 60;
+        const DAY: u64 = HOUR * 24;
+        let secs = match self {
+            CacheKind::Trending => 3 * HOUR,
+            CacheKind::Search => DAY,
+            CacheKind::Show | CacheKind::Season | CacheKind::Episode => DAY,
+            CacheKind::Movie | CacheKind::Collection | CacheKind::Person => 7 * DAY,
+            CacheKind::EndedShow | CacheKind::ExternalIds => 30 * DAY,
+        };
+        Duration::from_secs(secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    /// Response can be served as is
+    Fresh,
+    /// Response can be served, but it must be revalidated
+    Stale,
+    /// Response must be refetched, it is served only when provider is not available
+    Expired,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub body: String,
+    pub fetched_at: i64,
+    pub expires_at: i64,
+}
+
+impl CachedResponse {
+    pub fn freshness(&self, now: i64) -> Freshness {
+        if now < self.expires_at {
+            Freshness::Fresh
+        } else if now < self.expires_at + STALE_WHILE_REVALIDATE.as_secs() as i64 {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+}
+
+/// Persistent cache of the metadata provider responses.
+///
+/// Cloned instances share the same storage and revalidation state
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    db: Db,
+    /// Urls that are being revalidated right now
+    revalidating: Arc<Mutex<HashSet<(MetadataProvider, String)>>>,
+}
+
+pub fn unix_now() -> i64 {
+    time::OffsetDateTime::now_utc().unix_timestamp()
+}
+
+impl ResponseCache {
+    pub fn new(db: Db) -> Self {
+        Self {
+            db,
+            revalidating: Arc::default(),
+        }
+    }
+
+    pub async fn get(
+        &self,
+        provider: MetadataProvider,
+        url: &str,
+    ) -> sqlx::Result<Option<CachedResponse>> {
+        sqlx::query_as!(
+            CachedResponse,
+            "SELECT body, fetched_at, expires_at FROM provider_cache WHERE provider = ? AND url = ?",
+            provider,
+            url
+        )
+        .fetch_optional(&self.db.pool)
+        .await
+    }
+
+    pub async fn store(
+        &self,
+        provider: MetadataProvider,
+        url: &str,
+        kind: CacheKind,
+        body: &str,
+    ) -> sqlx::Result<()> {
+        let fetched_at = unix_now();
+        let expires_at = fetched_at + kind.ttl().as_secs() as i64;
+        sqlx::query!(
+            "INSERT OR REPLACE INTO provider_cache (provider, url, body, fetched_at, expires_at)
+            VALUES (?, ?, ?, ?, ?)",
+            provider,
+            url,
+            body,
+            fetched_at,
+            expires_at
+        )
+        .execute(&self.db.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Remove cached responses of the provider or all providers.
+    ///
+    /// With `expired_only` only responses that can't be served without refetch are removed
+    pub async fn purge(
+        &self,
+        provider: Option<MetadataProvider>,
+        expired_only: bool,
+    ) -> sqlx::Result<u64> {
+        let now = unix_now();
+        let stale_window = STALE_WHILE_REVALIDATE.as_secs() as i64;
+        let result = sqlx::query!(
+            "DELETE FROM provider_cache WHERE (?1 IS NULL OR provider = ?1)
+            AND (NOT ?2 OR expires_at + ?3 <= ?4)",
+            provider,
+            expired_only,
+            stale_window,
+            now
+        )
+        .execute(&self.db.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Mark the url as being revalidated.
+    ///
+    /// Returns false if revalidation of the url is already in progress
+    pub fn begin_revalidation(&self, provider: MetadataProvider, url: &str) -> bool {
+        self.revalidating
+            .lock()
+            .unwrap()
+            .insert((provider, url.to_owned()))
+    }
+
+    pub fn end_revalidation(&self, provider: MetadataProvider, url: &str) {
+        self.revalidating
+            .lock()
+            .unwrap()
+            .remove(&(provider, url.to_owned()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use crate::{
+        db::Db,
+        metadata::{MetadataProvider, response_cache::STALE_WHILE_REVALIDATE},
+    };
+
+    use super::{CacheKind, Freshness, ResponseCache, unix_now};
+
+    #[sqlx::test]
+    async fn cache_freshness(pool: SqlitePool) -> anyhow::Result<()> {
+        let cache = ResponseCache::new(Db { pool });
+        let url = "https://api.themoviedb.org/3/tv/1399?language=en-US";
+        assert!(cache.get(MetadataProvider::Tmdb, url).await?.is_none());
+
+        cache
+            .store(MetadataProvider::Tmdb, url, CacheKind::EndedShow, "{}")
+            .await?;
+        assert!(cache.get(MetadataProvider::Tvdb, url).await?.is_none());
+        let cached = cache.get(MetadataProvider::Tmdb, url).await?.unwrap();
+        assert_eq!(cached.body, "{}");
+        let now = unix_now();
+        let ttl = CacheKind::EndedShow.ttl().as_secs() as i64;
+        let stale_window = STALE_WHILE_REVALIDATE.as_secs() as i64;
+        assert_eq!(cached.freshness(now), Freshness::Fresh);
+        assert_eq!(cached.freshness(now + ttl + 1), Freshness::Stale);
+        assert_eq!(
+            cached.freshness(now + ttl + stale_window + 1),
+            Freshness::Expired
+        );
+        assert!(CacheKind::Trending.ttl() < CacheKind::Show.ttl());
+        assert!(CacheKind::Show.ttl() < CacheKind::EndedShow.ttl());
+
+        assert!(cache.begin_revalidation(MetadataProvider::Tmdb, url));
+        assert!(!cache.begin_revalidation(MetadataProvider::Tmdb, url));
+        cache.end_revalidation(MetadataProvider::Tmdb, url);
+        assert!(cache.begin_revalidation(MetadataProvider::Tmdb, url));
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn cache_purge(pool: SqlitePool) -> anyhow::Result<()> {
+        let cache = ResponseCache::new(Db { pool: pool.clone() });
+        cache
+            .store(MetadataProvider::Tmdb, "tmdb/1", CacheKind::Movie, "{}")
+            .await?;
+        cache
+            .store(MetadataProvider::Tmdb, "tmdb/2", CacheKind::Movie, "{}")
+            .await?;
+        cache
+            .store(MetadataProvider::Tvdb, "tvdb/1", CacheKind::Movie, "{}")
+            .await?;
+        sqlx::query!("UPDATE provider_cache SET expires_at = 0 WHERE url = 'tmdb/1'")
+            .execute(&pool)
+            .await?;
+
+        assert_eq!(cache.purge(None, true).await?, 1);
+        assert!(cache.get(MetadataProvider::Tmdb, "tmdb/1").await?.is_none());
+        assert_eq!(cache.purge(Some(MetadataProvider::Tmdb), false).await?, 1);
+        assert!(cache.get(MetadataProvider::Tvdb, "tvdb/1").await?.is_some());
+        assert_eq!(cache.purge(None, false).await?, 1);
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{collections::HashSet, time::Duration};
 
 use anyhow::anyhow;
 use reqwest::{
@@ -8,12 +8,17 @@ use reqwest::{
 use serde::Deserialize;
 use time::OffsetDateTime;
 
-use crate::metadata::{PersonMetadata, ProviderIdentifier, RoleMetadata};
+use crate::metadata::{
+    CreditMetadata, CrewJob, PersonDetailsMetadata, PersonMetadata, PersonMetadataProvider,
+    ProviderIdentifier, RoleMetadata,
+};
 
 use super::{
-    DiscoverMetadataProvider, EpisodeMetadata, ExternalIdMetadata, LocaleMetadata,
-    MetadataProvider, MetadataSearchResult, MovieMetadata, MovieMetadataProvider, ParentMediaType,
-    SeasonMetadata, ShowMetadata, ShowMetadataProvider, request_client::LimitedRequestClient,
+    CollectionMetadata, CollectionPart, DiscoverMetadataProvider, EpisodeMetadata,
+    ExternalIdMetadata, LocaleMetadata, MetadataProvider, MetadataSearchResult, MovieMetadata,
+    MovieMetadataProvider, ParentMediaType, SeasonMetadata, ShowMetadata, ShowMetadataProvider,
+    request_client::LimitedRequestClient,
+    response_cache::{CacheKind, ResponseCache},
 };
 use super::{FetchParams, Language, provod_agent};
 
@@ -86,7 +91,13 @@ impl TmdbApi {
     ///
     /// If provided api key, requests will go directly to tmdb.
     /// Otherwise Provod proxy will be used.
-    pub fn new(http_client: Client, api_key: Option<String>) -> anyhow::Result<Self> {
+    ///
+    /// Responses are stored in the persistent `cache`
+    pub fn new(
+        http_client: Client,
+        api_key: Option<String>,
+        cache: ResponseCache,
+    ) -> anyhow::Result<Self> {
         // If we don't have token use provod agent
         let (headers, base_url) = match api_key {
             Some(api_key) => {
@@ -111,7 +122,8 @@ impl TmdbApi {
             headers,
             Self::RATE_LIMIT,
             std::time::Duration::from_secs(1),
-        );
+        )
+        .with_cache(cache, MetadataProvider::Tmdb);
         Ok(Self {
             client: limited_client,
             base_url,
@@ -131,7 +143,7 @@ impl TmdbApi {
         url.query_pairs_mut()
             .append_pair("language", &language.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Trending).await
     }
 
     pub async fn trending_movies(
@@ -147,7 +159,7 @@ impl TmdbApi {
         url.query_pairs_mut()
             .append_pair("language", &language.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Trending).await
     }
 
     pub async fn search_movie(
@@ -165,7 +177,7 @@ impl TmdbApi {
             .extend_pairs(query)
             .append_pair("language", &lang.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Search).await
     }
 
     pub async fn search_tv_show(
@@ -180,7 +192,7 @@ impl TmdbApi {
             .extend_pairs(query)
             .append_pair("language", &language.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Search).await
     }
 
     async fn search_multi(
@@ -198,7 +210,7 @@ impl TmdbApi {
             .extend_pairs(query)
             .append_pair("language", &lang.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Search).await
     }
 
     async fn tv_show_season(
@@ -218,7 +230,7 @@ impl TmdbApi {
             .append_pair("append_to_response", "credits");
         append_language(&mut url, fetch_params.lang);
         let req = Request::new(Method::GET, url);
-        let response: TmdbShowSeason = self.client.request(req).await?;
+        let response: TmdbShowSeason = self.client.cached_request(req, CacheKind::Season).await?;
 
         Ok(response)
     }
@@ -243,7 +255,7 @@ impl TmdbApi {
             .append_pair("append_to_response", "credits")
             .append_pair("language", &params.lang.to_string());
         let req = Request::new(Method::GET, url);
-        self.client.request(req).await
+        self.client.cached_request(req, CacheKind::Episode).await
     }
 
     #[allow(unused)]
@@ -253,7 +265,10 @@ impl TmdbApi {
         url.query_pairs_mut()
             .append_pair("external_source", "imdb_id");
         let req = Request::new(Method::GET, url);
-        let res = self.client.request(req).await?;
+        let res = self
+            .client
+            .cached_request(req, CacheKind::ExternalIds)
+            .await?;
         Ok(res)
     }
 
@@ -265,7 +280,10 @@ impl TmdbApi {
             .push(&id.to_string())
             .push("external_ids");
         let req = Request::new(Method::GET, url);
-        let res = self.client.request(req).await?;
+        let res = self
+            .client
+            .cached_request(req, CacheKind::ExternalIds)
+            .await?;
         Ok(res)
     }
 
@@ -277,7 +295,10 @@ impl TmdbApi {
             .push(&id.to_string())
             .push("external_ids");
         let req = Request::new(Method::GET, url);
-        let res = self.client.request(req).await?;
+        let res = self
+            .client
+            .cached_request(req, CacheKind::ExternalIds)
+            .await?;
         Ok(res)
     }
 
@@ -295,7 +316,26 @@ impl TmdbApi {
             .append_pair("language", &lang.to_string())
             .append_pair("append_to_response", "credits,external_ids");
         let req = Request::new(Method::GET, url);
-        let res = self.client.request(req).await?;
+        let res = self.client.cached_request(req, CacheKind::Movie).await?;
+        Ok(res)
+    }
+
+    async fn collection_details(
+        &self,
+        collection_id: usize,
+        lang: Language,
+    ) -> crate::Result<TmdbCollectionDetails> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .unwrap()
+            .push("collection")
+            .push(&collection_id.to_string());
+        append_language(&mut url, lang);
+        let req = Request::new(Method::GET, url);
+        let res = self
+            .client
+            .cached_request(req, CacheKind::Collection)
+            .await?;
         Ok(res)
     }
 
@@ -309,7 +349,31 @@ impl TmdbApi {
             .append_pair("language", &lang.to_string())
             .append_pair("append_to_response", "credits,external_ids");
         let req = Request::new(Method::GET, url);
-        let res = self.client.request(req).await?;
+        let res = self
+            .client
+            .cached_request_by(req, |show: &TmdbShowDetails| match show.in_production {
+                Some(false) => CacheKind::EndedShow,
+                _ => CacheKind::Show,
+            })
+            .await?;
+        Ok(res)
+    }
+
+    async fn person_details(
+        &self,
+        person_id: usize,
+        lang: Language,
+    ) -> crate::Result<TmdbPersonDetails> {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .unwrap()
+            .push("person")
+            .push(&person_id.to_string());
+        url.query_pairs_mut()
+            .append_pair("language", &lang.to_string())
+            .append_pair("append_to_response", "combined_credits");
+        let req = Request::new(Method::GET, url);
+        let res = self.client.cached_request(req, CacheKind::Person).await?;
         Ok(res)
     }
 }
@@ -340,6 +404,8 @@ impl From<TmdbSearchMovieResult> for MovieMetadata {
             cast: None,
             genres: None,
             external_ids: None,
+            collection: None,
+            ratings: None,
         }
     }
 }
@@ -372,6 +438,7 @@ impl From<TmdbSearchShowResult> for ShowMetadata {
             genres: None,
             external_ids: None,
             next_episode_air_date: None,
+            ratings: None,
         }
     }
 }
@@ -390,9 +457,7 @@ impl From<TmdbShowSeason> for SeasonMetadata {
             poster,
             number: val.season_number,
             title: Some(val.name),
-            cast: val
-                .credits
-                .map(|credits| credits.cast.into_iter().map(Into::into).collect()),
+            cast: val.credits.map(Into::into),
         }
     }
 }
@@ -415,9 +480,7 @@ impl From<TmdbSeasonEpisode> for EpisodeMetadata {
             plot: val.overview,
             season_number: val.season_number,
             poster,
-            cast: val
-                .credits
-                .map(|credits| credits.cast.into_iter().map(Into::into).collect()),
+            cast: val.credits.map(Into::into),
         }
     }
 }
@@ -445,6 +508,17 @@ impl MovieMetadataProvider for TmdbApi {
         let content = self.search_movie(query, fetch_params.lang).await?;
         Ok(content.results.into_iter().map(Into::into).collect())
     }
+
+    async fn collection(
+        &self,
+        collection_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<CollectionMetadata> {
+        let collection = self
+            .collection_details(collection_id.parse()?, fetch_params.lang)
+            .await?;
+        Ok(collection.into())
+    }
 }
 
 #[async_trait::async_trait]
@@ -539,6 +613,20 @@ impl DiscoverMetadataProvider for TmdbApi {
     }
 }
 
+#[async_trait::async_trait]
+impl PersonMetadataProvider for TmdbApi {
+    async fn person(
+        &self,
+        person_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<PersonDetailsMetadata> {
+        let person = self
+            .person_details(person_id.parse()?, fetch_params.lang)
+            .await?;
+        Ok(person.into())
+    }
+}
+
 impl From<TmdbMovieDetails> for MovieMetadata {
     fn from(val: TmdbMovieDetails) -> Self {
         let poster = val
@@ -568,11 +656,11 @@ impl From<TmdbMovieDetails> for MovieMetadata {
                 original_title: val.original_title,
                 original_language: val.original_language,
             }),
-            cast: val
-                .credits
-                .map(|credits| credits.cast.into_iter().map(Into::into).collect()),
+            cast: val.credits.map(Into::into),
             genres,
             external_ids: val.external_ids.map(Into::into),
+            collection: val.belongs_to_collection.map(Into::into),
+            ratings: None,
         }
     }
 }
@@ -604,9 +692,7 @@ impl From<TmdbShowDetails> for ShowMetadata {
                 original_title: val.original_name,
                 original_language: val.original_language,
             }),
-            cast: val
-                .credits
-                .map(|v| v.cast.into_iter().map(Into::into).collect()),
+            cast: val.credits.map(Into::into),
             genres,
             external_ids: val.external_ids.map(Into::into),
             next_episode_air_date: val
@@ -621,6 +707,7 @@ impl From<TmdbShowDetails> for ShowMetadata {
                 })
                 .map(|air_date| OffsetDateTime::new_utc(air_date, time::Time::MIDNIGHT))
                 .map(Into::into),
+            ratings: None,
         }
     }
 }
@@ -690,6 +777,7 @@ impl From<TmdbCast> for PersonMetadata {
                 character,
                 poster: None,
             }),
+            job: None,
         }
     }
 }
@@ -762,6 +850,7 @@ struct TmdbShowDetails {
     credits: Option<TmdbCredits>,
     external_ids: Option<TmdbExternalIds>,
     next_episode_to_air: Option<NextEpisodeAirDate>,
+    in_production: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -779,11 +868,120 @@ struct TmdbMovieDetails {
     title: String,
     credits: Option<TmdbCredits>,
     external_ids: Option<TmdbExternalIds>,
+    belongs_to_collection: Option<TmdbCollectionRef>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct TmdbCollectionRef {
+    id: usize,
+    name: String,
+    poster_path: Option<String>,
+    backdrop_path: Option<String>,
+}
+
+impl From<TmdbCollectionRef> for CollectionMetadata {
+    fn from(val: TmdbCollectionRef) -> Self {
+        CollectionMetadata {
+            metadata_id: val.id.to_string(),
+            metadata_provider: MetadataProvider::Tmdb,
+            title: val.name,
+            plot: None,
+            poster: val
+                .poster_path
+                .map(|p| TmdbImage::new(&p, PosterSizes::default()).to_string()),
+            backdrop: val
+                .backdrop_path
+                .map(|b| TmdbImage::new(&b, PosterSizes::Original).to_string()),
+            parts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct TmdbCollectionDetails {
+    id: usize,
+    name: String,
+    overview: Option<String>,
+    poster_path: Option<String>,
+    backdrop_path: Option<String>,
+    parts: Vec<TmdbSearchMovieResult>,
+}
+
+impl From<TmdbCollectionDetails> for CollectionMetadata {
+    fn from(val: TmdbCollectionDetails) -> Self {
+        let parts = val
+            .parts
+            .into_iter()
+            .map(|part| CollectionPart {
+                metadata_id: part.id.to_string(),
+                metadata_provider: MetadataProvider::Tmdb,
+                title: part.title,
+                release_date: part.release_date.filter(|d| !d.is_empty()),
+                poster: part
+                    .poster_path
+                    .map(|p| TmdbImage::new(&p, PosterSizes::default()).to_string()),
+            })
+            .collect();
+        CollectionMetadata {
+            metadata_id: val.id.to_string(),
+            metadata_provider: MetadataProvider::Tmdb,
+            title: val.name,
+            plot: val.overview.filter(|o| !o.is_empty()),
+            poster: val
+                .poster_path
+                .map(|p| TmdbImage::new(&p, PosterSizes::default()).to_string()),
+            backdrop: val
+                .backdrop_path
+                .map(|b| TmdbImage::new(&b, PosterSizes::Original).to_string()),
+            parts,
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct TmdbCredits {
     cast: Vec<TmdbCast>,
+    #[serde(default)]
+    crew: Vec<TmdbCrew>,
+}
+
+/// Cast followed by the crew members with the kept jobs
+impl From<TmdbCredits> for Vec<PersonMetadata> {
+    fn from(credits: TmdbCredits) -> Self {
+        let mut seen = HashSet::new();
+        let crew = credits.crew.into_iter().filter_map(|member| {
+            let id = member.id?;
+            let name = member.name?;
+            let job = crew_job(member.job.as_deref()?)?;
+            seen.insert((id, job)).then(|| PersonMetadata {
+                metadata_id: id.to_string(),
+                metadata_provider: MetadataProvider::Tmdb,
+                person_poster: member
+                    .profile_path
+                    .map(|p| TmdbImage::new(&p, Default::default()).to_string()),
+                name,
+                imdb_id: None,
+                role: None,
+                job: Some(job),
+            })
+        });
+        credits
+            .cast
+            .into_iter()
+            .map(Into::into)
+            .chain(crew)
+            .collect()
+    }
+}
+
+/// Crew job of the TMDB job name, other jobs are not kept
+fn crew_job(job: &str) -> Option<CrewJob> {
+    match job {
+        "Director" => Some(CrewJob::Director),
+        "Writer" | "Screenplay" | "Story" | "Novel" | "Teleplay" => Some(CrewJob::Writer),
+        "Original Music Composer" | "Music" | "Composer" => Some(CrewJob::Composer),
+        _ => None,
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -945,3 +1143,94 @@ pub struct TmdbSearchMovieResult {
     original_title: String,
     original_language: String,
 }
+
+#[derive(Deserialize, Debug, Clone)]
+struct TmdbPersonDetails {
+    id: usize,
+    name: String,
+    biography: Option<String>,
+    birthday: Option<String>,
+    deathday: Option<String>,
+    place_of_birth: Option<String>,
+    known_for_department: Option<String>,
+    imdb_id: Option<String>,
+    profile_path: Option<String>,
+    combined_credits: Option<TmdbCombinedCredits>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct TmdbCombinedCredits {
+    cast: Vec<TmdbPersonCredit>,
+    crew: Vec<TmdbPersonCredit>,
+}
+
+/// Movie or show of the person credits, shows have `name` instead of `title`
+#[derive(Deserialize, Debug, Clone)]
+struct TmdbPersonCredit {
+    id: usize,
+    media_type: String,
+    #[serde(alias = "name")]
+    title: String,
+    #[serde(alias = "first_air_date")]
+    release_date: Option<String>,
+    poster_path: Option<String>,
+    character: Option<String>,
+    job: Option<String>,
+}
+
+impl TmdbPersonCredit {
+    fn into_credit(self, job: Option<CrewJob>) -> Option<CreditMetadata> {
+        let content_type = match self.media_type.as_str() {
+            "movie" => ParentMediaType::Movie,
+            "tv" => ParentMediaType::Show,
+            _ => return None,
+        };
+        Some(CreditMetadata {
+            metadata_id: self.id.to_string(),
+            metadata_provider: MetadataProvider::Tmdb,
+            content_type,
+            title: self.title,
+            release_date: self.release_date.filter(|d| !d.is_empty()),
+            poster: self
+                .poster_path
+                .map(|p| TmdbImage::new(&p, PosterSizes::default()).to_string()),
+            character: self.character.filter(|c| !c.is_empty()),
+            job,
+        })
+    }
+}
+
+impl From<TmdbPersonDetails> for PersonDetailsMetadata {
+    fn from(val: TmdbPersonDetails) -> Self {
+        let mut credits = Vec::new();
+        if let Some(combined) = val.combined_credits {
+            credits.extend(
+                combined
+                    .cast
+                    .into_iter()
+                    .filter_map(|credit| credit.into_credit(None)),
+            );
+            let mut seen = HashSet::new();
+            credits.extend(combined.crew.into_iter().filter_map(|credit| {
+                let job = crew_job(credit.job.as_deref()?)?;
+                seen.insert((credit.id, credit.media_type.clone(), job))
+                    .then(|| credit.into_credit(Some(job)))?
+            }));
+        }
+        Self {
+            metadata_id: val.id.to_string(),
+            metadata_provider: MetadataProvider::Tmdb,
+            name: val.name,
+            poster: val
+                .profile_path
+                .map(|p| TmdbImage::new(&p, PosterSizes::default()).to_string()),
+            biography: val.biography.filter(|b| !b.is_empty()),
+            birthday: val.birthday.filter(|d| !d.is_empty()),
+            deathday: val.deathday.filter(|d| !d.is_empty()),
+            place_of_birth: val.place_of_birth.filter(|p| !p.is_empty()),
+            known_for_department: val.known_for_department,
+            imdb_id: val.imdb_id.filter(|id| !id.is_empty()),
+            credits,
+        }
+    }
+}
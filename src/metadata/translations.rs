@@ -0,0 +1,557 @@
+//! Titles, plots and posters in the additional metadata languages.
+//!
+//! Main metadata rows hold the metadata language of the content library, or the configured
+//! metadata language when the library doesn't have its own. Translations to the other languages
+//! are fetched from the prime provider after the library scan and stored next to them.
+//! Translations of the updated metadata rows are marked outdated and served until they are
+//! fetched again.
+
+use std::{collections::HashMap, path::Path};
+
+use serde::Serialize;
+
+use crate::{
+    AppErrorKind,
+    db::{Db, DbQueryBuilder},
+    library::libraries,
+    progress::{ProgressDispatcher, TaskProgress, TaskTrait},
+};
+
+use super::{
+    EpisodeMetadata, EpisodeOrdering, FetchParams, Language, MovieMetadata, SeasonMetadata,
+    ShowMetadata, metadata_api::refresh::prime_provider, metadata_stack::MetadataProvidersStack,
+};
+
+/// Fetch of the missing and outdated translations
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct TranslationTask {
+    pub languages: Vec<Language>,
+}
+
+impl PartialEq for TranslationTask {
+    fn eq(&self, _other: &Self) -> bool {
+        // Every fetch covers all untranslated content
+        true
+    }
+}
+
+impl Eq for TranslationTask {}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct TranslationProgress {
+    /// Language that is being fetched
+    pub language: Language,
+    /// Translated movies and shows
+    pub done: usize,
+    pub total: usize,
+}
+
+impl TaskTrait for TranslationTask {
+    type Progress = TranslationProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> TaskProgress
+    where
+        Self: Sized,
+    {
+        TaskProgress::Translation(status)
+    }
+}
+
+/// Translated fields of the metadata row
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translation {
+    pub title: Option<String>,
+    pub plot: Option<String>,
+    pub poster: Option<String>,
+}
+
+impl From<&MovieMetadata> for Translation {
+    fn from(movie: &MovieMetadata) -> Self {
+        Self {
+            title: Some(movie.title.clone()),
+            plot: movie.plot.clone(),
+            poster: movie.poster.clone(),
+        }
+    }
+}
+
+impl From<&ShowMetadata> for Translation {
+    fn from(show: &ShowMetadata) -> Self {
+        Self {
+            title: Some(show.title.clone()),
+            plot: show.plot.clone(),
+            poster: show.poster.clone(),
+        }
+    }
+}
+
+impl From<&SeasonMetadata> for Translation {
+    fn from(season: &SeasonMetadata) -> Self {
+        Self {
+            title: season.title.clone(),
+            plot: season.plot.clone(),
+            poster: season.poster.clone(),
+        }
+    }
+}
+
+impl From<&EpisodeMetadata> for Translation {
+    fn from(episode: &EpisodeMetadata) -> Self {
+        Self {
+            title: Some(episode.title.clone()),
+            plot: episode.plot.clone(),
+            poster: episode.poster.clone(),
+        }
+    }
+}
+
+/// Store the translation of the metadata row, replacing the previous or outdated one.
+///
+/// Empty translation marks that the provider doesn't have the content in this language
+pub async fn save(
+    db: &Db,
+    metadata_id: i64,
+    lang: Language,
+    translation: &Translation,
+) -> sqlx::Result<()> {
+    sqlx::query!(
+        "INSERT OR REPLACE INTO metadata_translations (metadata_id, language, title, plot, poster)
+        VALUES (?, ?, ?, ?, ?)",
+        metadata_id,
+        lang,
+        translation.title,
+        translation.plot,
+        translation.poster,
+    )
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+/// Translations of the metadata rows to `lang`, outdated translations included.
+///
+/// Rows without the translation fall back to the original title when `lang` is the original
+/// language of the content. Rows that have nothing in `lang` are not included
+pub async fn localized(
+    db: &Db,
+    metadata_ids: &[i64],
+    lang: Language,
+) -> sqlx::Result<HashMap<i64, Translation>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        id: i64,
+        title: Option<String>,
+        plot: Option<String>,
+        poster: Option<String>,
+        original_title: Option<String>,
+        original_language: Option<String>,
+    }
+    if metadata_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let mut query = DbQueryBuilder::new(
+        "SELECT metadata.id, t.title, t.plot, t.poster, metadata.original_title, metadata.original_language
+        FROM metadata LEFT JOIN metadata_translations t ON t.metadata_id = metadata.id AND t.language = ",
+    );
+    query.push_bind(lang);
+    query.push(" WHERE metadata.id IN (");
+    let mut ids = query.separated(", ");
+    for id in metadata_ids {
+        ids.push_bind(id);
+    }
+    query.push(")");
+    let rows: Vec<Row> = query.build_query_as().fetch_all(&db.pool).await?;
+    let lang = lang.to_string();
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let original_title = row
+                .original_title
+                .filter(|_| row.original_language.as_deref() == Some(lang.as_str()));
+            let translation = Translation {
+                title: row.title.or(original_title),
+                plot: row.plot,
+                poster: row.poster,
+            };
+            (translation != Translation::default()).then_some((row.id, translation))
+        })
+        .collect())
+}
+
+/// Languages of the main metadata rows, keyed by the metadata id.
+///
+/// Rows are in the language of their library, rows of the libraries without own language are in
+/// `default`
+pub async fn main_languages(
+    db: &Db,
+    metadata_ids: &[i64],
+    default: Language,
+) -> sqlx::Result<HashMap<i64, Language>> {
+    #[derive(sqlx::FromRow)]
+    struct Row {
+        id: i64,
+        path: String,
+    }
+    let mut languages: HashMap<_, _> = metadata_ids.iter().map(|id| (*id, default)).collect();
+    let media_libraries: Vec<_> = libraries::all(db)
+        .await?
+        .into_iter()
+        .filter(|l| {
+            l.settings
+                .metadata_language
+                .is_some_and(|lang| lang != default)
+        })
+        .collect();
+    if media_libraries.is_empty() || metadata_ids.is_empty() {
+        return Ok(languages);
+    }
+    // Library is found by the path of any video of the content
+    let mut query = DbQueryBuilder::new("SELECT videos.metadata_id AS id, videos.path FROM videos");
+    let push_ids = |query: &mut DbQueryBuilder, prefix: &str| {
+        query.push(prefix);
+        let mut ids = query.separated(", ");
+        for id in metadata_ids {
+            ids.push_bind(id);
+        }
+        query.push(")");
+    };
+    push_ids(&mut query, " WHERE videos.metadata_id IN (");
+    query.push(
+        " UNION ALL SELECT seasons.metadata_id, videos.path FROM seasons
+        JOIN episodes ON episodes.season_id = seasons.id
+        JOIN videos ON videos.metadata_id = episodes.metadata_id",
+    );
+    push_ids(&mut query, " WHERE seasons.metadata_id IN (");
+    query.push(
+        " UNION ALL SELECT shows.metadata_id, videos.path FROM shows
+        JOIN seasons ON seasons.show_id = shows.id
+        JOIN episodes ON episodes.season_id = seasons.id
+        JOIN videos ON videos.metadata_id = episodes.metadata_id",
+    );
+    push_ids(&mut query, " WHERE shows.metadata_id IN (");
+    let rows: Vec<Row> = query.build_query_as().fetch_all(&db.pool).await?;
+    for row in rows {
+        if let Some(lang) = media_libraries
+            .iter()
+            .find(|l| l.contains(Path::new(&row.path)))
+            .and_then(|l| l.settings.metadata_language)
+        {
+            languages.insert(row.id, lang);
+        }
+    }
+    Ok(languages)
+}
+
+/// Translation of the content or the empty translation if provider doesn't have it
+fn found<T>(result: crate::Result<T>) -> crate::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind == AppErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+async fn translate_movie(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    metadata_id: i64,
+    lang: Language,
+) -> crate::Result<()> {
+    let (provider, id) = prime_provider(db, metadata_id, |p| providers.movie_provider(p)).await?;
+    let params = FetchParams::new(lang);
+    let movie = found(provider.movie(&id, params).await)?;
+    let translation = movie.as_ref().map(Into::into).unwrap_or_default();
+    save(db, metadata_id, lang, &translation).await?;
+    Ok(())
+}
+
+/// Translate the show and its seasons that have untranslated nodes
+async fn translate_show(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    show_id: i64,
+    metadata_id: i64,
+    ordering: EpisodeOrdering,
+    lang: Language,
+) -> crate::Result<()> {
+    let (provider, id) = prime_provider(db, metadata_id, |p| providers.show_provider(p)).await?;
+    let params = FetchParams { lang, ordering };
+    let show = found(provider.show(&id, params).await)?;
+    let translation = show.as_ref().map(Into::into).unwrap_or_default();
+    save(db, metadata_id, lang, &translation).await?;
+
+    let seasons = sqlx::query!(
+        "SELECT seasons.id, seasons.metadata_id, seasons.number FROM seasons
+        WHERE seasons.show_id = ?1 AND (
+            NOT EXISTS (SELECT 1 FROM metadata_translations t
+                WHERE t.metadata_id = seasons.metadata_id AND t.language = ?2 AND NOT t.outdated)
+            OR EXISTS (SELECT 1 FROM episodes WHERE episodes.season_id = seasons.id
+                AND NOT EXISTS (SELECT 1 FROM metadata_translations t
+                    WHERE t.metadata_id = episodes.metadata_id AND t.language = ?2 AND NOT t.outdated))
+        )",
+        show_id,
+        lang,
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    for season in seasons {
+        let metadata = match show {
+            Some(_) => found(provider.season(&id, season.number as usize, params).await)?,
+            None => None,
+        };
+        let translation = metadata.as_ref().map(Into::into).unwrap_or_default();
+        save(db, season.metadata_id, lang, &translation).await?;
+        let episodes = sqlx::query!(
+            "SELECT metadata_id, number FROM episodes WHERE season_id = ?",
+            season.id
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        for episode in episodes {
+            let translation = metadata
+                .iter()
+                .flat_map(|s| &s.episodes)
+                .find(|e| e.number == episode.number as usize)
+                .map(Into::into)
+                .unwrap_or_default();
+            save(db, episode.metadata_id, lang, &translation).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch translations of the local content that doesn't have them in `languages` or has
+/// outdated ones.
+///
+/// Content that is already in the language and content without the ids of available providers
+/// are skipped. `metadata_lang` is the language of the libraries without their own language
+pub async fn fetch_missing(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    languages: &[Language],
+    metadata_lang: Language,
+    progress: &ProgressDispatcher<TranslationTask>,
+) -> sqlx::Result<()> {
+    for &lang in languages {
+        let mut movies = sqlx::query!(
+            "SELECT movies.metadata_id FROM movies WHERE NOT EXISTS
+            (SELECT 1 FROM metadata_translations t WHERE t.metadata_id = movies.metadata_id
+            AND t.language = ? AND NOT t.outdated)",
+            lang
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        let mut shows = sqlx::query!(
+            r#"SELECT shows.id, shows.metadata_id, shows.episode_ordering as "ordering: EpisodeOrdering"
+            FROM shows WHERE NOT EXISTS (SELECT 1 FROM metadata_translations t
+                WHERE t.metadata_id = shows.metadata_id AND t.language = ?1 AND NOT t.outdated)
+            OR EXISTS (SELECT 1 FROM seasons LEFT JOIN episodes ON episodes.season_id = seasons.id
+                WHERE seasons.show_id = shows.id AND (
+                    NOT EXISTS (SELECT 1 FROM metadata_translations t
+                        WHERE t.metadata_id = seasons.metadata_id AND t.language = ?1 AND NOT t.outdated)
+                    OR (episodes.id IS NOT NULL AND NOT EXISTS (SELECT 1 FROM metadata_translations t
+                        WHERE t.metadata_id = episodes.metadata_id AND t.language = ?1 AND NOT t.outdated))
+                ))"#,
+            lang
+        )
+        .fetch_all(&db.pool)
+        .await?;
+        let metadata_ids: Vec<_> = movies
+            .iter()
+            .map(|m| m.metadata_id)
+            .chain(shows.iter().map(|s| s.metadata_id))
+            .collect();
+        let main_languages = main_languages(db, &metadata_ids, metadata_lang).await?;
+        movies.retain(|m| main_languages[&m.metadata_id] != lang);
+        shows.retain(|s| main_languages[&s.metadata_id] != lang);
+        tracing::debug!(
+            movies = movies.len(),
+            shows = shows.len(),
+            "Fetching {lang} translations"
+        );
+        let total = movies.len() + shows.len();
+        let mut done = 0;
+        progress.progress(TranslationProgress {
+            language: lang,
+            done,
+            total,
+        });
+        for movie in movies {
+            if let Err(e) = translate_movie(db, providers, movie.metadata_id, lang).await {
+                tracing::debug!(
+                    movie.metadata_id,
+                    "Failed to translate movie to {lang}: {e}"
+                );
+            }
+            done += 1;
+            progress.progress(TranslationProgress {
+                language: lang,
+                done,
+                total,
+            });
+        }
+        for show in shows {
+            if let Err(e) = translate_show(
+                db,
+                providers,
+                show.id,
+                show.metadata_id,
+                show.ordering,
+                lang,
+            )
+            .await
+            {
+                tracing::debug!(show.id, "Failed to translate show to {lang}: {e}");
+            }
+            done += 1;
+            progress.progress(TranslationProgress {
+                language: lang,
+                done,
+                total,
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::SqlitePool;
+
+    use crate::{
+        db::{Db, DbActions, DbContentType, DbMetadata, DbVideo},
+        library::libraries::{self, LibrarySettings},
+        metadata::{Language, ParentMediaType},
+    };
+
+    use super::{Translation, localized, main_languages, save};
+
+    #[sqlx::test]
+    async fn translation_fallback(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        let metadata = DbMetadata {
+            id: None,
+            content_type: DbContentType::Movie,
+            title: "Spirited Away".into(),
+            original_language: Some("ja".into()),
+            original_title: Some("千と千尋の神隠し".into()),
+            release_date: None,
+            poster: None,
+            plot: Some("Plot".into()),
+        };
+        let translated = db.insert_metadata(&metadata).await?;
+        let original = db.insert_metadata(&metadata).await?;
+        let ru = Translation {
+            title: Some("Унесённые призраками".into()),
+            plot: None,
+            poster: Some("https://example.com/ru.jpg".into()),
+        };
+        save(&db, translated, Language::Ru, &ru).await?;
+
+        let ids = [translated, original];
+        let translations = localized(&db, &ids, Language::Ru).await?;
+        assert_eq!(translations.get(&translated), Some(&ru));
+        assert_eq!(translations.get(&original), None);
+
+        let translations = localized(&db, &ids, Language::Ja).await?;
+        assert_eq!(translations.len(), 2);
+        assert_eq!(
+            translations[&original].title.as_deref(),
+            Some("千と千尋の神隠し")
+        );
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn outdated_translation(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        let metadata = DbMetadata {
+            id: None,
+            content_type: DbContentType::Movie,
+            title: "Spirited Away".into(),
+            original_language: None,
+            original_title: None,
+            release_date: None,
+            poster: None,
+            plot: None,
+        };
+        let metadata_id = db.insert_metadata(&metadata).await?;
+        let ru = Translation {
+            title: Some("Унесённые призраками".into()),
+            plot: None,
+            poster: None,
+        };
+        save(&db, metadata_id, Language::Ru, &ru).await?;
+        db.update_metadata(metadata_id, &metadata).await?;
+
+        // Outdated translation is served until it is fetched again
+        let translations = localized(&db, &[metadata_id], Language::Ru).await?;
+        assert_eq!(translations.get(&metadata_id), Some(&ru));
+        let outdated = sqlx::query_scalar!(
+            "SELECT outdated FROM metadata_translations WHERE metadata_id = ?",
+            metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert!(outdated);
+
+        save(&db, metadata_id, Language::Ru, &ru).await?;
+        let outdated = sqlx::query_scalar!(
+            "SELECT outdated FROM metadata_translations WHERE metadata_id = ?",
+            metadata_id
+        )
+        .fetch_one(&db.pool)
+        .await?;
+        assert!(!outdated);
+        Ok(())
+    }
+
+    #[sqlx::test]
+    async fn library_language(pool: SqlitePool) -> anyhow::Result<()> {
+        let db = Db { pool };
+        libraries::insert(
+            &db,
+            &LibrarySettings {
+                name: "Anime".into(),
+                media_type: ParentMediaType::Movie,
+                paths: vec!["/media/anime".into()],
+                metadata_language: Some(Language::Ja),
+                provider_order: None,
+                detect_intros: false,
+                generate_previews: false,
+                scan_interval: None,
+                ignore_patterns: Vec::new(),
+                export_nfo: false,
+            },
+        )
+        .await?;
+        let metadata = DbMetadata {
+            id: None,
+            content_type: DbContentType::Movie,
+            title: "Spirited Away".into(),
+            original_language: None,
+            original_title: None,
+            release_date: None,
+            poster: None,
+            plot: None,
+        };
+        let anime = db.insert_metadata(&metadata).await?;
+        let other = db.insert_metadata(&metadata).await?;
+        for (metadata_id, path) in [
+            (anime, "/media/anime/Spirited Away.mkv"),
+            (other, "/media/movies/Spirited Away.mkv"),
+        ] {
+            db.insert_video(DbVideo {
+                path: path.into(),
+                metadata_id: Some(metadata_id),
+                ..Default::default()
+            })
+            .await?;
+        }
+
+        let languages = main_languages(&db, &[anime, other], Language::En).await?;
+        assert_eq!(languages[&anime], Language::Ja);
+        assert_eq!(languages[&other], Language::En);
+        Ok(())
+    }
+}
@@ -18,10 +18,12 @@ use crate::{
 };
 
 use super::{
-    DiscoverMetadataProvider, EpisodeMetadata, ExternalIdMetadata, FetchParams, Language,
-    METADATA_CACHE_SIZE, MetadataProvider, MetadataSearchResult, MovieMetadata,
-    MovieMetadataProvider, ParentMediaType, SeasonMetadata, ShowMetadata, ShowMetadataProvider,
-    provod_agent, request_client::LimitedRequestClient,
+    AbsoluteEpisode, DiscoverMetadataProvider, EpisodeMetadata, EpisodeOrdering,
+    ExternalIdMetadata, FetchParams, Language, METADATA_CACHE_SIZE, MetadataProvider,
+    MetadataSearchResult, MovieMetadata, MovieMetadataProvider, ParentMediaType, SeasonMetadata,
+    ShowMetadata, ShowMetadataProvider, provod_agent,
+    request_client::LimitedRequestClient,
+    response_cache::{CacheKind, ResponseCache},
 };
 
 #[derive(Debug)]
@@ -29,13 +31,18 @@ pub struct TvdbApi {
     client: LimitedRequestClient,
     show_cache: Mutex<LruCache<usize, TvdbSeriesExtendedRecord>>,
     movie_cache: Mutex<LruCache<usize, TvdbMovieExtendedRecord>>,
+    episodes_cache: Mutex<LruCache<(usize, EpisodeOrdering), Vec<TvdbEpisode>>>,
     base_url: Url,
 }
 
 impl TvdbApi {
     pub const RATE_LIMIT: usize = 10;
     pub const API_URL: &'static str = "https://api4.thetvdb.com/v4";
-    pub fn new(http_client: Client, api_key: Option<&str>) -> anyhow::Result<Self> {
+    pub fn new(
+        http_client: Client,
+        api_key: Option<&str>,
+        cache: ResponseCache,
+    ) -> anyhow::Result<Self> {
         let (headers, base_url) = match api_key {
             Some(api_key) => {
                 tracing::info!("Using personal tvdb token");
@@ -51,11 +58,13 @@ impl TvdbApi {
             headers,
             Self::RATE_LIMIT,
             std::time::Duration::from_secs(1),
-        );
+        )
+        .with_cache(cache, MetadataProvider::Tvdb);
         Ok(Self {
             client: limited_client,
             show_cache: Mutex::new(LruCache::new(METADATA_CACHE_SIZE)),
             movie_cache: Mutex::new(LruCache::new(METADATA_CACHE_SIZE)),
+            episodes_cache: Mutex::new(LruCache::new(METADATA_CACHE_SIZE)),
             base_url,
         })
     }
@@ -72,7 +81,10 @@ impl TvdbApi {
             .append_pair("query", query)
             .append_pair("type", "series");
         let request = Request::new(Method::GET, url);
-        let res: TvdbResponse<Vec<TvdbSearchResult>> = self.client.request(request).await?;
+        let res: TvdbResponse<Vec<TvdbSearchResult>> = self
+            .client
+            .cached_request(request, CacheKind::Search)
+            .await?;
         Ok(res.data)
     }
 
@@ -88,7 +100,10 @@ impl TvdbApi {
             .append_pair("query", query)
             .append_pair("type", "movie");
         let request = Request::new(Method::GET, url);
-        let res: TvdbResponse<Vec<TvdbSearchResult>> = self.client.request(request).await?;
+        let res: TvdbResponse<Vec<TvdbSearchResult>> = self
+            .client
+            .cached_request(request, CacheKind::Search)
+            .await?;
         Ok(res.data)
     }
 
@@ -102,7 +117,10 @@ impl TvdbApi {
             .unwrap();
         url.query_pairs_mut().append_pair("query", query);
         let request = Request::new(Method::GET, url);
-        let res: TvdbResponse<Vec<TvdbSearchResult>> = self.client.request(request).await?;
+        let res: TvdbResponse<Vec<TvdbSearchResult>> = self
+            .client
+            .cached_request(request, CacheKind::Search)
+            .await?;
         Ok(res.data)
     }
 
@@ -125,7 +143,8 @@ impl TvdbApi {
             .append_pair("short", "false")
             .append_pair("language", params.lang.as_str());
         let req = Request::new(Method::GET, url);
-        let res: TvdbResponse<TvdbMovieExtendedRecord> = self.client.request(req).await?;
+        let res: TvdbResponse<TvdbMovieExtendedRecord> =
+            self.client.cached_request(req, CacheKind::Movie).await?;
         let mut movie_cache = self.movie_cache.lock().unwrap();
         movie_cache.put(id, res.data.clone());
         Ok(res.data)
@@ -150,12 +169,62 @@ impl TvdbApi {
             .append_pair("short", "false")
             .append_pair("language", params.lang.as_str());
         let req = Request::new(Method::GET, url);
-        let res: TvdbResponse<TvdbSeriesExtendedRecord> = self.client.request(req).await?;
+        let res: TvdbResponse<TvdbSeriesExtendedRecord> = self
+            .client
+            .cached_request_by(
+                req,
+                |res: &TvdbResponse<TvdbSeriesExtendedRecord>| match res
+                    .data
+                    .status
+                    .as_ref()
+                    .and_then(|s| s.name.as_deref())
+                {
+                    Some("Ended") => CacheKind::EndedShow,
+                    _ => CacheKind::Show,
+                },
+            )
+            .await?;
         let mut show_cache = self.show_cache.lock().unwrap();
         show_cache.put(id, res.data.clone());
         Ok(res.data)
     }
 
+    // https://api4.thetvdb.com/v4/series/75897/episodes/dvd?page=0
+    async fn fetch_ordered_episodes(
+        &self,
+        id: usize,
+        ordering: EpisodeOrdering,
+    ) -> crate::Result<Vec<TvdbEpisode>> {
+        if let Some(episodes) = self.episodes_cache.lock().unwrap().get(&(id, ordering)) {
+            return Ok(episodes.clone());
+        }
+        let mut episodes = Vec::new();
+        let mut page = 0;
+        loop {
+            let mut url = self.base_url.clone();
+            url.path_segments_mut()
+                .map(|mut path| {
+                    path.push("series");
+                    path.push(&id.to_string());
+                    path.push("episodes");
+                    path.push(season_type(ordering));
+                })
+                .unwrap();
+            url.query_pairs_mut().append_pair("page", &page.to_string());
+            let req = Request::new(Method::GET, url);
+            let res: TvdbPagedResponse<TvdbSeriesEpisodes> =
+                self.client.cached_request(req, CacheKind::Season).await?;
+            episodes.extend(res.data.episodes);
+            if res.links.and_then(|l| l.next).is_none() {
+                break;
+            }
+            page += 1;
+        }
+        let mut episodes_cache = self.episodes_cache.lock().unwrap();
+        episodes_cache.put((id, ordering), episodes.clone());
+        Ok(episodes)
+    }
+
     fn get_movie_from_cache(&self, id: usize) -> Option<TvdbMovieExtendedRecord> {
         self.movie_cache.lock().unwrap().get(&id).cloned()
     }
@@ -222,17 +291,31 @@ impl ShowMetadataProvider for TvdbApi {
             Some(s) => s,
             None => self.fetch_show(show_id.parse()?, fetch_params).await?,
         };
-        let mut episodes = show.episodes;
-        let episodes = episodes
+        let ordering = fetch_params.ordering;
+        let episodes = match ordering {
+            EpisodeOrdering::Aired => show.episodes,
+            _ => self.fetch_ordered_episodes(show.id, ordering).await?,
+        };
+        let episodes: Vec<EpisodeMetadata> = episodes
             .into_iter()
             .filter(|e| e.season_number == season)
             .map(Into::into)
             .collect();
-        let season = show
-            .seasons
-            .into_iter()
-            .find(|s| s.number == season)
-            .ok_or(AppError::not_found("Season not found"))?;
+        let season_record = show.seasons.into_iter().find(|s| {
+            s.number == season
+                && s.season_type
+                    .as_ref()
+                    .is_none_or(|t| t.kind == season_type(ordering))
+        });
+        let season = match season_record {
+            Some(season) => season,
+            // Alternate orderings do not always have season records
+            None if !episodes.is_empty() => TvdbSeasonBaseRecord {
+                number: season,
+                ..Default::default()
+            },
+            None => return Err(AppError::not_found("Season not found")),
+        };
 
         let plot = season
             .overview_translations
@@ -266,6 +349,31 @@ impl ShowMetadataProvider for TvdbApi {
             .ok_or(AppError::not_found("episode is not found"))
     }
 
+    async fn absolute_episodes(
+        &self,
+        show_id: &str,
+        fetch_params: FetchParams,
+    ) -> crate::Result<Vec<AbsoluteEpisode>> {
+        let show = match self.get_show_from_cache(show_id.parse()?) {
+            Some(s) => s,
+            None => self.fetch_show(show_id.parse()?, fetch_params).await?,
+        };
+        let mut episodes: Vec<_> = show
+            .episodes
+            .into_iter()
+            .filter(|e| e.season_number > 0)
+            .filter_map(|e| {
+                Some(AbsoluteEpisode {
+                    absolute_number: e.absolute_number.filter(|n| *n > 0)?,
+                    season: e.season_number,
+                    episode: e.number,
+                })
+            })
+            .collect();
+        episodes.sort_unstable_by_key(|e| e.absolute_number);
+        Ok(episodes)
+    }
+
     async fn show_search(
         &self,
         query: &str,
@@ -366,6 +474,7 @@ impl Into<ShowMetadata> for TvdbSeriesExtendedRecord {
             genres: None,
             external_ids: None,
             next_episode_air_date: None,
+            ratings: None,
         }
     }
 }
@@ -405,6 +514,8 @@ impl Into<MovieMetadata> for TvdbMovieExtendedRecord {
             cast: None,
             genres: None,
             external_ids: None,
+            collection: None,
+            ratings: None,
         }
     }
 }
@@ -432,6 +543,8 @@ impl From<TvdbSearchResult> for MovieMetadata {
             cast: None,
             genres: None,
             external_ids: None,
+            collection: None,
+            ratings: None,
         }
     }
 }
@@ -465,7 +578,7 @@ impl From<TvdbEpisode> for EpisodeMetadata {
         Self {
             metadata_id: value.id.to_string(),
             metadata_provider: MetadataProvider::Tvdb,
-            release_date: value.year,
+            release_date: value.aired.or(value.year),
             number: value.number,
             title: value.name,
             plot: value.overview,
@@ -569,13 +682,16 @@ struct TvdbEpisode {
     seasons: Option<Vec<TvdbSeasonBaseRecord>>,
     number: usize,
     season_number: usize,
+    absolute_number: Option<usize>,
     last_updated: Option<String>,
     year: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct TvdbSeasonBaseRecord {
+    #[serde(rename = "type")]
+    season_type: Option<TvdbSeasonType>,
     id: usize,
     image: Option<String>,
     image_type: Option<usize>,
@@ -588,6 +704,18 @@ struct TvdbSeasonBaseRecord {
     year: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+struct TvdbSeasonType {
+    /// Season type identifier: official, dvd, absolute, alternate, etc.
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TvdbSeriesEpisodes {
+    episodes: Vec<TvdbEpisode>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct TvdbGenre {
@@ -653,6 +781,12 @@ struct TvdbSeriesExtendedRecord {
     remote_ids: Vec<TvdbRemoteIds>,
     characters: Option<Vec<TvdbCharacter>>,
     translations: TvdbTranslations,
+    status: Option<TvdbStatus>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TvdbStatus {
+    name: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -725,3 +859,24 @@ struct TvdbResponse<T> {
     status: String,
     data: T,
 }
+
+#[derive(Debug, Deserialize)]
+struct TvdbPagedResponse<T> {
+    data: T,
+    links: Option<TvdbLinks>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TvdbLinks {
+    next: Option<String>,
+}
+
+/// Tvdb season type of the episode ordering
+fn season_type(ordering: EpisodeOrdering) -> &'static str {
+    match ordering {
+        EpisodeOrdering::Aired => "official",
+        EpisodeOrdering::Dvd => "dvd",
+        EpisodeOrdering::Absolute => "absolute",
+        EpisodeOrdering::Story => "alternate",
+    }
+}
@@ -1,6 +1,17 @@
 use crate::parser::{symbol::Symbol, tokenizer::Token};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    serde::Serialize,
+    serde::Deserialize,
+    utoipa::ToSchema,
+)]
 pub enum ResolutionAttr {
     #[serde(rename = "480p")]
     P480,
@@ -40,13 +51,16 @@ impl ResolutionAttr {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum SourceAttr {
     Web,
     WebDl,
     WebRip,
     BluRay,
+    Remux,
     Dvd,
     Telesync,
 }
@@ -59,7 +73,9 @@ pub enum CodecAttr {
     Av1,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, utoipa::ToSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum Tag {
     Dubbed,
@@ -71,6 +87,25 @@ pub enum Tag {
     Hdr,
     Extended,
     Remastered,
+    DirectorsCut,
+    Theatrical,
+    Unrated,
+    Imax,
+}
+
+impl Tag {
+    /// Tags that describe the cut of the movie rather than the release itself
+    pub fn is_edition(&self) -> bool {
+        matches!(
+            self,
+            Tag::Extended
+                | Tag::Remastered
+                | Tag::DirectorsCut
+                | Tag::Theatrical
+                | Tag::Unrated
+                | Tag::Imax
+        )
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, utoipa::ToSchema)]
@@ -105,6 +140,13 @@ static PHRASES: &[(&[&str], Attribute)] = &[
     (&["dvdrip"], Attribute::Source(SourceAttr::Dvd)),
     (&["dvd", "rip"], Attribute::Source(SourceAttr::Dvd)),
     (&["telesync"], Attribute::Source(SourceAttr::Telesync)),
+    (&["remux"], Attribute::Source(SourceAttr::Remux)),
+    (&["bdremux"], Attribute::Source(SourceAttr::Remux)),
+    (&["bluray", "remux"], Attribute::Source(SourceAttr::Remux)),
+    (
+        &["blu", "ray", "remux"],
+        Attribute::Source(SourceAttr::Remux),
+    ),
     // Codec
     (&["x264"], Attribute::Codec(CodecAttr::H264)),
     (&["h264"], Attribute::Codec(CodecAttr::H264)),
@@ -126,6 +168,13 @@ static PHRASES: &[(&[&str], Attribute)] = &[
     (&["hdr"], Attribute::Tag(Tag::Hdr)),
     (&["extended"], Attribute::Tag(Tag::Extended)),
     (&["remastered"], Attribute::Tag(Tag::Remastered)),
+    (&["directors", "cut"], Attribute::Tag(Tag::DirectorsCut)),
+    (&["director's", "cut"], Attribute::Tag(Tag::DirectorsCut)),
+    (&["directors", "edition"], Attribute::Tag(Tag::DirectorsCut)),
+    (&["theatrical"], Attribute::Tag(Tag::Theatrical)),
+    (&["theatrical", "cut"], Attribute::Tag(Tag::Theatrical)),
+    (&["unrated"], Attribute::Tag(Tag::Unrated)),
+    (&["imax"], Attribute::Tag(Tag::Imax)),
 ];
 
 fn phrase_matches(words: &[&str], tokens: &[Token<'_>]) -> bool {
@@ -356,6 +405,21 @@ mod tests {
         assert_eq!(vec![Tag::DualAudio, Tag::MultiSubs], attributes.tags);
     }
 
+    #[test]
+    fn movie_editions() {
+        let attributes = parse("Blade.Runner.1982.Directors.Cut.2160p.BluRay.REMUX.HEVC");
+        assert_eq!(Some(ResolutionAttr::P2160), attributes.resolution);
+        assert_eq!(Some(SourceAttr::Remux), attributes.source);
+        assert_eq!(vec![Tag::DirectorsCut], attributes.tags);
+
+        let attributes = parse("Aliens (1986) Director's Cut [1080p]");
+        assert_eq!(vec![Tag::DirectorsCut], attributes.tags);
+
+        let attributes = parse("The.Dark.Knight.2008.IMAX.Theatrical.Cut.720p.WEB-DL");
+        assert_eq!(vec![Tag::Imax, Tag::Theatrical], attributes.tags);
+        assert!(attributes.tags.iter().all(Tag::is_edition));
+    }
+
     #[test]
     fn tags_are_deduplicated() {
         let attributes = parse("Show.DUBBED.1080p.dubbed.720p.x264.x265");
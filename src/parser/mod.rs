@@ -1,12 +1,14 @@
 use std::{
     ffi::OsStr,
     path::{Component, Path, PathBuf},
+    sync::Arc,
     time::Instant,
 };
 
 use crate::{
     library::{
         EXTRAS_FOLDERS,
+        ignore::IgnoreRules,
         media::{Video, container::VideoContainer},
     },
     parser::{
@@ -140,25 +142,27 @@ impl<T: Parseable> Parser<T> {
     }
 }
 
-pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
+pub fn walk_show_dirs(dirs: Vec<PathBuf>, ignore: &IgnoreRules) -> Vec<(Video, ShowIdentifier)> {
     use std::fs;
     let mut files = Vec::new();
     let start = Instant::now();
+    let ignore = Arc::new(ignore.clone());
 
-    let mut directories: Vec<(PathBuf, Parser<ShowIdent>)> = dirs
+    let mut directories: Vec<(PathBuf, Parser<ShowIdent>, Arc<IgnoreRules>)> = dirs
         .into_iter()
         .map(|p| {
             let mut parser = Parser::new(ShowIdent::default());
             parser.apply_dir_path(&p);
-            (p, parser)
+            (p, parser, ignore.clone())
         })
         .collect();
 
-    while let Some((current_dir, parser)) = directories.pop() {
+    while let Some((current_dir, parser, ignore)) = directories.pop() {
         let Ok(mut read_dir) = fs::read_dir(&current_dir) else {
             tracing::warn!("Failed to read show directory {}", current_dir.display());
             continue;
         };
+        let ignore = ignore.enter_dir(&current_dir);
         let mut supported_paths = Vec::new();
 
         // true value means that some episodes are missing number
@@ -171,6 +175,10 @@ pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
                 continue;
             };
             let path = entry.path();
+            if ignore.is_ignored(&path, metadata.is_dir()) {
+                tracing::trace!("Skipping ignored path: {}", path.display());
+                continue;
+            }
             if metadata.is_dir() {
                 let Some(dir_name) = path.file_name() else {
                     continue;
@@ -184,7 +192,7 @@ pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
                 }
                 let mut new_dir_parser = parser.clone();
                 new_dir_parser.feed_directory(dir_name);
-                directories.push((path, new_dir_parser));
+                directories.push((path, new_dir_parser, ignore.clone()));
                 continue;
             }
             if !path
@@ -204,10 +212,9 @@ pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
                 let metadata_parser = parser.clone();
                 let show_ident: Result<ShowIdentifier, ShowIdent> =
                     metadata_parser.feed_filename(file_name).try_into();
-                if let Err(ident) = &show_ident {
-                    if ident.episode.is_none() {
-                        need_sort = true;
-                    }
+                // Episode number is missing, order of the files is used instead
+                if show_ident.is_err() {
+                    need_sort = true;
                 }
                 supported_paths.push((path, show_ident));
             } else {
@@ -226,8 +233,9 @@ pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
                 }
                 Err(ident) => {
                     let identifier = ShowIdentifier {
-                        episode: ident.episode.unwrap_or(i as u16 + 1),
+                        episode: i as u16 + 1,
                         season: ident.season.unwrap_or(1),
+                        absolute: ident.season.is_none(),
                         title: ident.title,
                         year: ident.year,
                         attributes: ident.attributes,
@@ -242,19 +250,30 @@ pub fn walk_show_dirs(dirs: Vec<PathBuf>) -> Vec<(Video, ShowIdentifier)> {
     files
 }
 
-pub async fn walk_movie_dirs(mut dirs: Vec<PathBuf>) -> Vec<(Video, MovieIdentifier)> {
+pub async fn walk_movie_dirs(
+    dirs: Vec<PathBuf>,
+    ignore: &IgnoreRules,
+) -> Vec<(Video, MovieIdentifier)> {
     use tokio::fs;
     let mut files = Vec::new();
+    let ignore = Arc::new(ignore.clone());
+    let mut dirs: Vec<_> = dirs.into_iter().map(|d| (d, ignore.clone())).collect();
 
-    while let Some(current_dir) = dirs.pop() {
+    while let Some((current_dir, ignore)) = dirs.pop() {
         let Ok(mut read_dir) = fs::read_dir(&current_dir).await else {
             tracing::warn!("Failed to read movie directory {}", current_dir.display());
             continue;
         };
+        let ignore = ignore.enter_dir_async(&current_dir).await;
 
         while let Ok(Some(entry)) = read_dir.next_entry().await {
             let path = entry.path();
-            if path.is_dir() {
+            let is_dir = path.is_dir();
+            if ignore.is_ignored(&path, is_dir) {
+                tracing::trace!(path = %path.display(), "Skipping ignored path");
+                continue;
+            }
+            if is_dir {
                 let Some(dir_name) = path.file_name() else {
                     continue;
                 };
@@ -265,7 +284,7 @@ pub async fn walk_movie_dirs(mut dirs: Vec<PathBuf>) -> Vec<(Video, MovieIdentif
                     tracing::trace!("Skipping extras directory: {}", path.display());
                     continue;
                 }
-                dirs.push(path);
+                dirs.push((path, ignore.clone()));
                 continue;
             }
             if !path
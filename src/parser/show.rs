@@ -66,7 +66,7 @@ fn parse_0x0_episode(value: &str) -> Option<(u16, u16)> {
 /// Partial show identifier representation.
 ///
 /// This is used during parsing where not all parts are yet known.
-#[derive(Debug, Clone, Serialize, Default)]
+#[derive(Debug, Clone, Serialize, Default, utoipa::ToSchema)]
 pub struct ShowIdent {
     pub episode: Option<u16>,
     pub season: Option<u16>,
@@ -315,6 +315,8 @@ impl ShowIdent {
 pub struct ShowIdentifier {
     pub episode: u16,
     pub season: u16,
+    /// Season is missing in the path, episode number may be absolute
+    pub absolute: bool,
     pub title: String,
     pub year: Option<u16>,
     pub attributes: Attributes,
@@ -322,33 +324,11 @@ pub struct ShowIdentifier {
 
 impl ShowIdentifier {
     pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ShowIdent> {
-        let ident = Parser::parse_filename(path.as_ref(), ShowIdent::default());
-        if let Some((episode, season)) = ident.episode.zip(ident.season) {
-            Ok(Self {
-                episode,
-                season,
-                title: ident.title,
-                year: ident.year,
-                attributes: ident.attributes,
-            })
-        } else {
-            Err(ident)
-        }
+        Parser::parse_filename(path.as_ref(), ShowIdent::default()).try_into()
     }
 
     pub fn from_str(s: &str) -> Result<Self, ShowIdent> {
-        let ident = Parser::parse_str(s, ShowIdent::default());
-        if let Some((episode, season)) = ident.episode.zip(ident.season) {
-            Ok(Self {
-                episode,
-                season,
-                title: ident.title,
-                year: ident.year,
-                attributes: ident.attributes,
-            })
-        } else {
-            Err(ident)
-        }
+        Parser::parse_str(s, ShowIdent::default()).try_into()
     }
 }
 
@@ -361,18 +341,19 @@ impl From<ShowIdentifier> for ContentIdentifier {
 impl TryFrom<ShowIdent> for ShowIdentifier {
     type Error = ShowIdent;
 
+    /// Episode without a season is placed in the first season and marked as absolute
     fn try_from(ident: ShowIdent) -> Result<Self, Self::Error> {
-        if let Some((episode, season)) = ident.episode.zip(ident.season) {
-            Ok(Self {
-                episode,
-                season,
-                title: ident.title,
-                year: ident.year,
-                attributes: ident.attributes,
-            })
-        } else {
-            Err(ident)
-        }
+        let Some(episode) = ident.episode else {
+            return Err(ident);
+        };
+        Ok(Self {
+            episode,
+            season: ident.season.unwrap_or(1),
+            absolute: ident.season.is_none(),
+            title: ident.title,
+            year: ident.year,
+            attributes: ident.attributes,
+        })
     }
 }
 
@@ -753,4 +734,15 @@ mod tests {
             ),
         ];
     }
+
+    #[test]
+    fn absolute_identifier() {
+        let absolute = ShowIdentifier::from_path("Death Note/Death Note - 14.mkv").unwrap();
+        assert!(absolute.absolute);
+        assert_eq!((absolute.season, absolute.episode), (1, 14));
+
+        let explicit = ShowIdentifier::from_path("Death Note/Death Note S01E14.mkv").unwrap();
+        assert!(!explicit.absolute);
+        assert!(ShowIdentifier::from_path("Death Note/Special.mkv").is_err());
+    }
 }
@@ -10,7 +10,13 @@ use crate::{
     AppError,
     ffmpeg::{PreviewsJob, TranscodeJob},
     intro_detection::IntroJob,
+    library::{health::HealthCheckTask, nfo_export::NfoExportTask, organizer::ImportTask},
+    metadata::{
+        imdb::import::ImdbImportTask, metadata_api::refresh::MetadataRefreshTask,
+        translations::TranslationTask,
+    },
     scan::LibraryScanTask,
+    scheduler::ScheduledJobTask,
     torrent::PendingTorrent,
     watch::WatchTask,
 };
@@ -24,6 +30,13 @@ pub enum TaskProgress {
     Torrent(ProgressStatus<PendingTorrent>),
     LibraryScan(ProgressStatus<LibraryScanTask>),
     IntroDetection(ProgressStatus<IntroJob>),
+    NfoExport(ProgressStatus<NfoExportTask>),
+    Import(ProgressStatus<ImportTask>),
+    ScheduledJob(ProgressStatus<ScheduledJobTask>),
+    HealthCheck(ProgressStatus<HealthCheckTask>),
+    MetadataRefresh(ProgressStatus<MetadataRefreshTask>),
+    ImdbImport(ProgressStatus<ImdbImportTask>),
+    Translation(ProgressStatus<TranslationTask>),
 }
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -403,6 +416,13 @@ pub struct TaskResource {
     pub library_scan_tasks: TaskStorage<LibraryScanTask>,
     pub torrent_tasks: TaskStorage<PendingTorrent>,
     pub intro_detection_tasks: TaskStorage<IntroJob>,
+    pub nfo_export_tasks: TaskStorage<NfoExportTask>,
+    pub import_tasks: TaskStorage<ImportTask>,
+    pub scheduled_job_tasks: TaskStorage<ScheduledJobTask>,
+    pub health_check_tasks: TaskStorage<HealthCheckTask>,
+    pub metadata_refresh_tasks: TaskStorage<MetadataRefreshTask>,
+    pub imdb_import_tasks: TaskStorage<ImdbImportTask>,
+    pub translation_tasks: TaskStorage<TranslationTask>,
     pub watch_sessions: TaskStorage<WatchTask>,
 }
 
@@ -419,6 +439,20 @@ pub struct TasksSnapshot {
     pub torrent_tasks: serde_json::Value,
     #[schema(value_type = Vec<Task<IntroJob>>)]
     pub intro_detection_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<NfoExportTask>>)]
+    pub nfo_export_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<ImportTask>>)]
+    pub import_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<ScheduledJobTask>>)]
+    pub scheduled_job_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<HealthCheckTask>>)]
+    pub health_check_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<MetadataRefreshTask>>)]
+    pub metadata_refresh_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<ImdbImportTask>>)]
+    pub imdb_import_tasks: serde_json::Value,
+    #[schema(value_type = Vec<Task<TranslationTask>>)]
+    pub translation_tasks: serde_json::Value,
     #[schema(value_type = Vec<Task<WatchTask>>)]
     pub watch_sessions: serde_json::Value,
 }
@@ -480,6 +514,13 @@ impl TaskResource {
             torrent_tasks: TaskStorage::new(progress_channel.clone()),
             previews_tasks: TaskStorage::new(progress_channel.clone()),
             intro_detection_tasks: TaskStorage::new(progress_channel.clone()),
+            nfo_export_tasks: TaskStorage::new(progress_channel.clone()),
+            import_tasks: TaskStorage::new(progress_channel.clone()),
+            scheduled_job_tasks: TaskStorage::new(progress_channel.clone()),
+            health_check_tasks: TaskStorage::new(progress_channel.clone()),
+            metadata_refresh_tasks: TaskStorage::new(progress_channel.clone()),
+            imdb_import_tasks: TaskStorage::new(progress_channel.clone()),
+            translation_tasks: TaskStorage::new(progress_channel.clone()),
             watch_sessions: TaskStorage::new(progress_channel.clone()),
             tracker: TaskTracker::new(),
             progress_channel,
@@ -494,6 +535,13 @@ impl TaskResource {
             library_scan_tasks: self.library_scan_tasks.tasks(),
             torrent_tasks: self.torrent_tasks.tasks(),
             intro_detection_tasks: self.intro_detection_tasks.tasks(),
+            nfo_export_tasks: self.nfo_export_tasks.tasks(),
+            import_tasks: self.import_tasks.tasks(),
+            scheduled_job_tasks: self.scheduled_job_tasks.tasks(),
+            health_check_tasks: self.health_check_tasks.tasks(),
+            metadata_refresh_tasks: self.metadata_refresh_tasks.tasks(),
+            imdb_import_tasks: self.imdb_import_tasks.tasks(),
+            translation_tasks: self.translation_tasks.tasks(),
             watch_sessions: self.watch_sessions.tasks(),
         }
     }
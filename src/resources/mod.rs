@@ -3,10 +3,7 @@ use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::Instrument;
 
-use crate::{
-    config::{self, APP_RESOURCES},
-    db::Db,
-};
+use crate::{config::APP_RESOURCES, db::Db, library::libraries::LibraryFolders};
 
 mod disks;
 
@@ -111,8 +108,11 @@ impl From<ContentQuery> for MediaDirContent {
 
 #[tracing::instrument(name = "fetch_resources", skip_all)]
 pub async fn fetch(db: Db) -> anyhow::Result<Resources> {
-    let (config::MovieFolders(movie_dirs), config::ShowFolders(show_dirs)) =
-        config::CONFIG.get_values();
+    let LibraryFolders {
+        movies: movie_dirs,
+        shows: show_dirs,
+        ..
+    } = LibraryFolders::load(&db).await;
     let db_meta = fs::metadata(&APP_RESOURCES.database_path).await?;
     let mut movie_media_dirs = Vec::with_capacity(movie_dirs.len());
     let mut show_media_dirs = Vec::with_capacity(show_dirs.len());
@@ -0,0 +1,490 @@
+//! Dry run of the parser and metadata matcher.
+//!
+//! Reproduces the decisions of the scan pipeline for the given paths without touching the database,
+//! which helps to debug naming issues before the real scan. Provider responses are not cached either
+
+use std::path::{Component, Path};
+
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{Db, DbActions},
+    library::{
+        ContentIdentifier, LibraryItem, Media,
+        libraries::{self, MediaLibrary},
+        media::Video,
+    },
+    metadata::{
+        MetadataProvider, ParentMediaType, metadata_stack::MetadataProvidersStack,
+        request_client::with_read_only_cache,
+    },
+    parser::{
+        Parser,
+        attributes::Attributes,
+        movie::{MovieIdent, MovieIdentifier},
+        show::{ShowIdent, ShowIdentifier},
+        tokenizer::{Token, Tokenizer},
+    },
+    scan::ScanConfig,
+};
+
+/// Weight of the title similarity in the match score, the rest is the release year
+const TITLE_WEIGHT: f64 = 0.8;
+
+#[derive(Debug, Clone, Deserialize, utoipa::ToSchema)]
+pub struct DryRunPayload {
+    pub paths: Vec<String>,
+    /// Content type of the paths, guessed from the library folders and file name when empty
+    pub content_type: Option<ParentMediaType>,
+    /// Query metadata providers
+    #[serde(default = "default_search")]
+    pub search: bool,
+}
+
+fn default_search() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum TokenInfo {
+    Symbol(String),
+    GroupStart(String),
+    ExplicitSeparator,
+    GroupEnd(String),
+}
+
+impl From<Token<'_>> for TokenInfo {
+    fn from(token: Token<'_>) -> Self {
+        match token {
+            Token::Symbol(symbol) => Self::Symbol(symbol.0.to_string()),
+            Token::GroupStart(c) => Self::GroupStart(c.to_string()),
+            Token::ExplicitSeparator => Self::ExplicitSeparator,
+            Token::GroupEnd(c) => Self::GroupEnd(c.to_string()),
+        }
+    }
+}
+
+/// Tokenizer output of a single path component
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ComponentTokens {
+    pub component: String,
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// Local content the scan would link the file to instead of searching providers
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct LocalMatch {
+    pub id: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct MatchCandidate {
+    pub metadata_id: String,
+    pub metadata_provider: MetadataProvider,
+    pub title: String,
+    pub release_date: Option<String>,
+    /// Similarity of the candidate to the parsed title and year, from 0 to 1
+    pub score: f64,
+    /// Candidate is picked by the scan
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ProviderSearch {
+    pub provider: MetadataProvider,
+    pub query: String,
+    pub candidates: Vec<MatchCandidate>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct DryRunResult {
+    pub path: String,
+    pub content_type: ParentMediaType,
+    /// Library that contains the path
+    pub library_id: Option<i64>,
+    pub components: Vec<ComponentTokens>,
+    /// Show identifier parsed from the file name, including the missing numbers
+    pub show_ident: Option<ShowIdent>,
+    /// Identifier used by the scan, files without it are skipped
+    pub identifier: Option<ContentIdentifier>,
+    pub attributes: Attributes,
+    /// Existing content that is reused by the scan, providers are not queried in this case
+    pub local_match: Option<LocalMatch>,
+    pub searches: Vec<ProviderSearch>,
+    pub warnings: Vec<String>,
+}
+
+fn tokenize_path(path: &Path) -> Vec<ComponentTokens> {
+    let mut components: Vec<_> = path
+        .components()
+        .filter_map(|c| match c {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        })
+        .collect();
+    // File extension is not fed into the parser
+    if let Some(stem) = path.file_stem()
+        && let Some(last) = components.last_mut()
+    {
+        *last = stem.to_string_lossy().to_string();
+    }
+    components
+        .into_iter()
+        .map(|component| {
+            let tokens = Tokenizer::new(&component)
+                .tokens()
+                .iter()
+                .copied()
+                .map(TokenInfo::from)
+                .collect();
+            ComponentTokens { component, tokens }
+        })
+        .collect()
+}
+
+fn normalized_words(title: &str) -> Vec<String> {
+    title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Dice coefficient of the title words combined with the release year match
+fn match_score(title: &str, year: Option<u16>, candidate: &str, release_date: Option<&str>) -> f64 {
+    let words = normalized_words(title);
+    let mut candidate_words = normalized_words(candidate);
+    let total = words.len() + candidate_words.len();
+    let title_score = if total == 0 {
+        0.
+    } else {
+        let common = words
+            .iter()
+            .filter(|word| {
+                let position = candidate_words.iter().position(|c| c == *word);
+                position.map(|i| candidate_words.swap_remove(i)).is_some()
+            })
+            .count();
+        2. * common as f64 / total as f64
+    };
+    let candidate_year = release_date
+        .and_then(|d| d.get(..4))
+        .and_then(|y| y.parse::<u16>().ok());
+    let year_score = match (year, candidate_year) {
+        (Some(a), Some(b)) if a == b => 1.,
+        (Some(a), Some(b)) if a.abs_diff(b) == 1 => 0.5,
+        (Some(_), Some(_)) => 0.,
+        _ => 0.5,
+    };
+    TITLE_WEIGHT * title_score + (1. - TITLE_WEIGHT) * year_score
+}
+
+/// Scan reuses local content when the best local title has the same amount of words
+fn is_local_match(query: &str, local_title: &str) -> bool {
+    local_title.split_whitespace().count() == query.split_whitespace().count()
+}
+
+fn guess_content_type(path: &Path, libraries: &[MediaLibrary]) -> ParentMediaType {
+    if let Some(library) = libraries.iter().find(|l| l.contains(path)) {
+        return library.settings.media_type;
+    }
+    match ShowIdentifier::from_path(path) {
+        Ok(show) if !show.absolute => ParentMediaType::Show,
+        _ => ParentMediaType::Movie,
+    }
+}
+
+async fn show_searches(
+    providers: &MetadataProvidersStack,
+    config: &ScanConfig,
+    title: &str,
+    year: Option<u16>,
+) -> Vec<ProviderSearch> {
+    let mut show_providers = providers.show_providers_in(config.provider_order.as_deref());
+    show_providers.retain(|p| p.provider_identifier() != MetadataProvider::Local);
+    let mut searches = Vec::new();
+    for provider in show_providers {
+        let mut search = ProviderSearch {
+            provider: provider.provider_identifier(),
+            query: title.to_string(),
+            candidates: Vec::new(),
+            error: None,
+        };
+        match provider.show_search(title, config.fetch_params).await {
+            Ok(results) => {
+                search.candidates = results
+                    .into_iter()
+                    .map(|show| MatchCandidate {
+                        score: match_score(title, year, &show.title, show.release_date.as_deref()),
+                        metadata_id: show.metadata_id,
+                        metadata_provider: show.metadata_provider,
+                        title: show.title,
+                        release_date: show.release_date,
+                        selected: false,
+                    })
+                    .collect()
+            }
+            Err(e) => search.error = Some(e.to_string()),
+        }
+        searches.push(search);
+    }
+    searches
+}
+
+async fn movie_searches(
+    providers: &MetadataProvidersStack,
+    config: &ScanConfig,
+    title: &str,
+    year: Option<u16>,
+) -> Vec<ProviderSearch> {
+    let movie_providers = providers.movie_providers_in(config.provider_order.as_deref());
+    let mut searches = Vec::new();
+    for provider in movie_providers {
+        let mut search = ProviderSearch {
+            provider: provider.provider_identifier(),
+            query: title.to_string(),
+            candidates: Vec::new(),
+            error: None,
+        };
+        match provider.movie_search(title, config.fetch_params).await {
+            Ok(results) => {
+                search.candidates = results
+                    .into_iter()
+                    .map(|movie| MatchCandidate {
+                        score: match_score(
+                            title,
+                            year,
+                            &movie.title,
+                            movie.release_date.as_deref(),
+                        ),
+                        metadata_id: movie.metadata_id,
+                        metadata_provider: movie.metadata_provider,
+                        title: movie.title,
+                        release_date: movie.release_date,
+                        selected: false,
+                    })
+                    .collect()
+            }
+            Err(e) => search.error = Some(e.to_string()),
+        }
+        searches.push(search);
+    }
+    searches
+}
+
+/// Identify the file like the scan does, by its name and the container title tag.
+///
+/// Files that don't exist are identified by the name only
+async fn identify<T: Media>(path: &Path) -> anyhow::Result<T> {
+    match Video::from_path(path).await {
+        Ok(video) => LibraryItem::<T>::identify(&video).await,
+        Err(_) => {
+            let file_name = path.file_name().context("get filename")?;
+            T::identify(file_name).map_err(|_| anyhow!("File name does not identify the content"))
+        }
+    }
+}
+
+/// Scan picks the first result of the first provider that found anything
+fn mark_selected(searches: &mut [ProviderSearch]) {
+    if let Some(candidate) = searches.iter_mut().find_map(|s| s.candidates.first_mut()) {
+        candidate.selected = true;
+    }
+}
+
+async fn dry_run_path(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    libraries: &[MediaLibrary],
+    base_config: &ScanConfig,
+    path: &str,
+    content_type: Option<ParentMediaType>,
+    search: bool,
+) -> DryRunResult {
+    let file_path = Path::new(path);
+    let library = libraries.iter().find(|l| l.contains(file_path));
+    let config = library.map_or_else(|| base_config.clone(), |l| l.scan_config(base_config));
+    let content_type = content_type.unwrap_or_else(|| guess_content_type(file_path, libraries));
+    let mut warnings = Vec::new();
+    let mut show_ident = None;
+
+    let file_name = file_path.file_name().map(Path::new).unwrap_or(file_path);
+    let identified = match content_type {
+        ParentMediaType::Show => {
+            show_ident = Some(Parser::parse_filename(file_name, ShowIdent::default()));
+            identify::<ShowIdentifier>(file_path).await.map(Into::into)
+        }
+        ParentMediaType::Movie => identify::<MovieIdentifier>(file_path).await.map(Into::into),
+    };
+    let identifier = match identified {
+        Ok(identifier) => Some(identifier),
+        Err(e) => {
+            warnings.push(format!(
+                "Content is not identified, scan skips the file: {e:#}"
+            ));
+            None
+        }
+    };
+    if let Some(ContentIdentifier::Show(show)) = &identifier
+        && show.absolute
+    {
+        warnings.push("Season is missing, episode number is treated as absolute".into());
+    }
+    let (title, year, attributes) = match &identifier {
+        Some(ContentIdentifier::Show(show)) => {
+            (show.title.clone(), show.year, show.attributes.clone())
+        }
+        Some(ContentIdentifier::Movie(movie)) => {
+            (movie.title.clone(), movie.year, movie.attributes.clone())
+        }
+        None => {
+            let attributes = match &show_ident {
+                Some(ident) => ident.attributes.clone(),
+                None => Parser::parse_filename(file_name, MovieIdent::default()).attributes,
+            };
+            (String::new(), None, attributes)
+        }
+    };
+
+    let local_match = match content_type {
+        _ if title.is_empty() => None,
+        ParentMediaType::Show => db
+            .search_show(&title)
+            .await
+            .ok()
+            .and_then(|shows| shows.into_iter().next())
+            .map(|show| LocalMatch {
+                id: show.metadata_id,
+                title: show.title,
+            }),
+        ParentMediaType::Movie => db
+            .search_movie(&title)
+            .await
+            .ok()
+            .and_then(|movies| movies.into_iter().next())
+            .map(|movie| LocalMatch {
+                id: movie.metadata_id,
+                title: movie.title,
+            }),
+    }
+    .filter(|local| is_local_match(&title, &local.title));
+
+    let mut searches = Vec::new();
+    if search && !title.is_empty() {
+        searches = match content_type {
+            ParentMediaType::Show => show_searches(providers, &config, &title, year).await,
+            ParentMediaType::Movie => movie_searches(providers, &config, &title, year).await,
+        };
+        if local_match.is_none() {
+            mark_selected(&mut searches);
+        }
+        if local_match.is_none() && !searches.iter().any(|s| !s.candidates.is_empty()) {
+            warnings.push("No provider found anything, scan will use fallback metadata".into());
+        }
+    }
+
+    DryRunResult {
+        path: path.to_string(),
+        content_type,
+        library_id: library.map(|l| l.id),
+        components: tokenize_path(file_path),
+        show_ident,
+        identifier,
+        attributes,
+        local_match,
+        searches,
+        warnings,
+    }
+}
+
+/// Parse and match the paths the way the scan does it, nothing is written to the database.
+///
+/// Provider searches can use cached responses but never store new ones
+pub async fn dry_run(
+    db: &Db,
+    providers: &MetadataProvidersStack,
+    payload: DryRunPayload,
+) -> anyhow::Result<Vec<DryRunResult>> {
+    let libraries = libraries::all(db).await?;
+    let base_config = ScanConfig::new_from_server_configuration();
+    let results = with_read_only_cache(async {
+        let mut results = Vec::with_capacity(payload.paths.len());
+        for path in &payload.paths {
+            results.push(
+                dry_run_path(
+                    db,
+                    providers,
+                    &libraries,
+                    &base_config,
+                    path,
+                    payload.content_type,
+                    payload.search,
+                )
+                .await,
+            );
+        }
+        results
+    })
+    .await;
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::parser::show::ShowIdentifier;
+
+    use super::{TokenInfo, identify, is_local_match, match_score, tokenize_path};
+
+    #[test]
+    fn candidate_score() {
+        let exact = match_score("The Office", Some(2005), "The Office", Some("2005-03-24"));
+        assert!(exact > 0.99);
+        let remake = match_score("The Office", Some(2005), "The Office", Some("2001-07-09"));
+        assert!(remake < exact);
+        let other = match_score("The Office", None, "Office Space", Some("1999-02-19"));
+        assert!(other < remake);
+        assert!(match_score("", None, "", None) < 0.2);
+    }
+
+    #[test]
+    fn local_match_rule() {
+        assert!(is_local_match("Dark", "Dark"));
+        assert!(!is_local_match("Dark", "Dark Matter"));
+    }
+
+    #[test]
+    fn path_tokens() {
+        let components = tokenize_path(Path::new("/media/Show Name/Show.Name.S01E02.mkv"));
+        assert_eq!(components.len(), 3);
+        assert_eq!(components[2].component, "Show.Name.S01E02");
+        assert!(
+            components[2]
+                .tokens
+                .iter()
+                .any(|t| matches!(t, TokenInfo::Symbol(s) if s == "S01E02"))
+        );
+    }
+
+    #[tokio::test]
+    async fn file_name_identification() {
+        let show: ShowIdentifier = identify(Path::new("/media/Other/Show.Name.S01E02.mkv"))
+            .await
+            .unwrap();
+        assert_eq!(
+            (show.title.as_str(), show.season, show.episode),
+            ("Show Name", 1, 2)
+        );
+        // Directory is not used and the episode number is never made up
+        assert!(
+            identify::<ShowIdentifier>(Path::new("/media/Show Name S01E02/Special.mkv"))
+                .await
+                .is_err()
+        );
+    }
+}
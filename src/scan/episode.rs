@@ -5,7 +5,10 @@ use tracing::instrument;
 
 use crate::{
     db::{Db, DbActions},
-    library::{LibraryItem, Media},
+    library::{
+        LibraryItem, Media,
+        numbering::{self, AbsoluteOrdering, EpisodeNumbering, ShowNumbering},
+    },
     metadata::{
         EpisodeMetadata, ParentMediaType, SeasonMetadata, ShowMetadata, ShowMetadataProvider,
     },
@@ -78,6 +81,15 @@ impl EpisodeScanner {
             MetadataLookupWithIds::New { .. } => None,
         };
 
+        let show_numbering = match show_id {
+            Some(show_id) => numbering::get(&self.db, show_id).await.unwrap_or_default(),
+            None => ShowNumbering::default(),
+        };
+        let mut scanner = self.clone();
+        scanner.config.fetch_params.ordering = show_numbering.ordering;
+        scanner
+            .apply_absolute_numbering(&show_numbering, &mut videos)
+            .await;
         videos.sort_unstable_by_key(|v| v.identifier.season);
 
         let mut handles: JoinSet<ResolvedSeason> = JoinSet::new();
@@ -86,7 +98,7 @@ impl EpisodeScanner {
             .map(Vec::from)
         {
             let season_number = season_videos.first().unwrap().identifier.season as usize;
-            let scanner = self.clone();
+            let scanner = scanner.clone();
             handles.spawn(async move {
                 scanner
                     .resolve_season(show_id, season_number, season_videos)
@@ -101,6 +113,48 @@ impl EpisodeScanner {
         }
     }
 
+    /// Translate absolute episode numbers into provider seasons unless the show follows file numbering
+    async fn apply_absolute_numbering(
+        &self,
+        show_numbering: &ShowNumbering,
+        videos: &mut [LibraryItem<ShowIdentifier>],
+    ) {
+        if !videos.iter().any(|v| v.identifier.absolute) {
+            return;
+        }
+        if show_numbering.numbering == EpisodeNumbering::File {
+            return;
+        }
+        let mut ordering = AbsoluteOrdering::new(&[], &show_numbering.mappings);
+        let is_mapped = |ordering: &AbsoluteOrdering| {
+            videos
+                .iter()
+                .filter(|v| v.identifier.absolute)
+                .all(|v| ordering.contains(v.identifier.episode as usize))
+        };
+        if !is_mapped(&ordering) {
+            for ShowProvider { provider, id } in self.providers.iter() {
+                match provider
+                    .absolute_episodes(id, self.config.fetch_params)
+                    .await
+                {
+                    Ok(episodes) if !episodes.is_empty() => {
+                        ordering = AbsoluteOrdering::new(&episodes, &show_numbering.mappings);
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!(
+                            "Failed to fetch absolute ordering from {}: {e}",
+                            provider.provider_identifier()
+                        );
+                    }
+                }
+            }
+        }
+        ordering.apply(videos);
+    }
+
     #[instrument(skip(self, season_videos), fields(season = season_number))]
     async fn resolve_season(
         &self,
@@ -255,6 +309,7 @@ impl EpisodeScanner {
                     .map(|v| v.source.video.path().to_path_buf())
                     .collect(),
                 content_type,
+                error: None,
             },
             videos.len(),
         );
@@ -0,0 +1,128 @@
+//! Artwork that already sits next to the library media.
+//!
+//! Naming follows Kodi conventions (the same names that nfo export writes), so
+//! custom artwork survives rescans and the scan works without network access.
+
+use std::path::{Path, PathBuf};
+
+const POSTER_NAMES: [&str; 2] = ["poster.jpg", "folder.jpg"];
+const BACKDROP_NAMES: [&str; 1] = ["fanart.jpg"];
+
+fn find_file(dir: &Path, names: impl IntoIterator<Item = impl AsRef<str>>) -> Option<PathBuf> {
+    names
+        .into_iter()
+        .map(|name| dir.join(name.as_ref()))
+        .find(|path| path.is_file())
+}
+
+/// Video directory if it is dedicated to this video (not the library root itself).
+fn own_dir<'a>(video: &'a Path, roots: &[PathBuf]) -> Option<&'a Path> {
+    let dir = video.parent()?;
+    (!roots.iter().any(|root| root == dir)).then_some(dir)
+}
+
+fn stem_file(video: &Path, suffix: &str) -> Option<PathBuf> {
+    let stem = video.file_stem()?.to_string_lossy();
+    let path = video.with_file_name(format!("{stem}-{suffix}.jpg"));
+    path.is_file().then_some(path)
+}
+
+/// `<stem>-poster.jpg`, or `poster.jpg` / `folder.jpg` in the movie's own folder
+pub fn movie_poster(video: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    stem_file(video, "poster").or_else(|| find_file(own_dir(video, roots)?, POSTER_NAMES))
+}
+
+/// `<stem>-fanart.jpg`, or `fanart.jpg` in the movie's own folder
+pub fn movie_backdrop(video: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    stem_file(video, "fanart").or_else(|| find_file(own_dir(video, roots)?, BACKDROP_NAMES))
+}
+
+/// Top level show directory inside the library folder.
+///
+/// `None` when episodes are placed right inside the library folder.
+pub fn show_dir(video: &Path, roots: &[PathBuf]) -> Option<PathBuf> {
+    let root = roots.iter().find(|root| video.starts_with(root))?;
+    let relative = video.strip_prefix(root).ok()?;
+    if relative.components().count() < 2 {
+        return None;
+    }
+    relative.components().next().map(|dir| root.join(dir))
+}
+
+/// `poster.jpg` or `folder.jpg` in the show directory
+pub fn show_poster(show_dir: &Path) -> Option<PathBuf> {
+    find_file(show_dir, POSTER_NAMES)
+}
+
+/// `fanart.jpg` in the show directory
+pub fn show_backdrop(show_dir: &Path) -> Option<PathBuf> {
+    find_file(show_dir, BACKDROP_NAMES)
+}
+
+/// `seasonNN-poster.jpg` in the show directory, `season-specials-poster.jpg` for specials
+pub fn season_poster(show_dir: &Path, season: usize) -> Option<PathBuf> {
+    let mut names = vec![format!("season{season:02}-poster.jpg")];
+    if season == 0 {
+        names.push("season-specials-poster.jpg".to_owned());
+    }
+    find_file(show_dir, names)
+}
+
+/// `<stem>-thumb.jpg` next to the episode video
+pub fn episode_thumb(video: &Path) -> Option<PathBuf> {
+    stem_file(video, "thumb")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn show_dir() {
+        let roots = [PathBuf::from("/media/shows")];
+        assert_eq!(
+            super::show_dir(Path::new("/media/shows/Show/Season 1/S01E01.mkv"), &roots),
+            Some(PathBuf::from("/media/shows/Show"))
+        );
+        assert_eq!(
+            super::show_dir(Path::new("/media/shows/Show/S01E01.mkv"), &roots),
+            Some(PathBuf::from("/media/shows/Show"))
+        );
+        assert_eq!(
+            super::show_dir(Path::new("/media/shows/Show S01E01.mkv"), &roots),
+            None
+        );
+        assert_eq!(
+            super::show_dir(Path::new("/elsewhere/Show/S01E01.mkv"), &roots),
+            None
+        );
+    }
+
+    #[test]
+    fn movie_artwork() {
+        let root = std::env::temp_dir().join(format!("local_artwork_{}", std::process::id()));
+        let movie_dir = root.join("Movie (2020)");
+        std::fs::create_dir_all(&movie_dir).unwrap();
+        std::fs::write(movie_dir.join("folder.jpg"), b"").unwrap();
+        std::fs::write(root.join("poster.jpg"), b"").unwrap();
+        std::fs::write(root.join("Other-fanart.jpg"), b"").unwrap();
+        let roots = [root.clone()];
+
+        let own = movie_dir.join("Movie.mkv");
+        assert_eq!(
+            super::movie_poster(&own, &roots),
+            Some(movie_dir.join("folder.jpg"))
+        );
+        assert_eq!(super::movie_backdrop(&own, &roots), None);
+
+        // Root level poster.jpg must not be shared between movies
+        let other = root.join("Other.mkv");
+        assert_eq!(super::movie_poster(&other, &roots), None);
+        assert_eq!(
+            super::movie_backdrop(&other, &roots),
+            Some(root.join("Other-fanart.jpg"))
+        );
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
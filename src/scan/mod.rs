@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 
 use crate::{
     config,
@@ -16,8 +19,11 @@ use crate::{
     scan::scan_progress::MetadataProgressEmitter,
 };
 
+/// Parser and matcher dry run that doesn't write anything
+pub mod dry_run;
 pub mod episode;
 pub mod fallback;
+pub mod local_artwork;
 mod merge;
 pub mod movie;
 pub mod reconcile;
@@ -109,6 +115,8 @@ pub struct ScanConfig {
     pub max_show_concurrency: usize,
     pub max_movie_concurrency: usize,
     pub max_asset_concurrency: usize,
+    /// Library specific metadata providers order, server wide order when empty
+    pub provider_order: Option<Vec<MetadataProvider>>,
 }
 
 impl Default for ScanConfig {
@@ -119,6 +127,7 @@ impl Default for ScanConfig {
             use_season_episodes: config::scan::UseSeasonEpisodes::default().0,
             max_movie_concurrency: config::scan::MaxMovieConcurrency::default().0,
             max_asset_concurrency: config::scan::MaxAssetConcurrency::default().0,
+            provider_order: None,
         }
     }
 }
@@ -133,11 +142,12 @@ impl ScanConfig {
             config::scan::MaxAssetConcurrency(max_asset_concurrency),
         ) = config::CONFIG.get_values();
         Self {
-            fetch_params: FetchParams { lang },
+            fetch_params: FetchParams::new(lang),
             max_show_concurrency,
             use_season_episodes,
             max_movie_concurrency,
             max_asset_concurrency,
+            provider_order: None,
         }
     }
 }
@@ -152,7 +162,12 @@ pub enum AssetKind {
 pub enum AssetTaskSource {
     Url(String),
     VideoFrame(Source),
-    UrlWithFrameFallback { url: String, source: Source },
+    UrlWithFrameFallback {
+        url: String,
+        source: Source,
+    },
+    /// Image that already exists next to the media
+    LocalFile(PathBuf),
 }
 
 #[derive(Debug)]
@@ -185,10 +200,18 @@ impl AssetTaskSource {
                 save_asset_from_url_with_frame_fallback(http_client, url.parse()?, asset, &source)
                     .await
             }
+            AssetTaskSource::LocalFile(path) => save_asset_from_file(asset, &path).await,
         }
     }
 }
 
+#[tracing::instrument(level = "debug", skip(asset), fields(asset = %asset.path().display()))]
+async fn save_asset_from_file(asset: impl FileAsset, path: &Path) -> anyhow::Result<()> {
+    let mut file = tokio::fs::File::open(path).await?;
+    asset.save_from_reader(&mut file).await?;
+    Ok(())
+}
+
 #[tracing::instrument(level = "debug", skip_all, fields(asset = %asset.path().display()))]
 async fn save_asset_from_frame(asset: impl FileAsset, source: &Source) -> anyhow::Result<()> {
     use tokio::fs;
@@ -254,9 +277,9 @@ pub(crate) async fn insert_roles(
     }
 
     #[derive(Debug, Hash, Eq, PartialEq)]
-    struct MapKey<'a> {
+    struct MapKey {
         provider: MetadataProvider,
-        provider_id: &'a str,
+        provider_id: String,
     }
 
     let local_actors = DbQueryBuilder::new(
@@ -277,13 +300,13 @@ pub(crate) async fn insert_roles(
     .fetch_all(&mut **tx)
     .await?;
 
-    let local_actors_map: HashMap<_, _> = local_actors
-        .iter()
+    let mut local_actors_map: HashMap<_, _> = local_actors
+        .into_iter()
         .map(|v| {
             (
                 MapKey {
                     provider: v.external_metadata_provider,
-                    provider_id: &v.external_metadata_id,
+                    provider_id: v.external_metadata_id,
                 },
                 v.id,
             )
@@ -291,13 +314,16 @@ pub(crate) async fn insert_roles(
         .collect();
 
     for cast in cast {
-        let actor_id = match local_actors_map.get(&MapKey {
+        let key = MapKey {
             provider: cast.metadata_provider,
-            provider_id: &cast.metadata_id,
-        }) {
+            provider_id: cast.metadata_id.clone(),
+        };
+        let actor_id = match local_actors_map.get(&key) {
             Some(id) => *id,
             None => {
                 let actor_id = tx.insert_actor(&cast.into_db_actor()).await?;
+                // Same person can be credited both in the cast and in the crew
+                local_actors_map.insert(key, actor_id);
                 if let Some(poster_url) = cast.person_poster {
                     asset_tasks.push(AssetSaveTask {
                         kind: AssetKind::Poster(PosterAsset::new(
@@ -316,6 +342,7 @@ pub(crate) async fn insert_roles(
             actor_id,
             metadata_id,
             character: cast.role.map(|r| r.character),
+            job: cast.job,
         })
         .await?;
     }
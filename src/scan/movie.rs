@@ -6,7 +6,7 @@
 //! 3. `flush_to_db` — one transaction for all movies
 //! 4. `save_assets` — parallel asset downloads/frame extracts
 
-use std::{sync::Arc, time::Duration};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use tokio::{sync::Semaphore, task::JoinSet};
 use tracing::{Instrument, debug_span};
@@ -16,14 +16,17 @@ use crate::{
     library::{
         LibraryItem,
         assets::{BackdropAsset, BackdropContentType, PosterAsset, PosterContentType},
+        collections,
+        versions::{self, VersionCandidate, VersionLabel},
     },
     metadata::{
-        ExternalIdMetadata, MovieMetadata, MovieMetadataProvider, ParentMediaType,
-        metadata_api::asset_saver::AssetTasks, metadata_stack::MetadataProvidersStack,
+        CollectionMetadata, ExternalIdMetadata, MovieMetadata, MovieMetadataProvider,
+        ParentMediaType, metadata_api::asset_saver::AssetTasks,
+        metadata_stack::MetadataProvidersStack,
     },
     parser::movie::MovieIdentifier,
     scan::{
-        ContentScanner, insert_roles,
+        ContentScanner, insert_roles, local_artwork,
         scan_progress::{FailedContent, MetadataProgressEmitter},
     },
 };
@@ -43,14 +46,22 @@ pub struct MovieScanner {
     db: Db,
     providers: &'static MetadataProvidersStack,
     config: ScanConfig,
+    /// Library folders, used to look up artwork next to the media
+    library_roots: Vec<PathBuf>,
 }
 
 impl MovieScanner {
-    pub fn new(db: Db, providers: &'static MetadataProvidersStack, config: ScanConfig) -> Self {
+    pub fn new(
+        db: Db,
+        providers: &'static MetadataProvidersStack,
+        config: ScanConfig,
+        library_roots: Vec<PathBuf>,
+    ) -> Self {
         Self {
             db,
             providers,
             config,
+            library_roots,
         }
     }
 
@@ -66,7 +77,9 @@ impl MovieScanner {
                 .cmp(&b.identifier.title.to_lowercase())
         });
 
-        let movie_providers = self.providers.movie_providers();
+        let movie_providers = self
+            .providers
+            .movie_providers_in(self.config.provider_order.as_deref());
         let movie_providers: Arc<[&'static (dyn MovieMetadataProvider + Send + Sync)]> =
             Arc::from(movie_providers.into_boxed_slice());
 
@@ -175,6 +188,9 @@ impl ContentScanner for MovieScanner {
                     let movie_id = tx
                         .insert_movie(&metadata.into_db_movie(metadata_id, duration))
                         .await?;
+                    if let Some(collection) = &metadata.collection {
+                        save_collection(tx, asset_tasks, collection).await?;
+                    }
                     if let Some(cast) = metadata.cast {
                         insert_roles(tx, metadata_id, cast, asset_tasks).await?;
                     }
@@ -190,38 +206,46 @@ impl ContentScanner for MovieScanner {
                             })
                             .await;
                     }
+                    if let Some(ratings) = &metadata.ratings {
+                        tx.replace_content_ratings(metadata_id, ratings).await?;
+                    }
                     for genre in metadata.genres.into_iter().flatten() {
                         let _ = tx.insert_content_genre(metadata_id, genre.into()).await;
                     }
                     let first_source = videos.first().map(|v| v.source.clone());
-                    if let Some(url) = poster {
-                        let task_source = match first_source.clone() {
-                            Some(source) => AssetTaskSource::UrlWithFrameFallback { url, source },
-                            None => AssetTaskSource::Url(url),
-                        };
-                        asset_tasks.push(AssetSaveTask {
-                            kind: AssetKind::Poster(PosterAsset::new(
-                                movie_id,
-                                PosterContentType::Movie,
-                            )),
-                            source: task_source,
-                        });
-                    } else if let Some(source) = first_source.clone() {
+                    let first_video = first_source.as_ref().map(|s| s.video.path());
+                    let local_poster = first_video
+                        .and_then(|v| local_artwork::movie_poster(v, &self.library_roots));
+                    let local_backdrop = first_video
+                        .and_then(|v| local_artwork::movie_backdrop(v, &self.library_roots));
+                    let poster_source = match (local_poster, poster, first_source.clone()) {
+                        (Some(path), _, _) => Some(AssetTaskSource::LocalFile(path)),
+                        (None, Some(url), Some(source)) => {
+                            Some(AssetTaskSource::UrlWithFrameFallback { url, source })
+                        }
+                        (None, Some(url), None) => Some(AssetTaskSource::Url(url)),
+                        (None, None, Some(source)) => Some(AssetTaskSource::VideoFrame(source)),
+                        (None, None, None) => None,
+                    };
+                    if let Some(source) = poster_source {
                         asset_tasks.push(AssetSaveTask {
                             kind: AssetKind::Poster(PosterAsset::new(
                                 movie_id,
                                 PosterContentType::Movie,
                             )),
-                            source: AssetTaskSource::VideoFrame(source),
+                            source,
                         });
                     }
-                    if let Some(url) = backdrop {
+                    let backdrop_source = local_backdrop
+                        .map(AssetTaskSource::LocalFile)
+                        .or(backdrop.map(AssetTaskSource::Url));
+                    if let Some(source) = backdrop_source {
                         asset_tasks.push(AssetSaveTask {
                             kind: AssetKind::Backdrop(BackdropAsset::new(
                                 movie_id,
                                 BackdropContentType::Movie,
                             )),
-                            source: AssetTaskSource::Url(url),
+                            source,
                         });
                     }
                     metadata_id
@@ -238,12 +262,64 @@ impl ContentScanner for MovieScanner {
                 tx.update_video_metadata_id(video.source.id, metadata_id)
                     .await?;
             }
+
+            let has_prime = sqlx::query!(
+                "SELECT id FROM videos WHERE metadata_id = ? AND is_prime = true",
+                metadata_id
+            )
+            .fetch_optional(&mut **tx)
+            .await?
+            .is_some();
+            if !has_prime {
+                let candidates: Vec<_> = videos
+                    .iter()
+                    .map(|v| VersionCandidate {
+                        video_id: v.source.id,
+                        version: VersionLabel::new(&v.identifier.attributes),
+                        is_prime: false,
+                    })
+                    .collect();
+                if let Some(prime_id) = versions::pick_default(&candidates, None) {
+                    tx.set_prime_video(metadata_id, prime_id).await?;
+                }
+            }
         }
 
         Ok(())
     }
 }
 
+/// Save collection of the new movie, downloading collection artwork for the new collections
+pub(crate) async fn save_collection(
+    tx: &mut DbTransaction,
+    asset_tasks: &mut AssetTasks,
+    collection: &CollectionMetadata,
+) -> sqlx::Result<()> {
+    let (collection_id, is_new) = collections::save(tx, collection).await?;
+    if !is_new {
+        return Ok(());
+    }
+    if let Some(url) = &collection.poster {
+        asset_tasks.push(AssetSaveTask {
+            kind: AssetKind::Poster(PosterAsset::new(
+                collection_id,
+                PosterContentType::Collection,
+            )),
+            source: AssetTaskSource::Url(url.clone()),
+        });
+    }
+    if let Some(url) = &collection.backdrop {
+        asset_tasks.push(AssetSaveTask {
+            kind: AssetKind::Backdrop(BackdropAsset::new(
+                collection_id,
+                BackdropContentType::Collection,
+            )),
+            source: AssetTaskSource::Url(url.clone()),
+        });
+    }
+    Ok(())
+}
+
 async fn fetch_single_movie_chunk(
     db: Db,
     config: ScanConfig,
@@ -293,6 +369,20 @@ async fn fetch_single_movie_chunk(
                     else {
                         continue;
                     };
+                    // Parts are refetched once they get stale
+                    if let Some(collection) = movie_metadata.collection.as_mut()
+                        && !collections::has_fresh_parts(&db, collection)
+                            .await
+                            .unwrap_or(false)
+                    {
+                        match provider
+                            .collection(&collection.metadata_id, config.fetch_params)
+                            .await
+                        {
+                            Ok(full) => *collection = full,
+                            Err(e) => tracing::warn!("Failed to fetch movie collection: {e}"),
+                        }
+                    }
                     let mut external_ids = Vec::new();
                     movie_metadata
                         .external_ids
@@ -334,6 +424,7 @@ async fn fetch_single_movie_chunk(
                     .map(|v| v.source.video.path().to_path_buf())
                     .collect(),
                 content_type: ParentMediaType::Movie,
+                error: None,
             },
             videos.len(),
         );
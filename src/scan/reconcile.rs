@@ -1,6 +1,6 @@
 //! Diff the in-memory library against the database, fetch metadata for newly added
-//! videos through the show/movie scanners, flush the resolved trees to the database, and
-//! save the associated assets to disk.
+//! videos through the show/movie scanners, flush the resolved trees to the database,
+//! and save the associated assets to disk.
 
 use std::{collections::HashSet, sync::Mutex};
 
@@ -9,12 +9,21 @@ use tokio::task::JoinSet;
 
 use crate::{
     db::{Db, DbActions},
-    library::{Library, LibraryItem, Media},
-    metadata::{metadata_api::asset_saver::AssetTasks, metadata_stack::MetadataProvidersStack},
+    library::{
+        Library, LibraryItem, Media, health,
+        libraries::{self, LibraryFolders, MediaLibrary},
+        statistics,
+    },
+    metadata::{
+        ParentMediaType, metadata_api::asset_saver::AssetTasks,
+        metadata_stack::MetadataProvidersStack,
+    },
 };
 
 use super::{
-    ContentScanner, ScanConfig, movie::MovieScanner, scan_progress::ScanProgressEmitter,
+    ContentScanner, ScanConfig,
+    movie::MovieScanner,
+    scan_progress::{FailedContent, MetadataProgressEmitter, ScanProgressEmitter},
     show::ShowScanner,
 };
 
@@ -44,8 +53,12 @@ impl LibraryReconciler {
         }
     }
 
+    /// Returns ids of the newly added videos
     #[tracing::instrument(name = "reconcile", skip_all)]
-    pub async fn reconciliate(self, config: ScanConfig) -> crate::Result<()> {
+    pub async fn reconciliate(self, config: ScanConfig) -> crate::Result<Vec<i64>> {
+        let media_libraries = libraries::all(self.db).await?;
+        let folders = LibraryFolders::new(&media_libraries);
+
         let db_movies_videos = sqlx::query!(
             "SELECT videos.id FROM videos WHERE videos.metadata_id IN (SELECT movies.metadata_id FROM movies);"
         )
@@ -60,14 +73,13 @@ impl LibraryReconciler {
             .collect_new_videos(
                 movies,
                 db_movies_videos.iter().map(|d| d.id).collect(),
+                ParentMediaType::Movie,
                 "begin movies removal tx",
             )
             .await?;
 
         let max_asset_concurrency = config.max_asset_concurrency;
 
-        let movie_scanner = MovieScanner::new(self.db.clone(), self.providers, config.clone());
-
         let db_episodes_videos = sqlx::query!(
             "SELECT videos.id FROM videos WHERE videos.metadata_id IN (SELECT episodes.metadata_id FROM episodes);"
         )
@@ -82,43 +94,81 @@ impl LibraryReconciler {
             .collect_new_videos(
                 episodes,
                 db_episodes_videos.iter().map(|d| d.id).collect(),
+                ParentMediaType::Show,
                 "begin episodes removal tx",
             )
             .await?;
 
-        let show_scanner = ShowScanner::new(self.db.clone(), self.providers, config);
+        let added_videos: Vec<i64> = new_movies
+            .iter()
+            .map(|v| v.source.id)
+            .chain(new_episodes.iter().map(|v| v.source.id))
+            .collect();
         let metadata_progress = self
             .progress
             .metadata_progress_emitter(new_episodes.len() + new_movies.len());
 
+        let movie_groups: Vec<_> = group_by_library(new_movies, &media_libraries, &config)
+            .into_iter()
+            .map(|(config, videos)| {
+                let scanner = MovieScanner::new(
+                    self.db.clone(),
+                    self.providers,
+                    config,
+                    folders.movies.clone(),
+                );
+                (scanner, videos)
+            })
+            .collect();
+        let show_groups: Vec<_> = group_by_library(new_episodes, &media_libraries, &config)
+            .into_iter()
+            .map(|(config, videos)| {
+                let scanner = ShowScanner::new(
+                    self.db.clone(),
+                    self.providers,
+                    config,
+                    folders.shows.clone(),
+                );
+                (scanner, videos)
+            })
+            .collect();
+
         let (resolved_shows, resolved_movies) = tokio::join!(
-            show_scanner.resolve(new_episodes, metadata_progress.clone()),
-            movie_scanner.resolve(new_movies, metadata_progress),
+            resolve_groups(show_groups, metadata_progress.clone()),
+            resolve_groups(movie_groups, metadata_progress),
         );
 
         let mut tasks = AssetTasks::new(self.http_client.clone());
         let mut tx = self.db.begin().await?;
-        show_scanner
-            .flush_to_db(&mut tx, &mut tasks, resolved_shows)
-            .await?;
-        movie_scanner
-            .flush_to_db(&mut tx, &mut tasks, resolved_movies)
-            .await?;
+        for (show_scanner, resolved) in resolved_shows {
+            show_scanner
+                .flush_to_db(&mut tx, &mut tasks, resolved)
+                .await?;
+        }
+        for (movie_scanner, resolved) in resolved_movies {
+            movie_scanner
+                .flush_to_db(&mut tx, &mut tasks, resolved)
+                .await?;
+        }
         tx.commit().await?;
         let assets_progress = self.progress.assets_progress_emitter(tasks.len());
         tasks.save(max_asset_concurrency, assets_progress).await;
         self.progress.finish_scan();
         tracing::info!("Finished library reconciliation");
-        Ok(())
+        Ok(added_videos)
     }
 
     /// Diffs the in-memory `items` against the videos already known to the database,
     /// removing DB entries for videos that no longer exist on disk and returning the freshly
     /// added videos whose container metadata could be read (invalid ones are skipped).
+    ///
+    /// New videos are only probed here, the ones that can't be read at all are reported in the
+    /// scan failed content. Non fatal issues are saved for the health report.
     async fn collect_new_videos<I>(
         &self,
         items: Vec<LibraryItem<I>>,
         db_video_ids: Vec<i64>,
+        content_type: ParentMediaType,
         removal_context: &'static str,
     ) -> crate::Result<Vec<LibraryItem<I>>>
     where
@@ -139,26 +189,101 @@ impl LibraryReconciler {
         let mut set = JoinSet::new();
         for item in items.into_iter().filter(|l| !db_ids.contains(&l.source.id)) {
             set.spawn(async move {
-                match item.source.video.metadata().await {
-                    Ok(_) => Some(item),
-                    Err(e) => {
-                        tracing::warn!(
-                            path = ?item.source.video.path().display(), "Skipping invalid video: {e}",
-                        );
-                        None
-                    }
-                }
+                // Full decode pass is left to the health check task
+                let issues = health::probe_video(&item.source.video).await;
+                (item, issues)
             });
         }
 
+        let mut checks = Vec::new();
+        let mut probed = Vec::new();
         while let Some(v) = set.join_next().await {
-            match v {
-                Ok(Some(item)) => new_items.push(item),
-                Ok(None) => {}
+            let (item, issues) = match v {
+                Ok(v) => v,
                 Err(e) => panic!("metadata retrieve panicked: {}", e),
+            };
+            let path = item.source.video.path();
+            if !issues.is_empty() {
+                let error = issues
+                    .iter()
+                    .map(|i| i.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                tracing::warn!(path = ?path.display(), "Video failed the health check: {error}");
+            }
+            let fatal: Vec<_> = issues.iter().filter(|i| i.is_fatal()).collect();
+            if fatal.is_empty() {
+                probed.push((item.source.id, item.source.video.clone()));
+                checks.push((item.source.id, issues));
+                new_items.push(item);
+                continue;
             }
+            let error = fatal
+                .iter()
+                .map(|i| i.message.as_str())
+                .collect::<Vec<_>>()
+                .join("; ");
+            tracing::warn!(path = ?path.display(), "Skipping invalid video");
+            self.progress.dispatch_unhealthy(FailedContent {
+                title: item.identifier.title().to_owned(),
+                videos: vec![path.to_path_buf()],
+                content_type,
+                error: Some(error),
+            });
+            checks.push((item.source.id, issues));
+        }
+
+        if let Err(e) = health::save_all(self.db, &checks).await {
+            tracing::error!("Failed to save video health issues: {e}");
+        }
+        let probes: Vec<_> = probed
+            .iter()
+            .filter_map(|(id, video)| Some((*id, video.cached_metadata()?)))
+            .collect();
+        if let Err(e) = statistics::save_probes(self.db, &probes).await {
+            tracing::error!("Failed to save video probes: {e}");
         }
 
         Ok(new_items)
     }
 }
+
+/// Splits videos by the library they belong to, each group is scanned with its library settings.
+///
+/// Videos outside of any library are scanned with the server wide settings.
+fn group_by_library<I: Media>(
+    items: Vec<LibraryItem<I>>,
+    media_libraries: &[MediaLibrary],
+    config: &ScanConfig,
+) -> Vec<(ScanConfig, Vec<LibraryItem<I>>)> {
+    let mut groups: Vec<(Option<i64>, ScanConfig, Vec<LibraryItem<I>>)> = Vec::new();
+    for item in items {
+        let library = media_libraries
+            .iter()
+            .find(|l| l.contains(item.source.video.path()));
+        let library_id = library.map(|l| l.id);
+        match groups.iter_mut().find(|(id, ..)| *id == library_id) {
+            Some((_, _, videos)) => videos.push(item),
+            None => {
+                let config = library.map_or_else(|| config.clone(), |l| l.scan_config(config));
+                groups.push((library_id, config, vec![item]));
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, config, videos)| (config, videos))
+        .collect()
+}
+
+async fn resolve_groups<S: ContentScanner>(
+    groups: Vec<(S, Vec<LibraryItem<S::Identifier>>)>,
+    progress: MetadataProgressEmitter,
+) -> Vec<(S, Vec<S::Resolved>)> {
+    let mut out = Vec::with_capacity(groups.len());
+    for (scanner, videos) in groups {
+        let resolved = scanner.resolve(videos, progress.clone()).await;
+        out.push((scanner, resolved));
+    }
+    out
+}
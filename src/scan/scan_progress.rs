@@ -10,13 +10,15 @@ use crate::{
     progress::ProgressDispatcher,
 };
 
-/// Failed metadata fetch attempt
+/// Content that failed metadata fetch or health check
 #[derive(Debug, Clone, serde::Serialize, utoipa::ToSchema)]
 pub struct FailedContent {
     pub title: String,
     #[schema(value_type = Vec<String>)]
     pub videos: Vec<PathBuf>,
     pub content_type: ParentMediaType,
+    /// Why the content failed, empty when metadata is not found
+    pub error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
@@ -38,6 +40,8 @@ pub enum ProgressChunk {
         fail_count: usize,
         event_result: FetchResult,
     },
+    /// New video failed the health check
+    UnhealthyVideo { content: FailedContent },
     /// At this stage fetched metadata is being saved to database
     MetadataSave,
     /// Assets are being saved to the disk.
@@ -66,6 +70,15 @@ impl ScanProgressEmitter {
         dispatcher.finish();
     }
 
+    pub fn dispatch_unhealthy(&self, content: FailedContent) {
+        self.dispatch.progress_with_update(
+            ProgressChunk::UnhealthyVideo {
+                content: content.clone(),
+            },
+            |task| task.kind.failed_content.push(content),
+        );
+    }
+
     pub fn assets_progress_emitter(&self, asset_count: usize) -> AssetProgressEmitter {
         AssetProgressEmitter {
             total_count: asset_count,
@@ -7,7 +7,7 @@
 //! 4. `flush_to_db` — sequential DB inserts, one transaction per show
 //! 5. `save_assets` — parallel asset downloads/frame extracts
 
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use tokio::{sync::Semaphore, task::JoinSet};
 use tracing::{Instrument, debug_span};
@@ -23,7 +23,7 @@ use crate::{
         metadata_api::asset_saver::AssetTasks, metadata_stack::MetadataProvidersStack,
     },
     parser::show::ShowIdentifier,
-    scan::{ContentScanner, insert_roles, scan_progress::MetadataProgressEmitter},
+    scan::{ContentScanner, insert_roles, local_artwork, scan_progress::MetadataProgressEmitter},
 };
 
 use super::{
@@ -42,14 +42,22 @@ pub struct ShowScanner {
     db: Db,
     providers: &'static MetadataProvidersStack,
     config: ScanConfig,
+    /// Library folders, used to look up artwork next to the media
+    library_roots: Vec<PathBuf>,
 }
 
 impl ShowScanner {
-    pub fn new(db: Db, providers: &'static MetadataProvidersStack, config: ScanConfig) -> Self {
+    pub fn new(
+        db: Db,
+        providers: &'static MetadataProvidersStack,
+        config: ScanConfig,
+        library_roots: Vec<PathBuf>,
+    ) -> Self {
         Self {
             db,
             providers,
             config,
+            library_roots,
         }
     }
 
@@ -64,7 +72,9 @@ impl ShowScanner {
                 .cmp(&b.identifier.title.to_lowercase())
         });
 
-        let mut show_providers = self.providers.show_providers();
+        let mut show_providers = self
+            .providers
+            .show_providers_in(self.config.provider_order.as_deref());
         show_providers.retain(|p| p.provider_identifier() != MetadataProvider::Local);
         let show_providers: Arc<[&'static (dyn ShowMetadataProvider + Send + Sync)]> =
             Arc::from(show_providers.into_boxed_slice());
@@ -118,7 +128,9 @@ impl ShowScanner {
         progress: MetadataProgressEmitter,
     ) -> Vec<ResolvedShow> {
         let semaphore = Arc::new(Semaphore::new(self.config.max_show_concurrency));
-        let show_providers = self.providers.show_providers();
+        let show_providers = self
+            .providers
+            .show_providers_in(self.config.provider_order.as_deref());
         let mut handles: JoinSet<ResolvedShow> = JoinSet::new();
 
         for chunk in chunks {
@@ -198,6 +210,12 @@ impl ContentScanner for ShowScanner {
         let _enter = span.enter();
 
         for resolved in resolved_shows {
+            let show_dir = resolved
+                .seasons
+                .iter()
+                .flat_map(|s| &s.episodes)
+                .flat_map(|e| &e.videos)
+                .find_map(|v| local_artwork::show_dir(v.source.video.path(), &self.library_roots));
             let show_id = match resolved.show_lookup {
                 MetadataLookupWithIds::New {
                     metadata,
@@ -230,25 +248,38 @@ impl ContentScanner for ShowScanner {
                             );
                         }
                     }
+                    if let Some(ratings) = &metadata.ratings {
+                        tx.replace_content_ratings(metadata_id, ratings).await?;
+                    }
                     for genre in metadata.genres.into_iter().flatten() {
                         let _ = tx.insert_content_genre(metadata_id, genre.into()).await;
                     }
-                    if let Some(url) = poster {
+                    let poster_source = show_dir
+                        .as_deref()
+                        .and_then(local_artwork::show_poster)
+                        .map(AssetTaskSource::LocalFile)
+                        .or(poster.map(AssetTaskSource::Url));
+                    if let Some(source) = poster_source {
                         asset_tasks.push(AssetSaveTask {
                             kind: AssetKind::Poster(PosterAsset::new(
                                 show_id,
                                 PosterContentType::Show,
                             )),
-                            source: AssetTaskSource::Url(url),
+                            source,
                         });
                     }
-                    if let Some(url) = backdrop {
+                    let backdrop_source = show_dir
+                        .as_deref()
+                        .and_then(local_artwork::show_backdrop)
+                        .map(AssetTaskSource::LocalFile)
+                        .or(backdrop.map(AssetTaskSource::Url));
+                    if let Some(source) = backdrop_source {
                         asset_tasks.push(AssetSaveTask {
                             kind: AssetKind::Backdrop(BackdropAsset::new(
                                 show_id,
                                 BackdropContentType::Show,
                             )),
-                            source: AssetTaskSource::Url(url),
+                            source,
                         });
                     }
                     show_id
@@ -262,6 +293,9 @@ impl ContentScanner for ShowScanner {
                 let season_id = match lookup {
                     MetadataLookup::New { metadata } => {
                         let poster = metadata.poster.clone();
+                        let local_poster = show_dir
+                            .as_deref()
+                            .and_then(|dir| local_artwork::season_poster(dir, metadata.number));
                         let metadata_id = tx.insert_metadata(&metadata.into_db_metadata()).await?;
                         let season_id = tx
                             .insert_season(metadata.into_db_season(metadata_id, show_id))
@@ -269,13 +303,16 @@ impl ContentScanner for ShowScanner {
                         if let Some(cast) = metadata.cast {
                             insert_roles(tx, metadata_id, cast, asset_tasks).await?;
                         }
-                        if let Some(url) = poster {
+                        let poster_source = local_poster
+                            .map(AssetTaskSource::LocalFile)
+                            .or(poster.map(AssetTaskSource::Url));
+                        if let Some(source) = poster_source {
                             asset_tasks.push(AssetSaveTask {
                                 kind: AssetKind::Poster(PosterAsset::new(
                                     season_id,
                                     PosterContentType::Season,
                                 )),
-                                source: AssetTaskSource::Url(url.into()),
+                                source,
                             });
                         }
                         season_id
@@ -318,28 +355,30 @@ impl ContentScanner for ShowScanner {
                                 insert_roles(tx, metadata_id, cast, asset_tasks).await?;
                             }
                             let first_source = videos.first().map(|v| v.source.clone());
-                            if let Some(url) = poster {
-                                let task_source = match first_source {
-                                    Some(source) => AssetTaskSource::UrlWithFrameFallback {
+                            let local_thumb = first_source
+                                .as_ref()
+                                .and_then(|s| local_artwork::episode_thumb(s.video.path()));
+                            let poster_source = match (local_thumb, poster, first_source) {
+                                (Some(path), _, _) => Some(AssetTaskSource::LocalFile(path)),
+                                (None, Some(url), Some(source)) => {
+                                    Some(AssetTaskSource::UrlWithFrameFallback {
                                         url: url.into(),
                                         source,
-                                    },
-                                    None => AssetTaskSource::Url(url.into()),
-                                };
-                                asset_tasks.push(AssetSaveTask {
-                                    kind: AssetKind::Poster(PosterAsset::new(
-                                        episode_id,
-                                        PosterContentType::Episode,
-                                    )),
-                                    source: task_source,
-                                });
-                            } else if let Some(source) = first_source {
+                                    })
+                                }
+                                (None, Some(url), None) => Some(AssetTaskSource::Url(url.into())),
+                                (None, None, Some(source)) => {
+                                    Some(AssetTaskSource::VideoFrame(source))
+                                }
+                                (None, None, None) => None,
+                            };
+                            if let Some(source) = poster_source {
                                 asset_tasks.push(AssetSaveTask {
                                     kind: AssetKind::Poster(PosterAsset::new(
                                         episode_id,
                                         PosterContentType::Episode,
                                     )),
-                                    source: AssetTaskSource::VideoFrame(source),
+                                    source,
                                 });
                             }
                             metadata_id
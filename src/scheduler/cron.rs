@@ -0,0 +1,194 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::Context;
+use time::{Date, Duration, OffsetDateTime, Time};
+
+/// Maximum search window of the next run time
+const MAX_SEARCH_DAYS: i64 = 366 * 5;
+
+/// Five field cron expression: minute, hour, day of month, month and day of week.
+///
+/// Supports `*`, values, ranges (`1-5`), lists (`1,15`) and steps (`*/15`, `0-30/10`).
+/// Day of week is 0-7 where both 0 and 7 are Sunday.
+/// Expressions are evaluated in UTC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    source: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Day of month is restricted
+    days_restricted: bool,
+    /// Day of week is restricted
+    weekdays_restricted: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> anyhow::Result<u64> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().context("parse step")?;
+                anyhow::ensure!(step > 0, "step must be positive");
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (
+                    start.parse().context("parse range start")?,
+                    end.parse().context("parse range end")?,
+                ),
+                None => {
+                    let value = range.parse().context("parse value")?;
+                    // `5/10` means starting from 5 until the end
+                    let end = if part.contains('/') { max } else { value };
+                    (value, end)
+                }
+            },
+        };
+        anyhow::ensure!(
+            min <= start && start <= end && end <= max,
+            "{part} is out of {min}-{max} range"
+        );
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn is_set(mask: u64, value: u8) -> bool {
+    mask & (1 << value) != 0
+}
+
+impl CronSchedule {
+    fn matches_day(&self, date: Date) -> bool {
+        let day = is_set(self.days, date.day());
+        let weekday = is_set(self.weekdays, date.weekday().number_days_from_sunday());
+        match (self.days_restricted, self.weekdays_restricted) {
+            // Standard cron behavior: either of the restricted fields can match
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// Whether schedule fires at the given minute
+    pub fn matches(&self, time: OffsetDateTime) -> bool {
+        is_set(self.months, time.month().into())
+            && self.matches_day(time.date())
+            && is_set(self.hours, time.hour())
+            && is_set(self.minutes, time.minute())
+    }
+
+    /// First fire time strictly after the given time
+    pub fn next_after(&self, after: OffsetDateTime) -> Option<OffsetDateTime> {
+        let after = after.to_offset(time::UtcOffset::UTC);
+        let mut candidate =
+            after.replace_second(0).ok()?.replace_nanosecond(0).ok()? + Duration::MINUTE;
+        let limit = after + Duration::days(MAX_SEARCH_DAYS);
+        while candidate <= limit {
+            if !is_set(self.months, candidate.month().into()) || !self.matches_day(candidate.date())
+            {
+                candidate = candidate.replace_time(Time::MIDNIGHT) + Duration::DAY;
+                continue;
+            }
+            if !is_set(self.hours, candidate.hour()) {
+                candidate = candidate.replace_minute(0).ok()? + Duration::HOUR;
+                continue;
+            }
+            if !is_set(self.minutes, candidate.minute()) {
+                candidate += Duration::MINUTE;
+                continue;
+            }
+            return Some(candidate);
+        }
+        None
+    }
+}
+
+impl FromStr for CronSchedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let fields: Vec<_> = s.split_whitespace().collect();
+        let [minutes, hours, days, months, weekdays] = fields[..] else {
+            anyhow::bail!("cron expression must have 5 fields");
+        };
+        let mut weekdays_mask = parse_field(weekdays, 0, 7).context("day of week")?;
+        // Sunday can be written as 7
+        if is_set(weekdays_mask, 7) {
+            weekdays_mask |= 1;
+        }
+        Ok(Self {
+            source: fields.join(" "),
+            minutes: parse_field(minutes, 0, 59).context("minute")?,
+            hours: parse_field(hours, 0, 23).context("hour")?,
+            days: parse_field(days, 1, 31).context("day of month")?,
+            months: parse_field(months, 1, 12).context("month")?,
+            weekdays: weekdays_mask,
+            days_restricted: days != "*",
+            weekdays_restricted: weekdays != "*",
+        })
+    }
+}
+
+impl Display for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, OffsetDateTime, Time};
+
+    use super::CronSchedule;
+
+    fn at(day: u8, hour: u8, minute: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2026, Month::October, day)
+            .unwrap()
+            .with_time(Time::from_hms(hour, minute, 0).unwrap())
+            .assume_utc()
+    }
+
+    #[test]
+    fn parse_cron() {
+        assert!("* * * * *".parse::<CronSchedule>().is_ok());
+        assert!("*/15 0-6/2 1,15 * 1-5".parse::<CronSchedule>().is_ok());
+        assert!("* * * *".parse::<CronSchedule>().is_err());
+        assert!("60 * * * *".parse::<CronSchedule>().is_err());
+        assert!("* * 0 * *".parse::<CronSchedule>().is_err());
+        assert!("*/0 * * * *".parse::<CronSchedule>().is_err());
+        assert!("5-1 * * * *".parse::<CronSchedule>().is_err());
+    }
+
+    #[test]
+    fn next_run() {
+        // 2026-10-18 is Sunday
+        let daily: CronSchedule = "30 4 * * *".parse().unwrap();
+        assert_eq!(daily.next_after(at(18, 3, 0)), Some(at(18, 4, 30)));
+        assert_eq!(daily.next_after(at(18, 4, 30)), Some(at(19, 4, 30)));
+
+        let quarter: CronSchedule = "*/15 * * * *".parse().unwrap();
+        assert_eq!(quarter.next_after(at(18, 10, 7)), Some(at(18, 10, 15)));
+        assert!(quarter.matches(at(18, 10, 45)));
+        assert!(!quarter.matches(at(18, 10, 46)));
+
+        let weekdays: CronSchedule = "0 9 * * 1-5".parse().unwrap();
+        assert_eq!(weekdays.next_after(at(17, 12, 0)), Some(at(19, 9, 0)));
+
+        let sunday: CronSchedule = "0 0 * * 7".parse().unwrap();
+        assert!(sunday.matches(at(18, 0, 0)));
+
+        // Either day of month or day of week
+        let either: CronSchedule = "0 0 20 * 0".parse().unwrap();
+        assert_eq!(either.next_after(at(18, 0, 0)), Some(at(20, 0, 0)));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    app_state::AppState,
+    config::APP_RESOURCES,
+    library::{calendar, libraries, numbering},
+    metadata::ParentMediaType,
+    progress::{ProgressDispatcher, TaskError},
+    scan::{LibraryScanTask, ScanConfig},
+};
+
+use super::{JobKind, ScheduledJobProgress, ScheduledJobTask};
+
+/// Temporary files older than this are removed
+const TEMP_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// How often running previews generation is checked
+const PREVIEWS_POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// Seasons with fewer episodes can't be compared
+const MIN_INTRO_EPISODES: i64 = 2;
+
+pub(super) async fn run(
+    app_state: &AppState,
+    kind: JobKind,
+    progress: &ProgressDispatcher<ScheduledJobTask>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    match kind {
+        JobKind::LibraryScan => library_scan(app_state).await,
+        JobKind::MetadataRefresh => metadata_refresh(app_state, progress, cancel).await,
+        JobKind::MissingPreviews => missing_previews(app_state, progress, cancel).await,
+        JobKind::IntroDetection => intro_detection(app_state, progress, cancel).await,
+        JobKind::TempCleanup => temp_cleanup(app_state).await,
+        JobKind::CalendarRefresh => {
+            calendar::refresh(
+                app_state.db,
+                app_state.providers_stack,
+                app_state.metadata_fetch_params(),
+            )
+            .await?;
+            Ok(())
+        }
+    }
+}
+
+async fn library_scan(app_state: &AppState) -> anyhow::Result<()> {
+    let tasks = app_state.tasks;
+    let config = ScanConfig::new_from_server_configuration();
+    let task_id = match tasks
+        .library_scan_tasks
+        .start_task(LibraryScanTask::new(config.clone()), None)
+    {
+        Ok(task_id) => task_id,
+        Err(TaskError::Duplicate) => {
+            tracing::info!("Skipping library scan while another scan is running");
+            return Ok(());
+        }
+        Err(e) => return Err(e).context("Failed to start library scan"),
+    };
+    if let Err(e) = app_state.reconciliate_library(task_id, config).await {
+        tasks
+            .library_scan_tasks
+            .error_task(task_id, TaskError::Failure);
+        return Err(e.into());
+    }
+    tasks.library_scan_tasks.finish_task(task_id);
+    Ok(())
+}
+
+/// Scan the libraries which scan schedule has elapsed in the background
+pub(super) fn spawn_libraries_scan(app_state: &AppState, library_ids: Vec<i64>) {
+    let tasks = app_state.tasks;
+    let config = ScanConfig::new_from_server_configuration();
+    let task_id = match tasks
+        .library_scan_tasks
+        .start_task(LibraryScanTask::new(config.clone()), None)
+    {
+        Ok(task_id) => task_id,
+        Err(e) => {
+            tracing::debug!("Skipping scheduled libraries scan: {e}");
+            return;
+        }
+    };
+    let app_state = app_state.clone();
+    tasks.tracker.spawn(async move {
+        match app_state
+            .reconciliate_libraries(task_id, config, &library_ids)
+            .await
+        {
+            Ok(()) => {
+                tasks.library_scan_tasks.finish_task(task_id);
+            }
+            Err(e) => {
+                tracing::error!("Scheduled libraries scan failed: {e}");
+                // Wait for the next period instead of retrying every tick
+                if let Err(e) = libraries::mark_scanned(app_state.db, &library_ids).await {
+                    tracing::error!("Failed to update libraries scan time: {e}");
+                }
+                tasks
+                    .library_scan_tasks
+                    .error_task(task_id, TaskError::Failure);
+            }
+        }
+    });
+}
+
+/// Refresh episodes metadata of the shows that have upcoming episodes
+async fn metadata_refresh(
+    app_state: &AppState,
+    progress: &ProgressDispatcher<ScheduledJobTask>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let db = app_state.db;
+    let shows = sqlx::query_scalar!("select id from shows where next_episode_air_date is not null")
+        .fetch_all(&db.pool)
+        .await?;
+    let total = shows.len();
+    for (done, show_id) in shows.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        let result = match app_state
+            .content_fetch_params(ParentMediaType::Show, show_id)
+            .await
+        {
+            Ok(fetch_params) => {
+                numbering::reorder_show(
+                    db,
+                    app_state.providers_stack,
+                    show_id,
+                    fetch_params.ordering,
+                    fetch_params,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            tracing::warn!(show_id, "Failed to refresh show metadata: {e}");
+        }
+        progress.progress(ScheduledJobProgress {
+            done: done + 1,
+            total,
+        });
+    }
+    Ok(())
+}
+
+/// Generate missing previews one video at a time
+async fn missing_previews(
+    app_state: &AppState,
+    progress: &ProgressDispatcher<ScheduledJobTask>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let media_libraries = libraries::all(app_state.db).await?;
+    let candidates: Vec<_> = {
+        let library = app_state.library.lock().unwrap();
+        library
+            .videos
+            .iter()
+            .filter(|(_, file)| {
+                media_libraries
+                    .iter()
+                    .any(|l| l.settings.generate_previews && l.contains(file.source.video.path()))
+            })
+            .map(|(id, file)| (*id, file.source.previews_dir()))
+            .collect()
+    };
+    let videos: Vec<_> = candidates
+        .into_iter()
+        .filter(|(_, previews)| previews.previews_count() == 0)
+        .map(|(id, _)| id)
+        .collect();
+    let total = videos.len();
+    let previews_tasks = &app_state.tasks.previews_tasks;
+    for (done, video_id) in videos.into_iter().enumerate() {
+        if let Err(e) = app_state.generate_previews(video_id).await {
+            tracing::warn!(video_id, "Failed to start previews generation: {e}");
+        }
+        while !previews_tasks.tasks.lock().unwrap().is_empty() {
+            tokio::select! {
+                _ = tokio::time::sleep(PREVIEWS_POLL_INTERVAL) => {}
+                _ = cancel.cancelled() => return Ok(()),
+            }
+        }
+        progress.progress(ScheduledJobProgress {
+            done: done + 1,
+            total,
+        });
+    }
+    Ok(())
+}
+
+/// Detect intros of the seasons that don't have any
+async fn intro_detection(
+    app_state: &AppState,
+    progress: &ProgressDispatcher<ScheduledJobTask>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let media_libraries = libraries::all(app_state.db).await?;
+    let seasons = sqlx::query!(
+        r#"select seasons.show_id as "show_id!", seasons.number as "number!",
+        min(videos.id) as "video_id!: i64" from seasons
+        join episodes on episodes.season_id = seasons.id
+        join videos on videos.metadata_id = episodes.metadata_id
+        where not exists (select 1 from intros
+            join episodes as season_episodes on season_episodes.id = intros.episode_id
+            where season_episodes.season_id = seasons.id)
+        group by seasons.id having count(distinct episodes.id) >= ?"#,
+        MIN_INTRO_EPISODES,
+    )
+    .fetch_all(&app_state.db.pool)
+    .await?;
+    let seasons: Vec<_> = {
+        let library = app_state.library.lock().unwrap();
+        seasons
+            .into_iter()
+            .filter(|season| {
+                let Some(source) = library.get_source(season.video_id) else {
+                    return false;
+                };
+                media_libraries
+                    .iter()
+                    .any(|l| l.settings.detect_intros && l.contains(source.video.path()))
+            })
+            .collect()
+    };
+    let total = seasons.len();
+    for (done, season) in seasons.into_iter().enumerate() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if let Err(e) = app_state.detect_intros(season.show_id, season.number).await {
+            tracing::warn!(
+                show_id = season.show_id,
+                season = season.number,
+                "Failed to detect intros: {e}"
+            );
+        }
+        progress.progress(ScheduledJobProgress {
+            done: done + 1,
+            total,
+        });
+    }
+    Ok(())
+}
+
+/// Remove files modified before the cutoff and directories that become empty
+fn remove_stale(dir: &Path, cutoff: SystemTime) -> std::io::Result<usize> {
+    let mut removed = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            removed += remove_stale(&path, cutoff)?;
+            // Fails when directory is not empty
+            let _ = std::fs::remove_dir(&path);
+        } else if metadata.modified()? < cutoff {
+            std::fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove stale temporary files, skipped while anything can be writing into temp directory
+async fn temp_cleanup(app_state: &AppState) -> anyhow::Result<()> {
+    let tasks = app_state.tasks;
+    let busy = !tasks.transcode_tasks.tasks.lock().unwrap().is_empty()
+        || !tasks.previews_tasks.tasks.lock().unwrap().is_empty()
+        || !tasks.watch_sessions.tasks.lock().unwrap().is_empty();
+    if busy {
+        tracing::info!("Skipping temp cleanup while transcoding tasks are running");
+        return Ok(());
+    }
+    let temp_path: PathBuf = APP_RESOURCES.temp_path.clone();
+    if !temp_path.try_exists()? {
+        return Ok(());
+    }
+    let cutoff = SystemTime::now() - TEMP_MAX_AGE;
+    let removed = tokio::task::spawn_blocking(move || remove_stale(&temp_path, cutoff)).await??;
+    tracing::info!("Removed {removed} stale temporary files");
+    Ok(())
+}
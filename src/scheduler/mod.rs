@@ -0,0 +1,387 @@
+use std::{fmt::Display, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+use uuid::Uuid;
+
+use crate::{
+    AppError,
+    app_state::AppState,
+    db::Db,
+    library::libraries,
+    progress::{ProgressDispatcher, TaskError, TaskTrait},
+};
+
+pub mod cron;
+mod jobs;
+
+pub use cron::CronSchedule;
+
+/// How often job schedules are checked
+const SCHEDULER_TICK: Duration = Duration::from_secs(60);
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Reconcile all libraries
+    LibraryScan,
+    /// Refresh episodes metadata of the continuing shows
+    MetadataRefresh,
+    /// Generate previews that are missing in libraries with previews enabled
+    MissingPreviews,
+    /// Detect intros of seasons without intros in libraries with intro detection enabled
+    IntroDetection,
+    /// Remove stale temporary files
+    TempCleanup,
+    /// Refresh air dates of the tracked shows
+    CalendarRefresh,
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::LibraryScan => "library_scan",
+            JobKind::MetadataRefresh => "metadata_refresh",
+            JobKind::MissingPreviews => "missing_previews",
+            JobKind::IntroDetection => "intro_detection",
+            JobKind::TempCleanup => "temp_cleanup",
+            JobKind::CalendarRefresh => "calendar_refresh",
+        }
+    }
+}
+
+impl Display for JobKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Success,
+    Failure,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct JobSchedule {
+    pub enabled: bool,
+    /// Run period in minutes
+    pub interval_minutes: Option<i64>,
+    /// Five field cron expression evaluated in UTC, takes precedence over the interval
+    pub cron: Option<String>,
+}
+
+impl JobSchedule {
+    pub fn validate(&self) -> Result<(), AppError> {
+        match (&self.cron, self.interval_minutes) {
+            (Some(cron), _) => {
+                cron.parse::<CronSchedule>().map_err(|e| {
+                    AppError::bad_request(format!("Invalid cron expression: {e:#}"))
+                })?;
+            }
+            (None, Some(interval)) if interval > 0 => {}
+            (None, Some(_)) => return Err(AppError::bad_request("Interval must be positive")),
+            (None, None) => {
+                return Err(AppError::bad_request(
+                    "Either interval or cron expression is required",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Next run time of the job.
+    ///
+    /// Runs that were missed while the server was offline are caught up once.
+    /// Interval jobs that never ran are due at `since`
+    pub fn next_run(
+        &self,
+        last_run: Option<OffsetDateTime>,
+        since: OffsetDateTime,
+    ) -> Option<OffsetDateTime> {
+        if !self.enabled {
+            return None;
+        }
+        if let Some(cron) = &self.cron {
+            let cron: CronSchedule = cron.parse().ok()?;
+            return cron.next_after(last_run.unwrap_or(since));
+        }
+        let interval = time::Duration::minutes(self.interval_minutes?);
+        Some(last_run.map_or(since, |last_run| last_run + interval))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct ScheduledJob {
+    pub kind: JobKind,
+    #[serde(flatten)]
+    pub schedule: JobSchedule,
+    pub last_run: Option<crate::OffsetDateTime>,
+    pub last_finished: Option<crate::OffsetDateTime>,
+    pub last_status: Option<JobStatus>,
+    /// Error of the last failed run
+    pub last_error: Option<String>,
+    pub next_run: Option<crate::OffsetDateTime>,
+}
+
+/// Scheduled job run
+#[derive(Debug, Clone, Serialize, PartialEq, Eq, utoipa::ToSchema)]
+pub struct ScheduledJobTask {
+    pub job: JobKind,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, utoipa::ToSchema)]
+pub struct ScheduledJobProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl TaskTrait for ScheduledJobTask {
+    type Progress = ScheduledJobProgress;
+
+    fn into_progress(status: crate::progress::ProgressStatus<Self>) -> crate::progress::TaskProgress
+    where
+        Self: Sized,
+    {
+        crate::progress::TaskProgress::ScheduledJob(status)
+    }
+}
+
+pub async fn all(db: &Db, now: OffsetDateTime) -> sqlx::Result<Vec<ScheduledJob>> {
+    let rows = sqlx::query!(
+        r#"select kind as "kind: JobKind", enabled, interval_minutes, cron, last_run, last_finished,
+        last_status as "last_status: JobStatus", last_error from scheduled_jobs order by id"#
+    )
+    .fetch_all(&db.pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let schedule = JobSchedule {
+                enabled: row.enabled,
+                interval_minutes: row.interval_minutes,
+                cron: row.cron,
+            };
+            let next_run = schedule.next_run(row.last_run, now).map(Into::into);
+            ScheduledJob {
+                kind: row.kind,
+                schedule,
+                last_run: row.last_run.map(Into::into),
+                last_finished: row.last_finished.map(Into::into),
+                last_status: row.last_status,
+                last_error: row.last_error,
+                next_run,
+            }
+        })
+        .collect())
+}
+
+pub async fn update_schedule(db: &Db, kind: JobKind, schedule: &JobSchedule) -> sqlx::Result<()> {
+    let res = sqlx::query!(
+        "update scheduled_jobs set enabled = ?, interval_minutes = ?, cron = ? where kind = ?",
+        schedule.enabled,
+        schedule.interval_minutes,
+        schedule.cron,
+        kind,
+    )
+    .execute(&db.pool)
+    .await?;
+    if res.rows_affected() == 0 {
+        return Err(sqlx::Error::RowNotFound);
+    }
+    Ok(())
+}
+
+async fn mark_started(db: &Db, kind: JobKind) -> sqlx::Result<()> {
+    let now = OffsetDateTime::now_utc();
+    sqlx::query!(
+        "update scheduled_jobs set last_run = ?, last_status = ?, last_error = null where kind = ?",
+        now,
+        JobStatus::Running,
+        kind,
+    )
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+async fn mark_finished(db: &Db, kind: JobKind, error: Option<String>) -> sqlx::Result<()> {
+    let now = OffsetDateTime::now_utc();
+    let status = match error {
+        Some(_) => JobStatus::Failure,
+        None => JobStatus::Success,
+    };
+    sqlx::query!(
+        "update scheduled_jobs set last_finished = ?, last_status = ?, last_error = ? where kind = ?",
+        now,
+        status,
+        error,
+        kind,
+    )
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+/// Runs that were in progress when the server stopped
+async fn mark_interrupted(db: &Db) -> sqlx::Result<()> {
+    sqlx::query!(
+        "update scheduled_jobs set last_status = ?, last_error = 'Interrupted' where last_status = ?",
+        JobStatus::Failure,
+        JobStatus::Running,
+    )
+    .execute(&db.pool)
+    .await?;
+    Ok(())
+}
+
+/// Start the job in the background.
+///
+/// Fails with [TaskError::Duplicate] when the job is already running
+pub fn spawn_job(app_state: &AppState, kind: JobKind) -> Result<Uuid, TaskError> {
+    let storage = &app_state.tasks.scheduled_job_tasks;
+    let cancel = app_state.cancelation_token.child_token();
+    let task_id = storage.start_task(ScheduledJobTask { job: kind }, Some(cancel.clone()))?;
+    let app_state = app_state.clone();
+    app_state.tasks.tracker.spawn(async move {
+        let db = app_state.db;
+        let progress = ProgressDispatcher::new(storage, task_id);
+        if let Err(e) = mark_started(db, kind).await {
+            tracing::error!("Failed to save {kind} job start: {e}");
+        }
+        tracing::info!("Starting {kind} job");
+        let result = match jobs::run(&app_state, kind, &progress, &cancel).await {
+            Ok(_) if cancel.is_cancelled() => Err(anyhow::anyhow!("Canceled")),
+            result => result,
+        };
+        let error = result.as_ref().err().map(|e| format!("{e:#}"));
+        if let Err(e) = mark_finished(db, kind, error).await {
+            tracing::error!("Failed to save {kind} job status: {e}");
+        }
+        match result {
+            Ok(_) => {
+                tracing::info!("Finished {kind} job");
+                progress.finish();
+            }
+            Err(e) => {
+                tracing::error!("Job {kind} failed: {e:#}");
+                progress.error(TaskError::Failure);
+            }
+        }
+    });
+    Ok(task_id)
+}
+
+/// Jobs that are due at the given time
+async fn due_jobs(
+    db: &Db,
+    since: OffsetDateTime,
+    now: OffsetDateTime,
+) -> sqlx::Result<Vec<JobKind>> {
+    let jobs = all(db, since).await?;
+    let due: Vec<_> = jobs
+        .into_iter()
+        .filter(|job| {
+            job.next_run
+                .as_ref()
+                .is_some_and(|next_run| next_run.0 <= now)
+        })
+        .map(|job| job.kind)
+        .collect();
+    Ok(due)
+}
+
+/// Start scheduled jobs according to their schedules
+pub async fn run_scheduler(app_state: AppState) {
+    let db = app_state.db;
+    if let Err(e) = mark_interrupted(db).await {
+        tracing::error!("Failed to reset interrupted jobs: {e}");
+    }
+    let started = OffsetDateTime::now_utc();
+    loop {
+        match due_jobs(db, started, OffsetDateTime::now_utc()).await {
+            Ok(jobs) => {
+                for kind in jobs {
+                    match spawn_job(&app_state, kind) {
+                        Ok(_) | Err(TaskError::Duplicate) => {}
+                        Err(e) => tracing::error!("Failed to start {kind} job: {e}"),
+                    }
+                }
+            }
+            Err(e) => tracing::error!("Failed to check jobs schedule: {e}"),
+        }
+        // Libraries have their own scan schedule
+        match libraries::scan_due(db).await {
+            Ok(library_ids) if !library_ids.is_empty() => {
+                jobs::spawn_libraries_scan(&app_state, library_ids)
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("Failed to check libraries scan schedule: {e}"),
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(SCHEDULER_TICK) => {}
+            _ = app_state.cancelation_token.cancelled() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::{Date, Month, OffsetDateTime, Time};
+
+    use super::JobSchedule;
+
+    fn at(day: u8, hour: u8) -> OffsetDateTime {
+        Date::from_calendar_date(2026, Month::October, day)
+            .unwrap()
+            .with_time(Time::from_hms(hour, 0, 0).unwrap())
+            .assume_utc()
+    }
+
+    #[test]
+    fn job_next_run() {
+        let interval = JobSchedule {
+            enabled: true,
+            interval_minutes: Some(60),
+            cron: None,
+        };
+        assert_eq!(interval.next_run(None, at(18, 10)), Some(at(18, 10)));
+        assert_eq!(
+            interval.next_run(Some(at(18, 9)), at(18, 12)),
+            Some(at(18, 10))
+        );
+
+        let cron = JobSchedule {
+            enabled: true,
+            interval_minutes: Some(60),
+            cron: Some("0 4 * * *".into()),
+        };
+        assert_eq!(cron.next_run(None, at(18, 10)), Some(at(19, 4)));
+        // Missed run is caught up
+        assert_eq!(cron.next_run(Some(at(15, 4)), at(18, 10)), Some(at(16, 4)));
+
+        let disabled = JobSchedule {
+            enabled: false,
+            ..interval
+        };
+        assert_eq!(disabled.next_run(None, at(18, 10)), None);
+
+        assert!(cron.validate().is_ok());
+        assert!(
+            JobSchedule {
+                cron: Some("0 25 * * *".into()),
+                ..cron
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
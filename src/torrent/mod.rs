@@ -1,6 +1,6 @@
 use std::{
     collections::HashMap,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
@@ -857,8 +857,12 @@ impl TorrentInfo {
         content_type_hint: Option<DownloadContentHint>,
         providers_stack: &'static MetadataProvidersStack,
     ) -> Self {
-        let all_files = info.output_files("");
-        let files = parse_torrent_files(providers_stack, &all_files, content_type_hint).await;
+        let all_files: Vec<_> = info
+            .output_files("")
+            .iter()
+            .map(|f| (f.path().to_path_buf(), f.length()))
+            .collect();
+        let files = resolve_files(providers_stack, &all_files, content_type_hint).await;
 
         TorrentInfo {
             contents: files,
@@ -903,10 +907,14 @@ pub struct ResolvedTorrentFile {
 
 impl ResolvedTorrentFile {
     pub fn from_output_file(output_file: &OutputFile, offset: u64) -> Self {
+        Self::new(output_file.path(), output_file.length(), offset)
+    }
+
+    pub fn new(path: &Path, size: u64, offset: u64) -> Self {
         Self {
             offset,
-            size: output_file.length(),
-            path: path_components(output_file.path()),
+            size,
+            path: path_components(path),
             priority: Priority::Disabled,
         }
     }
@@ -971,30 +979,34 @@ fn path_components(path: impl AsRef<Path>) -> Vec<String> {
     out
 }
 
-async fn parse_torrent_files(
+/// Identify video files of a single release and resolve their metadata.
+///
+/// Files are given as relative paths with their sizes, in the order of the torrent files.
+pub async fn resolve_files(
     providers_stack: &'static MetadataProvidersStack,
-    files: &[OutputFile],
+    files: &[(PathBuf, u64)],
     content_hint: Option<DownloadContentHint>,
 ) -> TorrentContents {
     let mut all_files: Vec<ResolvedTorrentFile> = Vec::new();
     let mut show_identifiers: Vec<(usize, ShowIdentifier)> = Vec::new();
     let mut movie_identifiers: Vec<(usize, MovieIdentifier)> = Vec::new();
     let mut file_offset = 0;
-    for (i, output_file) in files.iter().enumerate() {
-        let path = output_file.path().to_path_buf();
-        let resolved_file = ResolvedTorrentFile::from_output_file(output_file, file_offset);
+    for (i, (path, size)) in files.iter().enumerate() {
+        let resolved_file = ResolvedTorrentFile::new(path, *size, file_offset);
         let Some(file_name) = path.file_stem() else {
             tracing::warn!("Torrent file contains .dotfile: {}", path.display());
             all_files.push(resolved_file);
-            file_offset += output_file.length();
+            file_offset += size;
             continue;
         };
         if is_format_supported(&path) {
             let content_identifier = match content_hint.as_ref().map(|h| h.content_type) {
+                // Lone number is not enough to tell that the file is an episode
                 None => ShowIdentifier::from_path(file_name)
+                    .ok()
+                    .filter(|s| !s.absolute)
                     .map(Into::into)
-                    .or_else(|_| MovieIdentifier::from_path(file_name).map(Into::into))
-                    .ok(),
+                    .or_else(|| MovieIdentifier::from_path(file_name).map(Into::into).ok()),
                 Some(ParentMediaType::Movie) => {
                     MovieIdentifier::from_path(file_name).map(Into::into).ok()
                 }
@@ -1009,7 +1021,7 @@ async fn parse_torrent_files(
             }
         }
         all_files.push(resolved_file);
-        file_offset += output_file.length();
+        file_offset += size;
     }
 
     if show_identifiers.is_empty() && movie_identifiers.is_empty() {
@@ -1029,7 +1041,7 @@ async fn parse_torrent_files(
             let show = match &content_hint {
                 Some(hint) => {
                     match providers_stack
-                        .get_show(&hint.metadata_id, hint.metadata_provider)
+                        .get_show(&hint.metadata_id, hint.metadata_provider, None)
                         .await
                     {
                         Ok(show) => show,
@@ -1098,7 +1110,7 @@ async fn parse_torrent_files(
                 let show_id = show_id.clone();
                 season_set.spawn(async move {
                     let resolved_season = providers_stack
-                        .get_season(&show_id, season as usize, show_metadata_provider)
+                        .get_season(&show_id, season as usize, show_metadata_provider, None)
                         .await;
                     (resolved_season, chunk)
                 });
@@ -82,8 +82,10 @@ impl Torrent {
             Some(ParentMediaType::Movie) => MovieIdentifier::from_str(&meta.name)
                 .ok()
                 .map(ContentIdentifier::Movie),
+            // Absolute episode number does not match the season and episode of the show
             Some(ParentMediaType::Show) => ShowIdentifier::from_str(&meta.name)
                 .ok()
+                .filter(|s| !s.absolute)
                 .map(ContentIdentifier::Show),
             None => None,
         };
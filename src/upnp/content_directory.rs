@@ -1,4 +1,4 @@
-use std::{fmt::Display, str::FromStr};
+use std::{collections::HashSet, fmt::Display, path::Path, str::FromStr};
 
 use anyhow::Context;
 use upnp::{
@@ -17,6 +17,8 @@ use upnp::{
 use crate::{
     app_state::AppState,
     db::{self, ContentFetchParams, DbActions},
+    library::{collections, libraries},
+    metadata::ParentMediaType,
 };
 
 #[derive(Clone)]
@@ -33,7 +35,7 @@ impl MediaServerContentDirectory {
         }
     }
 
-    pub fn root() -> DidlResponse {
+    pub async fn root(&self) -> anyhow::Result<DidlResponse> {
         let shows = Container::new(
             ContentId::AllShows.to_string(),
             ContentId::Root.to_string(),
@@ -44,10 +46,23 @@ impl MediaServerContentDirectory {
             ContentId::Root.to_string(),
             "Movies".to_string(),
         );
-        DidlResponse {
-            containers: vec![shows, movies],
-            items: vec![],
+        let collections = Container::new(
+            ContentId::AllCollections.to_string(),
+            ContentId::Root.to_string(),
+            "Collections".to_string(),
+        );
+        let mut containers = vec![shows, movies, collections];
+        for media_library in libraries::all(self.app_state.db).await? {
+            containers.push(Container::new(
+                ContentId::Library(media_library.id).to_string(),
+                ContentId::Root.to_string(),
+                media_library.settings.name,
+            ));
         }
+        Ok(DidlResponse {
+            containers,
+            items: vec![],
+        })
     }
 
     pub fn root_metadata() -> DidlResponse {
@@ -71,31 +86,42 @@ impl MediaServerContentDirectory {
                 ..Default::default()
             })
             .await?;
-        let mut containers = Vec::with_capacity(shows.len());
-        for show in shows {
-            let poster_url = format!(
-                "{server_url}/api/show/{show_id}/poster",
-                server_url = self.server_location,
-                show_id = show.provider_id
-            );
-            let show_id = show.provider_id.parse().expect("db ids to be integers");
-            let mut container = Container::new(
-                ContentId::Show(show_id).to_string(),
-                ContentId::AllShows.to_string(),
-                show.title,
-            );
-            container.set_property(properties::AlbumArtUri(poster_url));
-            if let Some(plot) = show.plot {
-                container.set_property(properties::Description(plot));
-            }
-            containers.push(container);
-        }
+        let containers = shows
+            .into_iter()
+            .map(|show| {
+                let show_id = show.provider_id.parse().expect("db ids to be integers");
+                self.show_container(show_id, show.title, show.plot, ContentId::AllShows)
+            })
+            .collect();
         Ok(DidlResponse {
             containers,
             items: vec![],
         })
     }
 
+    fn show_container(
+        &self,
+        show_id: i64,
+        title: String,
+        plot: Option<String>,
+        parent: ContentId,
+    ) -> Container {
+        let poster_url = format!(
+            "{server_url}/api/show/{show_id}/poster",
+            server_url = self.server_location,
+        );
+        let mut container = Container::new(
+            ContentId::Show(show_id).to_string(),
+            parent.to_string(),
+            title,
+        );
+        container.set_property(properties::AlbumArtUri(poster_url));
+        if let Some(plot) = plot {
+            container.set_property(properties::Description(plot));
+        }
+        container
+    }
+
     pub async fn show(&self, show_id: i64) -> anyhow::Result<DidlResponse> {
         let show = self.app_state.db.get_show(show_id).await?;
         let seasons = show.seasons.unwrap_or_default();
@@ -295,35 +321,159 @@ impl MediaServerContentDirectory {
                 ..Default::default()
             })
             .await?;
-        let mut items = Vec::with_capacity(movies.len());
-        for movie in movies {
-            let poster_url = format!(
-                "{server_url}/api/movie/{movie_id}/poster",
-                server_url = self.server_location,
-                movie_id = movie.provider_id
-            );
-            let watch_url = format!(
-                "{server_url}/api/local_movie/{movie_id}/watch",
-                server_url = self.server_location,
-                movie_id = movie.provider_id
-            );
-            let container_id =
-                ContentId::Movie(movie.provider_id.parse().expect("local ids to be integers"));
-            let mut item = Item::new(
-                container_id.to_string(),
-                ContentId::AllMovies.to_string(),
-                movie.title,
-            );
-            item.base
-                .set_upnp_class(Some(ItemType::VideoItem(Some(VideoItemType::Movie))));
-            item.set_property(properties::AlbumArtUri(poster_url));
-            if let Some(plot) = movie.plot {
-                item.set_property(properties::Description(plot));
+        let items = movies
+            .into_iter()
+            .map(|movie| {
+                let movie_id = movie.provider_id.parse().expect("local ids to be integers");
+                self.movie_item(movie_id, movie.title, movie.plot, ContentId::AllMovies)
+            })
+            .collect();
+        Ok(DidlResponse {
+            containers: vec![],
+            items,
+        })
+    }
+
+    fn movie_item(
+        &self,
+        movie_id: i64,
+        title: String,
+        plot: Option<String>,
+        parent: ContentId,
+    ) -> Item {
+        let poster_url = format!(
+            "{server_url}/api/movie/{movie_id}/poster",
+            server_url = self.server_location,
+        );
+        let watch_url = format!(
+            "{server_url}/api/local_movie/{movie_id}/watch?client=tv",
+            server_url = self.server_location,
+        );
+        let mut item = Item::new(
+            ContentId::Movie(movie_id).to_string(),
+            parent.to_string(),
+            title,
+        );
+        item.base
+            .set_upnp_class(Some(ItemType::VideoItem(Some(VideoItemType::Movie))));
+        item.set_property(properties::AlbumArtUri(poster_url));
+        if let Some(plot) = plot {
+            item.set_property(properties::Description(plot));
+        }
+        let watch_resource =
+            Resource::new(watch_url, ProtocolInfo::http_get("video/matroska".into()));
+        item.set_property(watch_resource);
+        item
+    }
+
+    /// Movies or shows that have videos inside the library folders
+    pub async fn library(
+        &self,
+        library_id: i64,
+        requested_count: i64,
+    ) -> anyhow::Result<DidlResponse> {
+        let media_library = libraries::get(self.app_state.db, library_id).await?;
+        let parent = ContentId::Library(library_id);
+        // Paths are matched by components, the same way library scan assigns videos to libraries
+        let mut seen = HashSet::new();
+        let mut in_library =
+            |id: i64, path: &str| media_library.contains(Path::new(path)) && seen.insert(id);
+        match media_library.settings.media_type {
+            ParentMediaType::Movie => {
+                let movies = sqlx::query!(
+                    "SELECT movies.id, metadata.title, metadata.plot, videos.path FROM movies
+                    JOIN metadata ON metadata.id = movies.metadata_id
+                    JOIN videos ON videos.metadata_id = movies.metadata_id
+                    ORDER BY metadata.title",
+                )
+                .fetch_all(&self.app_state.db.pool)
+                .await?;
+                let items = movies
+                    .into_iter()
+                    .filter(|m| in_library(m.id, &m.path))
+                    .take(requested_count as usize)
+                    .map(|m| self.movie_item(m.id, m.title, m.plot, parent))
+                    .collect();
+                Ok(DidlResponse {
+                    containers: vec![],
+                    items,
+                })
+            }
+            ParentMediaType::Show => {
+                let shows = sqlx::query!(
+                    "SELECT shows.id, metadata.title, metadata.plot, videos.path FROM shows
+                    JOIN metadata ON metadata.id = shows.metadata_id
+                    JOIN seasons ON seasons.show_id = shows.id
+                    JOIN episodes ON episodes.season_id = seasons.id
+                    JOIN videos ON videos.metadata_id = episodes.metadata_id
+                    ORDER BY metadata.title",
+                )
+                .fetch_all(&self.app_state.db.pool)
+                .await?;
+                let containers = shows
+                    .into_iter()
+                    .filter(|s| in_library(s.id, &s.path))
+                    .take(requested_count as usize)
+                    .map(|s| self.show_container(s.id, s.title, s.plot, parent))
+                    .collect();
+                Ok(DidlResponse {
+                    containers,
+                    items: vec![],
+                })
             }
-            let watch_resource =
-                Resource::new(watch_url, ProtocolInfo::http_get("video/matroska".into()));
-            item.set_property(watch_resource);
-            items.push(item);
+        }
+    }
+
+    pub async fn library_metadata(&self, library_id: i64) -> anyhow::Result<DidlResponse> {
+        let media_library = libraries::get(self.app_state.db, library_id).await?;
+        let container = Container::new(
+            ContentId::Library(library_id).to_string(),
+            ContentId::Root.to_string(),
+            media_library.settings.name,
+        );
+        Ok(DidlResponse {
+            containers: vec![container],
+            items: vec![],
+        })
+    }
+
+    pub async fn all_collections(&self, requested_count: i64) -> anyhow::Result<DidlResponse> {
+        let containers = collections::all(self.app_state.db)
+            .await?
+            .into_iter()
+            .take(requested_count as usize)
+            .map(|collection| {
+                let mut container = Container::new(
+                    ContentId::Collection(collection.id).to_string(),
+                    ContentId::AllCollections.to_string(),
+                    collection.title,
+                );
+                let poster_url = format!(
+                    "{server_url}/api/collections/{id}/poster",
+                    server_url = self.server_location,
+                    id = collection.id,
+                );
+                container.set_property(properties::AlbumArtUri(poster_url));
+                container
+            })
+            .collect();
+        Ok(DidlResponse {
+            containers,
+            items: vec![],
+        })
+    }
+
+    /// Collection movies that are present in the library
+    pub async fn collection(&self, collection_id: i64) -> anyhow::Result<DidlResponse> {
+        let details = collections::get(self.app_state.db, collection_id).await?;
+        let parent = ContentId::Collection(collection_id);
+        let mut items = Vec::new();
+        for entry in details.entries {
+            let Some(movie_id) = entry.movie_id else {
+                continue;
+            };
+            let movie = self.app_state.db.get_movie(movie_id).await?;
+            items.push(self.movie_item(movie_id, movie.title, movie.plot, parent));
         }
         Ok(DidlResponse {
             containers: vec![],
@@ -331,6 +481,31 @@ impl MediaServerContentDirectory {
         })
     }
 
+    pub fn all_collections_metadata() -> DidlResponse {
+        let all_collections = Container::new(
+            ContentId::AllCollections.to_string(),
+            ContentId::Root.to_string(),
+            "Collections".to_string(),
+        );
+        DidlResponse {
+            containers: vec![all_collections],
+            items: vec![],
+        }
+    }
+
+    pub async fn collection_metadata(&self, collection_id: i64) -> anyhow::Result<DidlResponse> {
+        let details = collections::get(self.app_state.db, collection_id).await?;
+        let container = Container::new(
+            ContentId::Collection(collection_id).to_string(),
+            ContentId::AllCollections.to_string(),
+            details.collection.title,
+        );
+        Ok(DidlResponse {
+            containers: vec![container],
+            items: vec![],
+        })
+    }
+
     pub fn all_movies_metadata() -> DidlResponse {
         let all_movies = Container::new(
             ContentId::AllMovies.to_string(),
@@ -363,7 +538,7 @@ impl MediaServerContentDirectory {
             movie_id = movie.provider_id
         );
         let watch_url = format!(
-            "{server_url}/api/local_movie/{movie_id}/watch",
+            "{server_url}/api/local_movie/{movie_id}/watch?client=tv",
             server_url = self.server_location,
             movie_id = movie.provider_id
         );
@@ -437,6 +612,9 @@ enum ContentId {
     Root,
     AllMovies,
     AllShows,
+    AllCollections,
+    Library(i64),
+    Collection(i64),
     Movie(i64),
     Show(i64),
     Season {
@@ -456,6 +634,9 @@ impl Display for ContentId {
             ContentId::Root => write!(f, "0"),
             ContentId::AllMovies => write!(f, "movies"),
             ContentId::AllShows => write!(f, "shows"),
+            ContentId::AllCollections => write!(f, "collections"),
+            ContentId::Library(id) => write!(f, "library.{id}"),
+            ContentId::Collection(id) => write!(f, "collection.{id}"),
             ContentId::Show(id) => write!(f, "show.{id}"),
             ContentId::Movie(id) => write!(f, "movie.{id}"),
             ContentId::Season { show_id, season } => write!(f, "show.{show_id}.{season}"),
@@ -481,6 +662,9 @@ impl FromStr for ContentId {
         if s == "shows" {
             return Ok(Self::AllShows);
         }
+        if s == "collections" {
+            return Ok(Self::AllCollections);
+        }
         if let Some(show) = s.strip_prefix("show.") {
             let mut split = show.split('.');
             let show_id = split.next().and_then(|s| s.parse().ok());
@@ -499,6 +683,14 @@ impl FromStr for ContentId {
                 _ => {}
             }
         }
+        if let Some(library) = s.strip_prefix("library.") {
+            let library_id = library.parse().context("parse library id")?;
+            return Ok(Self::Library(library_id));
+        }
+        if let Some(collection) = s.strip_prefix("collection.") {
+            let collection_id = collection.parse().context("parse collection id")?;
+            return Ok(Self::Collection(collection_id));
+        }
         if let Some(movie) = s.strip_prefix("movie.") {
             let movie_id = movie.parse().context("parse movie id")?;
             return Ok(Self::Movie(movie_id));
@@ -520,9 +712,12 @@ impl ContentDirectoryHandler for MediaServerContentDirectory {
             requested_count as i64
         };
         match content_id {
-            ContentId::Root => Ok(Self::root()),
+            ContentId::Root => Ok(self.root().await?),
             ContentId::AllMovies => Ok(self.all_movies(requested_count).await?),
             ContentId::AllShows => Ok(self.all_shows(requested_count).await?),
+            ContentId::AllCollections => Ok(self.all_collections(requested_count).await?),
+            ContentId::Library(id) => Ok(self.library(id, requested_count).await?),
+            ContentId::Collection(id) => Ok(self.collection(id).await?),
             ContentId::Show(id) => Ok(self.show(id).await?),
             ContentId::Season { show_id, season } => Ok(self.show_season(show_id, season).await?),
             ContentId::Movie(_) => Ok(DidlResponse::default()),
@@ -536,6 +731,9 @@ impl ContentDirectoryHandler for MediaServerContentDirectory {
             ContentId::Root => Ok(Self::root_metadata()),
             ContentId::AllMovies => Ok(Self::all_movies_metadata()),
             ContentId::AllShows => Ok(Self::all_shows_metadata()),
+            ContentId::AllCollections => Ok(Self::all_collections_metadata()),
+            ContentId::Library(id) => Ok(self.library_metadata(id).await?),
+            ContentId::Collection(id) => Ok(self.collection_metadata(id).await?),
             ContentId::Movie(movie_id) => Ok(self.movie_metadata(movie_id).await?),
             ContentId::Show(show_id) => Ok(self.show_metadata(show_id).await?),
             ContentId::Season { show_id, season } => {
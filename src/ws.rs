@@ -43,7 +43,7 @@ pub enum WsMessage {
         progress: Notification,
     },
     Connected {
-        state: TasksSnapshot,
+        state: Box<TasksSnapshot>,
     },
     TorrentUnsubscribe,
 }
@@ -128,7 +128,7 @@ async fn ws_handler_inner(connection: &mut Connection, app_state: AppState) -> a
 
     connection
         .send(WsMessage::Connected {
-            state: app_state.tasks.snapshot(),
+            state: Box::new(app_state.tasks.snapshot()),
         })
         .await?;
 